@@ -249,6 +249,10 @@ pub fn compiletest(build: &Build,
 
     cmd.args(&build.flags.cmd.test_args());
 
+    if build.flags.cmd.bless() {
+        cmd.arg("--bless");
+    }
+
     if build.is_verbose() {
         cmd.arg("--verbose");
     }
@@ -52,6 +52,7 @@ pub enum Subcommand {
         paths: Vec<PathBuf>,
         test_args: Vec<String>,
         fail_fast: bool,
+        bless: bool,
     },
     Bench {
         paths: Vec<PathBuf>,
@@ -133,6 +134,7 @@ To learn more about a subcommand, run `./x.py <subcommand> -h`");
         match subcommand.as_str() {
             "test"  => {
                 opts.optflag("", "no-fail-fast", "Run all tests regardless of failure");
+                opts.optflag("", "bless", "Overwrite UI test expectations with the actual output");
                 opts.optmulti("", "test-args", "extra arguments", "ARGS");
             },
             "bench" => { opts.optmulti("", "test-args", "extra arguments", "ARGS"); },
@@ -269,6 +271,7 @@ Arguments:
                     paths: paths,
                     test_args: matches.opt_strs("test-args"),
                     fail_fast: !matches.opt_present("no-fail-fast"),
+                    bless: matches.opt_present("bless"),
                 }
             }
             "bench" => {
@@ -350,6 +353,13 @@ impl Subcommand {
             _ => false,
         }
     }
+
+    pub fn bless(&self) -> bool {
+        match *self {
+            Subcommand::Test { bless, .. } => bless,
+            _ => false,
+        }
+    }
 }
 
 fn split(s: Vec<String>) -> Vec<String> {
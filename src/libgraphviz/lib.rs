@@ -497,6 +497,14 @@ pub trait Labeller<'a> {
         Style::None
     }
 
+    /// Maps `n` to one of the [graphviz `color` names][1]. If `None`
+    /// is returned, no `color` attribute is specified.
+    ///
+    /// [1]: http://www.graphviz.org/doc/info/colors.html
+    fn node_color(&'a self, _node: &Self::Node) -> Option<LabelText<'a>> {
+        None
+    }
+
     /// Maps `e` to a style that will be used in the rendered output.
     fn edge_style(&'a self, _e: &Self::Edge) -> Style {
         Style::None
@@ -676,6 +684,7 @@ pub fn render_opts<'a, N, E, G, W>(g: &'a G,
 
         let escaped = &g.node_label(n).to_dot_string();
         let shape;
+        let color;
 
         let mut text = vec![id.as_slice()];
 
@@ -699,6 +708,13 @@ pub fn render_opts<'a, N, E, G, W>(g: &'a G,
             text.push("]");
         }
 
+        if let Some(c) = g.node_color(n) {
+            color = c.to_dot_string();
+            text.push("[color=");
+            text.push(&color);
+            text.push("]");
+        }
+
         text.push(";");
         writeln(w, &text)?;
     }
@@ -803,6 +819,8 @@ mod tests {
 
         node_styles: Vec<Style>,
 
+        node_colors: Vec<Option<&'static str>>,
+
         /// Each edge relates a from-index to a to-index along with a
         /// label; `edges` collects them.
         edges: Vec<Edge>,
@@ -855,6 +873,7 @@ mod tests {
                     Some(nodes) => nodes,
                     None => vec![Style::None; count],
                 },
+                node_colors: vec![None; count],
             }
         }
     }
@@ -893,6 +912,9 @@ mod tests {
         fn node_style(&'a self, n: &Node) -> Style {
             self.node_styles[*n]
         }
+        fn node_color(&'a self, n: &Node) -> Option<LabelText<'a>> {
+            self.node_colors[*n].map(|c| LabelStr(c.into_cow()))
+        }
         fn edge_style(&'a self, e: &&'a Edge) -> Style {
             e.style
         }
@@ -998,6 +1020,19 @@ r#"digraph single_node {
 "#);
     }
 
+    #[test]
+    fn single_node_with_color() {
+        let labels: Trivial = UnlabelledNodes(1);
+        let mut g = LabelledGraph::new("single_node", labels, vec![], None);
+        g.node_colors = vec![Some("red")];
+        let r = test_input(g);
+        assert_eq!(r.unwrap(),
+r#"digraph single_node {
+    N0[label="N0"][color="red"];
+}
+"#);
+    }
+
     #[test]
     fn single_edge() {
         let labels: Trivial = UnlabelledNodes(2);
@@ -50,7 +50,6 @@ use std::{ascii, fmt, iter};
 use std::str::FromStr;
 
 use syntax::ast;
-use syntax::errors::DiagnosticBuilder;
 use syntax::parse::{self, token, parse_stream_from_source_str};
 use syntax::print::pprust;
 use syntax::symbol::Symbol;
@@ -606,13 +605,23 @@ pub mod __internal {
         TokenStream(inner)
     }
 
+    /// Parses the output of a procedural macro. Unlike `str::parse`, the
+    /// parser's error is emitted, as it is the best explanation of what
+    /// went wrong that the user will get.
     pub fn token_stream_parse_items(stream: TokenStream) -> Result<Vec<P<ast::Item>>, LexError> {
         with_sess(move |(sess, _)| {
             let mut parser = parse::stream_to_parser(sess, stream.0);
             let mut items = Vec::new();
 
-            while let Some(item) = try!(parser.parse_item().map_err(super::parse_to_lex_err)) {
-                items.push(item)
+            loop {
+                match parser.parse_item() {
+                    Ok(Some(item)) => items.push(item),
+                    Ok(None) => break,
+                    Err(mut err) => {
+                        err.emit();
+                        return Err(LexError { _inner: () });
+                    }
+                }
             }
 
             Ok(items)
@@ -671,8 +680,3 @@ pub mod __internal {
         f(unsafe { (&*p.0, p.1) })
     }
 }
-
-fn parse_to_lex_err(mut err: DiagnosticBuilder) -> LexError {
-    err.cancel();
-    LexError { _inner: () }
-}
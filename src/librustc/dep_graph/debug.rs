@@ -12,14 +12,35 @@
 
 use super::dep_node::DepNode;
 use std::error::Error;
+use std::fmt;
+use std::mem;
+
+/// Where in the compiler a dep-graph message was sent from, as captured
+/// by `dep_location!()`. The shadow graph mentions it in its errors.
+#[derive(Debug)]
+pub struct DepLocation {
+    pub file: &'static str,
+    pub line: u32,
+}
+
+impl fmt::Display for DepLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
 
 /// A dep-node filter goes from a user-defined string to a query over
 /// nodes. Right now the format is like this:
 ///
 ///     x & y & z
 ///
-/// where the format-string of the dep-node must contain `x`, `y`, and
-/// `z`.
+/// where the format-string of the dep-node must match each of `x`, `y`,
+/// and `z`. A term without a `*` matches if it appears anywhere in the
+/// format-string. A term containing `*` must match the whole
+/// format-string, with each `*` standing for any (possibly empty) run of
+/// characters. Since dep-nodes print as `Variant(item::path)`, this can
+/// select nodes by variant and path, e.g. `TypeckTables(*foo*)` or
+/// `Hir(*)`; note that `Hir(*)` does not match `HirBody(..)` nodes.
 #[derive(Debug)]
 pub struct DepNodeFilter {
     text: String
@@ -42,7 +63,221 @@ impl DepNodeFilter {
         let debug_str = format!("{:?}", node);
         self.text.split("&")
                  .map(|s| s.trim())
-                 .all(|f| debug_str.contains(f))
+                 .all(|f| {
+                     if f.contains('*') {
+                         glob_matches(f, &debug_str)
+                     } else {
+                         debug_str.contains(f)
+                     }
+                 })
+    }
+
+    /// Parses a comma-separated list of filters, like
+    /// `Hir(*), TypeckTables(*foo*)`.
+    pub fn new_list(text: &str) -> Vec<DepNodeFilter> {
+        text.split(",").map(DepNodeFilter::new).collect()
+    }
+}
+
+/// Matches `text` against `pattern` in its entirety, where `*` in the
+/// pattern matches any sequence of characters.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut pieces = pattern.split('*');
+
+    // The first piece is anchored at the start and, since the pattern
+    // contains at least one `*`, the last one at the end. Everything in
+    // between may float, and matching each piece as early as possible
+    // leaves the most room for the rest.
+    let first = pieces.next().unwrap();
+    if !text.starts_with(first) {
+        return false;
+    }
+    let mut rest = &text[first.len()..];
+
+    let mut pieces: Vec<&str> = pieces.collect();
+    let last = pieces.pop().unwrap_or("");
+    for piece in pieces {
+        match rest.find(piece) {
+            Some(i) => rest = &rest[i + piece.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+/// A regular expression over the format-string of dep-nodes, for
+/// `DepGraphQuery::find_nodes`. Only a subset of the usual syntax is
+/// supported: literal characters, `.`, classes like `[a-z_]` or `[^:]`, the
+/// repetitions `*`, `+` and `?`, `\` to escape any of these, and the
+/// anchors `^` and `$`. There is no alternation nor grouping. Like
+/// `grep`, a pattern matches if it matches anywhere in the string, unless
+/// it is anchored. Matching takes time proportional to the length of the
+/// string times that of the pattern: it never backtracks.
+#[derive(Debug)]
+pub struct NodeRegex {
+    anchored_start: bool,
+    anchored_end: bool,
+    // `x+` is stored as `x` followed by `x*`
+    pieces: Vec<(CharClass, Repeat)>,
+}
+
+#[derive(Clone, Debug)]
+enum CharClass {
+    Any,
+    Char(char),
+    Set { negated: bool, ranges: Vec<(char, char)> },
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            CharClass::Any => true,
+            CharClass::Char(d) => c == d,
+            CharClass::Set { negated, ref ranges } => {
+                ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != negated
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Repeat {
+    One,
+    ZeroOrOne,
+    ZeroOrMore,
+}
+
+impl NodeRegex {
+    pub fn new(pattern: &str) -> Result<NodeRegex, Box<Error>> {
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut i = 0;
+        let anchored_start = chars.first() == Some(&'^');
+        if anchored_start {
+            i += 1;
+        }
+        let mut anchored_end = false;
+        let mut pieces = vec![];
+        while i < chars.len() {
+            let c = chars[i];
+            i += 1;
+            let class = match c {
+                '$' if i == chars.len() => {
+                    anchored_end = true;
+                    break;
+                }
+                '.' => CharClass::Any,
+                '\\' => {
+                    if i == chars.len() {
+                        return Err(format!("trailing `\\` in `{}`", pattern).into());
+                    }
+                    i += 1;
+                    CharClass::Char(chars[i - 1])
+                }
+                '[' => {
+                    let (class, end) = parse_set(&chars, i, pattern)?;
+                    i = end;
+                    class
+                }
+                '*' | '+' | '?' => {
+                    return Err(format!("nothing to repeat before `{}` in `{}`",
+                                       c, pattern).into());
+                }
+                c => CharClass::Char(c),
+            };
+            let repeat = match chars.get(i) {
+                Some(&'?') => Repeat::ZeroOrOne,
+                Some(&'*') => Repeat::ZeroOrMore,
+                Some(&'+') => {
+                    pieces.push((class.clone(), Repeat::One));
+                    Repeat::ZeroOrMore
+                }
+                _ => Repeat::One,
+            };
+            if repeat != Repeat::One {
+                i += 1;
+            }
+            pieces.push((class, repeat));
+        }
+        Ok(NodeRegex { anchored_start, anchored_end, pieces })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        // Rather than trying each way for the pieces to match in turn, all
+        // of them are followed at once: `current[i]` is whether the first
+        // `i` pieces can match a part of `text` ending at the current
+        // character, so `current[pieces.len()]` is whether the whole
+        // pattern does.
+        let all = self.pieces.len();
+        let mut current = vec![false; all + 1];
+        let mut next = vec![false; all + 1];
+        current[0] = true;
+        self.skip_optional(&mut current);
+        for c in text.chars() {
+            if current[all] && !self.anchored_end {
+                return true;
+            }
+            for matched in &mut next {
+                *matched = false;
+            }
+            for (i, &(ref class, repeat)) in self.pieces.iter().enumerate() {
+                if current[i] && class.matches(c) {
+                    match repeat {
+                        Repeat::ZeroOrMore => next[i] = true,
+                        Repeat::One | Repeat::ZeroOrOne => next[i + 1] = true,
+                    }
+                }
+            }
+            // an unanchored match may start anywhere
+            next[0] |= !self.anchored_start;
+            self.skip_optional(&mut next);
+            mem::swap(&mut current, &mut next);
+        }
+        current[all]
+    }
+
+    // Pieces that may match nothing can be skipped.
+    fn skip_optional(&self, matched: &mut [bool]) {
+        for (i, &(_, repeat)) in self.pieces.iter().enumerate() {
+            if matched[i] && repeat != Repeat::One {
+                matched[i + 1] = true;
+            }
+        }
+    }
+}
+
+// Parses the class starting after the `[` at `chars[start - 1]`, returning
+// it and the index just past its `]`.
+fn parse_set(chars: &[char], start: usize, pattern: &str)
+             -> Result<(CharClass, usize), Box<Error>> {
+    let mut i = start;
+    let negated = chars.get(i) == Some(&'^');
+    if negated {
+        i += 1;
+    }
+    let mut ranges = vec![];
+    loop {
+        let lo = match chars.get(i) {
+            None => return Err(format!("unclosed `[` in `{}`", pattern).into()),
+            Some(&']') => return Ok((CharClass::Set { negated, ranges }, i + 1)),
+            Some(&'\\') if i + 1 < chars.len() => {
+                i += 1;
+                chars[i]
+            }
+            Some(&c) => c,
+        };
+        i += 1;
+        // a `-` right before the `]` is taken literally
+        if chars.get(i) == Some(&'-') && i + 1 < chars.len() && chars[i + 1] != ']' {
+            let hi = chars[i + 1];
+            if hi < lo {
+                return Err(format!("invalid range `{}-{}` in `{}`", lo, hi, pattern).into());
+            }
+            ranges.push((lo, hi));
+            i += 2;
+        } else {
+            ranges.push((lo, lo));
+        }
     }
 }
 
@@ -66,6 +301,14 @@ impl EdgeFilter {
         }
     }
 
+    /// Parses a comma-separated list of edge filters, like
+    /// `Hir(*foo*) -> TypeckTables(*), -> ItemSignature(*bar)`.
+    pub fn new_list(test: &str) -> Result<Vec<EdgeFilter>, Box<Error>> {
+        test.split(",")
+            .map(|s| EdgeFilter::new(s))
+            .collect()
+    }
+
     pub fn test(&self,
                 source: &DepNode,
                 target: &DepNode)
@@ -73,3 +316,81 @@ impl EdgeFilter {
         self.source.test(source) && self.target.test(target)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::NodeRegex;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        NodeRegex::new(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn literals_and_repetitions() {
+        assert!(matches("Hir", "TypeckTables(Hir)"));
+        assert!(!matches("Hir", "HiR"));
+        assert!(matches("a.c", "abc"));
+        assert!(!matches("a.c", "ac"));
+        assert!(matches("ab?c", "ac"));
+        assert!(matches("ab?c", "abc"));
+        assert!(!matches("ab?c", "abbc"));
+        assert!(matches("ab*c", "ac"));
+        assert!(matches("ab*c", "abbbc"));
+        assert!(!matches("ab+c", "ac"));
+        assert!(matches("ab+c", "abbbc"));
+        assert!(matches("", "anything"));
+    }
+
+    #[test]
+    fn classes() {
+        assert!(matches("^[a-z_]+$", "type_of"));
+        assert!(!matches("^[a-z_]+$", "TypeOf"));
+        assert!(matches("^[A-Za-z0-9]*$", "Hir0"));
+        assert!(matches("([^:]+)", "Hir(foo)"));
+        assert!(!matches("([^:]+)", "Hir(foo::bar)"));
+        // a `-` that ends a class, or is escaped, is literal
+        assert!(matches("^[a-]+$", "a-a"));
+        assert!(matches("^[\\]x]+$", "x]"));
+    }
+
+    #[test]
+    fn anchors() {
+        assert!(matches("^Hir", "Hir(foo)"));
+        assert!(!matches("^Hir", "TypeckTables(Hir)"));
+        assert!(matches("foo)$", "Hir(foo)"));
+        assert!(!matches("foo$", "Hir(foo)"));
+        assert!(matches("^$", ""));
+        assert!(!matches("^$", "x"));
+        // `^` and `$` are only anchors at the ends of the pattern
+        assert!(matches("a^b", "a^b"));
+        assert!(matches("a$b", "a$b"));
+    }
+
+    #[test]
+    fn escapes() {
+        assert!(matches("a\\.c", "a.c"));
+        assert!(!matches("a\\.c", "abc"));
+        assert!(matches("^a\\*$", "a*"));
+        assert!(matches("\\[x\\]", "[x]"));
+        assert!(matches("\\\\", "a\\b"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(NodeRegex::new("a\\").is_err());
+        assert!(NodeRegex::new("[abc").is_err());
+        assert!(NodeRegex::new("[z-a]").is_err());
+        assert!(NodeRegex::new("*a").is_err());
+        assert!(NodeRegex::new("^+").is_err());
+        assert!(NodeRegex::new("a**").is_err());
+    }
+
+    #[test]
+    fn no_backtracking() {
+        // A backtracking matcher takes exponential time on this.
+        let pattern = format!("^{}b$", "a?".repeat(30) + &"a".repeat(30));
+        assert!(!matches(&pattern, &"a".repeat(30)));
+        assert!(matches(&"a*".repeat(30), &"a".repeat(100)));
+        assert!(!matches(&format!("^{}b", "a*".repeat(30)), &"a".repeat(100)));
+    }
+}
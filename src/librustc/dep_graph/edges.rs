@@ -8,35 +8,220 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use rustc_data_structures::fx::{FxHashMap, FxHashSet};
-use super::{DepGraphQuery, DepNode};
+use rustc_data_structures::fx::FxHashMap;
+use std::cmp::Ordering;
+use std::panic;
+use super::{DepGraphQuery, DepKind, DepNode};
+use super::thread::DepMessage;
 
 pub struct DepGraphEdges {
     nodes: Vec<DepNode>,
-    indices: FxHashMap<DepNode, IdIndex>,
-    edges: FxHashSet<(IdIndex, IdIndex)>,
+    indices: FxHashMap<DepNode, DepNodeIndex>,
+    // the edges added since the last `finalize`; the ones before are in
+    // `compact`, and an edge is never in both
+    edges: FxHashMap<(DepNodeIndex, DepNodeIndex), DepEdgeKind>,
+    compact: CompactEdges,
     open_nodes: Vec<OpenNode>,
+    // the nodes each open anonymous task depends on, innermost last
+    anon_inputs: Vec<Vec<(DepNodeIndex, DepEdgeKind)>>,
+    // edges that were already in `edges` when added again
+    duplicate_edges: u64,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-struct IdIndex {
+/// A node of the graph, as numbered in the order the nodes were added.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DepNodeIndex {
     index: u32
 }
 
-impl IdIndex {
-    fn new(v: usize) -> IdIndex {
+impl DepNodeIndex {
+    pub fn new(v: usize) -> DepNodeIndex {
         assert!((v & 0xFFFF_FFFF) == v);
-        IdIndex { index: v as u32 }
+        DepNodeIndex { index: v as u32 }
     }
 
-    fn index(self) -> usize {
+    pub fn index(self) -> usize {
         self.index as usize
     }
 }
 
+/// What an edge of the graph stands for. This makes no difference to
+/// incremental compilation, but tells apart the edges in graph dumps.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DepEdgeKind {
+    /// A read of the signature of an item, or of anything else computed
+    /// from the source but its HIR.
+    Data,
+    /// A read of the body of an item (`DepKind::HirBody`).
+    Body,
+    /// An edge given by the structure of the program: a read of the HIR
+    /// of an item (`DepKind::Hir`), a task depending on its subtasks, or
+    /// a write.
+    Structural,
+}
+
+impl DepEdgeKind {
+    /// The kind of a read of `node`.
+    pub fn of_read(node: &DepNode) -> DepEdgeKind {
+        match node.kind {
+            DepKind::Hir => DepEdgeKind::Structural,
+            DepKind::HirBody => DepEdgeKind::Body,
+            _ => DepEdgeKind::Data,
+        }
+    }
+}
+
+/// The edges of a graph in compressed sparse row form: the targets of the
+/// edges from node `i` (and the kinds of the edges) are
+/// `targets[starts[i]..starts[i + 1]]`, ordered by target. This takes a
+/// fraction of the memory of a hash map of edges, but cannot be added
+/// to; `merge` builds a new one instead.
+pub struct CompactEdges {
+    starts: Vec<u32>,
+    targets: Vec<(DepNodeIndex, DepEdgeKind)>,
+}
+
+impl CompactEdges {
+    fn new() -> CompactEdges {
+        CompactEdges {
+            starts: vec![0],
+            targets: vec![],
+        }
+    }
+
+    /// The edges of `self` and `edges`, between `node_count` nodes.
+    fn merge(&self,
+             node_count: usize,
+             edges: &FxHashMap<(DepNodeIndex, DepNodeIndex), DepEdgeKind>)
+             -> CompactEdges {
+        let mut new_edges: Vec<_> = edges.iter().map(|(&(s, t), &kind)| (s, t, kind)).collect();
+        new_edges.sort_by_key(|&(source, target, _)| (source, target));
+        let mut new_edges = new_edges.into_iter().peekable();
+
+        let mut starts = Vec::with_capacity(node_count + 1);
+        let mut targets = Vec::with_capacity(self.targets.len() + edges.len());
+        starts.push(0);
+        for source in 0..node_count {
+            let start = targets.len();
+            targets.extend_from_slice(self.successors(DepNodeIndex::new(source)));
+            while let Some(&(s, target, kind)) = new_edges.peek() {
+                if s.index() != source {
+                    break;
+                }
+                targets.push((target, kind));
+                new_edges.next();
+            }
+            targets[start..].sort_by_key(|&(target, _)| target);
+            assert!(targets.len() <= u32::max_value() as usize);
+            starts.push(targets.len() as u32);
+        }
+
+        CompactEdges {
+            starts,
+            targets,
+        }
+    }
+
+    fn successors(&self, source: DepNodeIndex) -> &[(DepNodeIndex, DepEdgeKind)] {
+        let index = source.index();
+        if index + 1 >= self.starts.len() {
+            // added since we were built
+            return &[];
+        }
+        &self.targets[self.starts[index] as usize..self.starts[index + 1] as usize]
+    }
+
+    fn contains(&self, source: DepNodeIndex, target: DepNodeIndex) -> bool {
+        self.successors(source).binary_search_by_key(&target, |&(t, _)| t).is_ok()
+    }
+
+    /// Removes the edges into `target`, in place.
+    fn remove_target(&mut self, target: DepNodeIndex) {
+        let mut kept = 0;
+        let mut start = 0;
+        for source in 0..self.starts.len() - 1 {
+            let end = self.starts[source + 1] as usize;
+            for i in start..end {
+                if self.targets[i].0 != target {
+                    self.targets[kept] = self.targets[i];
+                    kept += 1;
+                }
+            }
+            start = end;
+            self.starts[source + 1] = kept as u32;
+        }
+        self.targets.truncate(kept);
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn iter(&self) -> CompactEdgesIter {
+        CompactEdgesIter {
+            edges: self,
+            source: 0,
+            next: 0,
+        }
+    }
+
+    /// Like `iter`, but skips the first `start` edges.
+    pub fn iter_from(&self, start: usize) -> CompactEdgesIter {
+        assert!(start <= self.targets.len());
+        // the source of the edge is the last node whose edges start
+        // at or before it
+        let found = self.starts.binary_search_by(|&s| {
+            if s as usize <= start { Ordering::Less } else { Ordering::Greater }
+        });
+        let source = match found {
+            Ok(_) => unreachable!(),
+            Err(i) => i - 1,
+        };
+        CompactEdgesIter {
+            edges: self,
+            source,
+            next: start,
+        }
+    }
+}
+
+/// The `(source, target, kind)` triples of `CompactEdges`, ordered by
+/// source.
+pub struct CompactEdgesIter<'a> {
+    edges: &'a CompactEdges,
+    source: usize,
+    next: usize,
+}
+
+impl<'a> Iterator for CompactEdgesIter<'a> {
+    type Item = (DepNodeIndex, DepNodeIndex, DepEdgeKind);
+
+    fn next(&mut self) -> Option<(DepNodeIndex, DepNodeIndex, DepEdgeKind)> {
+        if self.next == self.edges.targets.len() {
+            return None;
+        }
+        while self.edges.starts[self.source + 1] as usize == self.next {
+            self.source += 1;
+        }
+        let (target, kind) = self.edges.targets[self.next];
+        self.next += 1;
+        Some((DepNodeIndex::new(self.source), target, kind))
+    }
+}
+
+/// The payload of the panic when a task is pushed while it is open, with
+/// the tasks of the cycle, outermost first. Nodes can only be printed on
+/// the compiler thread, so it is up to whoever catches it to report it.
+pub struct DepTaskCycle {
+    pub nodes: Vec<DepNode>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 enum OpenNode {
-    Node(IdIndex),
+    Node(DepNodeIndex),
+    // a task whose node is only known when it ends; until then, the
+    // nodes it depends on are collected in `anon_inputs`
+    Anon,
     Ignore,
 }
 
@@ -45,22 +230,50 @@ impl DepGraphEdges {
         DepGraphEdges {
             nodes: vec![],
             indices: FxHashMap(),
-            edges: FxHashSet(),
-            open_nodes: Vec::new()
+            edges: FxHashMap(),
+            compact: CompactEdges::new(),
+            open_nodes: Vec::new(),
+            anon_inputs: Vec::new(),
+            duplicate_edges: 0,
         }
     }
 
-    fn id(&self, index: IdIndex) -> DepNode {
+    pub fn id(&self, index: DepNodeIndex) -> DepNode {
         self.nodes[index.index()].clone()
     }
 
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.compact.len() + self.edges.len()
+    }
+
+    /// The number of times an edge was added that was already in the
+    /// graph (and so dropped).
+    pub fn duplicate_edge_count(&self) -> u64 {
+        self.duplicate_edges
+    }
+
+    /// Adds `id`, which must be new, to the graph. Nodes added this way
+    /// are numbered in order, so that whoever sends us messages by index
+    /// can number them the same way.
+    pub fn add_node(&mut self, id: DepNode) -> DepNodeIndex {
+        let index = DepNodeIndex::new(self.nodes.len());
+        self.nodes.push(id.clone());
+        let previous = self.indices.insert(id, index);
+        assert!(previous.is_none(), "node {:?} added twice", id);
+        index
+    }
+
     /// Creates a node for `id` in the graph.
-    fn make_node(&mut self, id: DepNode) -> IdIndex {
+    fn make_node(&mut self, id: DepNode) -> DepNodeIndex {
         if let Some(&i) = self.indices.get(&id) {
             return i;
         }
 
-        let index = IdIndex::new(self.nodes.len());
+        let index = DepNodeIndex::new(self.nodes.len());
         self.nodes.push(id.clone());
         self.indices.insert(id, index);
         index
@@ -71,6 +284,50 @@ impl DepGraphEdges {
         self.open_nodes.last().cloned()
     }
 
+    /// Applies a message that opens or closes a task or records an edge.
+    /// Returns false for any other message, which the caller must handle.
+    pub fn apply(&mut self, message: DepMessage) -> bool {
+        match message {
+            DepMessage::Read(node, kind) => self.read(node, kind),
+            DepMessage::Write(node) => self.write(node),
+            DepMessage::PushTask(node) => self.push_task(node),
+            DepMessage::PopTask(node) => self.pop_task(node),
+            DepMessage::PushAnonTask => self.push_anon_task(),
+            DepMessage::PopAnonTask(node) => {
+                let index = self.make_node(node);
+                self.pop_anon_task_index(index)
+            }
+            DepMessage::PushIgnore => self.push_ignore(),
+            DepMessage::PopIgnore => self.pop_ignore(),
+            DepMessage::Invalidate(node) => self.invalidate(node),
+            DepMessage::Query |
+            DepMessage::StreamEdges |
+            DepMessage::Flush |
+            DepMessage::Shutdown => return false,
+        }
+        true
+    }
+
+    /// Like `apply`, for a message whose node was added with `add_node`.
+    pub fn apply_indexed(&mut self, message: DepMessage<DepNodeIndex>) -> bool {
+        match message {
+            DepMessage::Read(index, kind) => self.read_index(index, kind),
+            DepMessage::Write(index) => self.write_index(index),
+            DepMessage::PushTask(index) => self.push_task_index(index),
+            DepMessage::PopTask(index) => self.pop_task_index(index),
+            DepMessage::PushAnonTask => self.push_anon_task(),
+            DepMessage::PopAnonTask(index) => self.pop_anon_task_index(index),
+            DepMessage::PushIgnore => self.push_ignore(),
+            DepMessage::PopIgnore => self.pop_ignore(),
+            DepMessage::Invalidate(index) => self.invalidate_index(index),
+            DepMessage::Query |
+            DepMessage::StreamEdges |
+            DepMessage::Flush |
+            DepMessage::Shutdown => return false,
+        }
+        true
+    }
+
     pub fn push_ignore(&mut self) {
         self.open_nodes.push(OpenNode::Ignore);
     }
@@ -81,21 +338,94 @@ impl DepGraphEdges {
     }
 
     pub fn push_task(&mut self, key: DepNode) {
-        let top_node = self.current_node();
-
         let new_node = self.make_node(key);
+        self.push_task_index(new_node);
+    }
+
+    fn push_task_index(&mut self, new_node: DepNodeIndex) {
+        self.check_cycle(new_node);
+        let top_node = self.current_node();
         self.open_nodes.push(OpenNode::Node(new_node));
 
         // if we are in the midst of doing task T, then this new task
         // N is a subtask of T, so add an edge N -> T.
         if let Some(top_node) = top_node {
-            self.add_edge_from_open_node(top_node, |t| (new_node, t));
+            self.add_input(top_node, new_node, DepEdgeKind::Structural);
+        }
+    }
+
+    pub fn push_anon_task(&mut self) {
+        self.open_nodes.push(OpenNode::Anon);
+        self.anon_inputs.push(vec![]);
+    }
+
+    /// Ends the innermost anonymous task, which turned out to be the node
+    /// `index`: it depends on everything the task depended on, and the
+    /// enclosing task (if any) depends on it.
+    fn pop_anon_task_index(&mut self, index: DepNodeIndex) {
+        let popped_node = self.open_nodes.pop().unwrap();
+        assert_eq!(popped_node, OpenNode::Anon);
+        for (source, kind) in self.anon_inputs.pop().unwrap() {
+            self.add_edge_from_open_node(OpenNode::Node(index), kind, |target| (source, target));
+        }
+        if let Some(top_node) = self.current_node() {
+            self.add_input(top_node, index, DepEdgeKind::Structural);
+        }
+    }
+
+    /// Records that the open node `open_node` depends on `source`.
+    fn add_input(&mut self, open_node: OpenNode, source: DepNodeIndex, kind: DepEdgeKind) {
+        if open_node == OpenNode::Anon {
+            self.anon_inputs.last_mut().unwrap().push((source, kind));
+        } else {
+            self.add_edge_from_open_node(open_node, kind, |target| (source, target));
+        }
+    }
+
+    /// Panics with a `DepTaskCycle` if `new_node` is already open: it would
+    /// then depend on itself, through each of the tasks opened since. This
+    /// does not happen for cycles of queries, which `cycle_check` reports
+    /// (as errors in the user's code) before the task of the query is
+    /// pushed; the tasks opened with `with_task` and `in_task` must not
+    /// nest cyclically in the first place.
+    fn check_cycle(&self, new_node: DepNodeIndex) {
+        let open = self.open_nodes.iter().position(|n| *n == OpenNode::Node(new_node));
+        if let Some(open) = open {
+            let nodes = self.open_nodes[open..].iter().filter_map(|n| match *n {
+                OpenNode::Node(index) => Some(self.id(index)),
+                OpenNode::Anon | OpenNode::Ignore => None,
+            }).collect();
+            panic::resume_unwind(Box::new(DepTaskCycle { nodes }));
         }
     }
 
     pub fn pop_task(&mut self, key: DepNode) {
+        let index = self.indices[&key];
+        self.pop_task_index(index);
+    }
+
+    fn pop_task_index(&mut self, index: DepNodeIndex) {
         let popped_node = self.open_nodes.pop().unwrap();
-        assert_eq!(OpenNode::Node(self.indices[&key]), popped_node);
+        assert_eq!(OpenNode::Node(index), popped_node);
+    }
+
+    /// Removes the edges into `node`, i.e. everything its task was recorded
+    /// to depend on, so that running the task again starts from scratch
+    /// instead of adding to stale edges. The node itself stays in the
+    /// graph (its index is shared with the compiler thread), as do the
+    /// edges of the tasks that depend on it.
+    pub fn invalidate(&mut self, node: DepNode) {
+        if let Some(&index) = self.indices.get(&node) {
+            self.invalidate_index(index);
+        }
+    }
+
+    fn invalidate_index(&mut self, index: DepNodeIndex) {
+        if self.open_nodes.contains(&OpenNode::Node(index)) {
+            bug!("cannot invalidate {:?} while its task is open", self.id(index))
+        }
+        self.edges.retain(|&(_, target), _| target != index);
+        self.compact.remove_target(index);
     }
 
     /// Indicates that the current task `C` reads `v` by adding an
@@ -103,10 +433,16 @@ impl DepGraphEdges {
     /// effect. Note that *reading* from tracked state is harmless if
     /// you are not in a task; what is bad is *writing* to tracked
     /// state (and leaking data that you read into a tracked task).
-    pub fn read(&mut self, v: DepNode) {
+    pub fn read(&mut self, v: DepNode, kind: DepEdgeKind) {
         if self.current_node().is_some() {
             let source = self.make_node(v);
-            self.add_edge_from_current_node(|current| (source, current))
+            self.read_index(source, kind);
+        }
+    }
+
+    fn read_index(&mut self, source: DepNodeIndex, kind: DepEdgeKind) {
+        if let Some(current) = self.current_node() {
+            self.add_input(current, source, kind)
         }
     }
 
@@ -115,6 +451,10 @@ impl DepGraphEdges {
     /// you want to suppress this edge, use `ignore`.
     pub fn write(&mut self, v: DepNode) {
         let target = self.make_node(v);
+        self.write_index(target);
+    }
+
+    fn write_index(&mut self, target: DepNodeIndex) {
         self.add_edge_from_current_node(|current| (current, target))
     }
 
@@ -122,26 +462,30 @@ impl DepGraphEdges {
     /// panic if stack is empty.
     fn add_edge_from_current_node<OP>(&mut self,
                                       op: OP)
-        where OP: FnOnce(IdIndex) -> (IdIndex, IdIndex)
+        where OP: FnOnce(DepNodeIndex) -> (DepNodeIndex, DepNodeIndex)
     {
         match self.current_node() {
-            Some(open_node) => self.add_edge_from_open_node(open_node, op),
+            Some(open_node) => self.add_edge_from_open_node(open_node, DepEdgeKind::Structural, op),
             None => bug!("no current node, cannot add edge into dependency graph")
         }
     }
 
-    /// Adds an edge to or from the `open_node`, assuming `open_node`
-    /// is not `Ignore`. The direction of the edge is determined by
-    /// the closure `op` --- we pass as argument the open node `n`,
-    /// and the closure returns a (source, target) tuple, which should
-    /// include `n` in one spot or another.
+    /// Adds an edge of `kind` to or from the `open_node`, assuming
+    /// `open_node` is not `Ignore`. The direction of the edge is
+    /// determined by the closure `op` --- we pass as argument the open
+    /// node `n`, and the closure returns a (source, target) tuple, which
+    /// should include `n` in one spot or another.
     fn add_edge_from_open_node<OP>(&mut self,
                                    open_node: OpenNode,
+                                   kind: DepEdgeKind,
                                    op: OP)
-        where OP: FnOnce(IdIndex) -> (IdIndex, IdIndex)
+        where OP: FnOnce(DepNodeIndex) -> (DepNodeIndex, DepNodeIndex)
     {
         let (source, target) = match open_node {
             OpenNode::Node(n) => op(n),
+            OpenNode::Anon => {
+                bug!("cannot write in an anonymous task, which has no node yet")
+            }
             OpenNode::Ignore => { return; }
         };
 
@@ -150,17 +494,133 @@ impl DepGraphEdges {
             return;
         }
 
-        if self.edges.insert((source, target)) {
-            debug!("adding edge from {:?} to {:?}",
+        // an edge keeps the kind it was first added with
+        if !self.compact.contains(source, target) && !self.edges.contains_key(&(source, target)) {
+            debug!("adding {:?} edge from {:?} to {:?}",
+                   kind,
                    self.id(source),
                    self.id(target));
+            self.edges.insert((source, target), kind);
+        } else {
+            self.duplicate_edges += 1;
+        }
+    }
+
+    /// Checks that a query can be made now: outside of any task, or in an
+    /// ignore (see `DepGraph::query`).
+    pub fn check_query(&self) {
+        match self.current_node() {
+            Some(OpenNode::Node(index)) => {
+                bug!("dep-graph queried in task {:?}, outside of an ignore", self.id(index))
+            }
+            Some(OpenNode::Anon) => {
+                bug!("dep-graph queried in an anonymous task, outside of an ignore")
+            }
+            Some(OpenNode::Ignore) | None => {}
+        }
+    }
+
+    /// Moves the edges added since the last call into their compact form,
+    /// freeing the hash set that held them. Edges can still be added
+    /// afterwards, but the graph is meant to be traversed from now on.
+    pub fn finalize(&mut self) {
+        if self.edges.is_empty() {
+            return;
+        }
+        self.compact = self.compact.merge(self.nodes.len(), &self.edges);
+        self.edges = FxHashMap();
+    }
+
+    /// The graph so far, for traversals. This finalizes the edges, so that
+    /// we only hold on to their compact form while the query is built.
+    pub fn query(&mut self) -> DepGraphQuery {
+        self.finalize();
+        DepGraphQuery::with_indexed_edges(&self.nodes, self.compact.iter())
+    }
+
+    /// Up to `len` of the edges of the last `finalize`, from the
+    /// `start`-th one on, for streaming the graph a chunk at a time.
+    pub fn edge_chunk(&self, start: usize, len: usize) -> Vec<(DepNode, DepNode, DepEdgeKind)> {
+        self.compact.iter_from(start)
+                    .take(len)
+                    .map(|(i, j, kind)| (self.id(i), self.id(j), kind))
+                    .collect()
+    }
+
+    pub fn nodes(&self) -> &[DepNode] {
+        &self.nodes
+    }
+
+    pub fn edges(&self) -> Vec<(DepNode, DepNode, DepEdgeKind)> {
+        self.compact.iter()
+                    .chain(self.edges.iter().map(|(&(i, j), &kind)| (i, j, kind)))
+                    .map(|(i, j, kind)| (self.id(i), self.id(j), kind))
+                    .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dep_graph::{DepKind, DepNode};
+    use ich::Fingerprint;
+    use super::{DepEdgeKind, DepGraphEdges};
+
+    fn node(n: u64) -> DepNode {
+        DepNode { kind: DepKind::TypeckTables, hash: Fingerprint::from_smaller_hash(n) }
+    }
+
+    fn run_task(edges: &mut DepGraphEdges, task: u64, reads: &[u64]) {
+        edges.push_task(node(task));
+        for &n in reads {
+            edges.read(node(n), DepEdgeKind::Data);
         }
+        edges.pop_task(node(task));
+    }
+
+    // The successors of each node, in `compact` and in `edges` separately.
+    fn successors(edges: &DepGraphEdges) -> Vec<(DepNode, Vec<DepNode>, Vec<DepNode>)> {
+        (0..edges.node_count()).map(|i| {
+            let source = super::DepNodeIndex::new(i);
+            let compact = edges.compact.successors(source).iter().map(|&(t, _)| edges.id(t));
+            let mut added: Vec<_> = edges.edges.keys()
+                .filter(|&&(s, _)| s == source)
+                .map(|&(_, t)| edges.id(t))
+                .collect();
+            added.sort();
+            (edges.id(source), compact.collect(), added)
+        }).collect()
     }
 
-    pub fn query(&self) -> DepGraphQuery {
-        let edges: Vec<_> = self.edges.iter()
-                                      .map(|&(i, j)| (self.id(i), self.id(j)))
-                                      .collect();
-        DepGraphQuery::new(&self.nodes, &edges)
+    #[test]
+    fn invalidate_keeps_other_edges() {
+        let mut edges = DepGraphEdges::new();
+        run_task(&mut edges, 2, &[0, 1]);
+        run_task(&mut edges, 3, &[0, 2]);
+        edges.finalize();
+        // after `finalize`, so that these edges are not compacted yet
+        run_task(&mut edges, 2, &[4]);
+        run_task(&mut edges, 3, &[1]);
+        run_task(&mut edges, 5, &[2]);
+
+        edges.invalidate(node(2));
+
+        assert_eq!(successors(&edges), vec![
+            (node(2), vec![node(3)], vec![node(5)]),
+            (node(0), vec![node(3)], vec![]),
+            (node(1), vec![], vec![node(3)]),
+            (node(3), vec![], vec![]),
+            (node(4), vec![], vec![]),
+            (node(5), vec![], vec![]),
+        ]);
+
+        edges.finalize();
+        assert_eq!(successors(&edges), vec![
+            (node(2), vec![node(3), node(5)], vec![]),
+            (node(0), vec![node(3)], vec![]),
+            (node(1), vec![node(3)], vec![]),
+            (node(3), vec![], vec![]),
+            (node(4), vec![], vec![]),
+            (node(5), vec![], vec![]),
+        ]);
     }
 }
@@ -9,15 +9,19 @@
 // except according to those terms.
 
 use rustc_data_structures::fx::FxHashMap;
+use ich::Fingerprint;
 use session::config::OutputType;
 use std::cell::{Ref, RefCell};
 use std::rc::Rc;
 
-use super::dep_node::{DepNode, WorkProductId};
+use super::debug::DepLocation;
+use super::dep_node::{DepKind, DepNode, WorkProductId};
+use super::edges::DepEdgeKind;
 use super::query::DepGraphQuery;
 use super::raii;
 use super::safe::DepGraphSafe;
-use super::thread::{DepGraphThreadData, DepMessage};
+use super::sharded::ShardProducer;
+use super::thread::{DepGraphQueryHandle, DepGraphThreadConfig, DepGraphThreadData, DepMessage};
 
 #[derive(Clone)]
 pub struct DepGraph {
@@ -39,16 +43,27 @@ struct DepGraphData {
     work_products: RefCell<FxHashMap<WorkProductId, WorkProduct>>,
 
     dep_node_debug: RefCell<FxHashMap<DepNode, String>>,
+
+    /// The nodes of the previous session that were looked at by red/green
+    /// marking when loading, and what it found.
+    colors: RefCell<FxHashMap<DepNode, DepNodeColor>>,
 }
 
 impl DepGraph {
     pub fn new(enabled: bool) -> DepGraph {
+        DepGraph::with_config(enabled, DepGraphThreadConfig::default())
+    }
+
+    /// Like `new`, but configuring how messages are sent to the thread
+    /// building the graph.
+    pub fn with_config(enabled: bool, config: DepGraphThreadConfig) -> DepGraph {
         DepGraph {
             data: Rc::new(DepGraphData {
-                thread: DepGraphThreadData::new(enabled),
+                thread: DepGraphThreadData::new(enabled, config),
                 previous_work_products: RefCell::new(FxHashMap()),
                 work_products: RefCell::new(FxHashMap()),
                 dep_node_debug: RefCell::new(FxHashMap()),
+                colors: RefCell::new(FxHashMap()),
             })
         }
     }
@@ -59,18 +74,78 @@ impl DepGraph {
         self.data.thread.is_fully_enabled()
     }
 
+    /// Returns the graph as it is now, including the edges of any task that
+    /// is still open. Since the result depends on the whole graph, it must
+    /// not be used in a task: queries are only allowed outside of any task,
+    /// or in an ignore (see `with_ignore`), and ICE otherwise.
     pub fn query(&self) -> DepGraphQuery {
         self.data.thread.query()
     }
 
+    /// Like `query`, but returns a handle to retrieve the result later rather
+    /// than waiting for it.
+    pub fn query_async(&self) -> DepGraphQueryHandle {
+        self.data.thread.query_async()
+    }
+
+    /// Calls `op` on each edge of the graph, like a `query` would see it,
+    /// without building a copy of the whole graph. The edges are sent over
+    /// by the depgraph thread as `op` goes, so `op` must not use the
+    /// dep-graph, and ICEs if it does.
+    pub fn for_each_edge<OP>(&self, op: OP)
+        where OP: FnMut(&DepNode, &DepNode, DepEdgeKind)
+    {
+        self.data.thread.for_each_edge(op)
+    }
+
+    /// Prints the statistics of `-Z dep-graph-stats`, if enabled.
+    pub fn print_stats(&self) {
+        self.data.thread.print_stats()
+    }
+
+    /// Prints the tasks with the most edges, under `-Z dep-graph-task-edges`.
+    pub fn print_task_edges(&self) {
+        self.data.thread.print_task_edges()
+    }
+
+    /// Creates the means for another thread to record edges, which are
+    /// merged into the graph when it is queried. `None` unless
+    /// `-Z dep-graph-shards` was given.
+    pub fn shard_producer(&self) -> Option<ShardProducer> {
+        self.data.thread.shard_producer()
+    }
+
+    /// Waits until every edge recorded so far has been added to the graph,
+    /// without copying it the way `query` does.
+    pub fn flush(&self) {
+        self.data.thread.flush()
+    }
+
+    /// Suspends the current task until the returned guard is dropped: no
+    /// edges are recorded in the meantime, and the graph may be queried.
     pub fn in_ignore<'graph>(&'graph self) -> Option<raii::IgnoreTask<'graph>> {
-        raii::IgnoreTask::new(&self.data.thread)
+        raii::IgnoreTask::new(&self.data.thread, None)
+    }
+
+    /// Like `in_ignore`, naming the caller (given by `dep_location!()`) in
+    /// the errors of the shadow graph that concern this ignore.
+    pub fn in_ignore_at<'graph>(&'graph self, location: &'static DepLocation)
+                                -> Option<raii::IgnoreTask<'graph>> {
+        raii::IgnoreTask::new(&self.data.thread, Some(location))
     }
 
     pub fn in_task<'graph>(&'graph self, key: DepNode) -> Option<raii::DepTask<'graph>> {
-        raii::DepTask::new(&self.data.thread, key)
+        raii::DepTask::new(&self.data.thread, key, None)
+    }
+
+    /// Like `in_task`, naming the caller (given by `dep_location!()`) in
+    /// the errors of the shadow graph that concern this task.
+    pub fn in_task_at<'graph>(&'graph self, key: DepNode, location: &'static DepLocation)
+                              -> Option<raii::DepTask<'graph>> {
+        raii::DepTask::new(&self.data.thread, key, Some(location))
     }
 
+    /// Runs `op` in an ignore; see `in_ignore`.
     pub fn with_ignore<OP,R>(&self, op: OP) -> R
         where OP: FnOnce() -> R
     {
@@ -112,9 +187,53 @@ impl DepGraph {
         task(cx, arg)
     }
 
+    /// Runs `op` in an anonymous task, returning its result along with a
+    /// node of `kind` that is identified by what `op` depended on: running
+    /// the same computation on the same inputs yields the same node, so
+    /// it can be shared by closures and trait selection without a key of
+    /// its own. The enclosing task reads the node; an anonymous task
+    /// cannot write anything itself.
+    pub fn with_anon_task<OP, R>(&self, kind: DepKind, op: OP) -> (R, DepNode)
+        where OP: FnOnce() -> R
+    {
+        match raii::AnonTask::new(&self.data.thread, kind, None) {
+            Some(task) => {
+                let result = op();
+                (result, task.finish())
+            }
+            None => (op(), DepNode { kind: kind, hash: Fingerprint::zero() }),
+        }
+    }
+
+    /// Forgets what the task of `v` was recorded to depend on, so that it
+    /// can be run again (say, to replay its diagnostics) without keeping
+    /// the edges of the previous run. The task must not be open.
+    pub fn invalidate(&self, v: DepNode) {
+        if self.data.thread.is_enqueue_enabled() {
+            self.data.thread.enqueue(DepMessage::Invalidate(v));
+        }
+    }
+
     pub fn read(&self, v: DepNode) {
         if self.data.thread.is_enqueue_enabled() {
-            self.data.thread.enqueue(DepMessage::Read(v));
+            self.data.thread.enqueue(DepMessage::Read(v, DepEdgeKind::of_read(&v)));
+        }
+    }
+
+    /// Like `read`, naming the caller (given by `dep_location!()`) in the
+    /// errors of the shadow graph that concern this read.
+    pub fn read_at(&self, v: DepNode, location: &'static DepLocation) {
+        if self.data.thread.is_enqueue_enabled() {
+            let message = DepMessage::Read(v, DepEdgeKind::of_read(&v));
+            self.data.thread.enqueue_at(message, Some(location));
+        }
+    }
+
+    /// Like calling `read` on each of `vs`, but checking only once whether
+    /// the graph is enabled.
+    pub fn read_many(&self, vs: &[DepNode]) {
+        if self.data.thread.is_enqueue_enabled() {
+            self.data.thread.enqueue_reads(vs);
         }
     }
 
@@ -156,6 +275,20 @@ impl DepGraph {
         self.data.previous_work_products.borrow()
     }
 
+    /// Records what red/green marking found for `v`, a node of the previous
+    /// session. This is invoked during initial start-up, before dirty nodes
+    /// are dropped from the previous graph.
+    pub fn mark_color(&self, v: DepNode, color: DepNodeColor) {
+        debug!("mark_color({:?}, {:?})", v, color);
+        let old = self.data.colors.borrow_mut().insert(v, color);
+        debug_assert!(old.is_none(), "{:?} was already marked {:?}", v, old);
+    }
+
+    /// What red/green marking found for `v`, if it looked at it.
+    pub fn color(&self, v: &DepNode) -> Option<DepNodeColor> {
+        self.data.colors.borrow().get(v).cloned()
+    }
+
     #[inline(always)]
     pub(super) fn register_dep_node_debug_str<F>(&self,
                                                  dep_node: DepNode,
@@ -173,6 +306,20 @@ impl DepGraph {
     }
 }
 
+/// What red/green marking found for a node of the previous session that
+/// depends on some changed input. Only nodes whose results have a saved
+/// fingerprint can be marked green.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DepNodeColor {
+    /// The results of the node were computed again and are the same as in
+    /// the previous session, so the nodes depending on it are not dirtied
+    /// on its account.
+    Green,
+
+    /// The results of the node have changed, or could not be computed again.
+    Red,
+}
+
 /// A "work product" is an intermediate result that we save into the
 /// incremental directory for later re-use. The primary example are
 /// the object files that we save for each partition at code
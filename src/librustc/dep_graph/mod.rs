@@ -17,16 +17,21 @@ mod query;
 mod raii;
 mod safe;
 mod shadow;
+mod sharded;
 mod thread;
 
 pub use self::dep_tracking_map::{DepTrackingMap, DepTrackingMapConfig};
 pub use self::dep_node::DepNode;
 pub use self::dep_node::WorkProductId;
-pub use self::graph::DepGraph;
+pub use self::edges::DepEdgeKind;
+pub use self::graph::{DepGraph, DepNodeColor};
 pub use self::graph::WorkProduct;
-pub use self::query::DepGraphQuery;
+pub use self::query::{DepGraphQuery, DepKindCounts, DepKindHistogram};
+pub use self::thread::DepGraphQueryHandle;
 pub use self::safe::AssertDepGraphSafe;
 pub use self::safe::DepGraphSafe;
 pub use self::raii::DepTask;
+pub use self::sharded::ShardProducer;
+pub use self::thread::{DepGraphThreadConfig, DepGraphTransport};
 
 pub use self::dep_node::{DepKind, DepConstructor};
@@ -8,19 +8,61 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_data_structures::graph::{Direction, INCOMING, Graph, NodeIndex, OUTGOING};
+use std::cmp;
 
-use super::DepNode;
+use super::{DepKind, DepNode};
+use super::debug::NodeRegex;
+use super::edges::{DepEdgeKind, DepNodeIndex};
 
 pub struct DepGraphQuery {
-    pub graph: Graph<DepNode, ()>,
+    pub graph: Graph<DepNode, DepEdgeKind>,
     pub indices: FxHashMap<DepNode, NodeIndex>,
 }
 
+/// The number of nodes of one `DepKind`, and of the edges out of them; see
+/// `DepGraphQuery::kind_histogram`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DepKindCounts {
+    pub nodes: usize,
+    pub edges: usize,
+}
+
+impl DepKindCounts {
+    /// The average number of edges out of a node of this kind.
+    pub fn average_out_degree(&self) -> f64 {
+        self.edges as f64 / cmp::max(self.nodes, 1) as f64
+    }
+}
+
+/// Counts the nodes and edges of a graph by kind, as they are visited.
+#[derive(Default)]
+pub struct DepKindHistogram {
+    counts: FxHashMap<DepKind, DepKindCounts>,
+}
+
+impl DepKindHistogram {
+    pub fn add_node(&mut self, node: &DepNode) {
+        self.counts.entry(node.kind).or_insert_with(DepKindCounts::default).nodes += 1;
+    }
+
+    /// Counts an edge out of `source`.
+    pub fn add_edge(&mut self, source: &DepNode) {
+        self.counts.entry(source.kind).or_insert_with(DepKindCounts::default).edges += 1;
+    }
+
+    /// The counts of each kind, the kinds with the most nodes first.
+    pub fn counts(self) -> Vec<(DepKind, DepKindCounts)> {
+        let mut counts: Vec<_> = self.counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.nodes.cmp(&a.1.nodes).then(a.0.cmp(&b.0)));
+        counts
+    }
+}
+
 impl DepGraphQuery {
     pub fn new(nodes: &[DepNode],
-               edges: &[(DepNode, DepNode)])
+               edges: &[(DepNode, DepNode, DepEdgeKind)])
                -> DepGraphQuery {
         let mut graph = Graph::new();
         let mut indices = FxHashMap();
@@ -29,10 +71,33 @@ impl DepGraphQuery {
             graph.add_node(node.clone());
         }
 
-        for &(ref source, ref target) in edges {
+        for &(ref source, ref target, kind) in edges {
             let source = indices[source];
             let target = indices[target];
-            graph.add_edge(source, target, ());
+            graph.add_edge(source, target, kind);
+        }
+
+        DepGraphQuery {
+            graph,
+            indices,
+        }
+    }
+
+    /// Like `new`, for edges between the nodes at the given indices in
+    /// `nodes`, which saves looking up each node of each edge.
+    pub fn with_indexed_edges<I>(nodes: &[DepNode], edges: I) -> DepGraphQuery
+        where I: IntoIterator<Item = (DepNodeIndex, DepNodeIndex, DepEdgeKind)>
+    {
+        let mut graph = Graph::new();
+        let mut indices = FxHashMap();
+        for node in nodes {
+            indices.insert(node.clone(), graph.next_node_index());
+            graph.add_node(node.clone());
+        }
+
+        // nodes are added in order, so their indices agree
+        for (source, target, kind) in edges {
+            graph.add_edge(NodeIndex(source.index()), NodeIndex(target.index()), kind);
         }
 
         DepGraphQuery {
@@ -52,15 +117,29 @@ impl DepGraphQuery {
                   .collect()
     }
 
-    pub fn edges(&self) -> Vec<(&DepNode,&DepNode)> {
+    pub fn edges(&self) -> Vec<(&DepNode, &DepNode, DepEdgeKind)> {
         self.graph.all_edges()
                   .iter()
-                  .map(|edge| (edge.source(), edge.target()))
-                  .map(|(s, t)| (self.graph.node_data(s),
-                                 self.graph.node_data(t)))
+                  .map(|edge| (self.graph.node_data(edge.source()),
+                               self.graph.node_data(edge.target()),
+                               edge.data))
                   .collect()
     }
 
+    /// Counts the nodes of each kind, and the edges out of them, to see
+    /// which kinds of nodes make the graph big. The kinds with the most
+    /// nodes come first.
+    pub fn kind_histogram(&self) -> Vec<(DepKind, DepKindCounts)> {
+        let mut histogram = DepKindHistogram::default();
+        for node in self.graph.all_nodes() {
+            histogram.add_node(&node.data);
+        }
+        for edge in self.graph.all_edges() {
+            histogram.add_edge(self.graph.node_data(edge.source()));
+        }
+        histogram.counts()
+    }
+
     fn reachable_nodes(&self, node: &DepNode, direction: Direction) -> Vec<&DepNode> {
         if let Some(&index) = self.indices.get(node) {
             self.graph.depth_traverse(index, direction)
@@ -82,6 +161,56 @@ impl DepGraphQuery {
         self.reachable_nodes(node, INCOMING)
     }
 
+    /// Like `transitive_successors`, but only the nodes reachable from
+    /// `node` in at most `depth` edges, nearest first.
+    pub fn transitive_successors_within(&self, node: &DepNode, depth: usize) -> Vec<&DepNode> {
+        self.nodes_within(node, depth, OUTGOING)
+    }
+
+    /// Like `transitive_predecessors`, but only the nodes that can reach
+    /// `node` in at most `depth` edges, nearest first.
+    pub fn transitive_predecessors_within(&self, node: &DepNode, depth: usize) -> Vec<&DepNode> {
+        self.nodes_within(node, depth, INCOMING)
+    }
+
+    fn nodes_within(&self, node: &DepNode, depth: usize, direction: Direction) -> Vec<&DepNode> {
+        let start = match self.indices.get(node) {
+            Some(&index) => index,
+            None => return vec![],
+        };
+        let mut visited = FxHashSet();
+        visited.insert(start);
+        let mut result = vec![self.graph.node_data(start)];
+        let mut frontier = vec![start];
+        for _ in 0..depth {
+            let mut next = vec![];
+            for &index in &frontier {
+                for (_, edge) in self.graph.adjacent_edges(index, direction) {
+                    let neighbor = edge.source_or_target(direction);
+                    if visited.insert(neighbor) {
+                        result.push(self.graph.node_data(neighbor));
+                        next.push(neighbor);
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        result
+    }
+
+    /// The nodes whose format-string, e.g. `TypeckTables(foo::bar)`,
+    /// matches `pattern`.
+    pub fn find_nodes(&self, pattern: &NodeRegex) -> Vec<&DepNode> {
+        self.graph.all_nodes()
+                  .iter()
+                  .map(|n| &n.data)
+                  .filter(|node| pattern.is_match(&format!("{:?}", node)))
+                  .collect()
+    }
+
     /// Just the outgoing edges from `node`.
     pub fn immediate_successors(&self, node: &DepNode) -> Vec<&DepNode> {
         if let Some(&index) = self.indices.get(&node) {
@@ -92,4 +221,15 @@ impl DepGraphQuery {
             vec![]
         }
     }
+
+    /// Just the incoming edges to `node`.
+    pub fn immediate_predecessors(&self, node: &DepNode) -> Vec<&DepNode> {
+        if let Some(&index) = self.indices.get(&node) {
+            self.graph.predecessor_nodes(index)
+                      .map(|s| self.graph.node_data(s))
+                      .collect()
+        } else {
+            vec![]
+        }
+    }
 }
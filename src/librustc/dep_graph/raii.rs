@@ -8,20 +8,24 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use super::DepNode;
+use super::{DepKind, DepNode};
+use super::debug::DepLocation;
 use super::thread::{DepGraphThreadData, DepMessage};
 
 pub struct DepTask<'graph> {
     data: &'graph DepGraphThreadData,
     key: Option<DepNode>,
+    location: Option<&'static DepLocation>,
 }
 
 impl<'graph> DepTask<'graph> {
-    pub fn new(data: &'graph DepGraphThreadData, key: DepNode)
+    pub fn new(data: &'graph DepGraphThreadData,
+               key: DepNode,
+               location: Option<&'static DepLocation>)
                -> Option<DepTask<'graph>> {
         if data.is_enqueue_enabled() {
-            data.enqueue(DepMessage::PushTask(key.clone()));
-            Some(DepTask { data: data, key: Some(key) })
+            data.enqueue_at(DepMessage::PushTask(key.clone()), location);
+            Some(DepTask { data: data, key: Some(key), location: location })
         } else {
             None
         }
@@ -31,20 +35,23 @@ impl<'graph> DepTask<'graph> {
 impl<'graph> Drop for DepTask<'graph> {
     fn drop(&mut self) {
         if self.data.is_enqueue_enabled() {
-            self.data.enqueue(DepMessage::PopTask(self.key.take().unwrap()));
+            self.data.enqueue_at(DepMessage::PopTask(self.key.take().unwrap()), self.location);
         }
     }
 }
 
 pub struct IgnoreTask<'graph> {
-    data: &'graph DepGraphThreadData
+    data: &'graph DepGraphThreadData,
+    location: Option<&'static DepLocation>,
 }
 
 impl<'graph> IgnoreTask<'graph> {
-    pub fn new(data: &'graph DepGraphThreadData) -> Option<IgnoreTask<'graph>> {
+    pub fn new(data: &'graph DepGraphThreadData,
+               location: Option<&'static DepLocation>)
+               -> Option<IgnoreTask<'graph>> {
         if data.is_enqueue_enabled() {
-            data.enqueue(DepMessage::PushIgnore);
-            Some(IgnoreTask { data: data })
+            data.enqueue_at(DepMessage::PushIgnore, location);
+            Some(IgnoreTask { data: data, location: location })
         } else {
             None
         }
@@ -54,8 +61,42 @@ impl<'graph> IgnoreTask<'graph> {
 impl<'graph> Drop for IgnoreTask<'graph> {
     fn drop(&mut self) {
         if self.data.is_enqueue_enabled() {
-            self.data.enqueue(DepMessage::PopIgnore);
+            self.data.enqueue_at(DepMessage::PopIgnore, self.location);
         }
     }
 }
 
+pub struct AnonTask<'graph> {
+    data: &'graph DepGraphThreadData,
+    kind: DepKind,
+    location: Option<&'static DepLocation>,
+    finished: bool,
+}
+
+impl<'graph> AnonTask<'graph> {
+    pub fn new(data: &'graph DepGraphThreadData,
+               kind: DepKind,
+               location: Option<&'static DepLocation>)
+               -> Option<AnonTask<'graph>> {
+        if data.is_enqueue_enabled() {
+            data.push_anon_task(location);
+            Some(AnonTask { data: data, kind: kind, location: location, finished: false })
+        } else {
+            None
+        }
+    }
+
+    /// Ends the task, returning the node identified by its inputs.
+    pub fn finish(mut self) -> DepNode {
+        self.finished = true;
+        self.data.pop_anon_task(self.kind, self.location)
+    }
+}
+
+impl<'graph> Drop for AnonTask<'graph> {
+    fn drop(&mut self) {
+        if !self.finished && self.data.is_enqueue_enabled() {
+            self.data.pop_anon_task(self.kind, self.location);
+        }
+    }
+}
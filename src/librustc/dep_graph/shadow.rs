@@ -14,62 +14,143 @@
 //! a nice stack-trace telling you precisely what caused the error.
 //!
 //! NOTE: This is a debugging facility which can potentially have non-trivial
-//! runtime impact. Therefore, it is largely compiled out if
-//! debug-assertions are not enabled.
+//! runtime impact. Therefore, it is only enabled by default if
+//! debug-assertions are enabled; `-Z dep-graph-shadow` turns it on in
+//! other builds of the compiler.
 //!
 //! The basic sanity check, enabled if you have debug assertions
-//! enabled, is that there is always a task (or ignore) on the stack
-//! when you do read/write, and that the tasks are pushed/popped
-//! according to a proper stack discipline.
+//! enabled (or pass `-Z dep-graph-shadow`), is that there is always a
+//! task (or ignore) on the stack when you do read/write, and that the
+//! tasks are pushed/popped according to a proper stack discipline
+//! (which also excludes pushing a task that is already open). If
+//! a pop does not match, the error shows the whole stack and the last
+//! few messages, which is usually enough to see where the nesting went
+//! wrong.
 //!
 //! Optionally, if you specify RUST_FORBID_DEP_GRAPH_EDGE, you can
-//! specify an edge filter to be applied to each edge as it is
-//! created.  See `./README.md` for details.
+//! specify a comma-separated list of edge filters to be applied to
+//! each edge as it is created.  See `./README.md` for details.
+//!
+//! Reads and writes performed while an ignore is on top of the stack
+//! create no edges. To audit those, set RUST_DEP_GRAPH_AUDIT_IGNORE to
+//! a comma-separated list of dep-node filters: each such access of a
+//! matching node is then reported as a warning, or as an error if
+//! RUST_DEP_GRAPH_AUDIT_IGNORE_BUG is set as well.
+//!
+//! The errors are reported with `bug!`, so `RUST_BACKTRACE=1` shows
+//! where they came from. Setting RUST_DEP_GRAPH_BACKTRACE does the
+//! same for the shadow graph's errors only: the backtrace of the code
+//! that sent the offending message is captured and printed with the
+//! error. Before reporting an error, the task stack and the last few
+//! messages are also written to the file given by
+//! `-Z dep-graph-shadow-dump=PATH`, if any.
+//!
+//! Messages sent through the `_at` methods (`DepGraph::read_at`,
+//! `Map::read_at`, the `try_get_at` of queries and `TyCtxt::at_location`)
+//! carry the `dep_location!()` of their caller, which the errors name.
+//!
+//! Finally, `-Z dep-graph-task-edges=N` has the shadow graph count the
+//! reads and writes made by each task (before duplicates are removed)
+//! and print the `N` tasks that made the most, to find the passes that
+//! bloat the graph.
 
+use backtrace::Backtrace;
+use rustc_data_structures::fx::FxHashMap;
 use std::cell::RefCell;
+use std::cmp;
+use std::collections::VecDeque;
 use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
 
 use super::DepNode;
 use super::thread::DepMessage;
-use super::debug::EdgeFilter;
+use super::debug::{DepLocation, DepNodeFilter, EdgeFilter};
 
 pub struct ShadowGraph {
-    // if you push None onto the stack, that corresponds to an Ignore
-    stack: RefCell<Vec<Option<DepNode>>>,
-    forbidden_edge: Option<EdgeFilter>,
+    // the open tasks and ignores, each with where it was pushed from, if
+    // known
+    stack: RefCell<Vec<Frame>>,
+    // the last `HISTORY` messages, most recent last, for errors
+    history: RefCell<VecDeque<(DepMessage, Option<&'static DepLocation>)>>,
+    forbidden_edges: Vec<EdgeFilter>,
+    // nodes whose accesses in an ignore are reported, and whether as bugs
+    audited_in_ignore: Vec<DepNodeFilter>,
+    audit_is_bug: bool,
+    enabled: bool,
+    // whether to print a backtrace with our errors
+    backtrace: bool,
+    // where to dump our state on errors, if anywhere
+    dump: Option<PathBuf>,
+    // under `-Z dep-graph-task-edges`, the label of each task and the
+    // number of reads and writes it made, and how many tasks to print
+    task_edges: RefCell<FxHashMap<DepNode, (String, u64, u64)>>,
+    top_tasks: Option<usize>,
+}
+
+enum OpenTask {
+    Task(DepNode),
+    Anon,
+    Ignore,
 }
 
+type Frame = (OpenTask, Option<&'static DepLocation>);
+
 const ENABLED: bool = cfg!(debug_assertions);
 
+/// The number of messages mentioned when the task stack is unbalanced.
+const HISTORY: usize = 50;
+
 impl ShadowGraph {
-    pub fn new() -> Self {
-        let forbidden_edge = if !ENABLED {
-            None
+    /// Creates a shadow graph, which checks the messages it is given if
+    /// debug-assertions are enabled or `force` is set, and writes its
+    /// state to `dump` (if any) when a check fails. If `top_tasks` is set,
+    /// the messages are always checked, since we need to see them to
+    /// count the edges of each task.
+    pub fn new(force: bool, dump: Option<PathBuf>, top_tasks: Option<usize>) -> Self {
+        let enabled = ENABLED || force || top_tasks.is_some();
+        let forbidden_edges = if !enabled {
+            vec![]
         } else {
             match env::var("RUST_FORBID_DEP_GRAPH_EDGE") {
                 Ok(s) => {
-                    match EdgeFilter::new(&s) {
-                        Ok(f) => Some(f),
+                    match EdgeFilter::new_list(&s) {
+                        Ok(f) => f,
                         Err(err) => bug!("RUST_FORBID_DEP_GRAPH_EDGE invalid: {}", err),
                     }
                 }
-                Err(_) => None,
+                Err(_) => vec![],
             }
         };
+        let audited_in_ignore = match env::var("RUST_DEP_GRAPH_AUDIT_IGNORE") {
+            Ok(ref s) if enabled => DepNodeFilter::new_list(s),
+            _ => vec![],
+        };
 
         ShadowGraph {
             stack: RefCell::new(vec![]),
-            forbidden_edge,
+            history: RefCell::new(VecDeque::with_capacity(if enabled { HISTORY } else { 0 })),
+            forbidden_edges,
+            audited_in_ignore,
+            audit_is_bug: env::var_os("RUST_DEP_GRAPH_AUDIT_IGNORE_BUG").is_some(),
+            enabled,
+            backtrace: enabled && env::var_os("RUST_DEP_GRAPH_BACKTRACE").is_some(),
+            dump,
+            task_edges: RefCell::new(FxHashMap()),
+            top_tasks,
         }
     }
 
     #[inline]
     pub fn enabled(&self) -> bool {
-        ENABLED
+        self.enabled
     }
 
-    pub fn enqueue(&self, message: &DepMessage) {
-        if ENABLED {
+    /// Checks `message`, sent from `location` if known.
+    pub fn enqueue(&self, message: &DepMessage, location: Option<&'static DepLocation>) {
+        if self.enabled {
             if self.stack.try_borrow().is_err() {
                 // When we apply edge filters, that invokes the Debug trait on
                 // DefIds, which in turn reads from various bits of state and
@@ -78,6 +159,14 @@ impl ShadowGraph {
             }
 
             let mut stack = self.stack.borrow_mut();
+            {
+                let mut history = self.history.borrow_mut();
+                if history.len() == HISTORY {
+                    history.pop_front();
+                }
+                history.push_back((*message, location));
+            }
+
             match *message {
                 // It is ok to READ shared state outside of a
                 // task. That can't do any harm (at least, the only
@@ -85,67 +174,312 @@ impl ShadowGraph {
                 // query or task, which would be a problem
                 // anyway). What would be bad is WRITING to that
                 // state.
-                DepMessage::Read(_) => { }
-                DepMessage::Write(ref n) => self.check_edge(top(&stack), Some(Some(n))),
-                DepMessage::PushTask(ref n) => stack.push(Some(n.clone())),
-                DepMessage::PushIgnore => stack.push(None),
+                // We can still audit reads that create no edge, though, and
+                // the edges that reads in a task create.
+                DepMessage::Read(ref n, _) => {
+                    self.check_ignored("read", n, &stack, location);
+                    self.count_edge(&stack, false);
+                    self.check_read_edge(&stack, n, location)
+                }
+                DepMessage::Write(ref n) => {
+                    self.check_ignored("write", n, &stack, location);
+                    self.count_edge(&stack, true);
+                    self.check_edge(&stack, n, location)
+                }
+                DepMessage::PushTask(ref n) => {
+                    self.check_reentrant(&stack, n, location);
+                    stack.push((OpenTask::Task(n.clone()), location))
+                }
+                DepMessage::PushAnonTask => stack.push((OpenTask::Anon, location)),
+                DepMessage::PushIgnore => stack.push((OpenTask::Ignore, location)),
                 DepMessage::PopTask(ref n) => {
-                    match stack.pop() {
-                        Some(Some(m)) => {
-                            if *n != m {
-                                bug!("stack mismatch: found {:?} expected {:?}", m, n)
-                            }
-                        }
-                        Some(None) => bug!("stack mismatch: found Ignore expected {:?}", n),
-                        None => bug!("stack mismatch: found empty stack, expected {:?}", n),
+                    let balanced = match stack.last() {
+                        Some(&(OpenTask::Task(ref m), _)) => *m == *n,
+                        _ => false,
+                    };
+                    if !balanced {
+                        self.stack_mismatch(&stack, &format!("{:?}", n), location)
+                    }
+                    stack.pop();
+                }
+                DepMessage::PopAnonTask(_) => {
+                    match stack.last() {
+                        Some(&(OpenTask::Anon, _)) => (),
+                        _ => self.stack_mismatch(&stack, "anonymous task", location),
                     }
+                    stack.pop();
                 }
                 DepMessage::PopIgnore => {
-                    match stack.pop() {
-                        Some(Some(m)) => bug!("stack mismatch: found {:?} expected ignore", m),
-                        Some(None) => (),
-                        None => bug!("stack mismatch: found empty stack, expected ignore"),
+                    match stack.last() {
+                        Some(&(OpenTask::Ignore, _)) => (),
+                        _ => self.stack_mismatch(&stack, "ignore", location),
                     }
+                    stack.pop();
                 }
-                DepMessage::Query => (),
+                DepMessage::Invalidate(ref n) => {
+                    let open = stack.iter().position(|&(ref m, _)| match *m {
+                        OpenTask::Task(ref m) => m == n,
+                        OpenTask::Anon | OpenTask::Ignore => false,
+                    });
+                    if let Some(open) = open {
+                        self.violation(&stack, format_args!(
+                            "{:?} invalidated{} while its task is open{}",
+                            n, at(location), opened_at(stack[open].1)))
+                    }
+                }
+                // A query sees the whole graph, so it must not happen in a
+                // task, whose result could then depend on anything; see
+                // `DepGraph::query`.
+                DepMessage::Query | DepMessage::StreamEdges => {
+                    match stack.last() {
+                        Some(&(OpenTask::Task(ref n), opened)) => {
+                            self.violation(&stack, format_args!(
+                                "dep-graph queried{} in task {:?}{}, outside of an ignore",
+                                at(location), n, opened_at(opened)))
+                        }
+                        Some(&(OpenTask::Anon, opened)) => {
+                            self.violation(&stack, format_args!(
+                                "dep-graph queried{} in an anonymous task{}, outside of an \
+                                 ignore",
+                                at(location), opened_at(opened)))
+                        }
+                        Some(&(OpenTask::Ignore, _)) | None => (),
+                    }
+                }
+                DepMessage::Flush | DepMessage::Shutdown => (),
             }
         }
     }
 
-    fn check_edge(&self,
-                  source: Option<Option<&DepNode>>,
-                  target: Option<Option<&DepNode>>) {
-        assert!(ENABLED);
-        match (source, target) {
-            // cannot happen, one side is always Some(Some(_))
-            (None, None) => unreachable!(),
+    /// Prints the tasks that made the most reads and writes, under
+    /// `-Z dep-graph-task-edges`.
+    pub fn print_task_edges(&self) {
+        let top_tasks = match self.top_tasks {
+            Some(top_tasks) => top_tasks,
+            None => return,
+        };
+        let task_edges = self.task_edges.borrow();
+        let mut tasks: Vec<_> = task_edges.values().collect();
+        tasks.sort_by(|a, b| (b.1 + b.2).cmp(&(a.1 + a.2)));
+        println!("Dep-graph tasks with the most edges ({} of {}):",
+                 cmp::min(top_tasks, tasks.len()), tasks.len());
+        for &&(ref label, reads, writes) in tasks.iter().take(top_tasks) {
+            println!("  {:>10} reads {:>8} writes  {}", reads, writes, label);
+        }
+    }
+
+    /// Counts a read or write made by the task on top of `stack`, if any
+    /// and if we were asked to.
+    fn count_edge(&self, stack: &[Frame], write: bool) {
+        if self.top_tasks.is_none() {
+            return;
+        }
+        if let Some(&(OpenTask::Task(ref task), _)) = stack.last() {
+            let mut task_edges = self.task_edges.borrow_mut();
+            // Nodes can only be printed while we have a tcx, which will be
+            // gone by the time we print the counts, so label them now.
+            let counts = task_edges.entry(task.clone())
+                                   .or_insert_with(|| (format!("{:?}", task), 0, 0));
+            if write {
+                counts.2 += 1;
+            } else {
+                counts.1 += 1;
+            }
+        }
+    }
+
+    /// The open tasks, innermost last, if we keep track of them.
+    pub fn describe_stack(&self) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+        match self.stack.try_borrow() {
+            Ok(stack) => Some(describe_frames(&stack)),
+            Err(_) => None,
+        }
+    }
 
+    /// Reports a push of `node` while a task for it is already open, which
+    /// means there is a cycle (or a task boundary in the wrong place); the
+    /// depgraph thread would only record a self-edge. Cycles of queries
+    /// never get here: `cycle_check` reports them before the task of the
+    /// query is pushed.
+    fn check_reentrant(&self,
+                       stack: &[Frame],
+                       node: &DepNode,
+                       location: Option<&'static DepLocation>) {
+        let open = stack.iter().position(|&(ref m, _)| match *m {
+            OpenTask::Task(ref m) => m == node,
+            OpenTask::Anon | OpenTask::Ignore => false,
+        });
+        if let Some(open) = open {
+            self.violation(stack, format_args!(
+                "task {:?} pushed again{} while it is open{}\n\
+                 cycle (outermost first): {}\n\
+                 task stack (innermost last): {}",
+                node, at(location), opened_at(stack[open].1),
+                describe_frames(&stack[open..]), describe_frames(stack)))
+        }
+    }
+
+    /// Reports a pop of `expected` that does not match the top of
+    /// `stack`, along with the whole stack and the messages leading up
+    /// to it.
+    fn stack_mismatch(&self,
+                      stack: &[Frame],
+                      expected: &str,
+                      location: Option<&'static DepLocation>)
+                      -> ! {
+        let found = match stack.last() {
+            Some(&(OpenTask::Task(ref m), opened)) => format!("{:?}{}", m, opened_at(opened)),
+            Some(&(OpenTask::Anon, opened)) => format!("AnonTask{}", opened_at(opened)),
+            Some(&(OpenTask::Ignore, opened)) => format!("Ignore{}", opened_at(opened)),
+            None => "empty stack".to_string(),
+        };
+        self.violation(stack, format_args!(
+            "stack mismatch{}: found {} expected {}\n\
+             task stack (innermost last): {}\n\
+             {}",
+            at(location), found, expected, describe_frames(stack),
+            self.describe_history()))
+    }
+
+    /// The messages leading up to the current one, one per line.
+    fn describe_history(&self) -> String {
+        let history = self.history.borrow();
+        let messages: Vec<String> = history.iter().map(|&(ref message, location)| {
+            format!("    {:?}{}", message, at(location))
+        }).collect();
+        format!("last {} messages (most recent last):\n{}",
+                messages.len(), messages.join("\n"))
+    }
+
+    /// Reports the `access` of `node`, if it is to be audited and we are
+    /// in an ignore, so that it creates no edge.
+    fn check_ignored(&self,
+                     access: &str,
+                     node: &DepNode,
+                     stack: &[Frame],
+                     location: Option<&'static DepLocation>) {
+        if self.audited_in_ignore.is_empty() {
+            return;
+        }
+        if let Some(&(OpenTask::Ignore, opened)) = stack.last() {
+            if self.audited_in_ignore.iter().any(|f| f.test(node)) {
+                if self.audit_is_bug {
+                    self.violation(stack, format_args!(
+                        "{} of {:?}{} in an ignore{}",
+                        access, node, at(location), opened_at(opened)))
+                } else {
+                    eprintln!("warning: dep-graph: {} of {:?}{} in an ignore{}",
+                              access, node, at(location), opened_at(opened));
+                }
+            }
+        }
+    }
+
+    fn check_edge(&self,
+                  stack: &[Frame],
+                  target: &DepNode,
+                  location: Option<&'static DepLocation>) {
+        assert!(self.enabled);
+        match stack.last() {
             // nothing on top of the stack
-            (None, Some(n)) | (Some(n), None) => bug!("write of {:?} but no current task", n),
+            None => {
+                self.violation(stack, format_args!(
+                    "write of {:?}{} but no current task",
+                    target, at(location)))
+            }
+
+            // an anonymous task is only identified by what it reads
+            Some(&(OpenTask::Anon, opened)) => {
+                self.violation(stack, format_args!(
+                    "write of {:?}{} in an anonymous task{}",
+                    target, at(location), opened_at(opened)))
+            }
 
-            // this corresponds to an Ignore being top of the stack
-            (Some(None), _) | (_, Some(None)) => (),
+            Some(&(OpenTask::Ignore, _)) => (),
 
             // a task is on top of the stack
-            (Some(Some(source)), Some(Some(target))) => {
-                if let Some(ref forbidden_edge) = self.forbidden_edge {
-                    if forbidden_edge.test(source, target) {
-                        bug!("forbidden edge {:?} -> {:?} created", source, target)
-                    }
+            Some(&(OpenTask::Task(ref source), _)) => {
+                if self.forbidden_edges.iter().any(|f| f.test(source, target)) {
+                    self.violation(stack, format_args!(
+                        "forbidden edge {:?} -> {:?} created{}",
+                        source, target, at(location)))
                 }
             }
         }
     }
+
+    /// Checks the edge from `source` to the task on top of the stack, which
+    /// the read of `source` creates.
+    fn check_read_edge(&self,
+                       stack: &[Frame],
+                       source: &DepNode,
+                       location: Option<&'static DepLocation>) {
+        if let Some(&(OpenTask::Task(ref target), _)) = stack.last() {
+            if self.forbidden_edges.iter().any(|f| f.test(source, target)) {
+                self.violation(stack, format_args!(
+                    "forbidden edge {:?} -> {:?} created by a read{}",
+                    source, target, at(location)))
+            }
+        }
+    }
+
+    /// Reports a misuse of the dep-graph, which panics. If asked to, we
+    /// first dump our state (for failures we cannot reproduce locally), and
+    /// print a backtrace, which ends right here, in the code that sent the
+    /// offending message: the shadow graph runs on the sending thread.
+    fn violation(&self, stack: &[Frame], message: fmt::Arguments) -> ! {
+        if let Some(ref path) = self.dump {
+            let dump = format!("{}\n\ntask stack (innermost last):\n{}\n\n{}\n",
+                               message,
+                               stack.iter()
+                                    .map(|frame| format!("    {}", describe_frame(frame)))
+                                    .collect::<Vec<_>>()
+                                    .join("\n"),
+                               self.describe_history());
+            match File::create(path).and_then(|mut file| file.write_all(dump.as_bytes())) {
+                Ok(()) => eprintln!("dep-graph: shadow graph state written to `{}`",
+                                    path.display()),
+                Err(err) => eprintln!("dep-graph: could not write `{}`: {}",
+                                      path.display(), err),
+            }
+        }
+        if self.backtrace {
+            eprintln!("dep-graph: backtrace of the offending message:\n{:?}", Backtrace::new());
+        }
+        bug!("{}", message)
+    }
+}
+
+// Describes where a message was sent from, if we know, for errors.
+fn at(location: Option<&'static DepLocation>) -> String {
+    match location {
+        Some(location) => format!(" at {}", location),
+        None => String::new(),
+    }
+}
+
+// Describes where a task or ignore was opened, if we know, for errors.
+fn opened_at(location: Option<&'static DepLocation>) -> String {
+    match location {
+        Some(location) => format!(" (opened at {})", location),
+        None => String::new(),
+    }
+}
+
+// Describes the open tasks, innermost last, for errors.
+fn describe_frames(stack: &[Frame]) -> String {
+    let frames: Vec<String> = stack.iter().map(describe_frame).collect();
+    format!("[{}]", frames.join(", "))
 }
 
-// Do a little juggling: we get back a reference to an option at the
-// top of the stack, convert it to an optional reference.
-fn top<'s>(stack: &'s Vec<Option<DepNode>>) -> Option<Option<&'s DepNode>> {
-    stack.last()
-        .map(|n: &'s Option<DepNode>| -> Option<&'s DepNode> {
-            // (*)
-            // (*) type annotation just there to clarify what would
-            // otherwise be some *really* obscure code
-            n.as_ref()
-        })
+fn describe_frame(&(ref node, location): &Frame) -> String {
+    let frame = match *node {
+        OpenTask::Task(ref node) => format!("{:?}", node),
+        OpenTask::Anon => "AnonTask".to_string(),
+        OpenTask::Ignore => "Ignore".to_string(),
+    };
+    format!("{}{}", frame, opened_at(location))
 }
@@ -0,0 +1,305 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Building the dep-graph from several producer threads at once, for when
+//! the front end type-checks items in parallel.
+//!
+//! A single depgraph thread fed through a single channel (see the `thread`
+//! module) would become the bottleneck then, and sharing one
+//! `DepGraphEdges` would need a global lock. Instead, each producer thread
+//! gets its own `ShardProducer`, which batches its messages and sends them
+//! to one of `-Z dep-graph-shards=N` shard threads. A shard keeps a
+//! separate `DepGraphEdges` for every producer assigned to it: the stack
+//! of open tasks belongs to the thread running them, so messages from
+//! different producers must never be applied to the same edge set.
+//!
+//! The edge sets are only combined when the graph is queried (and hence
+//! when it is saved), by taking the union of their nodes and edges. A
+//! producer has to `flush` its messages (or be dropped) before the query
+//! for them to be included; in practice, queries happen once the parallel
+//! section has finished and all producers are gone.
+
+use rustc_data_structures::fx::FxHashSet;
+use std::cell::{Cell, RefCell};
+use std::mem;
+use std::sync::mpsc::{self, Sender, Receiver};
+use std::thread::{self, JoinHandle};
+
+use super::DepNode;
+use super::edges::{DepEdgeKind, DepGraphEdges};
+use super::thread::DepMessage;
+
+enum ShardMessage {
+    // messages from the producer with the given index within the shard
+    Batch(usize, Vec<DepMessage>),
+    Query(Sender<ShardGraph>),
+    Shutdown,
+}
+
+/// The nodes and edges recorded by one shard.
+type ShardGraph = (Vec<DepNode>, Vec<(DepNode, DepNode, DepEdgeKind)>);
+
+pub struct DepGraphShards {
+    shards: Vec<Sender<ShardMessage>>,
+    handles: Vec<JoinHandle<()>>,
+    // producers handed out so far; producer `i` goes to shard `i % N`
+    producers: Cell<usize>,
+    buffer_size: usize,
+}
+
+impl DepGraphShards {
+    pub fn new(shards: usize, buffer_size: usize) -> DepGraphShards {
+        assert!(shards > 0);
+        let mut senders = Vec::with_capacity(shards);
+        let mut handles = Vec::with_capacity(shards);
+        for i in 0..shards {
+            let (tx, rx) = mpsc::channel();
+            let thread = thread::Builder::new().name(format!("dep-graph-shard-{}", i));
+            senders.push(tx);
+            handles.push(thread.spawn(move || shard_main(rx)).unwrap());
+        }
+        DepGraphShards {
+            shards: senders,
+            handles,
+            producers: Cell::new(0),
+            buffer_size,
+        }
+    }
+
+    /// Creates the producer for a new thread. Each producer thread needs
+    /// its own, since tasks opened by one thread are closed by the same
+    /// thread.
+    pub fn producer(&self) -> ShardProducer {
+        let shards = self.shards.len();
+        let index = self.producers.get();
+        self.producers.set(index + 1);
+        ShardProducer {
+            index: index / shards,
+            messages: RefCell::new(Vec::with_capacity(self.buffer_size)),
+            buffer_size: self.buffer_size,
+            out: self.shards[index % shards].clone(),
+        }
+    }
+
+    /// The union of the graphs of all shards, as of the last flush of
+    /// each producer.
+    pub fn graph(&self) -> ShardGraph {
+        let results: Vec<Receiver<ShardGraph>> = self.shards.iter().map(|shard| {
+            let (tx, rx) = mpsc::channel();
+            if shard.send(ShardMessage::Query(tx)).is_err() {
+                bug!("dep-graph shard thread panicked");
+            }
+            rx
+        }).collect();
+
+        let mut nodes = FxHashSet();
+        let mut edges = FxHashSet();
+        for result in results {
+            let (shard_nodes, shard_edges) = result.recv().unwrap_or_else(|_| {
+                bug!("dep-graph shard thread panicked")
+            });
+            nodes.extend(shard_nodes);
+            edges.extend(shard_edges);
+        }
+        (nodes.into_iter().collect(), edges.into_iter().collect())
+    }
+}
+
+impl Drop for DepGraphShards {
+    fn drop(&mut self) {
+        for shard in &self.shards {
+            // a shard that hung up has panicked, which we report below
+            let _ = shard.send(ShardMessage::Shutdown);
+        }
+        for handle in self.handles.drain(..) {
+            if handle.join().is_err() && !thread::panicking() {
+                bug!("dep-graph shard thread panicked");
+            }
+        }
+    }
+}
+
+/// The sending end of a shard, owned by one producer thread.
+pub struct ShardProducer {
+    // which of the shard's edge sets our messages go to
+    index: usize,
+    messages: RefCell<Vec<DepMessage>>,
+    buffer_size: usize,
+    out: Sender<ShardMessage>,
+}
+
+impl ShardProducer {
+    /// Records that the current task of this thread reads `v`.
+    pub fn read(&self, v: DepNode) {
+        self.enqueue(DepMessage::Read(v, DepEdgeKind::of_read(&v)));
+    }
+
+    /// Records that the current task of this thread writes `v`.
+    pub fn write(&self, v: DepNode) {
+        self.enqueue(DepMessage::Write(v));
+    }
+
+    /// Runs `op` as the task `key`, like `DepGraph::with_task`.
+    pub fn with_task<OP, R>(&self, key: DepNode, op: OP) -> R
+        where OP: FnOnce() -> R
+    {
+        let _task = ShardTask::new(self, DepMessage::PushTask(key), DepMessage::PopTask(key));
+        op()
+    }
+
+    pub fn with_ignore<OP, R>(&self, op: OP) -> R
+        where OP: FnOnce() -> R
+    {
+        let _task = ShardTask::new(self, DepMessage::PushIgnore, DepMessage::PopIgnore);
+        op()
+    }
+
+    fn enqueue(&self, message: DepMessage) {
+        let len = {
+            let mut messages = self.messages.borrow_mut();
+            messages.push(message);
+            messages.len()
+        };
+        if len >= self.buffer_size {
+            self.flush();
+        }
+    }
+
+    /// Sends the messages enqueued so far to the shard.
+    pub fn flush(&self) {
+        let messages = mem::replace(&mut *self.messages.borrow_mut(),
+                                    Vec::with_capacity(self.buffer_size));
+        if messages.is_empty() {
+            return;
+        }
+        if self.out.send(ShardMessage::Batch(self.index, messages)).is_err() {
+            bug!("dep-graph shard thread panicked");
+        }
+    }
+}
+
+impl Drop for ShardProducer {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            self.flush();
+        }
+    }
+}
+
+/// Pops a task or ignore of a `ShardProducer` when dropped, so that the
+/// stack of the shard stays balanced even if the task returns early or
+/// panics.
+struct ShardTask<'a> {
+    producer: &'a ShardProducer,
+    pop: Option<DepMessage>,
+}
+
+impl<'a> ShardTask<'a> {
+    fn new(producer: &'a ShardProducer, push: DepMessage, pop: DepMessage) -> ShardTask<'a> {
+        producer.enqueue(push);
+        ShardTask { producer: producer, pop: Some(pop) }
+    }
+}
+
+impl<'a> Drop for ShardTask<'a> {
+    fn drop(&mut self) {
+        self.producer.enqueue(self.pop.take().unwrap());
+    }
+}
+
+fn shard_main(messages: Receiver<ShardMessage>) {
+    let mut edges: Vec<DepGraphEdges> = vec![];
+    for message in messages {
+        match message {
+            ShardMessage::Batch(index, batch) => {
+                while edges.len() <= index {
+                    edges.push(DepGraphEdges::new());
+                }
+                let edges = &mut edges[index];
+                for msg in batch {
+                    // queries, flushes and shutdowns go through
+                    // `DepGraphShards` rather than a producer
+                    if !edges.apply(msg) {
+                        bug!("unexpected `{:?}` from dep-graph shard producer", msg);
+                    }
+                }
+            }
+            ShardMessage::Query(result) => {
+                let mut nodes = vec![];
+                let mut all_edges = vec![];
+                for edges in &edges {
+                    nodes.extend_from_slice(edges.nodes());
+                    all_edges.extend(edges.edges());
+                }
+                // the querier is waiting for us unless it panicked
+                let _ = result.send((nodes, all_edges));
+            }
+            ShardMessage::Shutdown => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dep_graph::{DepGraph, DepGraphThreadConfig, DepKind, DepNode};
+    use ich::Fingerprint;
+    use std::thread;
+
+    fn node(kind: DepKind, n: u64) -> DepNode {
+        DepNode { kind, hash: Fingerprint::from_smaller_hash(n) }
+    }
+
+    fn sorted(mut nodes: Vec<&DepNode>) -> Vec<DepNode> {
+        nodes.sort_by_key(|node| node.hash);
+        nodes.into_iter().cloned().collect()
+    }
+
+    #[test]
+    fn query_merges_shards() {
+        let config = DepGraphThreadConfig {
+            shards: 2,
+            .. DepGraphThreadConfig::default()
+        };
+        let graph = DepGraph::with_config(true, config);
+        {
+            let _task = graph.in_task(node(DepKind::TypeckTables, 0));
+            graph.read(node(DepKind::Hir, 0));
+        }
+
+        // One producer for each shard, each running a task that reads a
+        // node of its own and the one read above.
+        let producers = vec![graph.shard_producer().unwrap(), graph.shard_producer().unwrap()];
+        let threads: Vec<_> = producers.into_iter().zip(1..).map(|(producer, n)| {
+            thread::spawn(move || {
+                producer.with_task(node(DepKind::TypeckTables, n), || {
+                    producer.read(node(DepKind::Hir, n));
+                    producer.read(node(DepKind::Hir, 0));
+                });
+            })
+        }).collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let query = graph.query();
+        assert_eq!(query.nodes().len(), 6);
+        assert_eq!(query.edges().len(), 5);
+        for n in 0..3 {
+            let reads = sorted(query.immediate_predecessors(&node(DepKind::TypeckTables, n)));
+            let mut expected = vec![node(DepKind::Hir, 0)];
+            if n > 0 {
+                expected.push(node(DepKind::Hir, n));
+            }
+            assert_eq!(reads, expected);
+        }
+        let readers = sorted(query.immediate_successors(&node(DepKind::Hir, 0)));
+        assert_eq!(readers, (0..3).map(|n| node(DepKind::TypeckTables, n)).collect::<Vec<_>>());
+    }
+}
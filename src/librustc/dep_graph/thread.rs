@@ -10,169 +10,1431 @@
 
 //! Manages the communication between the compiler's main thread and
 //! the thread that constructs the dependency graph. The basic idea is
-//! to use double buffering to lower the cost of producing a message.
-//! In the compiler thread, we accumulate messages in a vector until
-//! the vector is full, or until we want to query the graph, and then
-//! we send that vector over to the depgraph thread. At the same time,
-//! we receive an empty vector from the depgraph thread that we can use
-//! to accumulate more messages. This way we only ever have two vectors
-//! allocated (and both have a fairly large capacity).
+//! to use a small pool of buffers to lower the cost of producing a
+//! message. In the compiler thread, we accumulate messages in a vector
+//! until the vector is full, or until we want to query the graph, and
+//! then we send that vector over to the depgraph thread. At the same
+//! time, we receive an empty vector from the depgraph thread that we can
+//! use to accumulate more messages. The depgraph thread sends each vector
+//! back once it has processed it, so the vectors are recycled and we only
+//! ever have a fixed number of them allocated (all with a fairly large
+//! capacity): the one being filled, and `-Z dep-message-buffers=N` more
+//! (by default 4) that are either waiting to be processed or ready to be
+//! filled next. This way a burst of messages only blocks the compiler
+//! thread once all `N` buffers are waiting for the depgraph thread.
+//!
+//! Buffers are only ever obtained from the depgraph thread, so this also
+//! provides backpressure: if the depgraph thread falls behind, the compiler
+//! thread blocks when it next swaps, rather than queueing up more and more
+//! messages.
+//!
+//! To keep the buffers small, messages do not carry the `DepNode`s they
+//! concern but a `DepNodeIndex`. The compiler thread numbers each node the
+//! first time it sends it, and passes the new nodes along with the buffer
+//! (or ahead of the message, for the ring buffer). The depgraph thread then
+//! adds them to the graph in the same order, so that the numbers agree.
+//!
+//! For debugging, `-Z dep-graph-synchronous` does away with the thread
+//! altogether: each message is then applied to the graph as soon as it is
+//! enqueued, so a failure in `DepGraphEdges` comes with a backtrace into
+//! the code that sent the offending message.
+//!
+//! `-Z profile-dep-tracking` timestamps each message as it is enqueued.
+//! When it stops, the thread that built the graph then prints how long
+//! messages waited to be processed, and how long the graph construction
+//! took for each kind of node.
 
+use errors::FatalError;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_data_structures::spsc_ring::{self, Consumer, Producer};
+use rustc_data_structures::stable_hasher::StableHasher;
 use rustc_data_structures::veccell::VecCell;
-use std::sync::mpsc::{self, Sender, Receiver};
-use std::thread;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::cmp;
+use std::hash::Hash;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender, SyncSender, Receiver};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
-use super::DepGraphQuery;
-use super::DepNode;
-use super::edges::DepGraphEdges;
+use super::{DepGraphQuery, DepKindHistogram};
+use super::{DepKind, DepNode};
+use super::debug::DepLocation;
+use super::edges::{DepEdgeKind, DepGraphEdges, DepNodeIndex, DepTaskCycle};
 use super::shadow::ShadowGraph;
+use super::sharded::{DepGraphShards, ShardProducer};
 
-#[derive(Debug)]
-pub enum DepMessage {
-    Read(DepNode),
-    Write(DepNode),
-    PushTask(DepNode),
-    PopTask(DepNode),
+/// A message about the graph, concerning the node `N` if any. Messages
+/// are enqueued with full `DepNode`s but are sent to the depgraph thread
+/// with `DepNodeIndex`es, which are much smaller.
+#[derive(Clone, Copy, Debug)]
+pub enum DepMessage<N = DepNode> {
+    /// A read of `N`, which adds an edge of the given kind to the current
+    /// task.
+    Read(N, DepEdgeKind),
+    Write(N),
+    PushTask(N),
+    PopTask(N),
+    /// Starts a task that has no node of its own yet; see
+    /// `DepGraph::with_anon_task`.
+    PushAnonTask,
+    /// Ends an anonymous task, which becomes the node `N`.
+    PopAnonTask(N),
     PushIgnore,
     PopIgnore,
+    /// Drops what the node `N` was recorded to depend on, so that its
+    /// task can be run again from scratch; see `DepGraph::invalidate`.
+    Invalidate(N),
     Query,
+    /// Asks for the edges of the graph a chunk at a time; see
+    /// `DepGraphThreadData::for_each_edge`.
+    StreamEdges,
+    Flush,
+    Shutdown,
+}
+
+// How many of the most recent reads of the current task are remembered,
+// so that reading one of them again does not send another message.
+const RECENT_READS: usize = 4;
+
+// The names of the `DepMessage` variants, by `DepMessage::index`.
+const MESSAGE_NAMES: [&'static str; 13] = [
+    "Read", "Write", "PushTask", "PopTask", "PushAnonTask", "PopAnonTask", "PushIgnore",
+    "PopIgnore", "Invalidate", "Query", "StreamEdges", "Flush", "Shutdown",
+];
+
+// How many edges the depgraph thread sends at once for `for_each_edge`,
+// and how many such chunks may wait for the compiler thread.
+const EDGE_CHUNK_SIZE: usize = 4096;
+const STREAMED_CHUNKS: usize = 2;
+
+/// Some edges of the graph, as streamed by `for_each_edge`. An empty chunk
+/// marks the end.
+type EdgeChunk = Vec<(DepNode, DepNode, DepEdgeKind)>;
+
+/// A message as it is sent to the depgraph thread.
+type QueuedMessage = DepMessage<DepNodeIndex>;
+
+impl<N> DepMessage<N> {
+    fn map<M, F>(self, f: F) -> DepMessage<M>
+        where F: FnOnce(N) -> M
+    {
+        match self {
+            DepMessage::Read(node, kind) => DepMessage::Read(f(node), kind),
+            DepMessage::Write(node) => DepMessage::Write(f(node)),
+            DepMessage::PushTask(node) => DepMessage::PushTask(f(node)),
+            DepMessage::PopTask(node) => DepMessage::PopTask(f(node)),
+            DepMessage::PushAnonTask => DepMessage::PushAnonTask,
+            DepMessage::PopAnonTask(node) => DepMessage::PopAnonTask(f(node)),
+            DepMessage::PushIgnore => DepMessage::PushIgnore,
+            DepMessage::PopIgnore => DepMessage::PopIgnore,
+            DepMessage::Invalidate(node) => DepMessage::Invalidate(f(node)),
+            DepMessage::Query => DepMessage::Query,
+            DepMessage::StreamEdges => DepMessage::StreamEdges,
+            DepMessage::Flush => DepMessage::Flush,
+            DepMessage::Shutdown => DepMessage::Shutdown,
+        }
+    }
+
+    fn node(self) -> Option<N> {
+        match self {
+            DepMessage::Read(node, _) |
+            DepMessage::Write(node) |
+            DepMessage::PushTask(node) |
+            DepMessage::PopTask(node) |
+            DepMessage::PopAnonTask(node) |
+            DepMessage::Invalidate(node) => Some(node),
+            DepMessage::PushAnonTask |
+            DepMessage::PushIgnore |
+            DepMessage::PopIgnore |
+            DepMessage::Query |
+            DepMessage::StreamEdges |
+            DepMessage::Flush |
+            DepMessage::Shutdown => None,
+        }
+    }
+
+    fn index(&self) -> usize {
+        match *self {
+            DepMessage::Read(..) => 0,
+            DepMessage::Write(_) => 1,
+            DepMessage::PushTask(_) => 2,
+            DepMessage::PopTask(_) => 3,
+            DepMessage::PushAnonTask => 4,
+            DepMessage::PopAnonTask(_) => 5,
+            DepMessage::PushIgnore => 6,
+            DepMessage::PopIgnore => 7,
+            DepMessage::Invalidate(_) => 8,
+            DepMessage::Query => 9,
+            DepMessage::StreamEdges => 10,
+            DepMessage::Flush => 11,
+            DepMessage::Shutdown => 12,
+        }
+    }
 }
 
 pub struct DepGraphThreadData {
-    enabled: bool,
+    // cleared if the depgraph thread fails, so that we stop tracking
+    enabled: Cell<bool>,
 
     // The "shadow graph" is a debugging aid. We give it each message
     // in real time as it arrives and it checks for various errors
     // (for example, a read/write when there is no current task; it
     // can also apply user-defined filters; see `shadow` module for
-    // details). This only occurs if debug-assertions are enabled.
+    // details). This only occurs if debug-assertions are enabled, or
+    // with `-Z dep-graph-shadow`.
     //
     // Note that in some cases the same errors will occur when the
     // data is processed off the main thread, but that's annoying
     // because it lacks precision about the source of the error.
     shadow_graph: ShadowGraph,
 
-    // current buffer, where we accumulate messages
-    messages: VecCell<DepMessage>,
+    // how messages get to the depgraph thread
+    transport: Transport,
+
+    // number of messages after which the buffer is swapped
+    buffer_size: usize,
 
-    // where to receive new buffer when full
-    swap_in: Receiver<Vec<DepMessage>>,
+    // whether to timestamp messages for `-Z profile-dep-tracking`
+    profile: bool,
 
-    // where to send buffer when full
-    swap_out: Sender<Vec<DepMessage>>,
+    // the index of each node sent to the depgraph thread so far
+    node_indices: RefCell<FxHashMap<DepNode, DepNodeIndex>>,
 
-    // where to receive query results
+    // the number of tasks and ignores open, outside of which reads add
+    // no edges and need not be sent
+    open_tasks: Cell<usize>,
+
+    // the anonymous tasks open, innermost last, each with the value of
+    // `open_tasks` inside of it and the nodes it depends on so far
+    anon_tasks: RefCell<Vec<(usize, Vec<DepNode>)>>,
+
+    // the last few nodes read by the current task, in no particular
+    // order; forgotten whenever a task or ignore starts or ends
+    recent_reads: [Cell<Option<DepNode>>; RECENT_READS],
+
+    // where to remember the next read in `recent_reads`
+    next_recent_read: Cell<usize>,
+
+    // message traffic, if `-Z dep-graph-stats` was given
+    stats: Option<DepGraphStats>,
+
+    // where to receive query results, which arrive in the order the
+    // queries were made
     query_in: Receiver<DepGraphQuery>,
+
+    // where to receive the edges streamed for `for_each_edge`
+    stream_in: Receiver<EdgeChunk>,
+
+    // set while `for_each_edge` receives the edges, when sending the
+    // depgraph thread more messages could deadlock
+    streaming_edges: Cell<bool>,
+
+    // number of queries made, and of query results received so far
+    queries_sent: Cell<usize>,
+    queries_received: Cell<usize>,
+
+    // results received for a `DepGraphQueryHandle` that isn't waited for
+    // yet, and the handles that were dropped without asking for a result
+    query_results: RefCell<FxHashMap<usize, DepGraphQuery>>,
+    abandoned_queries: RefCell<FxHashSet<usize>>,
+
+    // where to receive the acknowledgement of a flush
+    flush_in: Receiver<()>,
+
+    // the depgraph thread, joined to find out why it panicked when
+    // it stops responding
+    handle: RefCell<Option<JoinHandle<()>>>,
+
+    // the shards building the graph for other threads, whose edges are
+    // merged into each query result (see the `sharded` module)
+    shards: Option<DepGraphShards>,
+}
+
+/// A query made with `DepGraphThreadData::query_async`, whose result can be
+/// retrieved once the depgraph thread has answered it.
+pub struct DepGraphQueryHandle<'graph> {
+    data: &'graph DepGraphThreadData,
+    // `None` once the result has been taken
+    ticket: Option<usize>,
+}
+
+impl<'graph> DepGraphQueryHandle<'graph> {
+    /// Returns the result if the query has been answered already.
+    pub fn try_get(&mut self) -> Option<DepGraphQuery> {
+        let ticket = self.ticket.expect("query result already taken");
+        while self.data.receive_query_result(false) {}
+        let result = self.data.query_results.borrow_mut().remove(&ticket);
+        if result.is_some() {
+            self.ticket = None;
+        }
+        result
+    }
+
+    /// Waits for the query to be answered.
+    pub fn wait(mut self) -> DepGraphQuery {
+        let ticket = self.ticket.take().expect("query result already taken");
+        loop {
+            if let Some(result) = self.data.query_results.borrow_mut().remove(&ticket) {
+                return result;
+            }
+            assert!(self.data.receive_query_result(true));
+        }
+    }
+}
+
+impl<'graph> Drop for DepGraphQueryHandle<'graph> {
+    fn drop(&mut self) {
+        if let Some(ticket) = self.ticket {
+            // Throw away the result, now or once it arrives.
+            if self.data.query_results.borrow_mut().remove(&ticket).is_none() {
+                self.data.abandoned_queries.borrow_mut().insert(ticket);
+            }
+        }
+    }
+}
+
+enum Transport {
+    Buffers {
+        // current buffer, where we accumulate messages
+        messages: VecCell<QueuedMessage>,
+
+        // the nodes interned since the last swap, which the depgraph
+        // thread adds to the graph before it processes `messages`
+        new_nodes: VecCell<DepNode>,
+
+        // when each of `messages` was enqueued, under
+        // `-Z profile-dep-tracking` (and empty otherwise)
+        enqueued: VecCell<Instant>,
+
+        // where to receive new buffer when full
+        swap_in: Receiver<Batch>,
+
+        // where to send buffer when full
+        swap_out: Sender<Batch>,
+    },
+
+    // each message is sent on its own through a ring buffer
+    Ring {
+        producer: Producer<QueuedMessage>,
+
+        // where to send each node as it is interned, before any message
+        // with its index
+        new_nodes: Sender<DepNode>,
+    },
+
+    // no depgraph thread, messages are processed as they are enqueued
+    Synchronous(RefCell<DepGraphWorker>),
+}
+
+/// A buffer of messages exchanged with the depgraph thread.
+struct Batch {
+    messages: Vec<QueuedMessage>,
+    new_nodes: Vec<DepNode>,
+    // when each message was enqueued, if we are profiling
+    enqueued: Vec<Instant>,
+}
+
+/// Builds the graph from the messages, in the depgraph thread (or in the
+/// compiler thread, under `-Z dep-graph-synchronous`).
+struct DepGraphWorker {
+    edges: DepGraphEdges,
+    query_out: Sender<DepGraphQuery>,
+    // where to stream the edges for `for_each_edge`; `None` if we run in
+    // the compiler thread, which then reads them itself
+    stream_out: Option<SyncSender<EdgeChunk>>,
+    flush_out: Sender<()>,
+    // under `-Z profile-dep-tracking`; taken when printed
+    profile: Option<DepTrackingProfile>,
+    // whether to print the edge statistics of `-Z dep-graph-stats`
+    record_stats: bool,
+    // the message being processed, for the report if we panic
+    last_message: Option<QueuedMessage>,
+}
+
+impl DepGraphWorker {
+    /// Applies `msg`, which was enqueued at `enqueued` if known, to the
+    /// graph. Returns false once we're asked to stop.
+    fn process(&mut self, msg: QueuedMessage, enqueued: Option<Instant>) -> bool {
+        self.last_message = Some(msg);
+        let start = self.profile.as_ref().map(|_| Instant::now());
+        let result = self.apply(msg);
+        if let (Some(profile), Some(start)) = (self.profile.as_mut(), start) {
+            let edges = &self.edges;
+            profile.record(msg.map(|index| edges.id(index)), enqueued, start);
+        }
+        result
+    }
+
+    fn apply(&mut self, msg: QueuedMessage) -> bool {
+        if self.edges.apply_indexed(msg) {
+            return true;
+        }
+        // the compiler thread only stops listening once it is done with
+        // us (or has panicked), so we can stop as well if it has
+        match msg {
+            DepMessage::Query => {
+                self.edges.check_query();
+                self.query_out.send(self.edges.query()).is_ok()
+            }
+            DepMessage::StreamEdges => {
+                self.edges.check_query();
+                self.edges.finalize();
+                self.stream_edges()
+            }
+            DepMessage::Flush => self.flush_out.send(()).is_ok(),
+            DepMessage::Shutdown => false,
+            _ => bug!("`{:?}` not applied to the dep-graph", msg),
+        }
+    }
+
+    /// Sends the edges to the compiler thread a chunk at a time. Only a few
+    /// chunks fit in the channel, so we wait for the compiler thread to
+    /// deal with them rather than copy the whole graph at once.
+    fn stream_edges(&self) -> bool {
+        let stream_out = match self.stream_out {
+            Some(ref stream_out) => stream_out,
+            None => return true,
+        };
+        let mut start = 0;
+        loop {
+            let chunk = self.edges.edge_chunk(start, EDGE_CHUNK_SIZE);
+            let done = chunk.is_empty();
+            start += chunk.len();
+            if stream_out.send(chunk).is_err() {
+                return false;
+            }
+            if done {
+                return true;
+            }
+        }
+    }
+
+    /// Adds the nodes interned for the ring buffer transport to the graph,
+    /// up to the one that `msg` concerns. Returns false if the compiler
+    /// thread hung up before sending them all.
+    fn receive_nodes(&mut self, msg: QueuedMessage, new_nodes: &Receiver<DepNode>) -> bool {
+        if let Some(index) = msg.node() {
+            while self.edges.node_count() <= index.index() {
+                match new_nodes.recv() {
+                    Ok(node) => self.edges.add_node(node),
+                    Err(_) => return false,
+                };
+            }
+        }
+        true
+    }
+
+    fn print_profile(&mut self) {
+        if let Some(profile) = self.profile.take() {
+            profile.print();
+        }
+    }
+
+    /// Prints how many edges were dropped as duplicates. The graph is
+    /// only complete once we stop, so this is not part of `print_stats`.
+    fn print_edge_stats(&self) {
+        if !self.record_stats {
+            return;
+        }
+        let edges = self.edges.edge_count() as u64;
+        let duplicates = self.edges.duplicate_edge_count();
+        println!("Dep-graph edge statistics:");
+        println!("  edges: {}", edges);
+        println!("  duplicate edges dropped: {} ({:.1}% of the edges added)",
+                 duplicates,
+                 100.0 * duplicates as f64 / cmp::max(edges + duplicates, 1) as f64);
+    }
+}
+
+// Latency bucket `i` counts the messages processed between 2^i and
+// 2^(i+1) microseconds after they were enqueued (the first bucket also
+// counts quicker ones, the last one slower ones).
+const LATENCY_BUCKETS: usize = 32;
+
+/// What `-Z profile-dep-tracking` measures in the depgraph thread.
+#[derive(Default)]
+struct DepTrackingProfile {
+    latencies: [u64; LATENCY_BUCKETS],
+    total_latency: Duration,
+    // messages and the time spent on them, by the kind of their node
+    costs: FxHashMap<DepKind, (u64, Duration)>,
+    // the same for the messages that do not concern a node
+    other_costs: (u64, Duration),
+}
+
+impl DepTrackingProfile {
+    fn record(&mut self, msg: DepMessage, enqueued: Option<Instant>, start: Instant) {
+        if let Some(enqueued) = enqueued {
+            let latency = start.duration_since(enqueued);
+            self.latencies[latency_bucket(latency)] += 1;
+            self.total_latency += latency;
+        }
+
+        let cost = match msg {
+            DepMessage::Read(node, _) |
+            DepMessage::Write(node) |
+            DepMessage::PushTask(node) |
+            DepMessage::PopTask(node) |
+            DepMessage::PopAnonTask(node) |
+            DepMessage::Invalidate(node) => {
+                self.costs.entry(node.kind).or_insert((0, Duration::new(0, 0)))
+            }
+            DepMessage::PushAnonTask |
+            DepMessage::PushIgnore |
+            DepMessage::PopIgnore |
+            DepMessage::Query |
+            DepMessage::StreamEdges |
+            DepMessage::Flush |
+            DepMessage::Shutdown => &mut self.other_costs,
+        };
+        cost.0 += 1;
+        cost.1 += start.elapsed();
+    }
+
+    fn print(&self) {
+        println!("Dep-tracking profile:");
+        let timed: u64 = self.latencies.iter().sum();
+        if timed > 0 {
+            println!("  latency from enqueueing to processing ({} messages, mean {}us):",
+                     timed, micros(self.total_latency) / timed);
+            let last = self.latencies.iter().rposition(|&count| count > 0).unwrap();
+            for (i, count) in self.latencies[..last + 1].iter().enumerate() {
+                let range = if i == 0 {
+                    format!("<{}", 1u64 << (i + 1))
+                } else if i == LATENCY_BUCKETS - 1 {
+                    format!(">={}", 1u64 << i)
+                } else {
+                    format!("{}-{}", 1u64 << i, 1u64 << (i + 1))
+                };
+                println!("    {:>10}us: {}", range, count);
+            }
+        } else {
+            println!("  latency from enqueueing to processing: not measured");
+        }
+
+        println!("  graph construction time by node kind:");
+        let mut costs: Vec<_> = self.costs.iter().collect();
+        costs.sort_by(|a, b| (b.1).1.cmp(&(a.1).1));
+        for (kind, &(count, time)) in costs {
+            println!("    {:<32} {:>10} messages {:>10}us",
+                     format!("{:?}", kind), count, micros(time));
+        }
+        println!("    {:<32} {:>10} messages {:>10}us",
+                 "(no node)", self.other_costs.0, micros(self.other_costs.1));
+    }
 }
 
-const INITIAL_CAPACITY: usize = 2048;
+fn latency_bucket(latency: Duration) -> usize {
+    let micros = micros(latency);
+    if micros < 2 {
+        0
+    } else {
+        cmp::min(63 - micros.leading_zeros() as usize, LATENCY_BUCKETS - 1)
+    }
+}
+
+fn micros(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000 + (duration.subsec_nanos() / 1_000) as u64
+}
+
+/// The other end of a `Transport`, in the depgraph thread.
+enum Incoming {
+    Buffers(Receiver<Batch>, Sender<Batch>),
+    Ring(Consumer<QueuedMessage>, Receiver<DepNode>),
+}
+
+/// The payload of a panic in the depgraph thread.
+struct DepThreadPanic {
+    message: String,
+    last_message: Option<DepMessage>,
+}
+
+/// How the compiler thread talks to the depgraph thread.
+#[derive(Clone, Debug)]
+pub struct DepGraphThreadConfig {
+    /// The number of messages sent to the depgraph thread at once
+    /// (`-Z dep-message-buffer-size`).
+    pub buffer_size: usize,
+    /// The number of full buffers that may be waiting for the depgraph
+    /// thread (`-Z dep-message-buffers`).
+    pub buffers: usize,
+    /// Whether to keep statistics of the messages (`-Z dep-graph-stats`).
+    pub record_stats: bool,
+    /// How the messages are sent (`-Z dep-graph-transport`).
+    pub transport: DepGraphTransport,
+    /// Whether to build the graph on the compiler thread instead
+    /// (`-Z dep-graph-synchronous`); `transport` is then ignored.
+    pub synchronous: bool,
+    /// The number of shards building the graph for producer threads other
+    /// than the compiler thread (`-Z dep-graph-shards`), if any.
+    pub shards: usize,
+    /// Whether to measure the latency of messages and the time spent
+    /// building the graph (`-Z profile-dep-tracking`).
+    pub profile: bool,
+    /// Whether to check the messages with the shadow graph even if the
+    /// compiler was built without debug assertions (`-Z dep-graph-shadow`).
+    pub shadow: bool,
+    /// Where the shadow graph writes its state when one of its checks
+    /// fails (`-Z dep-graph-shadow-dump`), if anywhere.
+    pub shadow_dump: Option<PathBuf>,
+    /// How many of the tasks with the most edges to print at the end
+    /// (`-Z dep-graph-task-edges`), if any. Turns on the shadow graph,
+    /// which does the counting.
+    pub task_edges: Option<usize>,
+}
+
+impl Default for DepGraphThreadConfig {
+    fn default() -> DepGraphThreadConfig {
+        DepGraphThreadConfig {
+            buffer_size: 2048,
+            buffers: 4,
+            record_stats: false,
+            transport: DepGraphTransport::Buffers,
+            synchronous: false,
+            shards: 0,
+            profile: false,
+            shadow: false,
+            shadow_dump: None,
+            task_edges: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DepGraphTransport {
+    /// Messages are sent in batches of `buffer_size`, in a pool of
+    /// `buffers` recycled buffers, as described in the module docs.
+    Buffers,
+    /// Messages are sent one at a time through a lock-free ring buffer
+    /// with room for `buffer_size` messages.
+    Ring,
+}
+
+#[derive(Default)]
+struct DepGraphStats {
+    messages: [Cell<u64>; 13],
+    swaps: Cell<u64>,
+    swapped_messages: Cell<u64>,
+    blocked: Cell<Duration>,
+    coalesced_reads: Cell<u64>,
+}
 
 impl DepGraphThreadData {
-    pub fn new(enabled: bool) -> DepGraphThreadData {
-        let (tx1, rx1) = mpsc::channel();
-        let (tx2, rx2) = mpsc::channel();
+    pub fn new(enabled: bool, config: DepGraphThreadConfig) -> DepGraphThreadData {
+        let buffer_size = cmp::max(config.buffer_size, 1);
+        let buffers = cmp::max(config.buffers, 1);
         let (txq, rxq) = mpsc::channel();
+        let (txf, rxf) = mpsc::channel();
+        let (txs, rxs) = mpsc::sync_channel(STREAMED_CHUNKS);
+        let worker = DepGraphWorker {
+            edges: DepGraphEdges::new(),
+            query_out: txq,
+            stream_out: if config.synchronous { None } else { Some(txs) },
+            flush_out: txf,
+            last_message: None,
+            record_stats: enabled && config.record_stats,
+            profile: if enabled && config.profile {
+                Some(DepTrackingProfile::default())
+            } else {
+                None
+            },
+        };
 
-        if enabled {
-            thread::spawn(move || main(rx1, tx2, txq));
-        }
+        let (transport, handle) = if config.synchronous {
+            (Transport::Synchronous(RefCell::new(worker)), None)
+        } else {
+            let (transport, incoming) = match config.transport {
+                DepGraphTransport::Buffers => {
+                    let (tx1, rx1) = mpsc::channel();
+                    let (tx2, rx2) = mpsc::channel();
+                    let transport = Transport::Buffers {
+                        messages: VecCell::with_capacity(buffer_size),
+                        new_nodes: VecCell::with_capacity(0),
+                        enqueued: VecCell::with_capacity(0),
+                        swap_in: rx2,
+                        swap_out: tx1,
+                    };
+                    (transport, Incoming::Buffers(rx1, tx2))
+                }
+                DepGraphTransport::Ring => {
+                    let (producer, consumer) = spsc_ring::ring(buffer_size);
+                    let (tx, rx) = mpsc::channel();
+                    let transport = Transport::Ring {
+                        producer,
+                        new_nodes: tx,
+                    };
+                    (transport, Incoming::Ring(consumer, rx))
+                }
+            };
+
+            let handle = if enabled {
+                let thread = thread::Builder::new().name("dep-graph".to_string());
+                let handle = thread.spawn(move || main(incoming, worker, buffer_size, buffers));
+                Some(handle.unwrap())
+            } else {
+                None
+            };
+            (transport, handle)
+        };
 
         DepGraphThreadData {
-            enabled,
-            shadow_graph: ShadowGraph::new(),
-            messages: VecCell::with_capacity(INITIAL_CAPACITY),
-            swap_in: rx2,
-            swap_out: tx1,
+            enabled: Cell::new(enabled),
+            shadow_graph: ShadowGraph::new(config.shadow, config.shadow_dump, config.task_edges),
+            transport,
+            buffer_size,
+            profile: enabled && config.profile,
+            node_indices: RefCell::new(FxHashMap()),
+            open_tasks: Cell::new(0),
+            anon_tasks: RefCell::new(vec![]),
+            recent_reads: Default::default(),
+            next_recent_read: Cell::new(0),
+            stats: if config.record_stats { Some(DepGraphStats::default()) } else { None },
             query_in: rxq,
+            stream_in: rxs,
+            streaming_edges: Cell::new(false),
+            queries_sent: Cell::new(0),
+            queries_received: Cell::new(0),
+            query_results: RefCell::new(FxHashMap()),
+            abandoned_queries: RefCell::new(FxHashSet()),
+            flush_in: rxf,
+            handle: RefCell::new(handle),
+            shards: if enabled && config.shards > 0 {
+                Some(DepGraphShards::new(config.shards, buffer_size))
+            } else {
+                None
+            },
         }
     }
 
     /// True if we are actually building the full dep-graph.
     #[inline]
     pub fn is_fully_enabled(&self) -> bool {
-        self.enabled
+        self.enabled.get()
     }
 
     /// True if (a) we are actually building the full dep-graph, or (b) we are
     /// only enqueuing messages in order to sanity-check them (which happens
-    /// when debug assertions are enabled).
+    /// when debug assertions are enabled, or with `-Z dep-graph-shadow`).
     #[inline]
     pub fn is_enqueue_enabled(&self) -> bool {
         self.is_fully_enabled() || self.shadow_graph.enabled()
     }
 
     /// Sends the current batch of messages to the thread. Installs a
-    /// new vector of messages.
+    /// new vector of messages. (Does nothing with a ring buffer, which has
+    /// no batches.)
     fn swap(&self) {
         assert!(self.is_fully_enabled(), "should never swap if not fully enabled");
 
+        let (messages, new_nodes, enqueued, swap_in, swap_out) = match self.transport {
+            Transport::Buffers {
+                ref messages, ref new_nodes, ref enqueued, ref swap_in, ref swap_out
+            } => {
+                (messages, new_nodes, enqueued, swap_in, swap_out)
+            }
+            Transport::Ring { .. } | Transport::Synchronous(_) => return,
+        };
+
         // should be a buffer waiting for us (though of course we may
         // have to wait for depgraph thread to finish processing the
         // old messages)
-        let new_messages = self.swap_in.recv().unwrap();
-        assert!(new_messages.is_empty());
+        let start = self.stats.as_ref().map(|_| Instant::now());
+        let new_batch = swap_in.recv().unwrap_or_else(|_| self.thread_panicked());
+        assert!(new_batch.messages.is_empty() && new_batch.new_nodes.is_empty());
 
         // swap in the empty buffer and extract the full one
-        let old_messages = self.messages.swap(new_messages);
+        let old_batch = Batch {
+            messages: messages.swap(new_batch.messages),
+            new_nodes: new_nodes.swap(new_batch.new_nodes),
+            enqueued: enqueued.swap(new_batch.enqueued),
+        };
+
+        if let (Some(stats), Some(start)) = (self.stats.as_ref(), start) {
+            stats.swaps.set(stats.swaps.get() + 1);
+            stats.swapped_messages.set(stats.swapped_messages.get() +
+                                       old_batch.messages.len() as u64);
+            stats.blocked.set(stats.blocked.get() + start.elapsed());
+        }
 
         // send full buffer to depgraph thread to be processed
-        self.swap_out.send(old_messages).unwrap();
+        if swap_out.send(old_batch).is_err() {
+            self.thread_panicked();
+        }
     }
 
     pub fn query(&self) -> DepGraphQuery {
+        self.query_async().wait()
+    }
+
+    /// Calls `op` on each edge of the graph as it is now. Unlike `query`,
+    /// this never holds a copy of the whole graph: the depgraph thread
+    /// sends the edges a few thousand at a time, and waits for us to deal
+    /// with them before sending more. So `op` must not use the dep-graph.
+    pub fn for_each_edge<OP>(&self, mut op: OP)
+        where OP: FnMut(&DepNode, &DepNode, DepEdgeKind)
+    {
+        assert!(self.is_fully_enabled(), "should never query if not fully enabled");
+        let mut shard_edges: FxHashMap<(DepNode, DepNode), DepEdgeKind> = match self.shards {
+            Some(ref shards) => {
+                shards.graph().1.into_iter().map(|(s, t, kind)| ((s, t), kind)).collect()
+            }
+            None => FxHashMap(),
+        };
+
+        self.enqueue_and_send(DepMessage::StreamEdges);
+        self.streaming_edges.set(true);
+        let mut start = 0;
+        loop {
+            let chunk = match self.transport {
+                Transport::Synchronous(ref worker) => {
+                    worker.borrow().edges.edge_chunk(start, EDGE_CHUNK_SIZE)
+                }
+                Transport::Buffers { .. } | Transport::Ring { .. } => {
+                    self.stream_in.recv().unwrap_or_else(|_| self.thread_panicked())
+                }
+            };
+            if chunk.is_empty() {
+                break;
+            }
+            start += chunk.len();
+            for (source, target, kind) in chunk {
+                // an edge recorded by a shard as well is only visited once
+                shard_edges.remove(&(source, target));
+                op(&source, &target, kind);
+            }
+        }
+        self.streaming_edges.set(false);
+
+        for ((source, target), kind) in shard_edges {
+            op(&source, &target, kind);
+        }
+    }
+
+    /// Creates the means for another thread to add to the graph, if
+    /// `-Z dep-graph-shards` was given.
+    pub fn shard_producer(&self) -> Option<ShardProducer> {
+        self.shards.as_ref().map(|shards| shards.producer())
+    }
+
+    /// Sends off a query of the graph as it is now, without waiting for the
+    /// depgraph thread to answer it.
+    pub fn query_async(&self) -> DepGraphQueryHandle {
         assert!(self.is_fully_enabled(), "should never query if not fully enabled");
+        let ticket = self.queries_sent.get();
+        self.queries_sent.set(ticket + 1);
         self.enqueue(DepMessage::Query);
         self.swap();
-        self.query_in.recv().unwrap()
+        DepGraphQueryHandle { data: self, ticket: Some(ticket) }
+    }
+
+    /// Receives the next query result, waiting for it if `block` is set.
+    /// Returns false if there is none.
+    fn receive_query_result(&self, block: bool) -> bool {
+        if self.queries_received.get() == self.queries_sent.get() {
+            return false;
+        }
+        let result = if block {
+            self.query_in.recv().unwrap_or_else(|_| self.thread_panicked())
+        } else {
+            match self.query_in.try_recv() {
+                Ok(result) => result,
+                Err(mpsc::TryRecvError::Empty) => return false,
+                Err(mpsc::TryRecvError::Disconnected) => self.thread_panicked(),
+            }
+        };
+        let ticket = self.queries_received.get();
+        self.queries_received.set(ticket + 1);
+        if !self.abandoned_queries.borrow_mut().remove(&ticket) {
+            let result = self.merge_shards(result);
+            self.query_results.borrow_mut().insert(ticket, result);
+        }
+        true
+    }
+
+    /// Adds the graphs of the shards, as of now, to a query result from
+    /// the depgraph thread.
+    fn merge_shards(&self, result: DepGraphQuery) -> DepGraphQuery {
+        let shards = match self.shards {
+            Some(ref shards) => shards,
+            None => return result,
+        };
+        let (shard_nodes, shard_edges) = shards.graph();
+
+        let mut nodes: Vec<DepNode> = result.nodes().into_iter().cloned().collect();
+        let mut edges: Vec<(DepNode, DepNode, DepEdgeKind)> =
+            result.edges().into_iter().map(|(s, t, kind)| (s.clone(), t.clone(), kind)).collect();
+        let known_nodes: FxHashSet<DepNode> = nodes.iter().cloned().collect();
+        let known_edges: FxHashSet<(DepNode, DepNode)> =
+            edges.iter().map(|&(s, t, _)| (s, t)).collect();
+        nodes.extend(shard_nodes.into_iter().filter(|node| !known_nodes.contains(node)));
+        edges.extend(shard_edges.into_iter().filter(|&(s, t, _)| !known_edges.contains(&(s, t))));
+        DepGraphQuery::new(&nodes, &edges)
+    }
+
+    /// Enqueues `message` and sends it to the depgraph thread right away,
+    /// with the rest of the current batch. If `message` filled the buffer,
+    /// enqueuing it already sent the batch; swapping again would then wait
+    /// for a buffer that the depgraph thread may not send back (it exits
+    /// after `Shutdown`, and after `StreamEdges` it waits for us to take
+    /// the edges first), so we only swap if something is left to send.
+    fn enqueue_and_send(&self, message: DepMessage) {
+        self.enqueue(message);
+        if let Transport::Buffers { ref messages, ref new_nodes, .. } = self.transport {
+            if messages.len() > 0 || new_nodes.len() > 0 {
+                self.swap();
+            }
+        }
+    }
+
+    /// Waits until the depgraph thread has processed every message enqueued
+    /// so far. (`query` implies this.)
+    pub fn flush(&self) {
+        if !self.is_fully_enabled() {
+            return;
+        }
+        self.enqueue_and_send(DepMessage::Flush);
+        self.flush_in.recv().unwrap_or_else(|_| self.thread_panicked())
+    }
+
+    /// Processes every message enqueued so far and stops the depgraph
+    /// thread, which must not be used afterwards. Called on drop.
+    pub fn shutdown(&self) {
+        if let Transport::Synchronous(ref worker) = self.transport {
+            worker.borrow_mut().print_profile();
+            worker.borrow().print_edge_stats();
+            return;
+        }
+        if !self.is_fully_enabled() || self.handle.borrow().is_none() {
+            return;
+        }
+        self.enqueue_and_send(DepMessage::Shutdown);
+        let handle = self.handle.borrow_mut().take().unwrap();
+        if let Err(payload) = handle.join() {
+            self.report_panic(payload);
+        }
+    }
+
+    /// Prints the statistics recorded under `-Z dep-graph-stats`.
+    pub fn print_stats(&self) {
+        let stats = match self.stats {
+            Some(ref stats) => stats,
+            None => return,
+        };
+        println!("Dep-graph message statistics:");
+        for (name, count) in MESSAGE_NAMES.iter().zip(stats.messages.iter()) {
+            println!("  {:<12} messages: {}", name, count.get());
+        }
+        let swaps = stats.swaps.get();
+        let blocked = stats.blocked.get();
+        println!("  buffer swaps: {}", swaps);
+        println!("  average buffer fill at swap: {:.1} of {}",
+                 stats.swapped_messages.get() as f64 / cmp::max(swaps, 1) as f64,
+                 self.buffer_size);
+        println!("  time blocked waiting for a buffer: {}.{:03}s",
+                 blocked.as_secs(), blocked.subsec_nanos() / 1_000_000);
+        println!("  redundant reads not sent: {}", stats.coalesced_reads.get());
+
+        if !self.is_fully_enabled() {
+            return;
+        }
+        // count without a copy of the graph, which may be very big
+        let mut histogram = DepKindHistogram::default();
+        {
+            let node_indices = self.node_indices.borrow();
+            for node in node_indices.keys() {
+                histogram.add_node(node);
+            }
+            if let Some(ref shards) = self.shards {
+                for node in shards.graph().0 {
+                    if !node_indices.contains_key(&node) {
+                        histogram.add_node(&node);
+                    }
+                }
+            }
+        }
+        self.for_each_edge(|source, _, _| histogram.add_edge(source));
+
+        println!("Dep-graph nodes and edges by kind:");
+        for (kind, counts) in histogram.counts() {
+            println!("  {:<32} nodes: {:>8}  edges: {:>8}  average out-degree: {:.2}",
+                     format!("{:?}", kind), counts.nodes, counts.edges,
+                     counts.average_out_degree());
+        }
+    }
+
+    /// Prints the tasks with the most edges under `-Z dep-graph-task-edges`.
+    pub fn print_task_edges(&self) {
+        self.shadow_graph.print_task_edges();
+    }
+
+    /// Called when the depgraph thread hung up on us, which it only does
+    /// when it panics. Reports what it was doing at the time, and stops
+    /// tracking dependencies, in case the compilation carries on after
+    /// catching the ICE (we would only report another one otherwise).
+    fn thread_panicked(&self) -> ! {
+        self.enabled.set(false);
+        let handle = match self.handle.borrow_mut().take() {
+            Some(handle) => handle,
+            None => bug!("depgraph thread used after shutdown"),
+        };
+        match handle.join() {
+            Ok(()) => bug!("depgraph thread exited unexpectedly"),
+            Err(payload) => self.report_panic(payload),
+        }
+    }
+
+    fn report_panic(&self, payload: Box<Any + Send>) -> ! {
+        self.enabled.set(false);
+        let payload = match payload.downcast::<DepTaskCycle>() {
+            Ok(cycle) => report_cycle(&cycle),
+            Err(payload) => payload,
+        };
+        let context = self.failure_context();
+        match payload.downcast::<DepThreadPanic>() {
+            Ok(panic) => match panic.last_message {
+                Some(message) => {
+                    bug!("depgraph thread panicked while processing `{:?}`: {} ({})",
+                         message, panic.message, context)
+                }
+                None => bug!("depgraph thread panicked: {} ({})", panic.message, context),
+            },
+            Err(payload) => {
+                bug!("depgraph thread panicked: {} ({})", panic_message(&*payload), context)
+            }
+        }
+    }
+
+    /// Describes the state of the compiler thread, for `report_panic`.
+    fn failure_context(&self) -> String {
+        let pending = match self.transport {
+            Transport::Buffers { ref messages, .. } => messages.len(),
+            Transport::Ring { .. } | Transport::Synchronous(_) => 0,
+        };
+        let mut context = format!("{} messages not yet sent, {} tasks open",
+                                  pending, self.open_tasks.get());
+        if let Some(stack) = self.shadow_graph.describe_stack() {
+            context.push_str(&format!(": {}", stack));
+        }
+        context
     }
 
     /// Enqueue a message to be sent when things are next swapped. (If
     /// the buffer is full, this may swap.)
     #[inline]
     pub fn enqueue(&self, message: DepMessage) {
+        self.enqueue_at(message, None)
+    }
+
+    /// Like `enqueue`, for a message sent from `location` (which the
+    /// shadow graph reports in its errors).
+    #[inline]
+    pub fn enqueue_at(&self, message: DepMessage, location: Option<&'static DepLocation>) {
         assert!(self.is_enqueue_enabled(), "should never enqueue if not enqueue-enabled");
-        self.shadow_graph.enqueue(&message);
+        self.shadow_graph.enqueue(&message, location);
+        if self.is_fully_enabled() {
+            self.record_anon_input(message);
+            if !self.is_redundant_read(message) {
+                self.enqueue_enabled(message);
+            }
+        }
+    }
+
+    /// Enqueues a `Read` of each of `nodes`.
+    pub fn enqueue_reads(&self, nodes: &[DepNode]) {
+        for &node in nodes {
+            self.enqueue(DepMessage::Read(node, DepEdgeKind::of_read(&node)));
+        }
+    }
+
+    /// True if `message` reads a node outside of any task, or a node that
+    /// the current task has read recently, so that it would add no new
+    /// edge. Keeps track of the reads and task boundaries this needs.
+    #[inline]
+    fn is_redundant_read(&self, message: DepMessage) -> bool {
+        match message {
+            DepMessage::Read(node, _) => {
+                if self.open_tasks.get() == 0 ||
+                   self.recent_reads.iter().any(|read| read.get() == Some(node)) {
+                    if let Some(ref stats) = self.stats {
+                        stats.coalesced_reads.set(stats.coalesced_reads.get() + 1);
+                    }
+                    return true;
+                }
+                let next = self.next_recent_read.get();
+                self.recent_reads[next].set(Some(node));
+                self.next_recent_read.set((next + 1) % RECENT_READS);
+            }
+            DepMessage::PushTask(_) |
+            DepMessage::PushAnonTask |
+            DepMessage::PushIgnore => {
+                self.open_tasks.set(self.open_tasks.get() + 1);
+                self.forget_recent_reads();
+            }
+            DepMessage::PopTask(_) |
+            DepMessage::PopAnonTask(_) |
+            DepMessage::PopIgnore => {
+                self.open_tasks.set(self.open_tasks.get() - 1);
+                self.forget_recent_reads();
+            }
+            DepMessage::Write(_) |
+            DepMessage::Invalidate(_) |
+            DepMessage::Query |
+            DepMessage::StreamEdges |
+            DepMessage::Flush |
+            DepMessage::Shutdown => {}
+        }
+        false
+    }
+
+    /// Starts an anonymous task; see `DepGraph::with_anon_task`.
+    pub fn push_anon_task(&self, location: Option<&'static DepLocation>) {
+        self.enqueue_at(DepMessage::PushAnonTask, location);
         if self.is_fully_enabled() {
-            self.enqueue_enabled(message);
+            self.anon_tasks.borrow_mut().push((self.open_tasks.get(), vec![]));
+        }
+    }
+
+    /// Ends the innermost anonymous task, which becomes a node of `kind`
+    /// identified by the nodes that the task depended on.
+    pub fn pop_anon_task(&self,
+                         kind: DepKind,
+                         location: Option<&'static DepLocation>)
+                         -> DepNode {
+        let inputs = if self.is_fully_enabled() {
+            match self.anon_tasks.borrow_mut().pop() {
+                Some((_, inputs)) => inputs,
+                None => bug!("no anonymous task to pop"),
+            }
+        } else {
+            vec![]
+        };
+        let node = anon_node(kind, inputs);
+        self.enqueue_at(DepMessage::PopAnonTask(node), location);
+        // the enclosing task now depends on the new node
+        self.record_anon_input(DepMessage::Read(node, DepEdgeKind::Structural));
+        node
+    }
+
+    /// Remembers what the innermost anonymous task depends on, if it is
+    /// the innermost task: the nodes it reads and the tasks it starts.
+    #[inline]
+    fn record_anon_input(&self, message: DepMessage) {
+        let mut anon_tasks = self.anon_tasks.borrow_mut();
+        if let Some(&mut (depth, ref mut inputs)) = anon_tasks.last_mut() {
+            if depth == self.open_tasks.get() {
+                match message {
+                    DepMessage::Read(node, _) |
+                    DepMessage::PushTask(node) => inputs.push(node),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn forget_recent_reads(&self) {
+        for read in &self.recent_reads {
+            read.set(None);
+        }
+    }
+
+    /// The index of `node` in the graph built by the depgraph thread. The
+    /// first time we see a node, we give it the next index and pass it on
+    /// to the depgraph thread ahead of any message with that index.
+    fn intern(&self, node: DepNode) -> DepNodeIndex {
+        let mut indices = self.node_indices.borrow_mut();
+        if let Some(&index) = indices.get(&node) {
+            return index;
+        }
+        let index = DepNodeIndex::new(indices.len());
+        indices.insert(node, index);
+        match self.transport {
+            Transport::Buffers { ref new_nodes, .. } => {
+                new_nodes.push(node);
+            }
+            Transport::Ring { ref new_nodes, .. } => {
+                if new_nodes.send(node).is_err() {
+                    self.thread_panicked();
+                }
+            }
+            Transport::Synchronous(ref worker) => {
+                worker.borrow_mut().edges.add_node(node);
+            }
         }
+        index
     }
 
     // Outline this fn since I expect it may want to be inlined
     // separately.
     fn enqueue_enabled(&self, message: DepMessage) {
-        let len = self.messages.push(message);
-        if len == INITIAL_CAPACITY {
-            self.swap();
+        if self.streaming_edges.get() {
+            bug!("`{:?}` sent to the dep-graph while its edges are streamed", message);
+        }
+        if let Some(ref stats) = self.stats {
+            let count = &stats.messages[message.index()];
+            count.set(count.get() + 1);
+        }
+        let message = message.map(|node| self.intern(node));
+        match self.transport {
+            Transport::Buffers { ref messages, ref enqueued, .. } => {
+                if self.profile {
+                    enqueued.push(Instant::now());
+                }
+                let len = messages.push(message);
+                if len == self.buffer_size {
+                    self.swap();
+                }
+            }
+            Transport::Ring { ref producer, .. } => {
+                if producer.send(message).is_err() {
+                    self.thread_panicked();
+                }
+            }
+            Transport::Synchronous(ref worker) => {
+                let enqueued = if self.profile { Some(Instant::now()) } else { None };
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    worker.borrow_mut().process(message, enqueued);
+                }));
+                if let Err(payload) = result {
+                    match payload.downcast::<DepTaskCycle>() {
+                        Ok(cycle) => {
+                            self.enabled.set(false);
+                            report_cycle(&cycle)
+                        }
+                        Err(payload) => panic::resume_unwind(payload),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for DepGraphThreadData {
+    fn drop(&mut self) {
+        // Don't turn a panic elsewhere into an abort by panicking again.
+        if !thread::panicking() {
+            self.shutdown();
         }
     }
 }
 
 /// Definition of the depgraph thread.
-pub fn main(swap_in: Receiver<Vec<DepMessage>>,
-            swap_out: Sender<Vec<DepMessage>>,
-            query_out: Sender<DepGraphQuery>) {
-    let mut edges = DepGraphEdges::new();
-
-    // the compiler thread always expects a fresh buffer to be
-    // waiting, so queue one up
-    swap_out.send(Vec::with_capacity(INITIAL_CAPACITY)).unwrap();
-
-    // process the buffers from compiler thread as we receive them
-    for mut messages in swap_in {
-        for msg in messages.drain(..) {
-            match msg {
-                DepMessage::Read(node) => edges.read(node),
-                DepMessage::Write(node) => edges.write(node),
-                DepMessage::PushTask(node) => edges.push_task(node),
-                DepMessage::PopTask(node) => edges.pop_task(node),
-                DepMessage::PushIgnore => edges.push_ignore(),
-                DepMessage::PopIgnore => edges.pop_ignore(),
-                DepMessage::Query => query_out.send(edges.query()).unwrap(),
-            }
-        }
-        if let Err(_) = swap_out.send(messages) {
-            // the receiver must have been dropped already
-            break;
+fn main(incoming: Incoming,
+        mut worker: DepGraphWorker,
+        buffer_size: usize,
+        buffers: usize) {
+    let profile = worker.profile.is_some();
+
+    // process the messages from compiler thread as we receive them
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        match incoming {
+            Incoming::Buffers(swap_in, swap_out) => {
+                // the compiler thread always expects a fresh buffer to be
+                // waiting, so fill up the pool; each buffer beyond the first
+                // lets the compiler thread get a buffer further ahead of us
+                for _ in 0..buffers {
+                    let batch = Batch {
+                        messages: Vec::with_capacity(buffer_size),
+                        new_nodes: Vec::new(),
+                        enqueued: Vec::with_capacity(if profile { buffer_size } else { 0 }),
+                    };
+                    if swap_out.send(batch).is_err() {
+                        // the compiler thread is gone already
+                        return;
+                    }
+                }
+
+                'buffers: for mut batch in swap_in {
+                    for node in batch.new_nodes.drain(..) {
+                        worker.edges.add_node(node);
+                    }
+                    for (i, msg) in batch.messages.drain(..).enumerate() {
+                        if !worker.process(msg, batch.enqueued.get(i).cloned()) {
+                            break 'buffers;
+                        }
+                    }
+                    batch.enqueued.clear();
+                    if let Err(_) = swap_out.send(batch) {
+                        // the receiver must have been dropped already
+                        break;
+                    }
+                }
+            }
+            Incoming::Ring(consumer, new_nodes) => {
+                for msg in consumer {
+                    if !worker.receive_nodes(msg, &new_nodes) {
+                        break;
+                    }
+                    // the ring has no room for timestamps, so we
+                    // only measure the cost of processing
+                    if !worker.process(msg, None) {
+                        break;
+                    }
+                }
+            }
+        }
+    }));
+
+    if let Err(payload) = result {
+        // a cycle is reported as such, and says what it was about
+        if payload.is::<DepTaskCycle>() {
+            panic::resume_unwind(payload);
+        }
+
+        // pass on the message we were processing, with its node
+        let edges = &worker.edges;
+        let last_message = worker.last_message.map(|msg| msg.map(|index| edges.id(index)));
+        panic::resume_unwind(Box::new(DepThreadPanic {
+            message: panic_message(&*payload),
+            last_message: last_message,
+        }));
+    }
+
+    worker.print_profile();
+    worker.print_edge_stats();
+}
+
+/// The node of an anonymous task of `kind` that depended on `inputs`:
+/// two such tasks that depend on the same nodes are the same node.
+fn anon_node(kind: DepKind, mut inputs: Vec<DepNode>) -> DepNode {
+    inputs.sort();
+    inputs.dedup();
+    let mut hasher = StableHasher::new();
+    kind.hash(&mut hasher);
+    inputs.hash(&mut hasher);
+    DepNode {
+        kind,
+        hash: hasher.finish(),
+    }
+}
+
+/// Reports a cycle of tasks found while building the graph, like the cycle
+/// errors of trait selection (or as a bug if there is no tcx to report it
+/// with). Query cycles are reported by `cycle_check` before they get this
+/// far, so such a cycle comes from tasks opened directly with `with_task`
+/// or `in_task`, and is a bug in how the compiler tracks them.
+fn report_cycle(cycle: &DepTaskCycle) -> ! {
+    ::ty::tls::with_opt(|tcx| {
+        if let Some(tcx) = tcx {
+            let mut err = tcx.sess.struct_fatal(
+                &format!("cycle detected when running dep-graph task `{:?}`", cycle.nodes[0]));
+            for node in &cycle.nodes[1..] {
+                err.note(&format!("...which runs task `{:?}`...", node));
+            }
+            err.note(&format!("...which runs task `{:?}` again, completing the cycle",
+                              cycle.nodes[0]));
+            err.emit();
+            panic!(FatalError);
+        }
+    });
+    bug!("cycle of dep-graph tasks: {:?}", cycle.nodes)
+}
+
+fn panic_message(payload: &(Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&'static str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Box<Any>".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dep_graph::{DepGraph, DepGraphThreadConfig, DepKind, DepNode};
+    use ich::Fingerprint;
+    use super::EDGE_CHUNK_SIZE;
+
+    fn node(kind: DepKind, n: u64) -> DepNode {
+        DepNode { kind, hash: Fingerprint::from_smaller_hash(n) }
+    }
+
+    #[test]
+    fn for_each_edge_with_one_buffer() {
+        // Every message fills a buffer, so `StreamEdges` is sent as soon as
+        // it is enqueued, and the only spare buffer is still with the
+        // depgraph thread while it streams more chunks than fit in the
+        // channel.
+        let config = DepGraphThreadConfig {
+            buffer_size: 1,
+            buffers: 1,
+            .. DepGraphThreadConfig::default()
+        };
+        let graph = DepGraph::with_config(true, config);
+        let task = node(DepKind::TypeckTables, 0);
+        let reads = 3 * EDGE_CHUNK_SIZE as u64;
+        {
+            let _task = graph.in_task(task);
+            for n in 0..reads {
+                graph.read(node(DepKind::Hir, n));
+            }
         }
+
+        let mut edges = 0;
+        graph.for_each_edge(|source, target, _| {
+            assert_eq!(source.kind, DepKind::Hir);
+            assert_eq!(*target, task);
+            edges += 1;
+        });
+        assert_eq!(edges, reads);
+    }
+
+    #[test]
+    fn flush_with_one_buffer() {
+        // `Flush` fills the buffer, so it is sent as it is enqueued, and a
+        // second flush has nothing but its own message to send.
+        let config = DepGraphThreadConfig {
+            buffer_size: 1,
+            buffers: 1,
+            .. DepGraphThreadConfig::default()
+        };
+        let graph = DepGraph::with_config(true, config);
+        let task = node(DepKind::TypeckTables, 0);
+        {
+            let _task = graph.in_task(task);
+            graph.read(node(DepKind::Hir, 0));
+            graph.flush();
+            graph.flush();
+            graph.read(node(DepKind::Hir, 1));
+        }
+        graph.flush();
+
+        let query = graph.query();
+        let mut reads = query.immediate_predecessors(&task);
+        reads.sort();
+        assert_eq!(reads, vec![&node(DepKind::Hir, 0), &node(DepKind::Hir, 1)]);
+    }
+
+    #[test]
+    fn anon_tasks_are_identified_by_their_reads() {
+        let graph = DepGraph::with_config(true, DepGraphThreadConfig::default());
+        let anon = |reads: &[u64]| {
+            graph.with_anon_task(DepKind::TraitSelect, || {
+                for &n in reads {
+                    graph.read(node(DepKind::Hir, n));
+                }
+            }).1
+        };
+        let task = node(DepKind::TypeckTables, 0);
+        let (first, same, other) = {
+            let _task = graph.in_task(task);
+            (anon(&[0, 1]), anon(&[1, 0, 1]), anon(&[2]))
+        };
+        assert_eq!(first, same);
+        assert!(first != other);
+
+        let query = graph.query();
+        let mut inputs = query.immediate_predecessors(&task);
+        inputs.sort();
+        let mut expected = vec![&first, &other];
+        expected.sort();
+        assert_eq!(inputs, expected);
+        let mut reads = query.immediate_predecessors(&first);
+        reads.sort();
+        assert_eq!(reads, vec![&node(DepKind::Hir, 0), &node(DepKind::Hir, 1)]);
+        assert_eq!(query.immediate_predecessors(&other), vec![&node(DepKind::Hir, 2)]);
     }
 }
@@ -206,6 +206,7 @@ pub struct Definitions {
     node_to_def_index: NodeMap<DefIndex>,
     def_index_to_node: [Vec<ast::NodeId>; 2],
     pub(super) node_to_hir_id: IndexVec<ast::NodeId, hir::HirId>,
+    hir_to_node_id: FxHashMap<hir::HirId, ast::NodeId>,
     macro_def_scopes: FxHashMap<Mark, DefId>,
     expansions: FxHashMap<DefIndex, Mark>,
 }
@@ -222,6 +223,7 @@ impl Clone for Definitions {
                 self.def_index_to_node[1].clone(),
             ],
             node_to_hir_id: self.node_to_hir_id.clone(),
+            hir_to_node_id: self.hir_to_node_id.clone(),
             macro_def_scopes: self.macro_def_scopes.clone(),
             expansions: self.expansions.clone(),
         }
@@ -454,6 +456,7 @@ impl Definitions {
             node_to_def_index: NodeMap(),
             def_index_to_node: [vec![], vec![]],
             node_to_hir_id: IndexVec::new(),
+            hir_to_node_id: FxHashMap(),
             macro_def_scopes: FxHashMap(),
             expansions: FxHashMap(),
         }
@@ -522,6 +525,10 @@ impl Definitions {
         self.node_to_hir_id[node_id]
     }
 
+    pub fn hir_to_node_id(&self, hir_id: hir::HirId) -> ast::NodeId {
+        self.hir_to_node_id[&hir_id]
+    }
+
     /// Add a definition with a parent definition.
     pub fn create_root_def(&mut self,
                            crate_name: &str,
@@ -619,6 +626,10 @@ impl Definitions {
                                           mapping: IndexVec<ast::NodeId, hir::HirId>) {
         assert!(self.node_to_hir_id.is_empty(),
                 "Trying initialize NodeId -> HirId mapping twice");
+        self.hir_to_node_id = mapping.iter_enumerated()
+                                     .filter(|&(_, &hir_id)| hir_id != hir::DUMMY_HIR_ID)
+                                     .map(|(node_id, &hir_id)| (hir_id, node_id))
+                                     .collect();
         self.node_to_hir_id = mapping;
     }
 
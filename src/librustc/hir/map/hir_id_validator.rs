@@ -138,6 +138,46 @@ impl<'a, 'hir: 'a> HirIdValidator<'a, 'hir> {
     }
 }
 
+impl<'a, 'hir: 'a> HirIdValidator<'a, 'hir> {
+    fn check_parent_and_def_path(&mut self, node_id: NodeId, hir_id: HirId) {
+        let definitions = self.hir_map.definitions();
+
+        // Except for the owner itself, every node has to be nested inside
+        // another node of the same owner.
+        if hir_id.local_id != ItemLocalId(0) {
+            let parent = self.hir_map.get_parent_node(node_id);
+            let parent_owner = definitions.node_to_hir_id[parent].owner;
+            if parent_owner != hir_id.owner {
+                self.errors.push(format!(
+                    "HirIdValidator: The parent {} of {} belongs to {} instead of {}",
+                    self.hir_map.node_to_string(parent),
+                    self.hir_map.node_to_string(node_id),
+                    self.hir_map.def_path(DefId::local(parent_owner)).to_string_no_crate(),
+                    self.hir_map.def_path(DefId::local(hir_id.owner)).to_string_no_crate()));
+            }
+        }
+
+        // A definition has to map back to its node, and its DefKey back to
+        // its DefIndex.
+        if let Some(def_id) = definitions.opt_local_def_id(node_id) {
+            if definitions.as_local_node_id(def_id) != Some(node_id) {
+                self.errors.push(format!(
+                    "HirIdValidator: {:?} of {} does not map back to it",
+                    def_id,
+                    self.hir_map.node_to_string(node_id)));
+            }
+
+            let def_key = definitions.def_key(def_id.index);
+            if definitions.def_index_for_def_key(def_key) != Some(def_id.index) {
+                self.errors.push(format!(
+                    "HirIdValidator: The DefKey of {} does not identify {:?}",
+                    self.hir_map.node_to_string(node_id),
+                    def_id));
+            }
+        }
+    }
+}
+
 impl<'a, 'hir: 'a> intravisit::Visitor<'hir> for HirIdValidator<'a, 'hir> {
 
     fn nested_visit_map<'this>(&'this mut self)
@@ -173,6 +213,12 @@ impl<'a, 'hir: 'a> intravisit::Visitor<'hir> for HirIdValidator<'a, 'hir> {
                     self.hir_map.node_to_string(node_id)));
             }
         }
+
+        // The remaining checks cross-check the rest of the map and are only
+        // worth their cost in compilers built with debug assertions.
+        if cfg!(debug_assertions) {
+            self.check_parent_and_def_path(node_id, stable_id);
+        }
     }
 
     fn visit_impl_item_ref(&mut self, _: &'hir hir::ImplItemRef) {
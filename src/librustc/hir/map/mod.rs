@@ -16,6 +16,7 @@ pub use self::definitions::{Definitions, DefKey, DefPath, DefPathData,
                             DisambiguatedDefPathData, DefPathHash};
 
 use dep_graph::{DepGraph, DepNode, DepKind};
+use dep_graph::debug::DepLocation;
 
 use hir::def_id::{CRATE_DEF_INDEX, DefId, DefIndex, DefIndexAddressSpace};
 
@@ -280,6 +281,12 @@ impl<'hir> Map<'hir> {
         self.dep_graph.read(self.dep_node(id));
     }
 
+    /// Like `read`, naming the caller (given by `dep_location!()`) in the
+    /// errors of the shadow graph that concern this read.
+    pub fn read_at(&self, id: NodeId, location: &'static DepLocation) {
+        self.dep_graph.read_at(self.dep_node(id), location);
+    }
+
     fn dep_node(&self, id0: NodeId) -> DepNode {
         let mut id = id0;
         let mut last_expr = None;
@@ -361,6 +368,16 @@ impl<'hir> Map<'hir> {
         &self.definitions
     }
 
+    #[inline]
+    pub fn node_to_hir_id(&self, node_id: NodeId) -> HirId {
+        self.definitions.node_to_hir_id(node_id)
+    }
+
+    #[inline]
+    pub fn hir_to_node_id(&self, hir_id: HirId) -> NodeId {
+        self.definitions.hir_to_node_id(hir_id)
+    }
+
     pub fn def_key(&self, def_id: DefId) -> DefKey {
         assert!(def_id.is_local());
         self.definitions.def_key(def_id.index)
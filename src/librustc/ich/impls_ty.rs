@@ -255,6 +255,14 @@ impl_stable_hash_for!(struct ty::FieldDef {
     vis
 });
 
+impl_stable_hash_for!(struct ty::TraitDef {
+    def_id,
+    unsafety,
+    paren_sugar,
+    has_default_impl,
+    def_path_hash
+});
+
 impl<'a, 'gcx, 'tcx> HashStable<StableHashingContext<'a, 'gcx, 'tcx>>
 for ::middle::const_val::ConstVal<'tcx> {
     fn hash_stable<W: StableHasherResult>(&self,
@@ -611,6 +619,7 @@ for ty::TypeckTables<'tcx> {
                                           hcx: &mut StableHashingContext<'a, 'gcx, 'tcx>,
                                           hasher: &mut StableHasher<W>) {
         let ty::TypeckTables {
+            local_id_root,
             ref type_dependent_defs,
             ref node_types,
             ref node_substs,
@@ -631,6 +640,7 @@ for ty::TypeckTables<'tcx> {
         } = *self;
 
         hcx.with_node_id_hashing_mode(NodeIdHashingMode::HashDefPath, |hcx| {
+            local_id_root.hash_stable(hcx, hasher);
             ich::hash_stable_nodemap(hcx, hasher, type_dependent_defs);
             ich::hash_stable_nodemap(hcx, hasher, node_types);
             ich::hash_stable_nodemap(hcx, hasher, node_substs);
@@ -648,9 +658,9 @@ for ty::TypeckTables<'tcx> {
 
             ich::hash_stable_nodemap(hcx, hasher, closure_tys);
             ich::hash_stable_nodemap(hcx, hasher, closure_kinds);
-            ich::hash_stable_nodemap(hcx, hasher, liberated_fn_sigs);
-            ich::hash_stable_nodemap(hcx, hasher, fru_field_types);
-            ich::hash_stable_nodemap(hcx, hasher, cast_kinds);
+            ich::hash_stable_itemlocalmap(hcx, hasher, liberated_fn_sigs);
+            ich::hash_stable_itemlocalmap(hcx, hasher, fru_field_types);
+            ich::hash_stable_itemlocalmap(hcx, hasher, cast_kinds);
 
             ich::hash_stable_hashset(hcx, hasher, used_trait_imports, |hcx, def_id| {
                 hcx.def_path_hash(*def_id)
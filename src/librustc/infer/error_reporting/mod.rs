@@ -62,15 +62,18 @@ use super::region_inference::{RegionResolutionError, ConcreteFailure, SubSupConf
 
 use std::fmt;
 use hir;
+use hir::intravisit::{self, NestedVisitorMap, Visitor};
 use hir::map as hir_map;
-use hir::def_id::DefId;
+use hir::def_id::{CrateNum, DefId};
+use middle::cstore::PathKind;
 use middle::region;
+use middle::resolve_lifetime as rl;
 use traits::{ObligationCause, ObligationCauseCode};
 use ty::{self, TyCtxt, TypeFoldable};
 use ty::{Region, Issue32330};
 use ty::error::TypeError;
 use syntax::ast::DUMMY_NODE_ID;
-use syntax_pos::{Pos, Span};
+use syntax_pos::{BytePos, Pos, Span};
 use errors::{DiagnosticBuilder, DiagnosticStyledString};
 mod note;
 
@@ -253,6 +256,100 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
         } else {
             err.note(&message);
         }
+
+        // Also point at where the lifetime comes from, in the main snippet,
+        // so that errors involving several lifetimes of one signature can
+        // be followed without counting elided lifetimes by hand.
+        if let Some((span, label)) = self.region_definition_label(region) {
+            let labelled = err.span.span_labels().iter().any(|l| l.span == span);
+            if !labelled {
+                err.span_label(span, label);
+            }
+        }
+    }
+
+    fn region_definition_label(self, region: ty::Region<'tcx>) -> Option<(Span, String)> {
+        let (def_id, name) = match *region {
+            ty::ReEarlyBound(ref br) => (br.def_id, br.name),
+            ty::ReFree(ty::FreeRegion { bound_region: ty::BrNamed(def_id, name), .. }) => {
+                (def_id, name)
+            }
+            ty::ReFree(ty::FreeRegion { scope, bound_region: ty::BrAnon(index) }) => {
+                return self.anonymous_lifetime_span(scope, index).map(|span| {
+                    (span, format!("anonymous lifetime #{} defined here", index + 1))
+                });
+            }
+            _ => return None,
+        };
+        self.hir.as_local_node_id(def_id).map(|node_id| {
+            (self.hir.span(node_id), format!("lifetime {} defined here", name))
+        })
+    }
+
+    /// Finds the elided lifetime in the signature of `fn_def_id` that the
+    /// anonymous lifetime `#index + 1` was introduced for.
+    fn anonymous_lifetime_span(self, fn_def_id: DefId, index: u32) -> Option<Span> {
+        let node_id = match self.hir.as_local_node_id(fn_def_id) {
+            Some(node_id) => node_id,
+            None => return None,
+        };
+        let decl = match self.hir.find(node_id) {
+            Some(hir_map::NodeItem(&hir::Item { node: hir::ItemFn(ref decl, ..), .. })) => decl,
+            Some(hir_map::NodeTraitItem(&hir::TraitItem {
+                node: hir::TraitItemKind::Method(ref sig, _), ..
+            })) |
+            Some(hir_map::NodeImplItem(&hir::ImplItem {
+                node: hir::ImplItemKind::Method(ref sig, _), ..
+            })) => &sig.decl,
+            _ => return None,
+        };
+
+        let mut finder = AnonymousLifetimeFinder {
+            tcx: self,
+            index,
+            span: None,
+        };
+        for ty in &decl.inputs {
+            finder.visit_ty(ty);
+        }
+        // The lifetime of `&T` is given the empty span at the `&`.
+        finder.span.map(|span| if span.lo == span.hi {
+            Span { hi: span.lo + BytePos(1), ..span }
+        } else {
+            span
+        })
+    }
+}
+
+struct AnonymousLifetimeFinder<'a, 'gcx: 'a + 'tcx, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'gcx, 'tcx>,
+    index: u32,
+    span: Option<Span>,
+}
+
+impl<'a, 'gcx, 'tcx> Visitor<'gcx> for AnonymousLifetimeFinder<'a, 'gcx, 'tcx> {
+    fn nested_visit_map<'this>(&'this mut self) -> NestedVisitorMap<'this, 'gcx> {
+        NestedVisitorMap::None
+    }
+
+    fn visit_ty(&mut self, ty: &'gcx hir::Ty) {
+        // Function pointers bind their anonymous lifetimes themselves.
+        if let hir::TyBareFn(..) = ty.node {
+            return;
+        }
+        intravisit::walk_ty(self, ty);
+    }
+
+    fn visit_lifetime(&mut self, lifetime: &'gcx hir::Lifetime) {
+        if self.span.is_some() {
+            return;
+        }
+        match self.tcx.named_region_map.defs.get(&lifetime.id) {
+            Some(&rl::Region::LateBoundAnon(_, index)) if index == self.index => {
+                self.span = Some(lifetime.span);
+            }
+            _ => {}
+        }
     }
 }
 
@@ -360,6 +457,8 @@ impl<'a, 'gcx, 'tcx> InferCtxt<'a, 'gcx, 'tcx> {
                     err.span_note(sp, &format!("Perhaps two different versions \
                                                 of crate `{}` are being used?",
                                                crate_name));
+                    self.note_crate_origin(err, did1.krate);
+                    self.note_crate_origin(err, did2.krate);
                 }
             }
         };
@@ -381,6 +480,26 @@ impl<'a, 'gcx, 'tcx> InferCtxt<'a, 'gcx, 'tcx> {
         }
     }
 
+    /// Explains where the crate `cnum` was loaded from, to tell apart different
+    /// versions of a crate with the same name.
+    fn note_crate_origin(&self, err: &mut DiagnosticBuilder, cnum: CrateNum) {
+        let cstore = &self.tcx.sess.cstore;
+        let name = cstore.crate_name(cnum);
+        let hash = cstore.crate_hash(cnum);
+        let source = cstore.used_crate_source(cnum);
+        let (path, kind) = match source.rlib.or(source.dylib).or(source.rmeta) {
+            Some(source) => source,
+            None => return,
+        };
+        let origin = if kind == PathKind::ExternFlag {
+            format!("passed with `--extern {}={}`", name, path.display())
+        } else {
+            "found in the library search path".to_string()
+        };
+        err.note(&format!("crate `{}` with hash `{}` was loaded from `{}`, {}",
+                          name, hash.to_string(), path.display(), origin));
+    }
+
     fn note_error_origin(&self,
                          err: &mut DiagnosticBuilder<'tcx>,
                          cause: &ObligationCause<'tcx>)
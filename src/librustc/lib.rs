@@ -46,6 +46,7 @@
 #![recursion_limit="256"]
 
 extern crate arena;
+extern crate backtrace;
 extern crate core;
 extern crate fmt_macros;
 extern crate getopts;
@@ -55,6 +55,18 @@ macro_rules! bug {
     })
 }
 
+/// The current source location, as a `&'static DepLocation`, for
+/// `DepGraph::in_task_at`, `in_ignore_at` and `read_at`, `Map::read_at`,
+/// the `try_get_at` of queries and `TyCtxt::at_location`.
+#[macro_export]
+macro_rules! dep_location {
+    () => ({
+        static LOCATION: $crate::dep_graph::debug::DepLocation =
+            $crate::dep_graph::debug::DepLocation { file: file!(), line: line!() };
+        &LOCATION
+    })
+}
+
 #[macro_export]
 macro_rules! span_bug {
     ($span:expr, $($message:tt)*) => ({
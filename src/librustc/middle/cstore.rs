@@ -201,6 +201,25 @@ impl EncodedMetadataHashes {
     }
 }
 
+/// The codec of the metadata stored in a dylib, recorded in the byte right after
+/// the metadata header so that crates compressed with different settings can
+/// still be loaded.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MetadataCodec {
+    Uncompressed = 0,
+    Zlib = 1,
+}
+
+impl MetadataCodec {
+    pub fn from_tag(tag: u8) -> Option<MetadataCodec> {
+        match tag {
+            0 => Some(MetadataCodec::Uncompressed),
+            1 => Some(MetadataCodec::Zlib),
+            _ => None,
+        }
+    }
+}
+
 /// The backend's way to give the crate store access to the metadata in a library.
 /// Note that it returns the raw metadata bytes stored in the library file, whether
 /// it is compressed, uncompressed, some weird mix, etc.
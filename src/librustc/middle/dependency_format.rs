@@ -376,11 +376,22 @@ fn verify_ok<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, list: &[Linkage]) {
         // First up, validate that our selected panic runtime is indeed exactly
         // our same strategy.
         if found_strategy != desired_strategy {
-            sess.err(&format!("the linked panic runtime `{}` is \
-                               not compiled with this crate's \
-                               panic strategy `{}`",
-                              sess.cstore.crate_name(cnum),
+            let mut err = sess.struct_err(&format!("the linked panic runtime `{}` is \
+                                                    not compiled with this crate's \
+                                                    panic strategy `{}`",
+                                                   sess.cstore.crate_name(cnum),
+                                                   desired_strategy.desc()));
+            if list[cnum.as_usize() - 1] == Linkage::IncludedFromDylib {
+                err.note(&format!("the panic runtime is already linked into a \
+                                   dynamic library this crate depends on, \
+                                   which was compiled with `-C panic={}`",
+                                  found_strategy.desc()));
+            }
+            err.help(&format!("compile this crate with `-C panic={}`, or link \
+                               against dependencies built with `-C panic={}`",
+                              found_strategy.desc(),
                               desired_strategy.desc()));
+            err.emit();
         }
 
         // Next up, verify that all other crates are compatible with this panic
@@ -400,13 +411,24 @@ fn verify_ok<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, list: &[Linkage]) {
                 continue
             }
 
-            sess.err(&format!("the crate `{}` is compiled with the \
-                               panic strategy `{}` which is \
-                               incompatible with this crate's \
-                               strategy of `{}`",
-                              sess.cstore.crate_name(cnum),
-                              found_strategy.desc(),
-                              desired_strategy.desc()));
+            let name = sess.cstore.crate_name(cnum);
+            sess.struct_err(&format!("the crate `{}` is compiled with the \
+                                      panic strategy `{}` which is \
+                                      incompatible with this crate's \
+                                      strategy of `{}`",
+                                     name,
+                                     found_strategy.desc(),
+                                     desired_strategy.desc()))
+                .note(&format!("`{}` assumes that panics never unwind through \
+                                its code, which would not hold once linked \
+                                with this crate",
+                               name))
+                .help(&format!("recompile `{}` with `-C panic={}`, or compile \
+                                this crate with `-C panic={}`",
+                               name,
+                               desired_strategy.desc(),
+                               found_strategy.desc()))
+                .emit();
         }
     }
 }
@@ -19,6 +19,7 @@ use hir::def_id::{CrateNum, CRATE_DEF_INDEX, DefId, DefIndex, LOCAL_CRATE};
 use ty::{self, TyCtxt};
 use middle::privacy::AccessLevels;
 use session::Session;
+use errors::{Diagnostic, Level};
 use syntax::symbol::Symbol;
 use syntax_pos::{Span, DUMMY_SP};
 use syntax::ast;
@@ -486,14 +487,29 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
             return;
         }
 
-        let lint_deprecated = |note: Option<Symbol>| {
+        let lint_deprecated = |note: Option<Symbol>, since: Option<Symbol>| {
             let msg = if let Some(note) = note {
                 format!("use of deprecated item: {}", note)
             } else {
                 format!("use of deprecated item")
             };
 
-            self.sess.add_lint(lint::builtin::DEPRECATED, id, span, msg);
+            let mut diagnostic = Diagnostic::new(Level::Warning, &msg);
+            diagnostic.set_span(span);
+
+            // The use site alone doesn't say much about an upstream item, so
+            // point at its definition, decoded from the exporting crate.
+            if !def_id.is_local() {
+                let path = self.item_path_str(def_id);
+                let note = if let Some(since) = since {
+                    format!("`{}` is defined here, deprecated since {}", path, since)
+                } else {
+                    format!("`{}` is defined here", path)
+                };
+                diagnostic.span_note(self.def_span(def_id), &note);
+            }
+
+            self.sess.add_lint_diagnostic(lint::builtin::DEPRECATED, id, diagnostic);
         };
 
         // Deprecated attributes apply in-crate and cross-crate.
@@ -508,7 +524,7 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
             };
 
             if !skip {
-                lint_deprecated(depr_entry.attr.note);
+                lint_deprecated(depr_entry.attr.note, depr_entry.attr.since);
             }
         }
 
@@ -524,10 +540,10 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
         debug!("stability: \
                 inspecting def_id={:?} span={:?} of stability={:?}", def_id, span, stability);
 
-        if let Some(&Stability{rustc_depr: Some(attr::RustcDeprecation { reason, .. }), ..})
+        if let Some(&Stability{rustc_depr: Some(attr::RustcDeprecation { reason, since }), ..})
                 = stability {
             if id != ast::DUMMY_NODE_ID {
-                lint_deprecated(Some(reason));
+                lint_deprecated(Some(reason), Some(since));
             }
         }
 
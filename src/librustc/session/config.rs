@@ -23,6 +23,7 @@ use rustc_back::{LinkerFlavor, PanicStrategy};
 use rustc_back::target::Target;
 use lint;
 use middle::cstore;
+use dep_graph::{DepGraph, DepGraphThreadConfig, DepGraphTransport};
 
 use syntax::ast::{self, IntTy, UintTy};
 use syntax::codemap::FilePathMapping;
@@ -44,7 +45,7 @@ use std::hash::Hasher;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashSet;
 use std::iter::FromIterator;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct Config {
     pub target: Target,
@@ -60,6 +61,56 @@ pub enum Sanitizer {
     Thread,
 }
 
+/// The scheme used to turn item paths into linker symbol names.
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum SymbolManglingVersion {
+    /// `_ZN...17h<hash>E`, C++-like paths with an opaque hash suffix.
+    Legacy,
+    /// `_R...`, a reversible encoding of the full path and generic
+    /// arguments (see `librustc_back::demangle`).
+    V0,
+}
+
+/// Whether object files are emitted as LLVM bitcode for a linker plugin
+/// (e.g. LLVMgold or LLD) to optimize across language boundaries.
+#[derive(Clone, PartialEq, Hash, Debug)]
+pub enum LinkerPluginLto {
+    /// Use the LTO plugin at the given path.
+    LinkerPlugin(PathBuf),
+    /// Rely on the linker finding its LTO plugin by itself.
+    LinkerPluginAuto,
+    Disabled,
+}
+
+impl LinkerPluginLto {
+    pub fn enabled(&self) -> bool {
+        match *self {
+            LinkerPluginLto::LinkerPlugin(_) |
+            LinkerPluginLto::LinkerPluginAuto => true,
+            LinkerPluginLto::Disabled => false,
+        }
+    }
+}
+
+/// How hard to compress the metadata embedded in dylibs and proc-macro crates,
+/// or the files of incremental compilation.
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum MetadataCompression {
+    None,
+    Fast,
+    Best,
+}
+
+/// A phase of the compiler after which `-Z stop-after` can end compilation.
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum CompilePhase {
+    Parse,
+    Expand,
+    Lower,
+    Analysis,
+    Trans,
+}
+
 #[derive(Clone, Copy, PartialEq, Hash)]
 pub enum OptLevel {
     No, // -O0
@@ -88,13 +139,15 @@ pub enum OutputType {
     Object,
     Exe,
     DepInfo,
+    ApiSummary,
 }
 
 impl OutputType {
     fn is_compatible_with_codegen_units_and_single_output_file(&self) -> bool {
         match *self {
             OutputType::Exe |
-            OutputType::DepInfo => true,
+            OutputType::DepInfo |
+            OutputType::ApiSummary => true,
             OutputType::Bitcode |
             OutputType::Assembly |
             OutputType::LlvmAssembly |
@@ -114,6 +167,7 @@ impl OutputType {
             OutputType::Metadata => "metadata",
             OutputType::Exe => "link",
             OutputType::DepInfo => "dep-info",
+            OutputType::ApiSummary => "api-summary",
         }
     }
 
@@ -127,6 +181,7 @@ impl OutputType {
             OutputType::Metadata => "rmeta",
             OutputType::DepInfo => "d",
             OutputType::Exe => "",
+            OutputType::ApiSummary => "api.json",
         }
     }
 }
@@ -181,7 +236,8 @@ impl OutputTypes {
             OutputType::Object |
             OutputType::Exe => true,
             OutputType::Metadata |
-            OutputType::DepInfo => false,
+            OutputType::DepInfo |
+            OutputType::ApiSummary => false,
         })
     }
 }
@@ -486,9 +542,38 @@ impl Options {
     pub fn build_dep_graph(&self) -> bool {
         self.incremental.is_some() ||
             self.debugging_opts.dump_dep_graph ||
+            self.debugging_opts.dump_dep_graph_dot.is_some() ||
+            self.debugging_opts.dump_dep_graph_json.is_some() ||
+            self.debugging_opts.dump_dep_graph_graphml.is_some() ||
+            self.debugging_opts.dump_dep_graph_around.is_some() ||
+            self.debugging_opts.dep_graph_find.is_some() ||
             self.debugging_opts.query_dep_graph
     }
 
+    /// Creates the dep-graph for a compilation with these options.
+    pub fn new_dep_graph(&self) -> DepGraph {
+        let mut config = DepGraphThreadConfig::default();
+        if let Some(buffer_size) = self.debugging_opts.dep_message_buffer_size {
+            config.buffer_size = buffer_size;
+        }
+        if let Some(buffers) = self.debugging_opts.dep_message_buffers {
+            config.buffers = buffers;
+        }
+        config.record_stats = self.debugging_opts.dep_graph_stats;
+        if let Some(transport) = self.debugging_opts.dep_graph_transport {
+            config.transport = transport;
+        }
+        config.synchronous = self.debugging_opts.dep_graph_synchronous;
+        if let Some(shards) = self.debugging_opts.dep_graph_shards {
+            config.shards = shards;
+        }
+        config.profile = self.debugging_opts.profile_dep_tracking;
+        config.shadow = self.debugging_opts.dep_graph_shadow;
+        config.shadow_dump = self.debugging_opts.dep_graph_shadow_dump.as_ref().map(PathBuf::from);
+        config.task_edges = self.debugging_opts.dep_graph_task_edges;
+        DepGraph::with_config(self.build_dep_graph(), config)
+    }
+
     pub fn single_codegen_unit(&self) -> bool {
         self.incremental.is_none() ||
         self.cg.codegen_units == 1
@@ -501,6 +586,40 @@ impl Options {
             ).map(|(src, dst)| (src.clone(), dst.clone())).collect()
         )
     }
+
+    /// The hash of the tracked options as seen by the incremental compilation
+    /// cache. It is the same as `dep_tracking_hash()`, except that every path
+    /// in the options is first remapped with `-Z remap-path-prefix-from/to`.
+    /// That way, a cache survives the relocation of the workspace that the
+    /// crate is built in (or is shared between identical CI workers), as long
+    /// as each build maps its own root to the same prefix.
+    pub fn incremental_dep_tracking_hash(&self) -> u64 {
+        let mapping = self.file_path_mapping();
+        let map_string = |path: &String| mapping.map_prefix(path.clone()).0;
+        let map_path = |path: &Path| {
+            match mapping.map_prefix(path.to_string_lossy().into_owned()) {
+                (mapped, true) => PathBuf::from(mapped),
+                (_, false) => path.to_path_buf(),
+            }
+        };
+
+        let mut opts = self.clone();
+        opts.search_paths = self.search_paths.map_paths(&map_path);
+        opts.externs = Externs(self.externs.iter().map(|(name, locations)| {
+            (name.clone(), locations.iter().map(&map_string).collect())
+        }).collect());
+        opts.output_types = OutputTypes(self.output_types.0.iter().map(|(&ty, path)| {
+            (ty, path.as_ref().map(|path| map_path(path.as_path())))
+        }).collect());
+        opts.maybe_sysroot = self.maybe_sysroot.as_ref().map(|path| map_path(path.as_path()));
+        opts.cg.profile_generate = self.cg.profile_generate.as_ref().map(&map_string);
+        opts.cg.profile_use = self.cg.profile_use.as_ref().map(&map_string);
+        // The prefixes themselves are rooted in the workspace: mapping them
+        // turns each of them into the (stable) prefix it is mapped to.
+        opts.debugging_opts.remap_path_prefix_from =
+            self.debugging_opts.remap_path_prefix_from.iter().map(&map_string).collect();
+        opts.dep_tracking_hash()
+    }
 }
 
 // The type of entry function, so
@@ -654,11 +773,24 @@ macro_rules! options {
             Some(::rustc_back::LinkerFlavor::one_of());
         pub const parse_optimization_fuel: Option<&'static str> =
             Some("crate=integer");
+        pub const parse_symbol_mangling_version: Option<&'static str> =
+            Some("either `legacy` or `v0`");
+        pub const parse_linker_plugin_lto: Option<&'static str> =
+            Some("nothing, or the path to the linker's LTO plugin");
+        pub const parse_metadata_compression: Option<&'static str> =
+            Some("one of: `none`, `fast` or `best`");
+        pub const parse_compile_phase: Option<&'static str> =
+            Some("one of: `parse`, `expand`, `lower`, `analysis` or `trans`");
+        pub const parse_dep_graph_transport: Option<&'static str> =
+            Some("one of: `buffers` or `ring`");
     }
 
     #[allow(dead_code)]
     mod $mod_set {
-        use super::{$struct_name, Passes, SomePasses, AllPasses, Sanitizer};
+        use super::{$struct_name, Passes, SomePasses, AllPasses, Sanitizer,
+                    SymbolManglingVersion, LinkerPluginLto, MetadataCompression,
+                    CompilePhase, DepGraphTransport};
+        use std::path::PathBuf;
         use rustc_back::{LinkerFlavor, PanicStrategy};
 
         $(
@@ -791,6 +923,57 @@ macro_rules! options {
             true
         }
 
+        fn parse_symbol_mangling_version(slot: &mut SymbolManglingVersion,
+                                         v: Option<&str>) -> bool {
+            match v {
+                Some("legacy") => *slot = SymbolManglingVersion::Legacy,
+                Some("v0") => *slot = SymbolManglingVersion::V0,
+                _ => return false,
+            }
+            true
+        }
+
+        fn parse_linker_plugin_lto(slot: &mut LinkerPluginLto, v: Option<&str>) -> bool {
+            *slot = match v {
+                Some(path) => LinkerPluginLto::LinkerPlugin(PathBuf::from(path)),
+                None => LinkerPluginLto::LinkerPluginAuto,
+            };
+            true
+        }
+
+        fn parse_metadata_compression(slot: &mut Option<MetadataCompression>,
+                                      v: Option<&str>) -> bool {
+            match v {
+                Some("none") => *slot = Some(MetadataCompression::None),
+                Some("fast") => *slot = Some(MetadataCompression::Fast),
+                Some("best") => *slot = Some(MetadataCompression::Best),
+                _ => return false,
+            }
+            true
+        }
+
+        fn parse_compile_phase(slot: &mut Option<CompilePhase>, v: Option<&str>) -> bool {
+            match v {
+                Some("parse") => *slot = Some(CompilePhase::Parse),
+                Some("expand") => *slot = Some(CompilePhase::Expand),
+                Some("lower") => *slot = Some(CompilePhase::Lower),
+                Some("analysis") => *slot = Some(CompilePhase::Analysis),
+                Some("trans") => *slot = Some(CompilePhase::Trans),
+                _ => return false,
+            }
+            true
+        }
+
+        fn parse_dep_graph_transport(slot: &mut Option<DepGraphTransport>,
+                                     v: Option<&str>) -> bool {
+            match v {
+                Some("buffers") => *slot = Some(DepGraphTransport::Buffers),
+                Some("ring") => *slot = Some(DepGraphTransport::Ring),
+                _ => return false,
+            }
+            true
+        }
+
         fn parse_linker_flavor(slote: &mut Option<LinkerFlavor>, v: Option<&str>) -> bool {
             match v.and_then(LinkerFlavor::from_str) {
                 Some(lf) => *slote = Some(lf),
@@ -823,6 +1006,8 @@ options! {CodegenOptions, CodegenSetter, basic_codegen_options,
         "tool to assemble archives with"),
     linker: Option<String> = (None, parse_opt_string, [UNTRACKED],
         "system linker to link outputs with"),
+    linker_flavor: Option<LinkerFlavor> = (None, parse_linker_flavor, [UNTRACKED],
+        "linker flavor, i.e. the style of command line the linker expects"),
     link_arg: Vec<String> = (vec![], parse_string_push, [UNTRACKED],
         "a single extra argument to append to the linker invocation (can be used several times)"),
     link_args: Option<Vec<String>> = (None, parse_opt_list, [UNTRACKED],
@@ -886,6 +1071,11 @@ options! {CodegenOptions, CodegenSetter, basic_codegen_options,
         "set the inlining threshold for"),
     panic: Option<PanicStrategy> = (None, parse_panic_strategy,
         [TRACKED], "panic strategy to compile crate with"),
+    profile_generate: Option<String> = (None, parse_opt_string, [TRACKED],
+        "instrument the generated code to write profiling data into the given \
+         directory, for later use with `-C profile-use`"),
+    profile_use: Option<String> = (None, parse_opt_string, [TRACKED],
+        "use the given `.profdata` file for profile-guided optimization"),
 }
 
 options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
@@ -968,13 +1158,73 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
     incremental_cc: bool = (true, parse_bool, [UNTRACKED],
           "enable cross-crate incremental compilation (even more experimental)"),
     incremental_info: bool = (false, parse_bool, [UNTRACKED],
-        "print high-level information about incremental reuse (or the lack thereof)"),
+        "print high-level information about incremental reuse (or the lack thereof), \
+         and a summary of it at the end of compilation"),
     incremental_dump_hash: bool = (false, parse_bool, [UNTRACKED],
         "dump hash information in textual format to stdout"),
+    incremental_dump_dir: Option<String> = (None, parse_opt_string, [UNTRACKED],
+        "keep a copy of the dep-graph of each incremental session in the given directory"),
+    incremental_explain: Option<String> = (None, parse_opt_string, [UNTRACKED],
+        "for each dep-node matching a filter that is found dirty, print the chain of \
+         dep-graph edges from the input that changed down to it"),
+    incremental_quota: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "the most space, in megabytes, that the incremental compilation directory may \
+         take, evicting least recently used session directories at the end of each \
+         session (default: $RUSTC_INCREMENTAL_QUOTA, or no limit)"),
+    incremental_compression: Option<MetadataCompression> = (None,
+        parse_metadata_compression, [UNTRACKED],
+        "how hard to compress the files saved by incremental compilation (`none`, `fast` \
+         or `best`; default: `fast`)"),
+    diff_dep_graph: Option<String> = (None, parse_opt_string, [UNTRACKED],
+        "compare the dep-graph saved in the given file to the one in the input file, \
+         and exit"),
     dump_dep_graph: bool = (false, parse_bool, [UNTRACKED],
           "dump the dependency graph to $RUST_DEP_GRAPH (default: /tmp/dep_graph.gv)"),
+    dump_dep_graph_dot: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "dump the dependency graph to the given path in graphviz format, with the nodes \
+           colored by kind (filtered by $RUST_DEP_GRAPH_FILTER)"),
+    dump_dep_graph_json: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "dump the dependency graph to the given path as JSON, for external tools \
+           (filtered by $RUST_DEP_GRAPH_FILTER)"),
+    dump_dep_graph_graphml: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "dump the dependency graph to the given path as GraphML, for graph analysis \
+           tools (filtered by $RUST_DEP_GRAPH_FILTER)"),
+    dump_dep_graph_around: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "dump the nodes of the dependency graph within N edges (default: 1) of the nodes \
+           matching a filter, given as `FILTER[,depth=N]`"),
+    dep_graph_find: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "print the nodes of the dependency graph matching a regular expression, with \
+           their direct predecessors and successors"),
     query_dep_graph: bool = (false, parse_bool, [UNTRACKED],
           "enable queries of the dependency graph for regression testing"),
+    dep_message_buffer_size: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+          "number of messages to batch before handing them to the dependency graph thread"),
+    dep_message_buffers: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+          "number of message buffers besides the one being filled that are recycled by \
+           the dependency graph thread (default: 4)"),
+    dep_graph_stats: bool = (false, parse_bool, [UNTRACKED],
+          "print statistics about the messages sent to the dependency graph thread, and \
+           the number of nodes and edges of the graph by kind"),
+    dep_graph_transport: Option<DepGraphTransport> = (None, parse_dep_graph_transport, [UNTRACKED],
+          "how to send messages to the dependency graph thread: batched in recycled \
+           buffers, or one at a time through a lock-free ring buffer (default: buffers)"),
+    dep_graph_synchronous: bool = (false, parse_bool, [UNTRACKED],
+          "build the dependency graph on the compiler thread as messages are sent, \
+           instead of on a separate thread (for debugging)"),
+    dep_graph_shards: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+          "number of threads building the dependency graph for threads other than \
+           the compiler's main thread (default: 0)"),
+    profile_dep_tracking: bool = (false, parse_bool, [UNTRACKED],
+          "print the latency of dependency graph messages and the time spent building \
+           the graph, by kind of node"),
+    dep_graph_shadow: bool = (false, parse_bool, [UNTRACKED],
+          "check the use of the dependency graph as if the compiler had been built \
+           with debug assertions (slow)"),
+    dep_graph_shadow_dump: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "where to write the state of the dependency graph checks if they fail"),
+    dep_graph_task_edges: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+          "print the given number of tasks that made the most reads and writes of the \
+           dependency graph (turns on the checks of `-Z dep-graph-shadow`)"),
     no_analysis: bool = (false, parse_bool, [UNTRACKED],
           "parse and expand the source, but run no analysis"),
     extra_plugins: Vec<String> = (Vec::new(), parse_list, [TRACKED],
@@ -1007,6 +1257,8 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "the directory the MIR is dumped into"),
     dump_mir_exclude_pass_number: bool = (false, parse_bool, [UNTRACKED],
           "if set, exclude the pass number when dumping MIR (used in tests)"),
+    nll: bool = (false, parse_bool, [UNTRACKED],
+          "report how borrowck errors would change with non-lexical lifetimes"),
     perf_stats: bool = (false, parse_bool, [UNTRACKED],
           "print some performance-related statistics"),
     hir_stats: bool = (false, parse_bool, [UNTRACKED],
@@ -1037,6 +1289,25 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "extra arguments to prepend to the linker invocation (space separated)"),
     profile: bool = (false, parse_bool, [TRACKED],
                      "insert profiling code"),
+    symbol_mangling_version: SymbolManglingVersion = (SymbolManglingVersion::Legacy,
+        parse_symbol_mangling_version, [TRACKED],
+        "which mangling scheme to use for symbol names (`legacy` or `v0`)"),
+    embed_bitcode: bool = (false, parse_bool, [TRACKED],
+        "embed LLVM bitcode in object files, so they can later be used for LTO"),
+    linker_plugin_lto: LinkerPluginLto = (LinkerPluginLto::Disabled,
+        parse_linker_plugin_lto, [TRACKED],
+        "emit LLVM bitcode instead of object files and have the linker's LTO plugin \
+         optimize the final artifact, optionally specifying the plugin's path"),
+    metadata_compression: Option<MetadataCompression> = (None,
+        parse_metadata_compression, [TRACKED],
+        "how hard to compress the metadata of dylibs (`none`, `fast` or `best`)"),
+    stop_after: Option<CompilePhase> = (None, parse_compile_phase, [UNTRACKED],
+        "stop compiling after the given phase (`parse`, `expand`, `lower`, `analysis` \
+         or `trans`), without producing the output of any later phase"),
+    verify_metadata: bool = (false, parse_bool, [TRACKED],
+        "record a checksum in the metadata of the crate, and check the checksum of each \
+         crate that is loaded and has one (which reads all of its metadata, rather than \
+         just what is used)"),
 }
 
 pub fn default_lib_output() -> CrateType {
@@ -1258,7 +1529,7 @@ pub fn rustc_short_optgroups() -> Vec<RustcOptGroup> {
                "NAME"),
         opt::multi_s("", "emit", "Comma separated list of types of output for \
                               the compiler to emit",
-                 "[asm|llvm-bc|llvm-ir|obj|metadata|link|dep-info|mir]"),
+                 "[asm|llvm-bc|llvm-ir|obj|metadata|link|dep-info|mir|api-summary]"),
         opt::multi_s("", "print", "Comma separated list of compiler information to \
                                print on stdout",
                      "[crate-name|file-names|sysroot|cfg|target-list|\
@@ -1420,6 +1691,7 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
                     "metadata" => OutputType::Metadata,
                     "link" => OutputType::Exe,
                     "dep-info" => OutputType::DepInfo,
+                    "api-summary" => OutputType::ApiSummary,
                     part => {
                         early_error(error_format, &format!("unknown emission type: `{}`",
                                                     part))
@@ -1476,6 +1748,15 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
         early_error(error_format, "Value for codegen units must be a positive nonzero integer");
     }
 
+    if debugging_opts.linker_plugin_lto.enabled() && cg.lto {
+        early_error(error_format, "options `-Z linker-plugin-lto` and `-C lto` are exclusive");
+    }
+
+    if cg.profile_generate.is_some() && cg.profile_use.is_some() {
+        early_error(error_format, "options `-C profile-generate` and `-C profile-use` \
+                                   are exclusive");
+    }
+
     let mut prints = Vec::<PrintRequest>::new();
     if cg.target_cpu.as_ref().map_or(false, |s| s == "help") {
         prints.push(PrintRequest::TargetCPUs);
@@ -1767,8 +2048,8 @@ mod dep_tracking {
     use std::hash::Hash;
     use std::path::PathBuf;
     use std::collections::hash_map::DefaultHasher;
-    use super::{Passes, CrateType, OptLevel, DebugInfoLevel,
-                OutputTypes, Externs, ErrorOutputType, Sanitizer};
+    use super::{Passes, CrateType, OptLevel, DebugInfoLevel, OutputTypes, Externs,
+                ErrorOutputType, Sanitizer, SymbolManglingVersion, LinkerPluginLto};
     use syntax::feature_gate::UnstableFeatures;
     use rustc_back::PanicStrategy;
 
@@ -1826,6 +2107,9 @@ mod dep_tracking {
     impl_dep_tracking_hash_via_hash!(cstore::NativeLibraryKind);
     impl_dep_tracking_hash_via_hash!(Sanitizer);
     impl_dep_tracking_hash_via_hash!(Option<Sanitizer>);
+    impl_dep_tracking_hash_via_hash!(SymbolManglingVersion);
+    impl_dep_tracking_hash_via_hash!(LinkerPluginLto);
+    impl_dep_tracking_hash_via_hash!(Option<MetadataCompression>);
 
     impl_dep_tracking_hash_for_sortable_vec_of!(String);
     impl_dep_tracking_hash_for_sortable_vec_of!(CrateType);
@@ -1898,7 +2182,7 @@ mod tests {
     use std::path::PathBuf;
     use std::rc::Rc;
     use super::{OutputType, OutputTypes, Externs};
-    use rustc_back::PanicStrategy;
+    use rustc_back::{LinkerFlavor, PanicStrategy};
     use syntax::symbol::Symbol;
 
     fn optgroups() -> getopts::Options {
@@ -2269,6 +2553,40 @@ mod tests {
         assert_eq!(v4.dep_tracking_hash(), v4.clone().dep_tracking_hash());
     }
 
+    #[test]
+    fn test_incremental_tracking_hash_remapped_paths() {
+        let mut v1 = super::basic_options();
+        let mut v2 = super::basic_options();
+        let mut v3 = super::basic_options();
+
+        // The same workspace, checked out at different locations
+        v1.debugging_opts.remap_path_prefix_from = vec![String::from("/a/ws")];
+        v1.debugging_opts.remap_path_prefix_to = vec![String::from("/ws")];
+        v1.search_paths.add_path("dependency=/a/ws/deps", super::ErrorOutputType::Json);
+        v1.externs = Externs::new(mk_map(vec![
+            (String::from("b"), mk_set(vec![String::from("/a/ws/deps/libb.rlib")])),
+        ]));
+
+        v2.debugging_opts.remap_path_prefix_from = vec![String::from("/c/ws")];
+        v2.debugging_opts.remap_path_prefix_to = vec![String::from("/ws")];
+        v2.search_paths.add_path("dependency=/c/ws/deps", super::ErrorOutputType::Json);
+        v2.externs = Externs::new(mk_map(vec![
+            (String::from("b"), mk_set(vec![String::from("/c/ws/deps/libb.rlib")])),
+        ]));
+
+        // A path outside of the mapped prefix changed
+        v3.debugging_opts.remap_path_prefix_from = vec![String::from("/a/ws")];
+        v3.debugging_opts.remap_path_prefix_to = vec![String::from("/ws")];
+        v3.search_paths.add_path("dependency=/c/ws/deps", super::ErrorOutputType::Json);
+        v3.externs = Externs::new(mk_map(vec![
+            (String::from("b"), mk_set(vec![String::from("/a/ws/deps/libb.rlib")])),
+        ]));
+
+        assert!(v1.dep_tracking_hash() != v2.dep_tracking_hash());
+        assert_eq!(v1.incremental_dep_tracking_hash(), v2.incremental_dep_tracking_hash());
+        assert!(v1.incremental_dep_tracking_hash() != v3.incremental_dep_tracking_hash());
+    }
+
     #[test]
     fn test_native_libs_tracking_hash_different_values() {
         let mut v1 = super::basic_options();
@@ -2348,6 +2666,12 @@ mod tests {
         opts.cg.linker = Some(String::from("linker"));
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
 
+        opts.cg.linker_flavor = Some(LinkerFlavor::Lld);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+
+        opts.cg.link_arg = vec![String::from("-nostartfiles")];
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+
         opts.cg.link_args = Some(vec![String::from("abc"), String::from("def")]);
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
 
@@ -2455,6 +2779,14 @@ mod tests {
         opts = reference.clone();
         opts.cg.panic = Some(PanicStrategy::Abort);
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.cg.profile_generate = Some(String::from("abc"));
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.cg.profile_use = Some(String::from("abc"));
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
     }
 
     #[test]
@@ -2477,6 +2809,8 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.borrowck_stats = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.nll = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.debug_llvm = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.meta_stats = true;
@@ -2491,6 +2825,16 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.ls = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.incremental_dump_dir = Some(String::from("dep-graphs"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.incremental_explain = Some(String::from("TypeckTables(*foo*)"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.incremental_quota = Some(4096);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.incremental_compression = Some(super::MetadataCompression::Best);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.diff_dep_graph = Some(String::from("old.bin"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.save_analysis = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.save_analysis_api = true;
@@ -2513,10 +2857,42 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.dump_dep_graph = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dump_dep_graph_dot = Some(String::from("dep-graph.dot"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dump_dep_graph_json = Some(String::from("dep-graph.json"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dump_dep_graph_graphml = Some(String::from("dep-graph.graphml"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dump_dep_graph_around = Some(String::from("Hir(*foo*),depth=2"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dep_graph_find = Some(String::from("^TypeckTables\\(.*::bar\\)$"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.query_dep_graph = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dep_message_buffer_size = Some(16384);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dep_message_buffers = Some(4);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dep_graph_stats = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dep_graph_transport = Some(super::DepGraphTransport::Ring);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dep_graph_synchronous = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dep_graph_shards = Some(4);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.profile_dep_tracking = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dep_graph_shadow = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dep_graph_shadow_dump = Some(String::from("shadow.txt"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dep_graph_task_edges = Some(20);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.no_analysis = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.stop_after = Some(super::CompilePhase::Analysis);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.unstable_options = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.trace_macros = true;
@@ -2576,5 +2952,25 @@ mod tests {
         opts = reference.clone();
         opts.debugging_opts.mir_opt_level = 3;
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.symbol_mangling_version = super::SymbolManglingVersion::V0;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.embed_bitcode = true;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.linker_plugin_lto = super::LinkerPluginLto::LinkerPluginAuto;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.metadata_compression = Some(super::MetadataCompression::None);
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.verify_metadata = true;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
     }
 }
@@ -42,6 +42,7 @@ use rustc_data_structures::flock;
 use jobserver::Client;
 
 use std::cell::{self, Cell, RefCell};
+use std::cmp;
 use std::collections::HashMap;
 use std::env;
 use std::fmt;
@@ -120,6 +121,9 @@ pub struct Session {
     /// Some measurements that are being gathered during compilation.
     pub perf_stats: PerfStats,
 
+    /// What incremental compilation could re-use, and what it cost.
+    pub incr_comp_stats: IncrCompStats,
+
     /// Data about code being compiled, gathered during compilation.
     pub code_stats: RefCell<CodeStats>,
 
@@ -145,6 +149,10 @@ pub struct Session {
 
     /// Metadata about the allocators for the current crate being compiled
     pub has_global_allocator: Cell<bool>,
+
+    /// Whether an incremental compilation artifact has been ignored for not
+    /// matching the compiler, which is only noted once per session.
+    pub incr_comp_artifact_ignored: Cell<bool>,
 }
 
 pub struct PerfStats {
@@ -158,10 +166,36 @@ pub struct PerfStats {
     pub incr_comp_bytes_hashed: Cell<u64>,
     // The accumulated time spent on computing symbol hashes
     pub symbol_hash_time: Cell<Duration>,
+    // The number of symbol hashes computed
+    pub symbol_hash_count: Cell<u64>,
     // The accumulated time spent decoding def path tables from metadata
     pub decode_def_path_tables_time: Cell<Duration>,
+    // The number of queries answered by decoding crate metadata
+    pub metadata_queries_count: Cell<u64>,
+    // The number of types found in, or newly added to, the type interners
+    pub ty_interning_hits: Cell<u64>,
+    pub ty_interning_misses: Cell<u64>,
+    // The number of region obligations processed by regionck
+    pub region_obligations_count: Cell<u64>,
+}
+
+pub struct IncrCompStats {
+    // The number of nodes in the dep-graph of the previous session, how
+    // many of them were found dirty, and how many red/green marking found
+    // unchanged although some of their inputs had changed
+    pub nodes_loaded: Cell<u64>,
+    pub nodes_dirty: Cell<u64>,
+    pub nodes_green: Cell<u64>,
+    // The number of codegen units re-used from the previous session, and the
+    // number of those that had to be translated
+    pub modules_reused: Cell<u64>,
+    pub modules_translated: Cell<u64>,
+    // The accumulated time spent loading and saving the incr. comp. cache
+    pub load_time: Cell<Duration>,
+    pub save_time: Cell<Duration>,
 }
 
+
 /// Enum to support dispatch of one-time diagnostics (in Session.diag_once)
 enum DiagnosticBuilderMethod {
     Note,
@@ -419,7 +453,9 @@ impl Session {
         self.opts.cg.panic.unwrap_or(self.target.target.options.panic_strategy)
     }
     pub fn linker_flavor(&self) -> LinkerFlavor {
-        self.opts.debugging_opts.linker_flavor.unwrap_or(self.target.target.linker_flavor)
+        self.opts.cg.linker_flavor
+            .or(self.opts.debugging_opts.linker_flavor)
+            .unwrap_or(self.target.target.linker_flavor)
     }
     pub fn no_landing_pads(&self) -> bool {
         self.opts.debugging_opts.no_landing_pads || self.panic_strategy() == PanicStrategy::Abort
@@ -555,11 +591,39 @@ impl Session {
                  self.perf_stats.incr_comp_bytes_hashed.get());
         println!("Average bytes hashed per incr. comp. HIR node: {}",
                  self.perf_stats.incr_comp_bytes_hashed.get() /
-                 self.perf_stats.incr_comp_hashes_count.get());
+                 cmp::max(self.perf_stats.incr_comp_hashes_count.get(), 1));
         println!("Total time spent computing symbol hashes:      {}",
                  duration_to_secs_str(self.perf_stats.symbol_hash_time.get()));
+        println!("Total number of symbol hashes computed:        {}",
+                 self.perf_stats.symbol_hash_count.get());
         println!("Total time spent decoding DefPath tables:      {}",
                  duration_to_secs_str(self.perf_stats.decode_def_path_tables_time.get()));
+        println!("Total number of queries answered by metadata:  {}",
+                 self.perf_stats.metadata_queries_count.get());
+        println!("Type interning hits / misses:                  {} / {}",
+                 self.perf_stats.ty_interning_hits.get(),
+                 self.perf_stats.ty_interning_misses.get());
+        println!("Total number of region obligations processed:  {}",
+                 self.perf_stats.region_obligations_count.get());
+    }
+
+    /// Prints the summary of `-Z incremental-info`.
+    pub fn print_incr_comp_stats(&self) {
+        let stats = &self.incr_comp_stats;
+        let nodes_loaded = stats.nodes_loaded.get();
+        let nodes_dirty = stats.nodes_dirty.get();
+        eprintln!("incremental: {} dep-nodes loaded: {} clean, {} dirty",
+                  nodes_loaded,
+                  nodes_loaded - nodes_dirty,
+                  nodes_dirty);
+        eprintln!("incremental: {} dep-nodes kept clean by red/green marking",
+                  stats.nodes_green.get());
+        eprintln!("incremental: {} modules re-used, {} translated",
+                  stats.modules_reused.get(),
+                  stats.modules_translated.get());
+        eprintln!("incremental: {} spent loading, {} saving",
+                  duration_to_secs_str(stats.load_time.get()),
+                  duration_to_secs_str(stats.save_time.get()));
     }
 
     /// We want to know if we're allowed to do an optimization for crate foo from -z fuel=foo=n.
@@ -730,7 +794,21 @@ pub fn build_session_(sopts: config::Options,
             incr_comp_hashes_count: Cell::new(0),
             incr_comp_bytes_hashed: Cell::new(0),
             symbol_hash_time: Cell::new(Duration::from_secs(0)),
+            symbol_hash_count: Cell::new(0),
             decode_def_path_tables_time: Cell::new(Duration::from_secs(0)),
+            metadata_queries_count: Cell::new(0),
+            ty_interning_hits: Cell::new(0),
+            ty_interning_misses: Cell::new(0),
+            region_obligations_count: Cell::new(0),
+        },
+        incr_comp_stats: IncrCompStats {
+            nodes_loaded: Cell::new(0),
+            nodes_dirty: Cell::new(0),
+            nodes_green: Cell::new(0),
+            modules_reused: Cell::new(0),
+            modules_translated: Cell::new(0),
+            load_time: Cell::new(Duration::from_secs(0)),
+            save_time: Cell::new(Duration::from_secs(0)),
         },
         code_stats: RefCell::new(CodeStats::new()),
         optimization_fuel_crate,
@@ -756,6 +834,7 @@ pub fn build_session_(sopts: config::Options,
             (*GLOBAL_JOBSERVER).clone()
         },
         has_global_allocator: Cell::new(false),
+        incr_comp_artifact_ignored: Cell::new(false),
     };
 
     sess
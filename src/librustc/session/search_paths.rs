@@ -60,6 +60,15 @@ impl SearchPaths {
     pub fn iter(&self, kind: PathKind) -> Iter {
         Iter { kind: kind, iter: self.paths.iter() }
     }
+
+    /// Returns a copy of these search paths, with `f` applied to every path.
+    pub fn map_paths<F>(&self, f: F) -> SearchPaths
+        where F: Fn(&Path) -> PathBuf
+    {
+        SearchPaths {
+            paths: self.paths.iter().map(|&(kind, ref path)| (kind, f(path))).collect(),
+        }
+    }
 }
 
 impl<'a> Iterator for Iter<'a> {
@@ -30,6 +30,7 @@ use super::{VtableImplData, VtableObjectData, VtableBuiltinData,
             VtableClosureData, VtableDefaultImplData, VtableFnPointerData};
 use super::util;
 
+use dep_graph::debug::DepLocation;
 use hir::def_id::DefId;
 use infer;
 use infer::{InferCtxt, InferOk, TypeFreshener};
@@ -467,7 +468,7 @@ impl<'cx, 'gcx, 'tcx> SelectionContext<'cx, 'gcx, 'tcx> {
 
         let tcx = self.tcx();
         let dep_node = obligation.predicate.dep_node(tcx);
-        let _task = tcx.dep_graph.in_task(dep_node);
+        let _task = tcx.dep_graph.in_task_at(dep_node, dep_location!());
 
         let stack = self.push_stack(TraitObligationStackList::empty(), obligation);
         let ret = match self.candidate_from_obligation(&stack)? {
@@ -703,7 +704,9 @@ impl<'cx, 'gcx, 'tcx> SelectionContext<'cx, 'gcx, 'tcx> {
 
         let stack = self.push_stack(previous_stack, &obligation);
         let fresh_trait_ref = stack.fresh_trait_ref;
-        if let Some(result) = self.check_evaluation_cache(obligation.param_env, fresh_trait_ref) {
+        if let Some(result) = self.check_evaluation_cache(obligation.param_env,
+                                                          fresh_trait_ref,
+                                                          dep_location!()) {
             debug!("CACHE HIT: EVAL({:?})={:?}",
                    fresh_trait_ref,
                    result);
@@ -867,7 +870,8 @@ impl<'cx, 'gcx, 'tcx> SelectionContext<'cx, 'gcx, 'tcx> {
 
     fn check_evaluation_cache(&self,
                               param_env: ty::ParamEnv<'tcx>,
-                              trait_ref: ty::PolyTraitRef<'tcx>)
+                              trait_ref: ty::PolyTraitRef<'tcx>,
+                              location: &'static DepLocation)
                               -> Option<EvaluationResult>
     {
         if self.can_use_global_caches(param_env) {
@@ -876,7 +880,7 @@ impl<'cx, 'gcx, 'tcx> SelectionContext<'cx, 'gcx, 'tcx> {
                 let dep_node = trait_ref
                     .to_poly_trait_predicate()
                     .dep_node(self.tcx());
-                self.tcx().hir.dep_graph.read(dep_node);
+                self.tcx().hir.dep_graph.read_at(dep_node, location);
                 return Some(cached.clone());
             }
         }
@@ -11,7 +11,7 @@
 //! type context book-keeping
 
 use dep_graph::DepGraph;
-use session::Session;
+use session::{Session, PerfStats};
 use lint;
 use middle;
 use hir::TraitMap;
@@ -40,7 +40,7 @@ use ty::layout::{Layout, TargetDataLayout};
 use ty::inhabitedness::DefIdForest;
 use ty::maps;
 use ty::steal::Steal;
-use util::nodemap::{NodeMap, NodeSet, DefIdSet};
+use util::nodemap::{NodeMap, NodeSet, DefIdSet, ItemLocalMap};
 use util::nodemap::{FxHashMap, FxHashSet};
 use rustc_data_structures::accumulate_vec::AccumulateVec;
 
@@ -120,7 +120,8 @@ impl<'gcx: 'tcx, 'tcx> CtxtInterners<'tcx> {
     /// Intern a type. global_interners is Some only if this is
     /// a local interner and global_interners is its counterpart.
     fn intern_ty(&self, st: TypeVariants<'tcx>,
-                 global_interners: Option<&CtxtInterners<'gcx>>)
+                 global_interners: Option<&CtxtInterners<'gcx>>,
+                 perf_stats: Option<&PerfStats>)
                  -> Ty<'tcx> {
         let ty = {
             let mut interner = self.type_.borrow_mut();
@@ -128,13 +129,22 @@ impl<'gcx: 'tcx, 'tcx> CtxtInterners<'tcx> {
                 interners.type_.borrow_mut()
             });
             if let Some(&Interned(ty)) = interner.get(&st) {
+                if let Some(stats) = perf_stats {
+                    stats.ty_interning_hits.set(stats.ty_interning_hits.get() + 1);
+                }
                 return ty;
             }
             if let Some(ref interner) = global_interner {
                 if let Some(&Interned(ty)) = interner.get(&st) {
+                    if let Some(stats) = perf_stats {
+                        stats.ty_interning_hits.set(stats.ty_interning_hits.get() + 1);
+                    }
                     return ty;
                 }
             }
+            if let Some(stats) = perf_stats {
+                stats.ty_interning_misses.set(stats.ty_interning_misses.get() + 1);
+            }
 
             let flags = super::flags::FlagComputation::for_sty(&st);
             let ty_struct = TyS {
@@ -204,8 +214,74 @@ pub struct CommonTypes<'tcx> {
     pub re_erased: Region<'tcx>,
 }
 
+/// Checks that `hir_id` belongs to the item-like `local_id_root` stands for,
+/// so that the `ItemLocalId`s of two different owners never get mixed up.
+fn validate_hir_id_for_typeck_tables(local_id_root: Option<DefId>, hir_id: hir::HirId) {
+    if let Some(local_id_root) = local_id_root {
+        if hir_id.owner != local_id_root.index {
+            bug!("node with HirId::owner {:?} cannot be placed in \
+                  TypeckTables with local_id_root {:?}",
+                 hir_id.owner,
+                 local_id_root)
+        }
+    }
+
+    // Tables without a `local_id_root` are the empty placeholders used
+    // outside of bodies, any lookup in them simply finds nothing.
+}
+
+/// Read access to one of the `ItemLocalId`-keyed tables of `TypeckTables`,
+/// looked up by `HirId`.
+pub struct LocalTableInContext<'a, V: 'a> {
+    local_id_root: Option<DefId>,
+    data: &'a ItemLocalMap<V>
+}
+
+impl<'a, V> LocalTableInContext<'a, V> {
+    pub fn contains_key(&self, id: hir::HirId) -> bool {
+        validate_hir_id_for_typeck_tables(self.local_id_root, id);
+        self.data.contains_key(&id.local_id)
+    }
+
+    pub fn get(&self, id: hir::HirId) -> Option<&V> {
+        validate_hir_id_for_typeck_tables(self.local_id_root, id);
+        self.data.get(&id.local_id)
+    }
+}
+
+impl<'a, V> ::std::ops::Index<hir::HirId> for LocalTableInContext<'a, V> {
+    type Output = V;
+
+    fn index(&self, key: hir::HirId) -> &V {
+        self.get(key).expect("LocalTableInContext: key not found")
+    }
+}
+
+/// Write access to one of the `ItemLocalId`-keyed tables of `TypeckTables`.
+pub struct LocalTableInContextMut<'a, V: 'a> {
+    local_id_root: Option<DefId>,
+    data: &'a mut ItemLocalMap<V>
+}
+
+impl<'a, V> LocalTableInContextMut<'a, V> {
+    pub fn get_mut(&mut self, id: hir::HirId) -> Option<&mut V> {
+        validate_hir_id_for_typeck_tables(self.local_id_root, id);
+        self.data.get_mut(&id.local_id)
+    }
+
+    pub fn insert(&mut self, id: hir::HirId, val: V) -> Option<V> {
+        validate_hir_id_for_typeck_tables(self.local_id_root, id);
+        self.data.insert(id.local_id, val)
+    }
+}
+
 #[derive(RustcEncodable, RustcDecodable)]
 pub struct TypeckTables<'tcx> {
+    /// The `HirId::owner` all the `ItemLocalId`-keyed tables below are
+    /// relative to. Only tables for a body have one; tables used as a
+    /// placeholder outside of any body are empty and have `None` here.
+    pub local_id_root: Option<DefId>,
+
     /// Resolved definitions for `<T>::X` associated paths and
     /// method calls, including those of overloaded operators.
     pub type_dependent_defs: NodeMap<Def>,
@@ -238,17 +314,17 @@ pub struct TypeckTables<'tcx> {
     /// (including late-bound regions) are replaced with free
     /// equivalents. This table is not used in trans (since regions
     /// are erased there) and hence is not serialized to metadata.
-    pub liberated_fn_sigs: NodeMap<ty::FnSig<'tcx>>,
+    pub liberated_fn_sigs: ItemLocalMap<ty::FnSig<'tcx>>,
 
     /// For each FRU expression, record the normalized types of the fields
     /// of the struct - this is needed because it is non-trivial to
     /// normalize while preserving regions. This table is used only in
     /// MIR construction and hence is not serialized to metadata.
-    pub fru_field_types: NodeMap<Vec<Ty<'tcx>>>,
+    pub fru_field_types: ItemLocalMap<Vec<Ty<'tcx>>>,
 
     /// Maps a cast expression to its kind. This is keyed on the
     /// *from* expression of the cast, not the cast itself.
-    pub cast_kinds: NodeMap<ty::cast::CastKind>,
+    pub cast_kinds: ItemLocalMap<ty::cast::CastKind>,
 
     /// Lints for the body of this fn generated by typeck.
     pub lints: lint::LintTable,
@@ -268,8 +344,9 @@ pub struct TypeckTables<'tcx> {
 }
 
 impl<'tcx> TypeckTables<'tcx> {
-    pub fn empty() -> TypeckTables<'tcx> {
+    pub fn empty(local_id_root: Option<DefId>) -> TypeckTables<'tcx> {
         TypeckTables {
+            local_id_root,
             type_dependent_defs: NodeMap(),
             node_types: FxHashMap(),
             node_substs: NodeMap(),
@@ -277,9 +354,9 @@ impl<'tcx> TypeckTables<'tcx> {
             upvar_capture_map: FxHashMap(),
             closure_tys: NodeMap(),
             closure_kinds: NodeMap(),
-            liberated_fn_sigs: NodeMap(),
-            fru_field_types: NodeMap(),
-            cast_kinds: NodeMap(),
+            liberated_fn_sigs: ItemLocalMap(),
+            fru_field_types: ItemLocalMap(),
+            cast_kinds: ItemLocalMap(),
             lints: lint::LintTable::new(),
             used_trait_imports: DefIdSet(),
             tainted_by_errors: false,
@@ -297,6 +374,48 @@ impl<'tcx> TypeckTables<'tcx> {
         }
     }
 
+    pub fn liberated_fn_sigs(&self) -> LocalTableInContext<ty::FnSig<'tcx>> {
+        LocalTableInContext {
+            local_id_root: self.local_id_root,
+            data: &self.liberated_fn_sigs
+        }
+    }
+
+    pub fn liberated_fn_sigs_mut(&mut self) -> LocalTableInContextMut<ty::FnSig<'tcx>> {
+        LocalTableInContextMut {
+            local_id_root: self.local_id_root,
+            data: &mut self.liberated_fn_sigs
+        }
+    }
+
+    pub fn fru_field_types(&self) -> LocalTableInContext<Vec<Ty<'tcx>>> {
+        LocalTableInContext {
+            local_id_root: self.local_id_root,
+            data: &self.fru_field_types
+        }
+    }
+
+    pub fn fru_field_types_mut(&mut self) -> LocalTableInContextMut<Vec<Ty<'tcx>>> {
+        LocalTableInContextMut {
+            local_id_root: self.local_id_root,
+            data: &mut self.fru_field_types
+        }
+    }
+
+    pub fn cast_kinds(&self) -> LocalTableInContext<ty::cast::CastKind> {
+        LocalTableInContext {
+            local_id_root: self.local_id_root,
+            data: &self.cast_kinds
+        }
+    }
+
+    pub fn cast_kinds_mut(&mut self) -> LocalTableInContextMut<ty::cast::CastKind> {
+        LocalTableInContextMut {
+            local_id_root: self.local_id_root,
+            data: &mut self.cast_kinds
+        }
+    }
+
     pub fn node_id_to_type(&self, id: NodeId) -> Ty<'tcx> {
         match self.node_id_to_type_opt(id) {
             Some(ty) => ty,
@@ -383,7 +502,7 @@ impl<'tcx> TypeckTables<'tcx> {
 
 impl<'tcx> CommonTypes<'tcx> {
     fn new(interners: &CtxtInterners<'tcx>) -> CommonTypes<'tcx> {
-        let mk = |sty| interners.intern_ty(sty, None);
+        let mk = |sty| interners.intern_ty(sty, None, None);
         let mk_region = |r| {
             if let Some(r) = interners.region.borrow().get(&r) {
                 return r.0;
@@ -1256,7 +1375,7 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
         } else {
             None
         };
-        self.interners.intern_ty(st, global_interners)
+        self.interners.intern_ty(st, global_interners, Some(&self.sess.perf_stats))
     }
 
     pub fn mk_mach_int(self, tm: ast::IntTy) -> Ty<'tcx> {
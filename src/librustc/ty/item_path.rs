@@ -224,8 +224,10 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
             let force_no_types = FORCE_IMPL_FILENAME_LINE.with(|f| f.get());
             !force_no_types && {
                 // Otherwise, use types if we can query them without inducing a cycle.
-                ty::queries::impl_trait_ref::try_get(self, DUMMY_SP, impl_def_id).is_ok() &&
-                    ty::queries::type_of::try_get(self, DUMMY_SP, impl_def_id).is_ok()
+                ty::queries::impl_trait_ref::try_get_at(self, DUMMY_SP, impl_def_id,
+                                                        dep_location!()).is_ok() &&
+                    ty::queries::type_of::try_get_at(self, DUMMY_SP, impl_def_id,
+                                                     dep_location!()).is_ok()
             }
         });
 
@@ -9,9 +9,12 @@
 // except according to those terms.
 
 use dep_graph::{DepConstructor, DepNode, DepTrackingMapConfig};
+use dep_graph::debug::DepLocation;
 use hir::def_id::{CrateNum, CRATE_DEF_INDEX, DefId, LOCAL_CRATE};
 use hir::def::Def;
 use hir;
+use hir::map as hir_map;
+use ich::{Fingerprint, StableHashingContext};
 use middle::const_val;
 use middle::cstore::{ExternCrate, LinkagePreference};
 use middle::privacy::AccessLevels;
@@ -30,6 +33,7 @@ use util::nodemap::{DefIdSet, NodeSet};
 
 use rustc_data_structures::indexed_vec::IndexVec;
 use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
 use std::cell::{RefCell, RefMut};
 use std::fmt::Debug;
 use std::hash::Hash;
@@ -565,6 +569,7 @@ macro_rules! define_maps {
             fn try_get_with<F, R>(tcx: TyCtxt<'a, $tcx, 'lcx>,
                                   mut span: Span,
                                   key: $K,
+                                  location: Option<&'static DepLocation>,
                                   f: F)
                                   -> Result<R, CycleError<'a, $tcx>>
                 where F: FnOnce(&$V) -> R
@@ -585,9 +590,15 @@ macro_rules! define_maps {
                     span = key.default_span(tcx)
                 }
 
-                let _task = tcx.dep_graph.in_task(Self::to_dep_node(tcx, &key));
-
+                // The task is only opened once we know there is no cycle, so
+                // that a cycle is reported as such rather than as a task
+                // pushed while it is open.
                 let result = tcx.cycle_check(span, Query::$name(key), || {
+                    let dep_node = Self::to_dep_node(tcx, &key);
+                    let _task = match location {
+                        Some(location) => tcx.dep_graph.in_task_at(dep_node, location),
+                        None => tcx.dep_graph.in_task(dep_node),
+                    };
                     let provider = tcx.maps.providers[key.map_crate()].$name;
                     provider(tcx.global_tcx(), key)
                 })?;
@@ -597,15 +608,37 @@ macro_rules! define_maps {
 
             pub fn try_get(tcx: TyCtxt<'a, $tcx, 'lcx>, span: Span, key: $K)
                            -> Result<$V, CycleError<'a, $tcx>> {
+                Self::try_get_from(tcx, span, key, None)
+            }
+
+            /// Like `try_get`, naming the caller (given by `dep_location!()`)
+            /// in the errors of the shadow graph that concern this query.
+            pub fn try_get_at(tcx: TyCtxt<'a, $tcx, 'lcx>,
+                              span: Span,
+                              key: $K,
+                              location: &'static DepLocation)
+                              -> Result<$V, CycleError<'a, $tcx>> {
+                Self::try_get_from(tcx, span, key, Some(location))
+            }
+
+            fn try_get_from(tcx: TyCtxt<'a, $tcx, 'lcx>,
+                            span: Span,
+                            key: $K,
+                            location: Option<&'static DepLocation>)
+                            -> Result<$V, CycleError<'a, $tcx>> {
                 // We register the `read` here, but not in `force`, since
                 // `force` does not give access to the value produced (and thus
                 // we actually don't read it).
-                tcx.dep_graph.read(Self::to_dep_node(tcx, &key));
-                Self::try_get_with(tcx, span, key, Clone::clone)
+                let dep_node = Self::to_dep_node(tcx, &key);
+                match location {
+                    Some(location) => tcx.dep_graph.read_at(dep_node, location),
+                    None => tcx.dep_graph.read(dep_node),
+                }
+                Self::try_get_with(tcx, span, key, location, Clone::clone)
             }
 
             pub fn force(tcx: TyCtxt<'a, $tcx, 'lcx>, span: Span, key: $K) {
-                match Self::try_get_with(tcx, span, key, |_| ()) {
+                match Self::try_get_with(tcx, span, key, None, |_| ()) {
                     Ok(()) => {}
                     Err(e) => tcx.report_cycle(e)
                 }
@@ -616,6 +649,7 @@ macro_rules! define_maps {
         pub struct TyCtxtAt<'a, 'gcx: 'a+'tcx, 'tcx: 'a> {
             pub tcx: TyCtxt<'a, 'gcx, 'tcx>,
             pub span: Span,
+            pub location: Option<&'static DepLocation>,
         }
 
         impl<'a, 'gcx, 'tcx> Deref for TyCtxtAt<'a, 'gcx, 'tcx> {
@@ -631,7 +665,20 @@ macro_rules! define_maps {
             pub fn at(self, span: Span) -> TyCtxtAt<'a, $tcx, 'lcx> {
                 TyCtxtAt {
                     tcx: self,
-                    span
+                    span,
+                    location: None,
+                }
+            }
+
+            /// Like `at`, also naming the caller (given by `dep_location!()`)
+            /// in the errors of the shadow graph that concern the queries
+            /// performed through it.
+            pub fn at_location(self, span: Span, location: &'static DepLocation)
+                               -> TyCtxtAt<'a, $tcx, 'lcx> {
+                TyCtxtAt {
+                    tcx: self,
+                    span,
+                    location: Some(location),
                 }
             }
 
@@ -644,10 +691,11 @@ macro_rules! define_maps {
         impl<'a, $tcx, 'lcx> TyCtxtAt<'a, $tcx, 'lcx> {
             $($(#[$attr])*
             pub fn $name(self, key: $K) -> $V {
-                queries::$name::try_get(self.tcx, self.span, key).unwrap_or_else(|e| {
-                    self.report_cycle(e);
-                    Value::from_cycle_error(self.global_tcx())
-                })
+                queries::$name::try_get_from(self.tcx, self.span, key, self.location)
+                    .unwrap_or_else(|e| {
+                        self.report_cycle(e);
+                        Value::from_cycle_error(self.global_tcx())
+                    })
             })*
         }
 
@@ -1050,3 +1098,211 @@ fn layout_dep_node<'tcx>(key: ty::ParamEnvAnd<'tcx, Ty<'tcx>>) -> DepConstructor
         .unwrap_or(DefId::local(CRATE_DEF_INDEX));
     DepConstructor::Layout(def_id)
 }
+
+/// The queries recorded in `ItemSignature` nodes whose results can be
+/// fingerprinted (see `SignatureFingerprint`). The others, `closure_kind`
+/// and `coerce_unsized_info`, keep a node from being fingerprinted.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, RustcEncodable, RustcDecodable)]
+pub enum SignatureQuery {
+    TypeOf,
+    GenericsOf,
+    PredicatesOf,
+    SuperPredicatesOf,
+    TraitDef,
+    AdtDef,
+    IsDefaultImpl,
+    ImplTraitRef,
+    ImplPolarity,
+    FnSig,
+}
+
+const SIGNATURE_QUERIES: &'static [SignatureQuery] = &[
+    SignatureQuery::TypeOf,
+    SignatureQuery::GenericsOf,
+    SignatureQuery::PredicatesOf,
+    SignatureQuery::SuperPredicatesOf,
+    SignatureQuery::TraitDef,
+    SignatureQuery::AdtDef,
+    SignatureQuery::IsDefaultImpl,
+    SignatureQuery::ImplTraitRef,
+    SignatureQuery::ImplPolarity,
+    SignatureQuery::FnSig,
+];
+
+/// The results of the `ItemSignature` node of a local item, as computed in
+/// some session. Incremental compilation saves it along with the dep-graph,
+/// so that the next session can tell whether the node actually changed
+/// when some of its inputs did, and spare the nodes depending on it if not.
+#[derive(Clone, Debug, RustcEncodable, RustcDecodable)]
+pub struct SignatureFingerprint {
+    /// What kind of HIR node the item was. The queries are only computed
+    /// again for the same kind of node, since most of them ICE on others.
+    pub kind: Fingerprint,
+
+    /// The queries that had been computed for the item, which are all those
+    /// the node can have been read through.
+    pub queries: Vec<SignatureQuery>,
+
+    /// The hash of the results of `queries`.
+    pub results: Fingerprint,
+}
+
+impl<'a, 'tcx> TyCtxt<'a, 'tcx, 'tcx> {
+    /// Fingerprints the `ItemSignature` node of `def_id` as computed so far.
+    /// Returns `None` if `def_id` is not a local item, if it is a closure or
+    /// an `impl Trait` type, or if the node was computed by a query that is
+    /// not a `SignatureQuery`.
+    pub fn signature_fingerprint(self, def_id: DefId) -> Option<SignatureFingerprint> {
+        let kind = match self.signature_kind(def_id) {
+            Some(kind) => kind,
+            None => return None,
+        };
+        if self.maps.closure_kind.borrow().map.contains_key(&def_id) ||
+           self.maps.coerce_unsized_info.borrow().map.contains_key(&def_id) {
+            return None;
+        }
+
+        let queries: Vec<_> = SIGNATURE_QUERIES.iter()
+                                               .cloned()
+                                               .filter(|&query| {
+                                                   self.signature_query_computed(query, def_id)
+                                               })
+                                               .collect();
+        if queries.is_empty() {
+            return None;
+        }
+
+        let results = self.signature_results(def_id, &queries);
+        Some(SignatureFingerprint { kind, queries, results })
+    }
+
+    /// Checks whether the `ItemSignature` node of `def_id` still has the
+    /// results fingerprinted as `prev` in a previous session, computing the
+    /// queries it was made of if they have not been computed yet.
+    pub fn signature_unchanged(self, def_id: DefId, prev: &SignatureFingerprint) -> bool {
+        if self.signature_kind(def_id) != Some(prev.kind) {
+            return false;
+        }
+        self.signature_results(def_id, &prev.queries) == prev.results
+    }
+
+    /// Hashes what kind of HIR node `def_id` is, if it is a local item whose
+    /// signature is computed from its own HIR. That leaves out closures and
+    /// `impl Trait` types, whose signatures come from type-checking the body
+    /// they are found in.
+    fn signature_kind(self, def_id: DefId) -> Option<Fingerprint> {
+        let mut hasher = StableHasher::new();
+        match self.hir.get_if_local(def_id) {
+            Some(hir_map::NodeItem(item)) => {
+                0u8.hash(&mut hasher);
+                mem::discriminant(&item.node).hash(&mut hasher);
+            }
+            Some(hir_map::NodeForeignItem(item)) => {
+                1u8.hash(&mut hasher);
+                mem::discriminant(&item.node).hash(&mut hasher);
+            }
+            Some(hir_map::NodeTraitItem(item)) => {
+                2u8.hash(&mut hasher);
+                mem::discriminant(&item.node).hash(&mut hasher);
+                // `type_of` only applies to associated types with a default.
+                if let hir::TraitItemKind::Type(_, ref default) = item.node {
+                    default.is_some().hash(&mut hasher);
+                }
+            }
+            Some(hir_map::NodeImplItem(item)) => {
+                3u8.hash(&mut hasher);
+                mem::discriminant(&item.node).hash(&mut hasher);
+            }
+            Some(hir_map::NodeVariant(_)) => {
+                4u8.hash(&mut hasher);
+            }
+            Some(hir_map::NodeField(_)) => {
+                5u8.hash(&mut hasher);
+            }
+            Some(hir_map::NodeStructCtor(data)) => {
+                6u8.hash(&mut hasher);
+                mem::discriminant(data).hash(&mut hasher);
+            }
+            Some(hir_map::NodeTyParam(_)) => {
+                7u8.hash(&mut hasher);
+            }
+            _ => return None,
+        }
+        Some(hasher.finish())
+    }
+
+    fn signature_query_computed(self, query: SignatureQuery, def_id: DefId) -> bool {
+        match query {
+            SignatureQuery::TypeOf => {
+                self.maps.type_of.borrow().map.contains_key(&def_id)
+            }
+            SignatureQuery::GenericsOf => {
+                self.maps.generics_of.borrow().map.contains_key(&def_id)
+            }
+            SignatureQuery::PredicatesOf => {
+                self.maps.predicates_of.borrow().map.contains_key(&def_id)
+            }
+            SignatureQuery::SuperPredicatesOf => {
+                self.maps.super_predicates_of.borrow().map.contains_key(&def_id)
+            }
+            SignatureQuery::TraitDef => {
+                self.maps.trait_def.borrow().map.contains_key(&def_id)
+            }
+            SignatureQuery::AdtDef => {
+                self.maps.adt_def.borrow().map.contains_key(&def_id)
+            }
+            SignatureQuery::IsDefaultImpl => {
+                self.maps.is_default_impl.borrow().map.contains_key(&def_id)
+            }
+            SignatureQuery::ImplTraitRef => {
+                self.maps.impl_trait_ref.borrow().map.contains_key(&def_id)
+            }
+            SignatureQuery::ImplPolarity => {
+                self.maps.impl_polarity.borrow().map.contains_key(&def_id)
+            }
+            SignatureQuery::FnSig => {
+                self.maps.fn_sig.borrow().map.contains_key(&def_id)
+            }
+        }
+    }
+
+    fn signature_results(self, def_id: DefId, queries: &[SignatureQuery]) -> Fingerprint {
+        let mut hcx = StableHashingContext::new(self);
+        let mut hasher = StableHasher::new();
+        for &query in queries {
+            match query {
+                SignatureQuery::TypeOf => {
+                    self.type_of(def_id).hash_stable(&mut hcx, &mut hasher);
+                }
+                SignatureQuery::GenericsOf => {
+                    self.generics_of(def_id).hash_stable(&mut hcx, &mut hasher);
+                }
+                SignatureQuery::PredicatesOf => {
+                    self.predicates_of(def_id).hash_stable(&mut hcx, &mut hasher);
+                }
+                SignatureQuery::SuperPredicatesOf => {
+                    self.super_predicates_of(def_id).hash_stable(&mut hcx, &mut hasher);
+                }
+                SignatureQuery::TraitDef => {
+                    self.trait_def(def_id).hash_stable(&mut hcx, &mut hasher);
+                }
+                SignatureQuery::AdtDef => {
+                    self.adt_def(def_id).hash_stable(&mut hcx, &mut hasher);
+                }
+                SignatureQuery::IsDefaultImpl => {
+                    self.is_default_impl(def_id).hash_stable(&mut hcx, &mut hasher);
+                }
+                SignatureQuery::ImplTraitRef => {
+                    self.impl_trait_ref(def_id).hash_stable(&mut hcx, &mut hasher);
+                }
+                SignatureQuery::ImplPolarity => {
+                    self.impl_polarity(def_id).hash_stable(&mut hcx, &mut hasher);
+                }
+                SignatureQuery::FnSig => {
+                    self.fn_sig(def_id).hash_stable(&mut hcx, &mut hasher);
+                }
+            }
+        }
+        hasher.finish()
+    }
+}
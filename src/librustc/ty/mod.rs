@@ -17,7 +17,7 @@ pub use self::fold::TypeFoldable;
 
 use dep_graph::{DepNode, DepConstructor};
 use hir::{map as hir_map, FreevarMap, TraitMap};
-use hir::def::{Def, CtorKind, ExportMap};
+use hir::def::{Def, CtorKind, ExportMap, PathResolution};
 use hir::def_id::{CrateNum, DefId, DefIndex, CRATE_DEF_INDEX, LOCAL_CRATE};
 use ich::StableHashingContext;
 use middle::const_val::ConstVal;
@@ -32,7 +32,7 @@ use ty::subst::{Subst, Substs};
 use ty::util::IntTypeExt;
 use ty::walk::TypeWalker;
 use util::common::ErrorReported;
-use util::nodemap::{NodeSet, DefIdMap, FxHashMap, FxHashSet};
+use util::nodemap::{NodeMap, NodeSet, DefIdMap, FxHashMap, FxHashSet};
 
 use serialize::{self, Encodable, Encoder};
 use std::collections::BTreeMap;
@@ -130,6 +130,7 @@ pub struct Resolutions {
     pub trait_map: TraitMap,
     pub maybe_unused_trait_imports: NodeSet,
     pub export_map: ExportMap,
+    pub doc_links: NodeMap<Vec<(String, PathResolution)>>,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -1711,7 +1712,7 @@ impl<'a, 'gcx, 'tcx> AdtDef {
     /// Due to normalization being eager, this applies even if
     /// the associated type is behind a pointer, e.g. issue #31299.
     pub fn sized_constraint(&self, tcx: TyCtxt<'a, 'gcx, 'tcx>) -> &'tcx [Ty<'tcx>] {
-        match queries::adt_sized_constraint::try_get(tcx, DUMMY_SP, self.did) {
+        match queries::adt_sized_constraint::try_get_at(tcx, DUMMY_SP, self.did, dep_location!()) {
             Ok(tys) => tys,
             Err(_) => {
                 debug!("adt_sized_constraint: {:?} is recursive", self);
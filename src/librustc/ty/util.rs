@@ -1062,7 +1062,10 @@ fn needs_drop_raw<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     let (param_env, ty) = query.into_parts();
 
     let needs_drop = |ty: Ty<'tcx>| -> bool {
-        match ty::queries::needs_drop_raw::try_get(tcx, DUMMY_SP, param_env.and(ty)) {
+        match ty::queries::needs_drop_raw::try_get_at(tcx,
+                                                       DUMMY_SP,
+                                                       param_env.and(ty),
+                                                       dep_location!()) {
             Ok(v) => v,
             Err(_) => {
                 // Cycles should be reported as an error by `check_representable`.
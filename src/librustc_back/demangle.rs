@@ -0,0 +1,556 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Encoding primitives and a demangler for the `v0` symbol mangling scheme
+//! (`-Z symbol-mangling-version=v0`).
+//!
+//! Unlike the legacy scheme, which flattens the item path C++-style and
+//! appends an opaque hash, `v0` symbols encode the complete path of the
+//! item including impl self types, trait references and generic arguments,
+//! so they can be turned back into a readable Rust path without any
+//! information from the compiler. The grammar is:
+//!
+//! ```text
+//! <symbol-name> = "_R" <path> [<instantiating-crate>]
+//!
+//! <path> = "C" [<disambiguator>] <ident>                // crate root
+//!        | "M" [<disambiguator>] <path> <type>          // <T> (inherent impl)
+//!        | "X" [<disambiguator>] <path> <type> <path>   // <T as Trait>
+//!        | "N" <namespace> <path> [<disambiguator>] <ident>
+//!        | "I" <path> {<type>} "E"                      // generic arguments
+//!
+//! <namespace> = "t" | "v"                               // type/value ns
+//!             | "C" | "K" | "A" | "X"                   // closure, ctor, const, other
+//!
+//! <type> = <basic-type> | <path>
+//!        | "R" <type> | "Q" <type>                      // &T, &mut T
+//!        | "P" <type> | "O" <type>                      // *const T, *mut T
+//!        | "A" <type> <base-62-number>                  // [T; N]
+//!        | "S" <type>                                   // [T]
+//!        | "T" {<type>} "E"                             // (T, U, ...)
+//!        | "F" ["U"] ["K" <ident>] ["V"] {<type>} "E" <type>
+//!        | "D" {<path> {"p" <ident> <type>}} "E"        // dyn Trait<Assoc = T>
+//!        | "Y" <type> <path> <ident>                    // <T as Trait>::Assoc
+//!        | "p" <base-62-number>                         // generic parameter
+//!
+//! <disambiguator> = "s" <base-62-number>
+//! <ident> = ["u"] <decimal-number> ["_"] <bytes>
+//! <base-62-number> = {<0-9a-zA-Z>} "_"
+//! ```
+//!
+//! The instantiating crate is only present for monomorphizations of generic
+//! items and keeps copies emitted into different crates apart.
+
+use std::ascii::AsciiExt;
+use std::fmt::Write;
+
+const BASE_62: &'static [u8; 62] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Pushes `x` as a `<base-62-number>`. To keep the encoding short, zero is
+/// encoded as the empty digit string, and everything else as `x - 1`.
+pub fn push_integer_62(x: u64, out: &mut String) {
+    if let Some(x) = x.checked_sub(1) {
+        let mut digits = [0u8; 11];
+        let mut x = x;
+        let mut i = digits.len();
+        loop {
+            i -= 1;
+            digits[i] = BASE_62[(x % 62) as usize];
+            x /= 62;
+            if x == 0 {
+                break;
+            }
+        }
+        for &d in &digits[i..] {
+            out.push(d as char);
+        }
+    }
+    out.push('_');
+}
+
+/// Pushes an optional `<disambiguator>`, which is omitted entirely for the
+/// (very common) disambiguator zero.
+pub fn push_disambiguator(dis: u64, out: &mut String) {
+    if let Some(dis) = dis.checked_sub(1) {
+        out.push('s');
+        push_integer_62(dis, out);
+    }
+}
+
+/// Pushes `ident` as an `<ident>`. Non-ASCII identifiers are marked with a
+/// leading `u` and have their non-ASCII characters written as `$<hex>$`,
+/// since most assemblers only accept `[a-zA-Z0-9_.$]` in symbols.
+pub fn push_ident(ident: &str, out: &mut String) {
+    let escaped;
+    let ident = if ident.is_ascii() {
+        ident
+    } else {
+        out.push('u');
+        let mut s = String::with_capacity(ident.len() * 2);
+        for c in ident.chars() {
+            if c.is_ascii() {
+                s.push(c);
+            } else {
+                let _ = write!(s, "${:x}$", c as u32);
+            }
+        }
+        escaped = s;
+        &escaped[..]
+    };
+
+    let _ = write!(out, "{}", ident.len());
+
+    // Add a `_` separator if the identifier would otherwise run into the
+    // length prefix.
+    match ident.chars().next() {
+        Some('_') | Some('0' ... '9') => out.push('_'),
+        _ => {}
+    }
+
+    out.push_str(ident);
+}
+
+/// Turns a `v0` symbol name back into a readable path, e.g.
+/// `<alloc::vec::Vec<u8>>::push`. Returns `None` if `sym` is not a valid
+/// `v0` symbol. Any suffix starting with `.` (as added by LLVM for local
+/// symbols) is preserved verbatim.
+pub fn demangle(sym: &str) -> Option<String> {
+    if !sym.starts_with("_R") {
+        return None;
+    }
+
+    let (sym, suffix) = match sym.find('.') {
+        Some(i) => (&sym[2..i], &sym[i..]),
+        None => (&sym[2..], ""),
+    };
+
+    let mut printer = Printer {
+        sym: sym.as_bytes(),
+        next: 0,
+        out: String::with_capacity(sym.len()),
+    };
+
+    if printer.print_path(true).is_err() {
+        return None;
+    }
+
+    // Skip the instantiating crate, it is not interesting to humans.
+    if printer.peek() == Some(b'C') {
+        let mut out = String::new();
+        ::std::mem::swap(&mut out, &mut printer.out);
+        if printer.print_path(false).is_err() {
+            return None;
+        }
+        printer.out = out;
+    }
+
+    if printer.next != printer.sym.len() {
+        return None;
+    }
+
+    printer.out.push_str(suffix);
+    Some(printer.out)
+}
+
+struct Invalid;
+
+struct Printer<'s> {
+    sym: &'s [u8],
+    next: usize,
+    out: String,
+}
+
+impl<'s> Printer<'s> {
+    fn peek(&self) -> Option<u8> {
+        self.sym.get(self.next).cloned()
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        if self.peek() == Some(b) {
+            self.next += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next(&mut self) -> Result<u8, Invalid> {
+        let b = self.peek().ok_or(Invalid)?;
+        self.next += 1;
+        Ok(b)
+    }
+
+    fn integer_62(&mut self) -> Result<u64, Invalid> {
+        if self.eat(b'_') {
+            return Ok(0);
+        }
+
+        let mut x: u64 = 0;
+        loop {
+            let d = match self.next()? {
+                d @ b'0' ... b'9' => d - b'0',
+                d @ b'a' ... b'z' => 10 + (d - b'a'),
+                d @ b'A' ... b'Z' => 36 + (d - b'A'),
+                b'_' => break,
+                _ => return Err(Invalid),
+            };
+            x = x.checked_mul(62).and_then(|x| x.checked_add(d as u64)).ok_or(Invalid)?;
+        }
+        x.checked_add(1).ok_or(Invalid)
+    }
+
+    fn disambiguator(&mut self) -> Result<u64, Invalid> {
+        if self.eat(b's') {
+            Ok(self.integer_62()? + 1)
+        } else {
+            Ok(0)
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, Invalid> {
+        let is_escaped = self.eat(b'u');
+
+        let start = self.next;
+        while let Some(b'0' ... b'9') = self.peek() {
+            self.next += 1;
+        }
+        let len: usize = match ::std::str::from_utf8(&self.sym[start..self.next]) {
+            Ok(digits) => digits.parse().map_err(|_| Invalid)?,
+            Err(_) => return Err(Invalid),
+        };
+        self.eat(b'_');
+
+        let end = self.next.checked_add(len).ok_or(Invalid)?;
+        let bytes = self.sym.get(self.next..end).ok_or(Invalid)?;
+        self.next = end;
+        let ident = ::std::str::from_utf8(bytes).map_err(|_| Invalid)?;
+
+        if !is_escaped {
+            return Ok(ident.to_string());
+        }
+
+        let mut result = String::with_capacity(ident.len());
+        let mut parts = ident.split('$');
+        result.push_str(parts.next().unwrap_or(""));
+        loop {
+            let code = match parts.next() {
+                Some(code) => code,
+                None => break,
+            };
+            let c = u32::from_str_radix(code, 16).ok()
+                                                .and_then(::std::char::from_u32)
+                                                .ok_or(Invalid)?;
+            result.push(c);
+            result.push_str(parts.next().ok_or(Invalid)?);
+        }
+        Ok(result)
+    }
+
+    fn print_path(&mut self, in_value: bool) -> Result<(), Invalid> {
+        match self.next()? {
+            b'C' => {
+                self.disambiguator()?;
+                let name = self.ident()?;
+                self.out.push_str(&name);
+            }
+            b'M' => {
+                self.disambiguator()?;
+                // The parent path of an impl is only needed for uniqueness.
+                self.skip_path()?;
+                self.out.push('<');
+                self.print_type()?;
+                self.out.push('>');
+            }
+            b'X' => {
+                self.disambiguator()?;
+                self.skip_path()?;
+                self.out.push('<');
+                self.print_type()?;
+                self.out.push_str(" as ");
+                self.print_path(false)?;
+                self.out.push('>');
+            }
+            b'N' => {
+                let ns = self.next()?;
+                self.print_path(in_value)?;
+                let dis = self.disambiguator()?;
+                let name = self.ident()?;
+                self.out.push_str("::");
+                match ns {
+                    b't' | b'v' => self.out.push_str(&name),
+                    b'C' => { let _ = write!(self.out, "{{closure#{}}}", dis); }
+                    b'K' => self.out.push_str("{constructor}"),
+                    b'A' => { let _ = write!(self.out, "{{constant#{}}}", dis); }
+                    b'X' => { let _ = write!(self.out, "{{{}#{}}}", name, dis); }
+                    _ => return Err(Invalid),
+                }
+            }
+            b'I' => {
+                self.print_path(in_value)?;
+                if in_value {
+                    self.out.push_str("::");
+                }
+                self.out.push('<');
+                let mut first = true;
+                while !self.eat(b'E') {
+                    if !first {
+                        self.out.push_str(", ");
+                    }
+                    first = false;
+                    self.print_type()?;
+                }
+                self.out.push('>');
+            }
+            _ => return Err(Invalid),
+        }
+        Ok(())
+    }
+
+    fn skip_path(&mut self) -> Result<(), Invalid> {
+        let mut out = String::new();
+        ::std::mem::swap(&mut out, &mut self.out);
+        let result = self.print_path(false);
+        self.out = out;
+        result
+    }
+
+    fn print_type(&mut self) -> Result<(), Invalid> {
+        let tag = self.peek().ok_or(Invalid)?;
+        let basic = match tag {
+            b'a' => "i8",
+            b'b' => "bool",
+            b'c' => "char",
+            b'd' => "f64",
+            b'e' => "str",
+            b'f' => "f32",
+            b'h' => "u8",
+            b'i' => "isize",
+            b'j' => "usize",
+            b'l' => "i32",
+            b'm' => "u32",
+            b'n' => "i128",
+            b'o' => "u128",
+            b's' => "i16",
+            b't' => "u16",
+            b'u' => "()",
+            b'x' => "i64",
+            b'y' => "u64",
+            b'z' => "!",
+            _ => "",
+        };
+        if !basic.is_empty() {
+            self.next += 1;
+            self.out.push_str(basic);
+            return Ok(());
+        }
+
+        match tag {
+            b'C' | b'M' | b'X' | b'N' | b'I' => return self.print_path(false),
+            _ => self.next += 1,
+        }
+
+        match tag {
+            b'R' | b'Q' => {
+                self.out.push_str(if tag == b'R' { "&" } else { "&mut " });
+                self.print_type()?;
+            }
+            b'P' | b'O' => {
+                self.out.push_str(if tag == b'P' { "*const " } else { "*mut " });
+                self.print_type()?;
+            }
+            b'A' => {
+                self.out.push('[');
+                self.print_type()?;
+                let len = self.integer_62()?;
+                let _ = write!(self.out, "; {}]", len);
+            }
+            b'S' => {
+                self.out.push('[');
+                self.print_type()?;
+                self.out.push(']');
+            }
+            b'T' => {
+                self.out.push('(');
+                let mut count = 0;
+                while !self.eat(b'E') {
+                    if count > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.print_type()?;
+                    count += 1;
+                }
+                if count == 1 {
+                    self.out.push(',');
+                }
+                self.out.push(')');
+            }
+            b'F' => {
+                if self.eat(b'U') {
+                    self.out.push_str("unsafe ");
+                }
+                if self.eat(b'K') {
+                    let abi = self.ident()?.replace('_', "-");
+                    let _ = write!(self.out, "extern \"{}\" ", abi);
+                }
+                let variadic = self.eat(b'V');
+                self.out.push_str("fn(");
+                let mut first = true;
+                while !self.eat(b'E') {
+                    if !first {
+                        self.out.push_str(", ");
+                    }
+                    first = false;
+                    self.print_type()?;
+                }
+                if variadic {
+                    self.out.push_str(if first { "..." } else { ", ..." });
+                }
+                self.out.push(')');
+                if self.eat(b'u') {
+                    // `-> ()` is left implicit, like in source code.
+                } else {
+                    self.out.push_str(" -> ");
+                    self.print_type()?;
+                }
+            }
+            b'D' => {
+                self.out.push_str("dyn ");
+                let mut first = true;
+                while !self.eat(b'E') {
+                    if !first {
+                        self.out.push_str(" + ");
+                    }
+                    first = false;
+                    self.print_path(false)?;
+                    if self.peek() == Some(b'p') {
+                        // Bindings are printed inside the trait's generic
+                        // argument list, merging with any that exist already.
+                        let open = self.out.ends_with('>');
+                        if open {
+                            self.out.pop();
+                        } else {
+                            self.out.push('<');
+                        }
+                        let mut first_binding = !open;
+                        while self.eat(b'p') {
+                            if !first_binding {
+                                self.out.push_str(", ");
+                            }
+                            first_binding = false;
+                            let name = self.ident()?;
+                            self.out.push_str(&name);
+                            self.out.push_str(" = ");
+                            self.print_type()?;
+                        }
+                        self.out.push('>');
+                    }
+                }
+            }
+            b'Y' => {
+                self.out.push('<');
+                self.print_type()?;
+                self.out.push_str(" as ");
+                self.print_path(false)?;
+                self.out.push_str(">::");
+                let name = self.ident()?;
+                self.out.push_str(&name);
+            }
+            b'p' => {
+                self.integer_62()?;
+                self.out.push('_');
+            }
+            _ => return Err(Invalid),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{demangle, push_disambiguator, push_ident, push_integer_62};
+
+    #[test]
+    fn integer_62() {
+        let enc = |x| { let mut s = String::new(); push_integer_62(x, &mut s); s };
+        assert_eq!(enc(0), "_");
+        assert_eq!(enc(1), "0_");
+        assert_eq!(enc(62), "Z_");
+        assert_eq!(enc(63), "10_");
+    }
+
+    #[test]
+    fn ident() {
+        let enc = |x| { let mut s = String::new(); push_ident(x, &mut s); s };
+        assert_eq!(enc("foo"), "3foo");
+        assert_eq!(enc("_foo"), "4__foo");
+        assert_eq!(enc("1x"), "2_1x");
+        assert_eq!(enc("föö"), "u9f$f6$$f6$");
+    }
+
+    fn crate_root(name: &str, out: &mut String) {
+        out.push('C');
+        push_disambiguator(0x1234, out);
+        push_ident(name, out);
+    }
+
+    #[test]
+    fn demangle_paths() {
+        assert_eq!(demangle("_RC3std").unwrap(), "std");
+        assert_eq!(demangle("_RNvNtC3std3mem4swap").unwrap(), "std::mem::swap");
+        assert_eq!(demangle("_RNvNtC3std3mem4swap.llvm.1234").unwrap(),
+                   "std::mem::swap.llvm.1234");
+        assert_eq!(demangle("_RNCNvC3foo4mains_0").unwrap(), "foo::main::{closure#1}");
+        assert_eq!(demangle("_RNCNvC3foo4main0").unwrap(), "foo::main::{closure#0}");
+        assert_eq!(demangle("_RNvXC3fooNtC3foo3BarNtC3foo5Trait6method").unwrap(),
+                   "<foo::Bar as foo::Trait>::method");
+        assert_eq!(demangle("_RINvNtC3std3mem4swapRhEC3bar").unwrap(),
+                   "std::mem::swap::<&u8>");
+        assert_eq!(demangle("_RNvMC5alloc\
+                             INtC5alloc3VechE4push").unwrap(),
+                   "<alloc::Vec<u8>>::push");
+    }
+
+    #[test]
+    fn demangle_types() {
+        let check = |t: &str, expected: &str| {
+            let mut sym = String::from("_RINvC1a1f");
+            sym.push_str(t);
+            sym.push('E');
+            assert_eq!(demangle(&sym).unwrap(), format!("a::f::<{}>", expected));
+        };
+
+        check("Ql", "&mut i32");
+        check("POb", "*const *mut bool");
+        check("Ah2_", "[u8; 3]");
+        check("Se", "[str]");
+        check("TuE", "((),)");
+        check("TcdE", "(char, f64)");
+        check("FUK1CVhEl", "unsafe extern \"C\" fn(u8, ...) -> i32");
+        check("FEu", "fn()");
+        check("DNtC4core5Debugp4Itemp_E", "dyn core::Debug<Item = _>");
+        check("YhNtC4core4Iter4Item", "<u8 as core::Iter>::Item");
+    }
+
+    #[test]
+    fn demangle_rejects_garbage() {
+        assert!(demangle("_ZN3foo3barE").is_none());
+        assert!(demangle("_RNvC3foo").is_none());
+        assert!(demangle("_RC3foo_trailing").is_none());
+        assert!(demangle("_RC9foo").is_none());
+    }
+
+    #[test]
+    fn round_trip_crate_root() {
+        let mut sym = String::from("_R");
+        crate_root("my_crate", &mut sym);
+        assert_eq!(demangle(&sym).unwrap(), "my_crate");
+    }
+}
@@ -46,6 +46,7 @@ pub mod tempdir;
 pub mod target;
 pub mod slice;
 pub mod dynamic_lib;
+pub mod demangle;
 
 use serialize::json::{Json, ToJson};
 
@@ -88,6 +89,7 @@ linker_flavor! {
     (Em, "em"),
     (Gcc, "gcc"),
     (Ld, "ld"),
+    (Lld, "lld"),
     (Msvc, "msvc"),
 }
 
@@ -26,9 +26,14 @@ use rustc::middle::mem_categorization as mc;
 use rustc::middle::mem_categorization::Categorization;
 use rustc::middle::region;
 use rustc::ty::{self, TyCtxt};
+use rustc::util::nodemap::NodeSet;
 use syntax::ast;
-use syntax_pos::Span;
+use syntax_pos::{Span, SyntaxContext};
 use rustc::hir;
+use rustc::hir::def::Def;
+use rustc::hir::intravisit::{self, NestedVisitorMap, Visitor};
+use rustc::hir::map as hir_map;
+use errors::DiagnosticBuilder;
 
 use std::rc::Rc;
 
@@ -202,6 +207,37 @@ pub fn check_loans<'a, 'b, 'c, 'tcx>(bccx: &BorrowckCtxt<'a, 'tcx>,
     };
     euv::ExprUseVisitor::new(&mut clcx, bccx.tcx, param_env, &bccx.region_maps, bccx.tables)
         .consume_body(body);
+
+    // The mutable half of a two-phase borrow is generated at the method
+    // call rather than at the borrow itself, which `euv` knows nothing of.
+    for loan in all_loans.iter().filter(|loan| loan.activated_by_call) {
+        clcx.check_for_conflicting_loans(loan.gen_scope.node_id());
+    }
+}
+
+/// Looks for uses of any of `bindings`, including captures by closures.
+struct BindingUseFinder<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    bindings: &'a NodeSet,
+    found: bool,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for BindingUseFinder<'a, 'tcx> {
+    fn nested_visit_map<'this>(&'this mut self) -> NestedVisitorMap<'this, 'tcx> {
+        NestedVisitorMap::OnlyBodies(&self.tcx.hir)
+    }
+
+    fn visit_path(&mut self, path: &'tcx hir::Path, _: ast::NodeId) {
+        match path.def {
+            Def::Local(def_id) | Def::Upvar(def_id, ..) => {
+                if let Some(node_id) = self.tcx.hir.as_local_node_id(def_id) {
+                    self.found |= self.bindings.contains(&node_id);
+                }
+            }
+            _ => {}
+        }
+        intravisit::walk_path(self, path);
+    }
 }
 
 #[derive(PartialEq)]
@@ -579,6 +615,8 @@ impl<'a, 'tcx> CheckLoanCtxt<'a, 'tcx> {
                 _ => { }
             }
 
+            self.suggest_scope_for_loan(&mut err, old_loan, new_loan);
+
             err.emit();
             return false;
         }
@@ -586,6 +624,82 @@ impl<'a, 'tcx> CheckLoanCtxt<'a, 'tcx> {
         true
     }
 
+    fn suggest_scope_for_loan(&self,
+                              err: &mut DiagnosticBuilder,
+                              old_loan: &Loan<'tcx>,
+                              new_loan: &Loan<'tcx>) {
+        //! A loan stored in a `let` binding lasts until the end of the
+        //! enclosing block. If the bindings introduced up to the statement
+        //! taking `new_loan` are not used any more from there on, wrapping
+        //! those statements in a block ends `old_loan` in time.
+
+        let remainder = match old_loan.kill_scope {
+            region::CodeExtent::Remainder(remainder) => remainder,
+            _ => return,
+        };
+        let block = match self.tcx().hir.find(remainder.block) {
+            Some(hir_map::NodeBlock(block)) => block,
+            _ => return,
+        };
+
+        let first = remainder.first_statement_index as usize;
+        let conflict = match block.stmts.iter().position(|s| s.span.contains(new_loan.span)) {
+            Some(index) => index,
+            None => match block.expr {
+                Some(ref expr) if expr.span.contains(new_loan.span) => block.stmts.len(),
+                _ => return,
+            },
+        };
+        // With nothing but the `let` itself to move into the block, the
+        // borrow is simply unused, and a block would only obscure that.
+        if conflict < first + 2 {
+            return;
+        }
+
+        let scoped = &block.stmts[first..conflict];
+        let mut bindings = NodeSet();
+        for stmt in scoped {
+            if stmt.span.ctxt != SyntaxContext::empty() {
+                return;
+            }
+            if let hir::StmtDecl(ref decl, _) = stmt.node {
+                match decl.node {
+                    hir::DeclLocal(ref local) => {
+                        local.pat.each_binding(|_, id, _, _| {
+                            bindings.insert(id);
+                        });
+                    }
+                    // Moving an item into a block would hide it from the
+                    // rest of this one.
+                    hir::DeclItem(_) => return,
+                }
+            }
+        }
+
+        let mut finder = BindingUseFinder {
+            tcx: self.tcx(),
+            bindings: &bindings,
+            found: false,
+        };
+        for stmt in &block.stmts[conflict..] {
+            finder.visit_stmt(stmt);
+        }
+        if let Some(ref expr) = block.expr {
+            finder.visit_expr(expr);
+        }
+        if finder.found {
+            return;
+        }
+
+        let span = scoped[0].span.to(scoped[scoped.len() - 1].span);
+        if let Ok(snippet) = self.tcx().sess.codemap().span_to_snippet(span) {
+            err.span_suggestion(span,
+                                "consider introducing a scope, so that the previous \
+                                 borrow ends before this one",
+                                format!("{{ {} }}", snippet));
+        }
+    }
+
     fn consume_common(&self,
                       id: ast::NodeId,
                       span: Span,
@@ -23,10 +23,12 @@ use rustc::middle::mem_categorization as mc;
 use rustc::middle::mem_categorization::Categorization;
 use rustc::middle::region;
 use rustc::ty::{self, TyCtxt};
+use rustc::ty::adjustment::{Adjust, AutoBorrow};
 
 use syntax::ast;
 use syntax_pos::Span;
 use rustc::hir;
+use rustc::hir::map as hir_map;
 
 use self::restrictions::RestrictionResult;
 
@@ -375,7 +377,14 @@ impl<'a, 'tcx> GatherLoanCtxt<'a, 'tcx> {
                 debug!("loan_scope = {:?}", loan_scope);
 
                 let borrow_scope = region::CodeExtent::Misc(borrow_id);
-                let gen_scope = self.compute_gen_scope(borrow_scope, loan_scope);
+                let two_phase = self.two_phase_call(borrow_id, req_kind, cause);
+                let gen_scope = match two_phase {
+                    Some((call_id, _)) => {
+                        let call_scope = region::CodeExtent::Misc(call_id);
+                        self.compute_gen_scope(call_scope, loan_scope)
+                    }
+                    None => self.compute_gen_scope(borrow_scope, loan_scope),
+                };
                 debug!("gen_scope = {:?}", gen_scope);
 
                 let kill_scope = self.compute_kill_scope(loan_scope, &loan_path);
@@ -385,6 +394,26 @@ impl<'a, 'tcx> GatherLoanCtxt<'a, 'tcx> {
                     self.mark_loan_path_as_mutated(&loan_path);
                 }
 
+                if let Some((_, last_arg_id)) = two_phase {
+                    // Until the call, the borrow is only reserved: the
+                    // arguments may read the receiver, as they could with
+                    // a shared borrow of it in scope, but nothing more.
+                    let reservation = Loan {
+                        index: self.all_loans.len(),
+                        loan_path: loan_path.clone(),
+                        kind: ty::ImmBorrow,
+                        gen_scope: self.compute_gen_scope(borrow_scope, loan_scope),
+                        kill_scope: region::CodeExtent::Misc(last_arg_id),
+                        span: borrow_span,
+                        restricted_paths: restricted_paths.clone(),
+                        cause: cause,
+                        activated_by_call: false,
+                    };
+                    debug!("guarantee_valid(borrow_id={}), reservation={:?}",
+                           borrow_id, reservation);
+                    self.all_loans.push(reservation);
+                }
+
                 Loan {
                     index: self.all_loans.len(),
                     loan_path: loan_path,
@@ -394,6 +423,7 @@ impl<'a, 'tcx> GatherLoanCtxt<'a, 'tcx> {
                     span: borrow_span,
                     restricted_paths: restricted_paths,
                     cause: cause,
+                    activated_by_call: two_phase.is_some(),
                 }
             }
         };
@@ -452,6 +482,43 @@ impl<'a, 'tcx> GatherLoanCtxt<'a, 'tcx> {
         }
     }
 
+    fn two_phase_call(&self,
+                      borrow_id: ast::NodeId,
+                      req_kind: ty::BorrowKind,
+                      cause: euv::LoanCause)
+                      -> Option<(ast::NodeId, ast::NodeId)> {
+        //! With `#![feature(two_phase_borrows)]`, the mutable autoref of
+        //! a method receiver is only activated at the call, after the
+        //! arguments have been evaluated. If `borrow_id` is such a
+        //! receiver, returns the ids of the call and of its last argument.
+
+        if req_kind != ty::MutBorrow || cause != euv::AutoRef ||
+           !self.tcx().sess.features.borrow().two_phase_borrows {
+            return None;
+        }
+
+        let call_id = self.tcx().hir.get_parent_node(borrow_id);
+        let args = match self.tcx().hir.find(call_id) {
+            Some(hir_map::NodeExpr(&hir::Expr { node: hir::ExprMethodCall(_, _, ref args), .. }))
+                if args.len() > 1 && args[0].id == borrow_id => args,
+            _ => return None,
+        };
+
+        // An overloaded `deref_mut` of the receiver is a call of its own,
+        // which happens before the arguments are evaluated.
+        let adjustments = self.bccx.tables.expr_adjustments(&args[0]);
+        let plain_autoref = adjustments.iter().all(|adjustment| match adjustment.kind {
+            Adjust::Deref(None) => true,
+            Adjust::Borrow(AutoBorrow::Ref(_, hir::MutMutable)) => true,
+            _ => false,
+        });
+        if !plain_autoref {
+            return None;
+        }
+
+        Some((call_id, args[args.len() - 1].id))
+    }
+
     pub fn compute_gen_scope(&self,
                              borrow_scope: region::CodeExtent,
                              loan_scope: region::CodeExtent)
@@ -34,6 +34,7 @@ use rustc::middle::mem_categorization::Categorization;
 use rustc::middle::mem_categorization::ImmutabilityBlame;
 use rustc::middle::region::{self, RegionMaps};
 use rustc::middle::free_region::RegionRelations;
+use rustc::traits;
 use rustc::ty::{self, TyCtxt};
 use rustc::ty::maps::Providers;
 
@@ -41,7 +42,7 @@ use std::fmt;
 use std::rc::Rc;
 use std::hash::{Hash, Hasher};
 use syntax::ast;
-use syntax_pos::{MultiSpan, Span};
+use syntax_pos::{MultiSpan, Span, SyntaxContext};
 use errors::DiagnosticBuilder;
 
 use rustc::hir;
@@ -221,6 +222,11 @@ pub struct Loan<'tcx> {
     kill_scope: region::CodeExtent,
     span: Span,
     cause: euv::LoanCause,
+
+    /// Whether this is the mutable half of a two-phase borrow, i.e. a
+    /// loan that only takes effect at the method call in `gen_scope`.
+    /// See also `GatherLoanCtxt::two_phase_call`.
+    activated_by_call: bool,
 }
 
 impl<'tcx> Loan<'tcx> {
@@ -517,7 +523,7 @@ impl<'a, 'tcx> BorrowckCtxt<'a, 'tcx> {
                                      lp: &LoanPath<'tcx>,
                                      the_move: &move_data::Move,
                                      moved_lp: &LoanPath<'tcx>,
-                                     _param_env: ty::ParamEnv<'tcx>) {
+                                     param_env: ty::ParamEnv<'tcx>) {
         let (verb, verb_participle) = match use_kind {
             MovedInUse => ("use", "used"),
             MovedInCapture => ("capture", "captured"),
@@ -637,14 +643,89 @@ impl<'a, 'tcx> BorrowckCtxt<'a, 'tcx> {
                               moved_lp.ty));
         }
 
-        // Note: we used to suggest adding a `ref binding` or calling
-        // `clone` but those suggestions have been removed because
-        // they are often not what you actually want to do, and were
-        // not considered particularly helpful.
+        self.suggest_avoiding_move(&mut err, the_move, moved_lp, param_env);
 
         err.emit();
     }
 
+    fn suggest_avoiding_move(&self,
+                             err: &mut DiagnosticBuilder,
+                             the_move: &move_data::Move,
+                             moved_lp: &LoanPath<'tcx>,
+                             param_env: ty::ParamEnv<'tcx>) {
+        //! Suggests calling `clone` or binding by reference at the move
+        //! site. We only do so when the suggestion can be spelled out
+        //! precisely; blanket advice to clone or borrow was removed in
+        //! the past for usually not being what one actually wants.
+
+        let moved_expr = match (the_move.kind, self.tcx.hir.find(the_move.id)) {
+            (move_data::MoveExpr, Some(hir_map::NodeExpr(expr))) => expr,
+            (move_data::MovePat, Some(hir_map::NodeLocal(pat))) => {
+                // `let y = x;` moves `x` through the pattern `y`, but
+                // what one wants to clone is still `x` itself.
+                let parent = self.tcx.hir.get_parent_node(pat.id);
+                if let Some(hir_map::NodeStmt(stmt)) = self.tcx.hir.find(parent) {
+                    if let hir::StmtDecl(ref decl, _) = stmt.node {
+                        if let hir::DeclLocal(ref local) = decl.node {
+                            if local.pat.id == pat.id && local.init.is_some() {
+                                return self.suggest_clone(err, local.init.as_ref().unwrap(),
+                                                          moved_lp, param_env);
+                            }
+                        }
+                    }
+                }
+
+                if let hir::PatKind::Binding(hir::BindByValue(hir::MutImmutable),
+                                             _, ref name, None) = pat.node {
+                    if pat.span.ctxt == SyntaxContext::empty() {
+                        err.span_suggestion(pat.span,
+                                            "consider binding by reference instead, \
+                                             so that the value is not moved",
+                                            format!("ref {}", name.node));
+                    }
+                }
+                return;
+            }
+            _ => return,
+        };
+        self.suggest_clone(err, moved_expr, moved_lp, param_env);
+    }
+
+    fn suggest_clone(&self,
+                     err: &mut DiagnosticBuilder,
+                     expr: &hir::Expr,
+                     moved_lp: &LoanPath<'tcx>,
+                     param_env: ty::ParamEnv<'tcx>) {
+        // Only suggest `x.clone()` where no parentheses are needed, and
+        // where the moved value is the whole of what `expr` refers to.
+        match expr.node {
+            hir::ExprPath(..) | hir::ExprField(..) | hir::ExprTupField(..) => {}
+            _ => return,
+        }
+        if expr.span.ctxt != SyntaxContext::empty() || self.tables.expr_ty(expr) != moved_lp.ty {
+            return;
+        }
+
+        let clone_trait = match self.tcx.lang_items.clone_trait() {
+            Some(clone_trait) => clone_trait,
+            None => return,
+        };
+        let is_clone = self.tcx.infer_ctxt().enter(|infcx| {
+            traits::type_known_to_meet_bound(&infcx, param_env, moved_lp.ty,
+                                             clone_trait, expr.span)
+        });
+        if !is_clone {
+            return;
+        }
+
+        if let Ok(snippet) = self.tcx.sess.codemap().span_to_snippet(expr.span) {
+            err.span_suggestion(expr.span,
+                                "consider cloning the value here, so that the \
+                                 original can still be used",
+                                format!("{}.clone()", snippet));
+        }
+    }
+
     pub fn report_partial_reinitialization_of_uninitialized_structure(
             &self,
             span: Span,
@@ -0,0 +1,335 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A C interface to the compiler, for build systems and editors that want
+//! to embed it rather than run `rustc` and parse its output.
+//!
+//! A `RustcSession` collects the command-line flags, the input and the
+//! output directory of one compilation, which `rustc_session_compile` then
+//! runs as `rustc` would. Progress and diagnostics are reported through a
+//! callback as they happen: the name of each phase when it is done (see
+//! `-Z stop-after`), and each diagnostic as a line of JSON, in the format of
+//! `--error-format=json`. If the compiler crashes, what it printed when it
+//! panicked is reported once the compilation is over. A compilation can be
+//! cancelled from any thread with `rustc_session_cancel`, which takes effect
+//! at the end of the current phase.
+//!
+//! The declarations for C are in `rustc.h` next to this file.
+//!
+//! # Note
+//!
+//! This API is completely unstable and subject to change.
+
+#![crate_name = "rustc_c"]
+#![crate_type = "dylib"]
+#![doc(html_logo_url = "https://www.rust-lang.org/logos/rust-logo-128x128-blk-v2.png",
+      html_favicon_url = "https://doc.rust-lang.org/favicon.ico",
+      html_root_url = "https://doc.rust-lang.org/nightly/")]
+#![deny(warnings)]
+
+#![feature(box_syntax)]
+#![feature(libc)]
+#![feature(rustc_private)]
+#![feature(set_stdio)]
+
+extern crate getopts;
+extern crate libc;
+extern crate rustc;
+extern crate rustc_driver;
+extern crate rustc_errors as errors;
+extern crate syntax;
+
+use rustc::session::Session;
+use rustc::session::config::{self, Input};
+use rustc_driver::{CompilerCalls, Compilation, RustcDefaultCalls};
+use rustc_driver::driver::{CompileController, CompileState, PhaseController};
+use syntax::ast;
+
+use libc::{c_char, c_int, c_uint, c_void};
+use std::ffi::{CStr, CString};
+use std::io::{self, Write};
+use std::mem;
+use std::panic;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const RUSTC_OK: c_int = 0;
+pub const RUSTC_ERRORED: c_int = 1;
+pub const RUSTC_CANCELLED: c_int = 2;
+pub const RUSTC_ICE: c_int = 3;
+pub const RUSTC_INVALID_ARGUMENT: c_int = -1;
+
+pub const RUSTC_EVENT_PHASE: c_uint = 0;
+pub const RUSTC_EVENT_DIAGNOSTIC: c_uint = 1;
+pub const RUSTC_EVENT_ICE: c_uint = 2;
+
+/// The callback receiving the events of a compilation: the user data it
+/// was registered with, the kind of event and its message, which is only
+/// valid for the duration of the call.
+pub type RustcCallback = Option<extern "C" fn(*mut c_void, c_uint, *const c_char)>;
+
+/// The configuration of a compilation, and the means to cancel it.
+pub struct RustcSession {
+    flags: Vec<String>,
+    input: Option<String>,
+    out_dir: Option<String>,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Creates an empty session, to be freed with `rustc_session_free`.
+#[no_mangle]
+pub extern "C" fn rustc_session_new() -> *mut RustcSession {
+    Box::into_raw(box RustcSession {
+        flags: vec![],
+        input: None,
+        out_dir: None,
+        cancelled: Arc::new(AtomicBool::new(false)),
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rustc_session_free(session: *mut RustcSession) {
+    if !session.is_null() {
+        drop(Box::from_raw(session));
+    }
+}
+
+/// Adds a command-line flag, e.g. `-O` or `--crate-type=lib`. Flags taking
+/// a value may also be added as two successive flags.
+#[no_mangle]
+pub unsafe extern "C" fn rustc_session_add_flag(session: *mut RustcSession,
+                                                flag: *const c_char) -> c_int {
+    match (session.as_mut(), to_string(flag)) {
+        (Some(session), Some(flag)) => {
+            session.flags.push(flag);
+            RUSTC_OK
+        }
+        _ => RUSTC_INVALID_ARGUMENT,
+    }
+}
+
+/// Sets the path of the crate root to compile.
+#[no_mangle]
+pub unsafe extern "C" fn rustc_session_set_input(session: *mut RustcSession,
+                                                 path: *const c_char) -> c_int {
+    match (session.as_mut(), to_string(path)) {
+        (Some(session), Some(path)) => {
+            session.input = Some(path);
+            RUSTC_OK
+        }
+        _ => RUSTC_INVALID_ARGUMENT,
+    }
+}
+
+/// Sets the directory to write the output to, like `--out-dir`.
+#[no_mangle]
+pub unsafe extern "C" fn rustc_session_set_out_dir(session: *mut RustcSession,
+                                                   dir: *const c_char) -> c_int {
+    match (session.as_mut(), to_string(dir)) {
+        (Some(session), Some(dir)) => {
+            session.out_dir = Some(dir);
+            RUSTC_OK
+        }
+        _ => RUSTC_INVALID_ARGUMENT,
+    }
+}
+
+/// Requests the compilation running in `session` to stop, at the end of the
+/// current phase. This may be called from any thread; the session must not
+/// otherwise be modified while it compiles.
+#[no_mangle]
+pub unsafe extern "C" fn rustc_session_cancel(session: *const RustcSession) {
+    if let Some(session) = session.as_ref() {
+        session.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Runs the compilation configured in `session`, blocking until it is done,
+/// and returns one of the `RUSTC_*` status codes. `callback` is called on
+/// another thread, but never concurrently with itself, and may be null. An
+/// ICE is reported on the calling thread, just before returning `RUSTC_ICE`.
+#[no_mangle]
+pub unsafe extern "C" fn rustc_session_compile(session: *const RustcSession,
+                                               callback: RustcCallback,
+                                               user_data: *mut c_void) -> c_int {
+    let session = match session.as_ref() {
+        Some(session) => session,
+        None => return RUSTC_INVALID_ARGUMENT,
+    };
+    let input = match session.input {
+        Some(ref input) => input.clone(),
+        None => return RUSTC_INVALID_ARGUMENT,
+    };
+
+    let mut args = vec!["rustc".to_string(), "--error-format=json".to_string()];
+    args.extend(session.flags.iter().cloned());
+    if let Some(ref out_dir) = session.out_dir {
+        args.push("--out-dir".to_string());
+        args.push(out_dir.clone());
+    }
+    args.push(input);
+
+    session.cancelled.store(false, Ordering::SeqCst);
+    let cancelled = session.cancelled.clone();
+    let callback = Callback { callback: callback, user_data: user_data };
+    let panic_output = Arc::new(Mutex::new(vec![]));
+    let panic_writer = PanicWriter(panic_output.clone());
+
+    let result = rustc_driver::in_rustc_thread(move || {
+        // Fatal errors unwind, and have been reported as diagnostics already,
+        // so what is printed on panics is only reported for ICEs, below.
+        io::set_panic(Some(box panic_writer));
+
+        let mut calls = EmbedCalls { callback: callback, cancelled: cancelled };
+        let emitter = box DiagnosticWriter { callback: callback, line: vec![] };
+        rustc_driver::run_compiler(&args, &mut calls, None, Some(emitter)).0
+    });
+
+    match result {
+        Ok(Ok(())) => RUSTC_OK,
+        Ok(Err(_)) => RUSTC_ERRORED,
+        Err(ref value) if value.is::<Cancelled>() => RUSTC_CANCELLED,
+        Err(ref value) if value.is::<errors::FatalError>() => RUSTC_ERRORED,
+        Err(_) => {
+            let output = panic_output.lock().unwrap_or_else(|err| err.into_inner());
+            let message = match output.last() {
+                Some(&b'\n') => &output[..output.len() - 1],
+                _ => &output[..],
+            };
+            callback.call(RUSTC_EVENT_ICE, message);
+            RUSTC_ICE
+        }
+    }
+}
+
+unsafe fn to_string(s: *const c_char) -> Option<String> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok().map(|s| s.to_string())
+}
+
+/// The payload of the unwinding out of a cancelled compilation.
+struct Cancelled;
+
+#[derive(Copy, Clone)]
+struct Callback {
+    callback: RustcCallback,
+    user_data: *mut c_void,
+}
+
+// The user data is only handed back to the callback, which the caller of
+// `rustc_session_compile` expects to be called from the compiler's thread.
+unsafe impl Send for Callback {}
+
+impl Callback {
+    fn call(&self, kind: c_uint, message: &[u8]) {
+        if let Some(callback) = self.callback {
+            let message = CString::new(message).unwrap_or_else(|_| CString::new("").unwrap());
+            callback(self.user_data, kind, message.as_ptr());
+        }
+    }
+}
+
+/// Reports each line written by the JSON emitter, i.e. each diagnostic.
+struct DiagnosticWriter {
+    callback: Callback,
+    line: Vec<u8>,
+}
+
+impl Write for DiagnosticWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        for &byte in data {
+            if byte == b'\n' {
+                self.callback.call(RUSTC_EVENT_DIAGNOSTIC, &self.line);
+                self.line.clear();
+            } else {
+                self.line.push(byte);
+            }
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Collects what is printed when the compiler's thread panics.
+struct PanicWriter(Arc<Mutex<Vec<u8>>>);
+
+impl Write for PanicWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner()).extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Compiles like `rustc`, reporting the end of each phase and stopping
+/// there if the compilation was cancelled.
+struct EmbedCalls {
+    callback: Callback,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl EmbedCalls {
+    fn hook<'a>(&self, phase: &'static str, controller: &mut PhaseController<'a>) {
+        let callback = self.callback;
+        let cancelled = self.cancelled.clone();
+        let inner = mem::replace(&mut controller.callback, box |_: &mut CompileState| {});
+        controller.callback = box move |state: &mut CompileState| {
+            inner(state);
+            callback.call(RUSTC_EVENT_PHASE, phase.as_bytes());
+            if cancelled.load(Ordering::SeqCst) {
+                panic::resume_unwind(box Cancelled);
+            }
+        };
+    }
+}
+
+impl<'a> CompilerCalls<'a> for EmbedCalls {
+    fn late_callback(&mut self,
+                     matches: &getopts::Matches,
+                     sess: &Session,
+                     input: &Input,
+                     odir: &Option<PathBuf>,
+                     ofile: &Option<PathBuf>)
+                     -> Compilation {
+        RustcDefaultCalls.late_callback(matches, sess, input, odir, ofile)
+    }
+
+    fn early_callback(&mut self,
+                      matches: &getopts::Matches,
+                      sopts: &config::Options,
+                      cfg: &ast::CrateConfig,
+                      descriptions: &errors::registry::Registry,
+                      output: config::ErrorOutputType)
+                      -> Compilation {
+        RustcDefaultCalls.early_callback(matches, sopts, cfg, descriptions, output)
+    }
+
+    fn build_controller(&mut self,
+                        sess: &Session,
+                        matches: &getopts::Matches)
+                        -> CompileController<'a> {
+        let mut control = RustcDefaultCalls.build_controller(sess, matches);
+        self.hook("parse", &mut control.after_parse);
+        self.hook("expand", &mut control.after_expand);
+        self.hook("lower", &mut control.after_hir_lowering);
+        self.hook("analysis", &mut control.after_analysis);
+        self.hook("trans", &mut control.after_llvm);
+        control
+    }
+}
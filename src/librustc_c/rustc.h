@@ -0,0 +1,66 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// The C interface to the compiler implemented by `librustc_c`. This API is
+// completely unstable and subject to change.
+
+#ifndef RUSTC_H
+#define RUSTC_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// Status codes returned by the functions below.
+#define RUSTC_OK 0
+#define RUSTC_ERRORED 1
+#define RUSTC_CANCELLED 2
+#define RUSTC_ICE 3
+#define RUSTC_INVALID_ARGUMENT (-1)
+
+// Kinds of events passed to a `rustc_callback`. The message of a phase
+// event is the name of the phase that is done: "parse", "expand", "lower",
+// "analysis" or "trans". The message of a diagnostic event is the
+// diagnostic, as a line of JSON in the format of `--error-format=json`.
+// The message of an ICE event is what the compiler printed when it
+// panicked: it is sent once, before `rustc_session_compile` returns
+// `RUSTC_ICE`.
+#define RUSTC_EVENT_PHASE 0
+#define RUSTC_EVENT_DIAGNOSTIC 1
+#define RUSTC_EVENT_ICE 2
+
+typedef struct rustc_session rustc_session;
+
+// The message is only valid for the duration of the call.
+typedef void (*rustc_callback)(void *user_data, unsigned kind, const char *message);
+
+rustc_session *rustc_session_new(void);
+void rustc_session_free(rustc_session *session);
+
+// All strings are UTF-8, and copied by the session.
+int rustc_session_add_flag(rustc_session *session, const char *flag);
+int rustc_session_set_input(rustc_session *session, const char *path);
+int rustc_session_set_out_dir(rustc_session *session, const char *dir);
+
+// Blocks until the compilation is done. The callback, which may be null,
+// is called on a thread of the compiler, except for the ICE event, which
+// is sent from the calling thread.
+int rustc_session_compile(const rustc_session *session,
+                          rustc_callback callback,
+                          void *user_data);
+
+// May be called from any thread while `rustc_session_compile` runs.
+void rustc_session_cancel(const rustc_session *session);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
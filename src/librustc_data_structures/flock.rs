@@ -218,6 +218,16 @@ mod imp {
                 Ok(Lock { fd: fd })
             }
         }
+
+        /// Whether `err`, as returned by `Lock::new`, means that the file
+        /// system does not support locking at all (e.g. NFS without a lock
+        /// daemon), rather than that the lock is held by someone else.
+        pub fn error_unsupported(err: &io::Error) -> bool {
+            match err.raw_os_error() {
+                Some(libc::ENOTSUP) | Some(libc::ENOSYS) | Some(libc::ENOLCK) => true,
+                _ => false,
+            }
+        }
     }
 
     impl Drop for Lock {
@@ -257,6 +267,8 @@ mod imp {
     const LOCKFILE_EXCLUSIVE_LOCK: DWORD = 0x00000002;
     const LOCKFILE_FAIL_IMMEDIATELY: DWORD = 0x00000001;
 
+    const ERROR_INVALID_FUNCTION: i32 = 0x1;
+
     const FILE_SHARE_DELETE: DWORD = 0x4;
     const FILE_SHARE_READ: DWORD = 0x1;
     const FILE_SHARE_WRITE: DWORD = 0x2;
@@ -347,6 +359,13 @@ mod imp {
                 Ok(Lock { _file: file })
             }
         }
+
+        /// Whether `err`, as returned by `Lock::new`, means that the file
+        /// system does not support locking at all, rather than that the lock
+        /// is held by someone else.
+        pub fn error_unsupported(err: &io::Error) -> bool {
+            err.raw_os_error() == Some(ERROR_INVALID_FUNCTION)
+        }
     }
 
     // Note that we don't need a Drop impl on the Windows: The file is unlocked
@@ -66,6 +66,7 @@ pub mod indexed_vec;
 pub mod obligation_forest;
 pub mod snapshot_map;
 pub mod snapshot_vec;
+pub mod spsc_ring;
 pub mod stable_hasher;
 pub mod transitive_relation;
 pub mod unify;
@@ -75,6 +76,7 @@ pub mod tuple_slice;
 pub mod veccell;
 pub mod control_flow_graph;
 pub mod flock;
+pub mod mmap;
 
 // See comments in src/librustc/lib.rs
 #[doc(hidden)]
@@ -0,0 +1,107 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Read-only memory maps of whole files.
+//!
+//! Used to load crate metadata without reading and copying it up front: only
+//! the pages that actually get decoded are faulted in. Platforms without an
+//! implementation fall back to reading the file into memory.
+//!
+//! `Mmap::map` is unsafe because the contents of the map change if the file
+//! is modified while it is mapped; callers must only map files they don't
+//! expect to be touched for the lifetime of the map.
+
+use std::ops::Deref;
+
+pub use self::imp::Mmap;
+
+#[cfg(unix)]
+mod imp {
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::prelude::*;
+    use std::ptr;
+    use std::slice;
+    use libc;
+
+    pub struct Mmap {
+        ptr: *mut libc::c_void,
+        len: usize,
+    }
+
+    impl Mmap {
+        pub unsafe fn map(file: &File) -> io::Result<Mmap> {
+            let len = file.metadata()?.len() as usize;
+            if len == 0 {
+                // `mmap` rejects empty mappings.
+                return Ok(Mmap { ptr: ptr::null_mut(), len: 0 });
+            }
+            let ptr = libc::mmap(ptr::null_mut(),
+                                 len,
+                                 libc::PROT_READ,
+                                 libc::MAP_PRIVATE,
+                                 file.as_raw_fd(),
+                                 0);
+            if ptr == libc::MAP_FAILED {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(Mmap { ptr, len })
+            }
+        }
+
+        pub fn as_slice(&self) -> &[u8] {
+            if self.len == 0 {
+                &[]
+            } else {
+                unsafe { slice::from_raw_parts(self.ptr as *const u8, self.len) }
+            }
+        }
+    }
+
+    impl Drop for Mmap {
+        fn drop(&mut self) {
+            if self.len != 0 {
+                unsafe {
+                    libc::munmap(self.ptr, self.len);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use std::fs::File;
+    use std::io::{self, Read};
+
+    pub struct Mmap {
+        bytes: Vec<u8>,
+    }
+
+    impl Mmap {
+        pub unsafe fn map(mut file: &File) -> io::Result<Mmap> {
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            Ok(Mmap { bytes })
+        }
+
+        pub fn as_slice(&self) -> &[u8] {
+            &self.bytes
+        }
+    }
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
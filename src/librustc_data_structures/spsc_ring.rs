@@ -0,0 +1,275 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A fixed-size, lock-free ring buffer to send values from one thread to
+//! another, i.e. a single-producer single-consumer channel.
+//!
+//! Sending and receiving only touch two atomic indices, so in the common
+//! case neither side takes a lock or allocates. When the ring is full the
+//! producer spins (yielding its time slice) until the consumer catches up;
+//! when it is empty the consumer parks until the producer sends something
+//! or goes away.
+
+use std::cell::{Cell, UnsafeCell};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{self, AtomicBool, AtomicUsize, Ordering};
+use std::thread::{self, Thread};
+
+struct Ring<T> {
+    slots: Vec<UnsafeCell<Option<T>>>,
+    // Total number of values ever received and sent. Slot `i % capacity`
+    // holds a value iff `head <= i < tail`.
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    producer_alive: AtomicBool,
+    consumer_alive: AtomicBool,
+    // The consumer, if it is (about to be) parked waiting for a value.
+    parked_consumer: Mutex<Option<Thread>>,
+    consumer_parked: AtomicBool,
+}
+
+// Each slot is only ever accessed by one side at a time, as handed over by
+// `head` and `tail`.
+unsafe impl<T: Send> Sync for Ring<T> {}
+
+/// The sending half of a ring. It can be sent to another thread, but not
+/// shared between threads.
+pub struct Producer<T> {
+    ring: Arc<Ring<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+/// The receiving half of a ring. It can be sent to another thread, but not
+/// shared between threads.
+pub struct Consumer<T> {
+    ring: Arc<Ring<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+unsafe impl<T: Send> Send for Producer<T> {}
+unsafe impl<T: Send> Send for Consumer<T> {}
+
+/// Creates a ring holding up to `capacity` values that have been sent but
+/// not received yet.
+pub fn ring<T>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    assert!(capacity > 0, "a ring must have room for at least one value");
+    let ring = Arc::new(Ring {
+        slots: (0..capacity).map(|_| UnsafeCell::new(None)).collect(),
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        producer_alive: AtomicBool::new(true),
+        consumer_alive: AtomicBool::new(true),
+        parked_consumer: Mutex::new(None),
+        consumer_parked: AtomicBool::new(false),
+    });
+    (Producer { ring: ring.clone(), _not_sync: PhantomData },
+     Consumer { ring: ring, _not_sync: PhantomData })
+}
+
+impl<T> Ring<T> {
+    fn slot(&self, index: usize) -> *mut Option<T> {
+        self.slots[index % self.slots.len()].get()
+    }
+
+    fn wake_consumer(&self) {
+        if self.consumer_parked.load(Ordering::SeqCst) {
+            if let Some(ref thread) = *self.parked_consumer.lock().unwrap() {
+                thread.unpark();
+            }
+        }
+    }
+}
+
+impl<T> Producer<T> {
+    /// Sends `value`, waiting for room in the ring if needed. Gives the
+    /// value back if the consumer is gone.
+    pub fn send(&self, value: T) -> Result<(), T> {
+        let ring = &*self.ring;
+        let tail = ring.tail.load(Ordering::Relaxed);
+        while tail - ring.head.load(Ordering::Acquire) == ring.slots.len() {
+            if !ring.consumer_alive.load(Ordering::SeqCst) {
+                return Err(value);
+            }
+            thread::yield_now();
+        }
+        if !ring.consumer_alive.load(Ordering::Relaxed) {
+            return Err(value);
+        }
+        unsafe {
+            *ring.slot(tail) = Some(value);
+        }
+        ring.tail.store(tail + 1, Ordering::SeqCst);
+        ring.wake_consumer();
+        Ok(())
+    }
+}
+
+impl<T> Drop for Producer<T> {
+    fn drop(&mut self) {
+        self.ring.producer_alive.store(false, Ordering::SeqCst);
+        self.ring.wake_consumer();
+    }
+}
+
+impl<T> Consumer<T> {
+    /// Receives the next value if there is one already.
+    pub fn try_recv(&self) -> Option<T> {
+        let ring = &*self.ring;
+        let head = ring.head.load(Ordering::Relaxed);
+        if head == ring.tail.load(Ordering::Acquire) {
+            return None;
+        }
+        let value = unsafe { (*ring.slot(head)).take() };
+        ring.head.store(head + 1, Ordering::Release);
+        value
+    }
+
+    /// Receives the next value, waiting for it if needed. Returns `None` once
+    /// the producer is gone and every value it sent has been received.
+    pub fn recv(&self) -> Option<T> {
+        loop {
+            if let Some(value) = self.try_recv() {
+                return Some(value);
+            }
+
+            let ring = &*self.ring;
+            *ring.parked_consumer.lock().unwrap() = Some(thread::current());
+            ring.consumer_parked.store(true, Ordering::SeqCst);
+            atomic::fence(Ordering::SeqCst);
+
+            // Check again now that the producer knows to wake us up, so
+            // that a value sent in the meantime isn't missed.
+            let value = self.try_recv();
+            if value.is_none() && ring.producer_alive.load(Ordering::SeqCst) {
+                thread::park();
+            }
+            ring.consumer_parked.store(false, Ordering::SeqCst);
+
+            if value.is_some() {
+                return value;
+            }
+            if !ring.producer_alive.load(Ordering::SeqCst) {
+                return self.try_recv();
+            }
+        }
+    }
+}
+
+impl<T> Drop for Consumer<T> {
+    fn drop(&mut self) {
+        self.ring.consumer_alive.store(false, Ordering::SeqCst);
+    }
+}
+
+impl<T> Iterator for Consumer<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.recv()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use self::test::Bencher;
+    use super::ring;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn send_and_receive_in_order() {
+        let (producer, consumer) = ring(4);
+        let receiver = thread::spawn(move || consumer.collect::<Vec<u32>>());
+        for i in 0..1000 {
+            producer.send(i).unwrap();
+        }
+        drop(producer);
+        assert_eq!(receiver.join().unwrap(), (0..1000).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn try_recv_when_empty() {
+        let (producer, consumer) = ring(2);
+        assert_eq!(consumer.try_recv(), None);
+        producer.send(1).unwrap();
+        assert_eq!(consumer.try_recv(), Some(1));
+        assert_eq!(consumer.try_recv(), None);
+    }
+
+    #[test]
+    fn send_after_consumer_dropped() {
+        let (producer, consumer) = ring(1);
+        drop(consumer);
+        assert_eq!(producer.send(1), Err(1));
+    }
+
+    #[test]
+    fn recv_after_producer_dropped() {
+        let (producer, consumer) = ring(2);
+        producer.send(1).unwrap();
+        drop(producer);
+        assert_eq!(consumer.recv(), Some(1));
+        assert_eq!(consumer.recv(), None);
+    }
+
+    const MESSAGES: usize = 100_000;
+    const BATCH: usize = 2048;
+
+    // Sends `MESSAGES` values through a ring of `BATCH` slots.
+    #[bench]
+    fn bench_ring(b: &mut Bencher) {
+        b.iter(|| {
+            let (producer, consumer) = ring(BATCH);
+            let receiver = thread::spawn(move || consumer.count());
+            for i in 0..MESSAGES {
+                producer.send(i).unwrap();
+            }
+            drop(producer);
+            receiver.join().unwrap()
+        });
+    }
+
+    // Sends `MESSAGES` values in batches of `BATCH`, recycling the buffers,
+    // as the dep-graph thread does by default.
+    #[bench]
+    fn bench_batched_channels(b: &mut Bencher) {
+        b.iter(|| {
+            let (full_tx, full_rx) = mpsc::channel::<Vec<usize>>();
+            let (empty_tx, empty_rx) = mpsc::channel();
+            for _ in 0..4 {
+                empty_tx.send(Vec::with_capacity(BATCH)).unwrap();
+            }
+            let receiver = thread::spawn(move || {
+                let mut count = 0;
+                for mut batch in full_rx {
+                    count += batch.drain(..).count();
+                    if empty_tx.send(batch).is_err() {
+                        break;
+                    }
+                }
+                count
+            });
+            let mut batch = Vec::with_capacity(BATCH);
+            for i in 0..MESSAGES {
+                batch.push(i);
+                if batch.len() == BATCH {
+                    let empty = empty_rx.recv().unwrap();
+                    full_tx.send(::std::mem::replace(&mut batch, empty)).unwrap();
+                }
+            }
+            full_tx.send(batch).unwrap();
+            drop(full_tx);
+            receiver.join().unwrap()
+        });
+    }
+}
@@ -37,6 +37,10 @@ impl<T> VecCell<T> {
         }
     }
 
+    pub fn len(&self) -> usize {
+        unsafe { (*self.data.get()).len() }
+    }
+
     pub fn swap(&self, mut data: Vec<T>) -> Vec<T> {
         unsafe {
             let v = self.data.get();
@@ -15,7 +15,7 @@ use rustc_data_structures::stable_hasher::StableHasher;
 use rustc_mir as mir;
 use rustc::session::{Session, CompileResult};
 use rustc::session::CompileIncomplete;
-use rustc::session::config::{self, Input, OutputFilenames, OutputType,
+use rustc::session::config::{self, CompilePhase, Input, OutputFilenames, OutputType,
                              OutputTypes};
 use rustc::session::search_paths::PathKind;
 use rustc::lint;
@@ -24,13 +24,14 @@ use rustc::middle::privacy::AccessLevels;
 use rustc::mir::transform::{MIR_CONST, MIR_VALIDATED, MIR_OPTIMIZED, Passes};
 use rustc::ty::{self, TyCtxt, Resolutions, GlobalArenas};
 use rustc::traits;
-use rustc::util::common::{ErrorReported, time};
+use rustc::util::common::{ErrorReported, record_time, time};
 use rustc::util::nodemap::NodeSet;
 use rustc::util::fs::rename_or_copy_remove;
 use rustc_allocator as allocator;
 use rustc_borrowck as borrowck;
 use rustc_incremental::{self, IncrementalHashesMap};
 use rustc_resolve::{MakeGlobMap, Resolver};
+use rustc_metadata::api_summary;
 use rustc_metadata::creader::CrateLoader;
 use rustc_metadata::cstore::{self, CStore};
 use rustc_trans::back::{link, write};
@@ -39,7 +40,8 @@ use rustc_typeck as typeck;
 use rustc_privacy;
 use rustc_plugin::registry::Registry;
 use rustc_plugin as plugin;
-use rustc_passes::{ast_validation, no_asm, loops, consts, static_recursion, hir_stats};
+use rustc_passes::{ast_validation, no_asm, loops, naked_functions, consts, static_recursion,
+                   hir_stats};
 use rustc_const_eval::{self, check_match};
 use super::Compilation;
 
@@ -87,6 +89,16 @@ pub fn compile_input(sess: &Session,
         }}
     }
 
+    // `-Z stop-after`: end compilation once `phase` is done, before any
+    // output of a later phase (including dep-info) has been written.
+    macro_rules! stop_after {
+        ($phase: ident) => {{
+            if sess.opts.debugging_opts.stop_after == Some(CompilePhase::$phase) {
+                return sess.compile_status().and_then(|_| Err(CompileIncomplete::Stopped));
+            }
+        }}
+    }
+
     // We need nested scopes here, because the intermediate results can keep
     // large chunks of memory alive and we want to free them as soon as
     // possible to keep the peak memory usage low
@@ -110,6 +122,7 @@ pub fn compile_input(sess: &Session,
                                     sess,
                                     compile_state,
                                     Ok(()));
+            stop_after!(Parse);
 
             (compile_state.krate.unwrap(), compile_state.registry)
         };
@@ -124,6 +137,7 @@ pub fn compile_input(sess: &Session,
                         input, sess, outdir, output, &cstore, expanded_crate, &crate_name,
                     );
                     controller_entry_point!(after_expand, sess, state, Ok(()));
+                    stop_after!(Expand);
                     Ok(())
                 }
             )?
@@ -162,6 +176,7 @@ pub fn compile_input(sess: &Session,
                                                                   &crate_name),
                                     Ok(()));
         }
+        stop_after!(Lower);
 
         time(sess.time_passes(), "attribute checking", || {
             hir::check_attr::check_crate(sess, &expanded_crate);
@@ -197,7 +212,8 @@ pub fn compile_input(sess: &Session,
                                                                    &crate_name);
                 (control.after_analysis.callback)(&mut state);
 
-                if control.after_analysis.stop == Compilation::Stop {
+                if control.after_analysis.stop == Compilation::Stop ||
+                   sess.opts.debugging_opts.stop_after == Some(CompilePhase::Analysis) {
                     return result.and_then(|_| Err(CompileIncomplete::Stopped));
                 }
             }
@@ -223,6 +239,13 @@ pub fn compile_input(sess: &Session,
                 }
             }
 
+            if tcx.sess.opts.output_types.contains_key(&OutputType::ApiSummary) {
+                if let Err(e) = api_summary::emit_api_summary(tcx, &outputs) {
+                    sess.err(&format!("could not emit API summary: {}", e));
+                    sess.abort_if_errors();
+                }
+            }
+
             Ok((outputs, trans))
         })??
     };
@@ -231,6 +254,11 @@ pub fn compile_input(sess: &Session,
         sess.code_stats.borrow().print_type_sizes();
     }
 
+    if sess.opts.debugging_opts.stop_after == Some(CompilePhase::Trans) {
+        write::cleanup_llvm(&trans);
+        return sess.compile_status().and_then(|_| Err(CompileIncomplete::Stopped));
+    }
+
     let phase5_result = phase_5_run_llvm_passes(sess, &trans, &outputs);
 
     controller_entry_point!(after_llvm,
@@ -251,6 +279,18 @@ pub fn compile_input(sess: &Session,
         sess.print_perf_stats();
     }
 
+    if sess.opts.debugging_opts.incremental_info && sess.opts.incremental.is_some() {
+        sess.print_incr_comp_stats();
+    }
+
+    if sess.opts.debugging_opts.dep_graph_stats {
+        sess.dep_graph.print_stats();
+    }
+
+    if sess.opts.debugging_opts.dep_graph_task_edges.is_some() {
+        sess.dep_graph.print_task_edges();
+    }
+
     controller_entry_point!(compilation_done,
                             sess,
                             CompileState::state_when_compilation_done(input, sess, outdir, output),
@@ -834,6 +874,7 @@ pub fn phase_2_configure_and_expand<F>(sess: &Session,
             export_map: resolver.export_map,
             trait_map: resolver.trait_map,
             maybe_unused_trait_imports: resolver.maybe_unused_trait_imports,
+            doc_links: resolver.doc_links,
         },
         hir_forest: hir_forest,
     })
@@ -893,6 +934,10 @@ pub fn phase_3_run_analysis_passes<'tcx, F, R>(sess: &'tcx Session,
          "loop checking",
          || loops::check_crate(sess, &hir_map));
 
+    time(time_passes,
+         "naked function checking",
+         || naked_functions::check_crate(sess, &hir_map));
+
     time(time_passes,
               "static item recursion checking",
               || static_recursion::check_crate(sess, &hir_map))?;
@@ -938,6 +983,7 @@ pub fn phase_3_run_analysis_passes<'tcx, F, R>(sess: &'tcx Session,
     passes.push_pass(MIR_VALIDATED,
                      mir::transform::simplify_branches::SimplifyBranches::new("initial"));
     passes.push_pass(MIR_VALIDATED, mir::transform::simplify::SimplifyCfg::new("qualify-consts"));
+    passes.push_pass(MIR_VALIDATED, mir::transform::nll::NLL);
 
     // Optimizations begin.
     passes.push_pass(MIR_OPTIMIZED, mir::transform::no_landing_pads::NoLandingPads);
@@ -977,14 +1023,18 @@ pub fn phase_3_run_analysis_passes<'tcx, F, R>(sess: &'tcx Session,
                  "compute_incremental_hashes_map",
                  || rustc_incremental::compute_incremental_hashes_map(tcx));
 
-        time(time_passes,
-             "load_dep_graph",
-             || rustc_incremental::load_dep_graph(tcx, &incremental_hashes_map));
-
+        // The stability index comes first since loading the dep-graph may
+        // compute the signatures of items, which checks their stability.
         time(time_passes, "stability index", || {
             tcx.stability.borrow_mut().build(tcx)
         });
 
+        time(time_passes, "load_dep_graph", || {
+            record_time(&tcx.sess.incr_comp_stats.load_time, || {
+                rustc_incremental::load_dep_graph(tcx, &incremental_hashes_map)
+            })
+        });
+
         time(time_passes,
              "stability checking",
              || stability::check_unstable_api_usage(tcx));
@@ -1072,12 +1122,14 @@ pub fn phase_4_translate_to_llvm<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
          "assert dep graph",
          || rustc_incremental::assert_dep_graph(tcx));
 
-    time(time_passes,
-         "serialize dep graph",
-         || rustc_incremental::save_dep_graph(tcx,
+    time(time_passes, "serialize dep graph", || {
+        record_time(&tcx.sess.incr_comp_stats.save_time, || {
+            rustc_incremental::save_dep_graph(tcx,
                                               &incremental_hashes_map,
                                               &translation.metadata.hashes,
-                                              translation.link.crate_hash));
+                                              translation.link.crate_hash)
+        })
+    });
     translation
 }
 
@@ -1086,6 +1138,12 @@ pub fn phase_4_translate_to_llvm<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
 pub fn phase_5_run_llvm_passes(sess: &Session,
                                trans: &trans::CrateTranslation,
                                outputs: &OutputFilenames) -> CompileResult {
+    // Metadata-only compilations write the `.rmeta` file straight from the
+    // encoded metadata, so there is nothing for LLVM to do.
+    if !sess.opts.output_types.should_trans() {
+        return sess.compile_status();
+    }
+
     if sess.opts.cg.no_integrated_as ||
         (sess.target.target.options.no_integrated_as &&
          (outputs.outputs.contains_key(&OutputType::Object) ||
@@ -1118,9 +1176,10 @@ pub fn phase_5_run_llvm_passes(sess: &Session,
              || write::run_passes(sess, trans, &sess.opts.output_types, outputs));
     }
 
-    time(sess.time_passes(),
-         "serialize work products",
-         move || rustc_incremental::save_work_products(sess));
+    time(sess.time_passes(), "serialize work products", move || {
+        record_time(&sess.incr_comp_stats.save_time,
+                    || rustc_incremental::save_work_products(sess))
+    });
 
     sess.compile_status()
 }
@@ -66,7 +66,6 @@ use rustc_save_analysis as save;
 use rustc_save_analysis::DumpHandler;
 use rustc_trans::back::link;
 use rustc_trans::back::write::{RELOC_MODEL_ARGS, CODE_GEN_MODEL_ARGS};
-use rustc::dep_graph::DepGraph;
 use rustc::session::{self, config, Session, build_session, CompileResult};
 use rustc::session::CompileIncomplete;
 use rustc::session::config::{Input, PrintRequest, OutputType, ErrorOutputType};
@@ -74,8 +73,10 @@ use rustc::session::config::nightly_options;
 use rustc::session::{early_error, early_warn};
 use rustc::lint::Lint;
 use rustc::lint;
+use rustc::middle::cstore::MetadataLoader;
 use rustc_metadata::locator;
 use rustc_metadata::cstore::CStore;
+use rustc_metadata::metadata_cache::{CachingMetadataLoader, MetadataCache};
 use rustc::util::common::{time, ErrorReported};
 
 use serialize::json::ToJson;
@@ -88,7 +89,7 @@ use std::env;
 use std::ffi::OsString;
 use std::io::{self, Read, Write};
 use std::iter::repeat;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{self, Command, Stdio};
 use std::rc::Rc;
 use std::str;
@@ -159,6 +160,47 @@ pub fn run_compiler<'a>(args: &[String],
                         file_loader: Option<Box<FileLoader + 'static>>,
                         emitter_dest: Option<Box<Write + Send>>)
                         -> (CompileResult, Option<Session>)
+{
+    run_compiler_with_metadata_loader(args,
+                                      callbacks,
+                                      file_loader,
+                                      emitter_dest,
+                                      box rustc_trans::LlvmMetadataLoader)
+}
+
+/// Runs the compiler once for each of the given argument lists, one after the
+/// other. The metadata of upstream crates is only loaded from disk once, and
+/// kept in `metadata_cache`, which may be reused for later batches: this is
+/// most of the startup cost of compiling a small crate.
+///
+/// Nothing else is shared. Interned types and decoded crate metadata live in
+/// the arenas of a single session and refer to its codemap and crate
+/// numbers, so they are rebuilt for each crate.
+pub fn run_compiler_batch<'a>(invocations: &[Vec<String>],
+                              callbacks: &mut CompilerCalls<'a>,
+                              metadata_cache: &Rc<MetadataCache>)
+                              -> Vec<CompileResult>
+{
+    let mut results = Vec::with_capacity(invocations.len());
+    for args in invocations {
+        let metadata_loader = CachingMetadataLoader::new(box rustc_trans::LlvmMetadataLoader,
+                                                         metadata_cache.clone());
+        let (result, _) = run_compiler_with_metadata_loader(args,
+                                                            callbacks,
+                                                            None,
+                                                            None,
+                                                            box metadata_loader);
+        results.push(result);
+    }
+    results
+}
+
+fn run_compiler_with_metadata_loader<'a>(args: &[String],
+                                         callbacks: &mut CompilerCalls<'a>,
+                                         file_loader: Option<Box<FileLoader + 'static>>,
+                                         emitter_dest: Option<Box<Write + Send>>,
+                                         metadata_loader: Box<MetadataLoader>)
+                                         -> (CompileResult, Option<Session>)
 {
     macro_rules! do_or_return {($expr: expr, $sess: expr) => {
         match $expr {
@@ -196,8 +238,8 @@ pub fn run_compiler<'a>(args: &[String],
         },
     };
 
-    let dep_graph = DepGraph::new(sopts.build_dep_graph());
-    let cstore = Rc::new(CStore::new(&dep_graph, box rustc_trans::LlvmMetadataLoader));
+    let dep_graph = sopts.new_dep_graph();
+    let cstore = Rc::new(CStore::new(&dep_graph, metadata_loader));
 
     let loader = file_loader.unwrap_or(box RealFileLoader);
     let codemap = Rc::new(CodeMap::with_file_loader(loader, sopts.file_path_mapping()));
@@ -476,7 +518,7 @@ impl<'a> CompilerCalls<'a> for RustcDefaultCalls {
                     describe_lints(&ls, false);
                     return None;
                 }
-                let dep_graph = DepGraph::new(sopts.build_dep_graph());
+                let dep_graph = sopts.new_dep_graph();
                 let cstore = Rc::new(CStore::new(&dep_graph, box rustc_trans::LlvmMetadataLoader));
                 let mut sess = build_session(sopts.clone(),
                     &dep_graph,
@@ -510,6 +552,7 @@ impl<'a> CompilerCalls<'a> for RustcDefaultCalls {
                      -> Compilation {
         RustcDefaultCalls::print_crate_info(sess, Some(input), odir, ofile)
             .and_then(|| RustcDefaultCalls::list_metadata(sess, matches, input))
+            .and_then(|| RustcDefaultCalls::diff_dep_graph(sess, input))
     }
 
     fn build_controller(&mut self,
@@ -642,6 +685,23 @@ impl RustcDefaultCalls {
     }
 
 
+    /// Handles `-Z diff-dep-graph=OLD`, which compares the dep-graph saved
+    /// in `OLD` to the one in the input file.
+    fn diff_dep_graph(sess: &Session, input: &Input) -> Compilation {
+        let old = match sess.opts.debugging_opts.diff_dep_graph {
+            Some(ref old) => Path::new(old),
+            None => return Compilation::Continue,
+        };
+        match input {
+            &Input::File(ref new) => rustc_incremental::diff_dep_graphs(sess, old, new),
+            &Input::Str { .. } => {
+                early_error(ErrorOutputType::default(), "cannot diff a dep-graph from stdin");
+            }
+        }
+        sess.abort_if_errors();
+        Compilation::Stop
+    }
+
     fn print_crate_info(sess: &Session,
                         input: Option<&Input>,
                         odir: &Option<PathBuf>,
@@ -35,10 +35,12 @@ use syntax::print::pprust::PrintState;
 use syntax::ptr::P;
 use syntax::util::small_vector::SmallVector;
 use syntax_pos;
+use syntax_pos::hygiene::{ExpnFormat, SyntaxContext};
 
 use graphviz as dot;
 
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeSet;
 use std::fs::File;
 use std::io::{self, Write};
 use std::option;
@@ -60,6 +62,7 @@ pub enum PpSourceMode {
     PpmIdentified,
     PpmExpandedIdentified,
     PpmExpandedHygiene,
+    PpmHygiene,
     PpmTyped,
 }
 
@@ -91,6 +94,7 @@ impl PpMode {
             PpmSource(PpmExpanded) |
             PpmSource(PpmExpandedIdentified) |
             PpmSource(PpmExpandedHygiene) |
+            PpmSource(PpmHygiene) |
             PpmHir(_) |
             PpmMir |
             PpmMirCFG |
@@ -121,6 +125,7 @@ pub fn parse_pretty(sess: &Session,
         ("expanded", _) => PpmSource(PpmExpanded),
         ("expanded,identified", _) => PpmSource(PpmExpandedIdentified),
         ("expanded,hygiene", _) => PpmSource(PpmExpandedHygiene),
+        ("hygiene", true) => PpmSource(PpmHygiene),
         ("hir", true) => PpmHir(PpmNormal),
         ("hir,identified", true) => PpmHir(PpmIdentified),
         ("hir,typed", true) => PpmHir(PpmTyped),
@@ -132,8 +137,8 @@ pub fn parse_pretty(sess: &Session,
             if extended {
                 sess.fatal(&format!("argument to `unpretty` must be one of `normal`, \
                                      `expanded`, `flowgraph[,unlabelled]=<nodeid>`, \
-                                     `identified`, `expanded,identified`, `everybody_loops`, \
-                                     `hir`, `hir,identified`, `hir,typed`, or `mir`; got {}",
+                                     `identified`, `expanded,identified`, `hygiene`, \
+                                     `everybody_loops`, `hir`, `hir,identified`, `hir,typed`, or `mir`; got {}",
                                     name));
             } else {
                 sess.fatal(&format!("argument to `pretty` must be one of `normal`, `expanded`, \
@@ -187,9 +192,11 @@ impl PpSourceMode {
                 };
                 f(&annotation, payload)
             }
-            PpmExpandedHygiene => {
+            PpmExpandedHygiene | PpmHygiene => {
                 let annotation = HygieneAnnotation {
                     sess: sess,
+                    contexts: if *self == PpmHygiene { Some(RefCell::new(BTreeSet::new())) }
+                              else { None },
                 };
                 f(&annotation, payload)
             }
@@ -234,7 +241,7 @@ impl PpSourceMode {
                                                                  arenas,
                                                                  id,
                                                                  |tcx, _, _, _| {
-                    let empty_tables = ty::TypeckTables::empty();
+                    let empty_tables = ty::TypeckTables::empty(None);
                     let annotation = TypedAnnotation {
                         tcx: tcx,
                         tables: Cell::new(&empty_tables)
@@ -259,6 +266,11 @@ trait PrinterSupport: pprust::PpAnn {
     /// (Rust does not yet support upcasting from a trait object to
     /// an object for one of its super-traits.)
     fn pp_ann<'a>(&'a self) -> &'a pprust::PpAnn;
+
+    /// Text to append after the printed crate, if any.
+    fn trailer(&self) -> Option<String> {
+        None
+    }
 }
 
 trait HirPrinterSupport<'hir>: pprust_hir::PpAnn {
@@ -439,7 +451,10 @@ impl<'hir> pprust_hir::PpAnn for IdentifiedAnnotation<'hir> {
 }
 
 struct HygieneAnnotation<'a> {
-    sess: &'a Session
+    sess: &'a Session,
+    /// The syntax contexts seen while printing, for the legend that
+    /// `-Z unpretty=hygiene` appends to the crate.
+    contexts: Option<RefCell<BTreeSet<SyntaxContext>>>,
 }
 
 impl<'a> PrinterSupport for HygieneAnnotation<'a> {
@@ -450,6 +465,41 @@ impl<'a> PrinterSupport for HygieneAnnotation<'a> {
     fn pp_ann(&self) -> &pprust::PpAnn {
         self
     }
+
+    fn trailer(&self) -> Option<String> {
+        let contexts = match self.contexts {
+            Some(ref contexts) => contexts.borrow(),
+            None => return None,
+        };
+
+        // Each context is the one before it plus a mark, so walk back to
+        // the root to also explain the contexts that only show up as
+        // ancestors of the printed ones.
+        let mut all = BTreeSet::new();
+        for &ctxt in contexts.iter() {
+            let mut ctxt = ctxt;
+            while ctxt != SyntaxContext::empty() && all.insert(ctxt) {
+                ctxt.remove_mark();
+            }
+        }
+
+        let mut legend = String::from("\n// Syntax contexts:\n// #0: root\n");
+        for &ctxt in &all {
+            let mut prev = ctxt;
+            let mark = prev.remove_mark();
+            let introduced_by = match mark.expn_info() {
+                Some(info) => match info.callee.format {
+                    ExpnFormat::MacroBang(name) => format!("`{}!`", name),
+                    ExpnFormat::MacroAttribute(name) => format!("`#[{}]`", name),
+                    ExpnFormat::CompilerDesugaring(name) => format!("desugaring of `{}`", name),
+                },
+                None => "an unknown expansion".to_string(),
+            };
+            legend.push_str(&format!("// {:?}: {:?} + mark {} from {}\n",
+                                     ctxt, prev, mark.as_u32(), introduced_by));
+        }
+        Some(legend)
+    }
 }
 
 impl<'a> pprust::PpAnn for HygieneAnnotation<'a> {
@@ -457,8 +507,9 @@ impl<'a> pprust::PpAnn for HygieneAnnotation<'a> {
         match node {
             pprust::NodeIdent(&ast::Ident { name, ctxt }) => {
                 pp::space(&mut s.s)?;
-                // FIXME #16420: this doesn't display the connections
-                // between syntax contexts
+                if let Some(ref contexts) = self.contexts {
+                    contexts.borrow_mut().insert(ctxt);
+                }
                 s.synth_comment(format!("{}{:?}", name.as_u32(), ctxt))
             }
             pprust::NodeName(&name) => {
@@ -507,18 +558,24 @@ impl<'a, 'tcx> pprust_hir::PpAnn for TypedAnnotation<'a, 'tcx> {
     }
     fn pre(&self, s: &mut pprust_hir::State, node: pprust_hir::AnnNode) -> io::Result<()> {
         match node {
-            pprust_hir::NodeExpr(_) => s.popen(),
+            pprust_hir::NodeExpr(expr) if self.tables.get().expr_ty_opt(expr).is_some() => {
+                s.popen()
+            }
             _ => Ok(()),
         }
     }
     fn post(&self, s: &mut pprust_hir::State, node: pprust_hir::AnnNode) -> io::Result<()> {
         match node {
             pprust_hir::NodeExpr(expr) => {
-                pp::space(&mut s.s)?;
-                pp::word(&mut s.s, "as")?;
-                pp::space(&mut s.s)?;
-                pp::word(&mut s.s, &self.tables.get().expr_ty(expr).to_string())?;
-                s.pclose()
+                // Expressions typeck never got to are printed as they are.
+                if let Some(ty) = self.tables.get().expr_ty_opt(expr) {
+                    pp::space(&mut s.s)?;
+                    pp::word(&mut s.s, "as")?;
+                    pp::space(&mut s.s)?;
+                    pp::word(&mut s.s, &ty.to_string())?;
+                    s.pclose()?;
+                }
+                Ok(())
             }
             _ => Ok(()),
         }
@@ -883,7 +940,7 @@ pub fn print_after_hir_lowering<'tcx, 'a: 'tcx>(sess: &'a Session,
             (PpmSource(s), _) => {
                 // Silently ignores an identified node.
                 let out: &mut Write = &mut out;
-                s.call_with_pp_support(sess, Some(hir_map), box out, |annotation, out| {
+                s.call_with_pp_support(sess, Some(hir_map), box out, |annotation, mut out| {
                     debug!("pretty printing source code {:?}", s);
                     let sess = annotation.sess();
                     pprust::print_crate(sess.codemap(),
@@ -891,9 +948,13 @@ pub fn print_after_hir_lowering<'tcx, 'a: 'tcx>(sess: &'a Session,
                                         krate,
                                         src_name.to_string(),
                                         &mut rdr,
-                                        out,
+                                        box &mut *out,
                                         annotation.pp_ann(),
-                                        true)
+                                        true)?;
+                    match annotation.trailer() {
+                        Some(trailer) => out.write_all(trailer.as_bytes()),
+                        None => Ok(()),
+                    }
                 })
             }
 
@@ -44,19 +44,21 @@
 //! ```
 
 use graphviz as dot;
-use rustc::dep_graph::{DepGraphQuery, DepNode, DepKind};
-use rustc::dep_graph::debug::{DepNodeFilter, EdgeFilter};
+use rustc::dep_graph::{DepEdgeKind, DepGraphQuery, DepNode, DepKind};
+use rustc::dep_graph::debug::{DepNodeFilter, EdgeFilter, NodeRegex};
 use rustc::hir::def_id::DefId;
 use rustc::ty::TyCtxt;
-use rustc_data_structures::fx::FxHashSet;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_data_structures::graph::{Direction, INCOMING, OUTGOING, NodeIndex};
 use rustc::hir;
 use rustc::hir::intravisit::{self, NestedVisitorMap, Visitor};
-use rustc::ich::{ATTR_IF_THIS_CHANGED, ATTR_THEN_THIS_WOULD_NEED};
+use rustc::ich::{ATTR_IF_THIS_CHANGED, ATTR_THEN_THIS_WOULD_NEED, Fingerprint};
 use graphviz::IntoCow;
+use rustc_serialize::json::Json;
+use std::collections::BTreeMap;
 use std::env;
 use std::fs::File;
-use std::io::Write;
+use std::io::{BufWriter, Write};
 use syntax::ast;
 use syntax_pos::Span;
 
@@ -67,6 +69,26 @@ pub fn assert_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) {
         dump_graph(tcx);
     }
 
+    if let Some(ref path) = tcx.sess.opts.debugging_opts.dump_dep_graph_dot {
+        dump_graph_dot(tcx, path);
+    }
+
+    if let Some(ref path) = tcx.sess.opts.debugging_opts.dump_dep_graph_json {
+        dump_graph_json(tcx, path);
+    }
+
+    if let Some(ref path) = tcx.sess.opts.debugging_opts.dump_dep_graph_graphml {
+        dump_graph_graphml(tcx, path);
+    }
+
+    if let Some(ref around) = tcx.sess.opts.debugging_opts.dump_dep_graph_around {
+        dump_graph_around(tcx, around);
+    }
+
+    if let Some(ref pattern) = tcx.sess.opts.debugging_opts.dep_graph_find {
+        find_nodes(tcx, pattern);
+    }
+
     // if the `rustc_attrs` feature is not enabled, then the
     // attributes we are interested in cannot be present anyway, so
     // skip the walk.
@@ -229,65 +251,315 @@ fn check_paths<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
 fn dump_graph(tcx: TyCtxt) {
     let path: String = env::var("RUST_DEP_GRAPH").unwrap_or_else(|_| format!("dep_graph"));
     let query = tcx.dep_graph.query();
+    let (nodes, edges) = filtered_graph(&query);
+    write_txt_and_dot(&path, nodes, edges);
+}
 
-    let nodes = match env::var("RUST_DEP_GRAPH_FILTER") {
-        Ok(string) => {
-            // Expect one of: "-> target", "source -> target", or "source ->".
-            let edge_filter = EdgeFilter::new(&string).unwrap_or_else(|e| {
-                bug!("invalid filter: {}", e)
-            });
-            let sources = node_set(&query, &edge_filter.source);
-            let targets = node_set(&query, &edge_filter.target);
-            filter_nodes(&query, &sources, &targets)
+/// For `-Z dump-dep-graph-around=FILTER[,depth=N]`: dumps the nodes that
+/// are at most `N` (by default 1) edges away from a node matching `FILTER`,
+/// in either direction, and the edges between them. The files are named
+/// like those of `dump_graph`, with `-around` appended.
+fn dump_graph_around(tcx: TyCtxt, around: &str) {
+    let (filter, depth) = match around.rfind(",depth=") {
+        Some(i) => {
+            match around[i + ",depth=".len()..].parse() {
+                Ok(depth) => (&around[..i], depth),
+                Err(_) => {
+                    tcx.sess.fatal(&format!("invalid depth in `-Z dump-dep-graph-around={}`",
+                                            around))
+                }
+            }
         }
-        Err(_) => {
-            query.nodes()
-                 .into_iter()
-                 .collect()
+        None => (around, 1),
+    };
+    let node_filter = DepNodeFilter::new(filter);
+
+    let query = tcx.dep_graph.query();
+    let mut nodes = FxHashSet();
+    for node in query.nodes() {
+        if node_filter.test(node) {
+            nodes.extend(query.transitive_successors_within(node, depth));
+            nodes.extend(query.transitive_predecessors_within(node, depth));
         }
+    }
+    if nodes.is_empty() {
+        tcx.sess.warn(&format!("no node of the dep-graph matches `{}`", filter));
+    }
+
+    let path: String = env::var("RUST_DEP_GRAPH").unwrap_or_else(|_| format!("dep_graph"));
+    let (nodes, edges) = graph_of(&query, vec![nodes]);
+    write_txt_and_dot(&format!("{}-around", path), nodes, edges);
+}
+
+/// For `-Z dep-graph-find=REGEX`: prints the nodes matching `REGEX`, each
+/// followed by its direct predecessors (`<-`) and successors (`->`).
+fn find_nodes(tcx: TyCtxt, pattern: &str) {
+    let regex = match NodeRegex::new(pattern) {
+        Ok(regex) => regex,
+        Err(err) => tcx.sess.fatal(&format!("invalid `-Z dep-graph-find` pattern: {}", err)),
     };
-    let edges = filter_edges(&query, &nodes);
+    let query = tcx.dep_graph.query();
+    let nodes = query.find_nodes(&regex);
+    if nodes.is_empty() {
+        tcx.sess.warn(&format!("no node of the dep-graph matches `{}`", pattern));
+    }
+    for node in nodes {
+        println!("{:?}", node);
+        for source in query.immediate_predecessors(node) {
+            println!("    <- {:?}", source);
+        }
+        for target in query.immediate_successors(node) {
+            println!("    -> {:?}", target);
+        }
+    }
+}
 
-    { // dump a .txt file with just the edges:
+// Dumps the graph to `path.txt`, with just the edges, and to `path.dot`,
+// in graphviz format.
+fn write_txt_and_dot<'q>(path: &str,
+                         nodes: FxHashSet<&'q DepNode>,
+                         edges: Vec<(&'q DepNode, &'q DepNode, DepEdgeKind)>) {
+    {
         let txt_path = format!("{}.txt", path);
         let mut file = File::create(&txt_path).unwrap();
-        for &(ref source, ref target) in &edges {
-            write!(file, "{:?} -> {:?}\n", source, target).unwrap();
+        for &(ref source, ref target, kind) in &edges {
+            write!(file, "{:?} -> {:?} ({:?})\n", source, target, kind).unwrap();
         }
     }
 
-    { // dump a .dot file in graphviz format:
-        let dot_path = format!("{}.dot", path);
-        let mut v = Vec::new();
-        dot::render(&GraphvizDepGraph(nodes, edges), &mut v).unwrap();
-        File::create(&dot_path).and_then(|mut f| f.write_all(&v)).unwrap();
+    write_dot(&format!("{}.dot", path), nodes, edges);
+}
+
+/// Like `dump_graph`, for `-Z dump-dep-graph-dot`: writes just the
+/// graphviz file, to `path`.
+fn dump_graph_dot(tcx: TyCtxt, path: &str) {
+    let query = tcx.dep_graph.query();
+    let (nodes, edges) = filtered_graph(&query);
+    write_dot(path, nodes, edges);
+}
+
+/// For `-Z dump-dep-graph-json`: writes the graph to `path` as JSON, for
+/// tools outside of the compiler. Each node gets an ID that is stable
+/// across compilations (as long as the item it belongs to is there): its
+/// kind and DefPath, or its kind and hash if it has no DefPath. Nodes and
+/// edges are sorted by these IDs, so that the files can be diffed.
+fn dump_graph_json(tcx: TyCtxt, path: &str) {
+    let query = tcx.dep_graph.query();
+    let (nodes, edges) = filtered_graph(&query);
+
+    let mut ids = FxHashMap();
+    let mut json_nodes = vec![];
+    for &node in &nodes {
+        let (id, json) = json_node(tcx, node);
+        ids.insert(node, id.clone());
+        json_nodes.push((id, json));
     }
+    json_nodes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut json_edges: Vec<_> = edges.iter().map(|&(source, target, kind)| {
+        (&ids[source], &ids[target], format!("{:?}", kind))
+    }).collect();
+    json_edges.sort();
+
+    let mut graph = BTreeMap::new();
+    graph.insert("version".to_string(), Json::U64(1));
+    graph.insert("nodes".to_string(),
+                 Json::Array(json_nodes.into_iter().map(|(_, json)| json).collect()));
+    graph.insert("edges".to_string(), Json::Array(json_edges.into_iter().map(|(s, t, kind)| {
+        let mut edge = BTreeMap::new();
+        edge.insert("source".to_string(), Json::String(s.clone()));
+        edge.insert("target".to_string(), Json::String(t.clone()));
+        edge.insert("kind".to_string(), Json::String(kind));
+        Json::Object(edge)
+    }).collect()));
+
+    let mut file = File::create(path).unwrap();
+    write!(file, "{}", Json::Object(graph).pretty()).unwrap();
 }
 
+// The stable ID of `node` in JSON dumps, and the JSON object describing it.
+fn json_node(tcx: TyCtxt, node: &DepNode) -> (String, Json) {
+    let info = NodeInfo::new(tcx, node);
+    let mut object = BTreeMap::new();
+    object.insert("id".to_string(), Json::String(info.id.clone()));
+    object.insert("kind".to_string(), Json::String(info.kind));
+    object.insert("hash".to_string(), Json::String(info.hash));
+    if let Some((krate, def_path)) = info.def_path {
+        object.insert("crate".to_string(), Json::String(krate));
+        object.insert("def_path".to_string(), Json::String(def_path));
+    }
+    (info.id, Json::Object(object))
+}
+
+/// What the JSON and GraphML dumps say about a node.
+struct NodeInfo {
+    // the kind and DefPath of the node, or its kind and hash if it has no
+    // DefPath, which tells nodes apart from one compilation to the next (an
+    // anonymous node, see `DepGraph::with_anon_task`, only has its hash)
+    id: String,
+    kind: String,
+    hash: String,
+    // the crate and the rest of the DefPath, if any
+    def_path: Option<(String, String)>,
+}
+
+impl NodeInfo {
+    fn new(tcx: TyCtxt, node: &DepNode) -> NodeInfo {
+        let kind = format!("{:?}", node.kind);
+        let hash = node.hash.to_hex();
+        let (id, def_path) = match node.extract_def_id(tcx) {
+            Some(def_id) => {
+                let def_path = tcx.def_path(def_id);
+                let krate = tcx.original_crate_name(def_id.krate).as_str().to_string();
+                (format!("{}({})", kind, def_path.to_string(tcx)),
+                 Some((krate, def_path.to_string_no_crate())))
+            }
+            None if node.kind.has_params() || node.hash != Fingerprint::zero() => {
+                (format!("{}({})", kind, hash), None)
+            }
+            None => (kind.clone(), None),
+        };
+        NodeInfo { id, kind, hash, def_path }
+    }
+}
+
+/// For `-Z dump-dep-graph-graphml=PATH`: writes the (filtered) graph to
+/// `PATH` in GraphML, for tools like Gephi or yEd. The nodes have the IDs
+/// of the JSON dump, and their kind, crate and DefPath as attributes; the
+/// edges have their kind.
+fn dump_graph_graphml(tcx: TyCtxt, path: &str) {
+    let query = tcx.dep_graph.query();
+    let (nodes, edges) = filtered_graph(&query);
+
+    let mut ids = FxHashMap();
+    let mut infos = vec![];
+    for &node in &nodes {
+        let info = NodeInfo::new(tcx, node);
+        ids.insert(node, info.id.clone());
+        infos.push(info);
+    }
+    infos.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut edges: Vec<_> = edges.iter().map(|&(source, target, kind)| {
+        (&ids[source], &ids[target], kind)
+    }).collect();
+    edges.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+
+    let mut file = BufWriter::new(File::create(path).unwrap());
+    write!(file, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                  <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n\
+                  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n\
+                  <key id=\"crate\" for=\"node\" attr.name=\"crate\" attr.type=\"string\"/>\n\
+                  <key id=\"def_path\" for=\"node\" attr.name=\"def_path\" \
+                       attr.type=\"string\"/>\n\
+                  <key id=\"edge_kind\" for=\"edge\" attr.name=\"kind\" \
+                       attr.type=\"string\"/>\n\
+                  <graph id=\"dep_graph\" edgedefault=\"directed\">\n").unwrap();
+    for info in &infos {
+        write!(file, "<node id=\"{}\"><data key=\"kind\">{}</data>",
+               xml_escape(&info.id), info.kind).unwrap();
+        if let Some((ref krate, ref def_path)) = info.def_path {
+            write!(file, "<data key=\"crate\">{}</data><data key=\"def_path\">{}</data>",
+                   xml_escape(krate), xml_escape(def_path)).unwrap();
+        }
+        write!(file, "</node>\n").unwrap();
+    }
+    for &(source, target, kind) in &edges {
+        write!(file, "<edge source=\"{}\" target=\"{}\"><data key=\"edge_kind\">{:?}</data>\
+                      </edge>\n",
+               xml_escape(source), xml_escape(target), kind).unwrap();
+    }
+    write!(file, "</graph>\n</graphml>\n").unwrap();
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn write_dot<'q>(path: &str,
+                 nodes: FxHashSet<&'q DepNode>,
+                 edges: Vec<(&'q DepNode, &'q DepNode, DepEdgeKind)>) {
+    let mut v = Vec::new();
+    dot::render(&GraphvizDepGraph(nodes, edges), &mut v).unwrap();
+    File::create(path).and_then(|mut f| f.write_all(&v)).unwrap();
+}
+
+// The nodes and edges selected by `RUST_DEP_GRAPH_FILTER`, or all of them
+// if it is not set.
+fn filtered_graph(query: &DepGraphQuery)
+                  -> (FxHashSet<&DepNode>, Vec<(&DepNode, &DepNode, DepEdgeKind)>) {
+    let node_sets: Vec<FxHashSet<&DepNode>> = match env::var("RUST_DEP_GRAPH_FILTER") {
+        Ok(string) => {
+            // Expect a comma-separated list of: "-> target",
+            // "source -> target", or "source ->". Keep the nodes selected
+            // by any of them.
+            let edge_filters = EdgeFilter::new_list(&string).unwrap_or_else(|e| {
+                bug!("invalid filter: {}", e)
+            });
+            edge_filters.iter().map(|edge_filter| {
+                let sources = node_set(query, &edge_filter.source);
+                let targets = node_set(query, &edge_filter.target);
+                filter_nodes(query, &sources, &targets)
+            }).collect()
+        }
+        Err(_) => {
+            vec![query.nodes()
+                      .into_iter()
+                      .collect()]
+        }
+    };
+    graph_of(query, node_sets)
+}
+
+// The union of `node_sets`, and the edges between the nodes of each set.
+fn graph_of<'q>(query: &'q DepGraphQuery, node_sets: Vec<FxHashSet<&'q DepNode>>)
+                -> (FxHashSet<&'q DepNode>, Vec<(&'q DepNode, &'q DepNode, DepEdgeKind)>) {
+    let edges = filter_edges(query, &node_sets);
+    let nodes = node_sets.into_iter().flat_map(|nodes| nodes).collect();
+    (nodes, edges)
+}
+
+// The colors of the nodes in graphviz dumps, picked by the kind of the
+// node: light ones, so that the labels stay readable.
+const NODE_COLORS: &'static [&'static str] = &[
+    "lightblue", "lightpink", "palegreen", "khaki", "lightsalmon", "plum",
+    "lightcyan", "wheat", "lightgray", "aquamarine", "thistle", "peachpuff",
+];
+
 pub struct GraphvizDepGraph<'q>(FxHashSet<&'q DepNode>,
-                                Vec<(&'q DepNode, &'q DepNode)>);
+                                Vec<(&'q DepNode, &'q DepNode, DepEdgeKind)>);
 
 impl<'a, 'tcx, 'q> dot::GraphWalk<'a> for GraphvizDepGraph<'q> {
     type Node = &'q DepNode;
-    type Edge = (&'q DepNode, &'q DepNode);
+    type Edge = (&'q DepNode, &'q DepNode, DepEdgeKind);
     fn nodes(&self) -> dot::Nodes<&'q DepNode> {
         let nodes: Vec<_> = self.0.iter().cloned().collect();
         nodes.into_cow()
     }
-    fn edges(&self) -> dot::Edges<(&'q DepNode, &'q DepNode)> {
+    fn edges(&self) -> dot::Edges<(&'q DepNode, &'q DepNode, DepEdgeKind)> {
         self.1[..].into_cow()
     }
-    fn source(&self, edge: &(&'q DepNode, &'q DepNode)) -> &'q DepNode {
+    fn source(&self, edge: &(&'q DepNode, &'q DepNode, DepEdgeKind)) -> &'q DepNode {
         edge.0
     }
-    fn target(&self, edge: &(&'q DepNode, &'q DepNode)) -> &'q DepNode {
+    fn target(&self, edge: &(&'q DepNode, &'q DepNode, DepEdgeKind)) -> &'q DepNode {
         edge.1
     }
 }
 
 impl<'a, 'tcx, 'q> dot::Labeller<'a> for GraphvizDepGraph<'q> {
     type Node = &'q DepNode;
-    type Edge = (&'q DepNode, &'q DepNode);
+    type Edge = (&'q DepNode, &'q DepNode, DepEdgeKind);
     fn graph_id(&self) -> dot::Id {
         dot::Id::new("DependencyGraph").unwrap()
     }
@@ -302,11 +574,20 @@ impl<'a, 'tcx, 'q> dot::Labeller<'a> for GraphvizDepGraph<'q> {
     fn node_label(&self, n: &&'q DepNode) -> dot::LabelText {
         dot::LabelText::label(format!("{:?}", n))
     }
+    fn node_style(&self, _n: &&'q DepNode) -> dot::Style {
+        dot::Style::Filled
+    }
+    fn node_color(&self, n: &&'q DepNode) -> Option<dot::LabelText> {
+        let color = NODE_COLORS[n.kind as usize % NODE_COLORS.len()];
+        Some(dot::LabelText::label(color))
+    }
+    fn edge_label(&self, e: &(&'q DepNode, &'q DepNode, DepEdgeKind)) -> dot::LabelText {
+        dot::LabelText::label(format!("{:?}", e.2))
+    }
 }
 
-// Given an optional filter like `"x,y,z"`, returns either `None` (no
-// filter) or the set of nodes whose labels contain all of those
-// substrings.
+// Given an optional filter like `"x & y & z"`, returns either `None` (no
+// filter) or the set of nodes whose labels match all of those terms.
 fn node_set<'q>(query: &'q DepGraphQuery, filter: &DepNodeFilter)
                 -> Option<FxHashSet<&'q DepNode>>
 {
@@ -429,12 +710,16 @@ fn walk_between<'q>(query: &'q DepGraphQuery,
     }
 }
 
+// The edges between nodes selected by the same filter. (Those between the
+// nodes of two filters need not lie on a path that either filter asks for.)
 fn filter_edges<'q>(query: &'q DepGraphQuery,
-                    nodes: &FxHashSet<&'q DepNode>)
-                    -> Vec<(&'q DepNode, &'q DepNode)>
+                    node_sets: &[FxHashSet<&'q DepNode>])
+                    -> Vec<(&'q DepNode, &'q DepNode, DepEdgeKind)>
 {
     query.edges()
          .into_iter()
-         .filter(|&(source, target)| nodes.contains(source) && nodes.contains(target))
+         .filter(|&(source, target, _)| {
+             node_sets.iter().any(|nodes| nodes.contains(source) && nodes.contains(target))
+         })
          .collect()
 }
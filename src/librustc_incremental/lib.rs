@@ -21,6 +21,7 @@
 #![feature(rand)]
 #![feature(conservative_impl_trait)]
 
+extern crate flate2;
 extern crate graphviz;
 #[macro_use] extern crate rustc;
 extern crate rustc_data_structures;
@@ -38,6 +39,7 @@ pub use assert_dep_graph::assert_dep_graph;
 pub use calculate_svh::compute_incremental_hashes_map;
 pub use calculate_svh::IncrementalHashesMap;
 pub use calculate_svh::IchHasher;
+pub use persist::diff_dep_graphs;
 pub use persist::load_dep_graph;
 pub use persist::save_dep_graph;
 pub use persist::save_trans_partition;
@@ -15,6 +15,7 @@ use rustc::hir::def_id::DefIndex;
 use rustc::hir::map::DefPathHash;
 use rustc::ich::Fingerprint;
 use rustc::middle::cstore::EncodedMetadataHash;
+use rustc::ty::maps::SignatureFingerprint;
 use rustc_data_structures::fx::FxHashMap;
 use rustc_data_structures::indexed_vec::{IndexVec, Idx};
 
@@ -56,6 +57,13 @@ pub struct SerializedDepGraph {
     /// but rather the `DefPathIndex`. This can then be retraced
     /// to find the current def-id.
     pub hashes: Vec<(DepNodeIndex, Fingerprint)>,
+
+    /// These are fingerprints of the results of the `ItemSignature` nodes
+    /// of the items in this crate. When one of their inputs has changed,
+    /// the next compile computes them again and compares: a node whose
+    /// results are still the same is marked green and does not dirty the
+    /// nodes that depend on it (see `try_mark_green` in `load.rs`).
+    pub signatures: Vec<(DepNodeIndex, SignatureFingerprint)>,
 }
 
 impl SerializedDepGraph {
@@ -135,3 +143,13 @@ pub struct SerializedMetadataHashes {
     /// the DefIndex.
     pub index_map: FxHashMap<DefIndex, DefPathHash>
 }
+
+/// A source file of the local crate, as it was when the session was saved.
+/// The next session compares these to tell which files have changed, which
+/// is only reported (under `-Z incremental-info`): every file is still
+/// parsed again.
+#[derive(Debug, RustcEncodable, RustcDecodable)]
+pub struct SerializedSourceFile {
+    pub name: String,
+    pub src_hash: u128,
+}
@@ -0,0 +1,134 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Comparing the dep-graphs saved by two compilation sessions, for
+//! `-Z diff-dep-graph`. The graphs are read from their files alone, so
+//! nodes are shown by their kind and hash (as in
+//! `-Z dump-dep-graph-json`) rather than by item path.
+
+use rustc::dep_graph::DepNode;
+use rustc::ich::Fingerprint;
+use rustc::session::Session;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_serialize::Decodable as RustcDecodable;
+use rustc_serialize::opaque::Decoder;
+use std::hash::Hash;
+use std::path::Path;
+
+use super::data::*;
+use super::file_format;
+
+/// The parts of a saved dep-graph that we compare.
+struct SavedGraph {
+    commandline_args_hash: u64,
+    nodes: FxHashSet<DepNode>,
+    edges: FxHashSet<(DepNode, DepNode)>,
+    hashes: FxHashMap<DepNode, Fingerprint>,
+}
+
+/// Prints the nodes and edges that were added to or removed from the
+/// dep-graph saved at `old_path` to get the one at `new_path`, and the
+/// inputs whose hash changed. Note that saved graphs only keep the paths
+/// from inputs to outputs, not the intermediate nodes.
+pub fn diff_dep_graphs(sess: &Session, old_path: &Path, new_path: &Path) {
+    let (old, new) = match (load_graph(sess, old_path), load_graph(sess, new_path)) {
+        (Some(old), Some(new)) => (old, new),
+        _ => return,
+    };
+
+    println!("dep-graph changes from `{}` to `{}`:", old_path.display(), new_path.display());
+    if old.commandline_args_hash != new.commandline_args_hash {
+        println!("(the command-line arguments differ, so nothing was reused)");
+    }
+
+    print_nodes("nodes added", difference(&new.nodes, &old.nodes));
+    print_nodes("nodes removed", difference(&old.nodes, &new.nodes));
+    print_edges("edges added", difference(&new.edges, &old.edges));
+    print_edges("edges removed", difference(&old.edges, &new.edges));
+
+    let rehashed = old.hashes.iter().filter_map(|(node, hash)| {
+        match new.hashes.get(node) {
+            Some(new_hash) if new_hash != hash => Some(*node),
+            _ => None,
+        }
+    }).collect();
+    print_nodes("inputs re-hashed", rehashed);
+}
+
+fn load_graph(sess: &Session, path: &Path) -> Option<SavedGraph> {
+    let data = match file_format::read_file(sess, path) {
+        Ok(Some(data)) => data,
+        Ok(None) => {
+            sess.err(&format!("`{}` does not exist or was not saved by this version \
+                               of the compiler",
+                              path.display()));
+            return None;
+        }
+        Err(err) => {
+            sess.err(&format!("could not read dep-graph from `{}`: {}", path.display(), err));
+            return None;
+        }
+    };
+
+    let mut decoder = Decoder::new(&data, 0);
+    let decoded = u64::decode(&mut decoder).and_then(|commandline_args_hash| {
+        SerializedDepGraph::decode(&mut decoder).map(|graph| (commandline_args_hash, graph))
+    });
+    let (commandline_args_hash, graph) = match decoded {
+        Ok(decoded) => decoded,
+        Err(err) => {
+            sess.err(&format!("decoding error in dep-graph from `{}`: {}", path.display(), err));
+            return None;
+        }
+    };
+
+    let mut edges = FxHashSet();
+    for (source_index, &source) in graph.nodes.iter_enumerated() {
+        for &target_index in graph.edge_targets_from(source_index) {
+            edges.insert((source, graph.nodes[target_index]));
+        }
+    }
+
+    Some(SavedGraph {
+        commandline_args_hash,
+        nodes: graph.nodes.iter().chain(&graph.bootstrap_outputs).cloned().collect(),
+        edges,
+        hashes: graph.hashes.iter().map(|&(index, hash)| (graph.nodes[index], hash)).collect(),
+    })
+}
+
+fn difference<T: Copy + Ord + Hash + Eq>(a: &FxHashSet<T>, b: &FxHashSet<T>) -> Vec<T> {
+    let mut result: Vec<T> = a.difference(b).cloned().collect();
+    result.sort();
+    result
+}
+
+fn print_nodes(title: &str, mut nodes: Vec<DepNode>) {
+    nodes.sort();
+    println!("{} ({}):", title, nodes.len());
+    for node in nodes {
+        println!("    {}", label(&node));
+    }
+}
+
+fn print_edges(title: &str, edges: Vec<(DepNode, DepNode)>) {
+    println!("{} ({}):", title, edges.len());
+    for (source, target) in edges {
+        println!("    {} -> {}", label(&source), label(&target));
+    }
+}
+
+fn label(node: &DepNode) -> String {
+    if node.kind.has_params() {
+        format!("{:?}({})", node.kind, node.hash.to_hex())
+    } else {
+        format!("{:?}", node.kind)
+    }
+}
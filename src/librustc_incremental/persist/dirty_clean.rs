@@ -21,6 +21,11 @@
 //! - `#[rustc_clean(label="TypeckTables", cfg="rev2")]` same as above,
 //!   except that the node MUST exist.
 //!
+//! The `label` may also be a comma-separated list of labels, e.g.
+//! `#[rustc_clean(label="Hir, TypeckTables", cfg="rev2")]`, or be given
+//! several times. Each of the nodes is then checked on its own, and an
+//! error is reported for every one that does not meet the condition.
+//!
 //! Errors are reported if we are in the suitable configuration but
 //! the required condition is not met.
 //!
@@ -42,7 +47,7 @@
 
 use super::data::DepNodeIndex;
 use super::load::DirtyNodes;
-use rustc::dep_graph::{DepGraphQuery, DepNode, DepKind};
+use rustc::dep_graph::{DepGraphQuery, DepNode, DepNodeColor, DepKind};
 use rustc::hir;
 use rustc::hir::def_id::DefId;
 use rustc::hir::itemlikevisit::ItemLikeVisitor;
@@ -111,23 +116,29 @@ pub struct DirtyCleanVisitor<'a, 'tcx:'a> {
 }
 
 impl<'a, 'tcx> DirtyCleanVisitor<'a, 'tcx> {
-    fn dep_node(&self, attr: &Attribute, def_id: DefId) -> DepNode {
+    fn dep_nodes(&self, attr: &Attribute, def_id: DefId) -> Vec<DepNode> {
         let def_path_hash = self.tcx.def_path_hash(def_id);
+        let mut dep_nodes = vec![];
         for item in attr.meta_item_list().unwrap_or_else(Vec::new) {
             if item.check_name(LABEL) {
                 let value = expect_associated_value(self.tcx, &item);
-                match DepNode::from_label_string(&value.as_str(), def_path_hash) {
-                    Ok(dep_node) => return dep_node,
-                    Err(()) => {
-                        self.tcx.sess.span_fatal(
-                            item.span,
-                            &format!("dep-node label `{}` not recognized", value));
+                for label in value.as_str().split(',').map(|label| label.trim()) {
+                    match DepNode::from_label_string(label, def_path_hash) {
+                        Ok(dep_node) => dep_nodes.push(dep_node),
+                        Err(()) => {
+                            self.tcx.sess.span_fatal(
+                                item.span,
+                                &format!("dep-node label `{}` not recognized", label));
+                        }
                     }
                 }
             }
         }
 
-        self.tcx.sess.span_fatal(attr.span, "no `label` found");
+        if dep_nodes.is_empty() {
+            self.tcx.sess.span_fatal(attr.span, "no `label` found");
+        }
+        dep_nodes
     }
 
     fn dep_node_str(&self, dep_node: &DepNode) -> String {
@@ -140,6 +151,17 @@ impl<'a, 'tcx> DirtyCleanVisitor<'a, 'tcx> {
         }
     }
 
+    /// Whether a node that is not an input was kept from the previous
+    /// session. Red/green marking computes the nodes it looks at again, so
+    /// those are in the dep-graph whatever it finds, and their color tells.
+    fn is_clean_target(&self, dep_node: &DepNode) -> bool {
+        match self.tcx.dep_graph.color(dep_node) {
+            Some(DepNodeColor::Green) => true,
+            Some(DepNodeColor::Red) => false,
+            None => self.query.contains_node(dep_node),
+        }
+    }
+
     fn assert_dirty(&self, item_span: Span, dep_node: DepNode) {
         debug!("assert_dirty({:?})", dep_node);
 
@@ -160,7 +182,7 @@ impl<'a, 'tcx> DirtyCleanVisitor<'a, 'tcx> {
             _ => {
                 // Other kinds of nodes would be targets, so check if
                 // the dep-graph contains the node.
-                if self.query.contains_node(&dep_node) {
+                if self.is_clean_target(&dep_node) {
                     let dep_node_str = self.dep_node_str(&dep_node);
                     self.tcx.sess.span_err(
                         item_span,
@@ -188,7 +210,7 @@ impl<'a, 'tcx> DirtyCleanVisitor<'a, 'tcx> {
             }
             _ => {
                 // Otherwise, check if the dep-node exists.
-                if !self.query.contains_node(&dep_node) {
+                if !self.is_clean_target(&dep_node) {
                     let dep_node_str = self.dep_node_str(&dep_node);
                     self.tcx.sess.span_err(
                         item_span,
@@ -205,12 +227,16 @@ impl<'a, 'tcx> DirtyCleanVisitor<'a, 'tcx> {
             if attr.check_name(ATTR_DIRTY) {
                 if check_config(self.tcx, attr) {
                     self.checked_attrs.insert(attr.id);
-                    self.assert_dirty(item_span, self.dep_node(attr, def_id));
+                    for dep_node in self.dep_nodes(attr, def_id) {
+                        self.assert_dirty(item_span, dep_node);
+                    }
                 }
             } else if attr.check_name(ATTR_CLEAN) {
                 if check_config(self.tcx, attr) {
                     self.checked_attrs.insert(attr.id);
-                    self.assert_clean(item_span, self.dep_node(attr, def_id));
+                    for dep_node in self.dep_nodes(attr, def_id) {
+                        self.assert_clean(item_span, dep_node);
+                    }
                 }
             }
         }
@@ -18,30 +18,83 @@
 //! Since we can always just ignore the incremental compilation cache and
 //! compiler versions don't change frequently for the typical user, being
 //! conservative here practically has no downside.
+//!
+//! The version of a development build often lacks a commit hash though, so
+//! the header also has a version of the format of the data that follows
+//! it, to be bumped whenever that changes. Files with a header that does
+//! not match are ignored, with a note for the first one (and for each of
+//! them under `-Z incremental-info`).
+//!
+//! The data after the header is compressed, unless
+//! `-Z incremental-compression=none` is given; the last byte of the header
+//! says how (see `Compression`), and `read_file` decompresses it.
 
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 use std::path::Path;
 use std::fs::File;
 use std::env;
 
+use flate2;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
 use rustc::session::Session;
-use rustc::session::config::nightly_options;
+use rustc::session::config::{nightly_options, MetadataCompression};
 
 /// The first few bytes of files generated by incremental compilation
 const FILE_MAGIC: &'static [u8] = b"RSIC";
 
 /// Change this if the header format changes
-const HEADER_FORMAT_VERSION: u16 = 0;
+const HEADER_FORMAT_VERSION: u16 = 2;
+
+/// Change this if the encoding of the data after the header changes, e.g.
+/// of `SerializedDepGraph`, so that development builds, whose
+/// `RUSTC_VERSION` is the same from one commit to the next, do not try to
+/// decode the files of another build.
+const DATA_FORMAT_VERSION: u16 = 2;
 
 /// A version string that hopefully is always different for compiler versions
 /// with different encodings of incremental compilation artifacts. Contains
 /// the git commit hash.
 const RUSTC_VERSION: Option<&'static str> = option_env!("CFG_VERSION");
 
-pub fn write_file_header<W: io::Write>(stream: &mut W) -> io::Result<()> {
+/// How the data after the header is stored.
+#[derive(Clone, Copy, PartialEq)]
+enum Compression {
+    None = 0,
+    Zlib = 1,
+}
+
+/// Writes the header and then `data` to `stream`, compressed as
+/// `-Z incremental-compression` says.
+pub fn write_file<W: io::Write>(sess: &Session, stream: &mut W, data: &[u8]) -> io::Result<()> {
+    let level = match sess.opts.debugging_opts.incremental_compression {
+        Some(MetadataCompression::None) => None,
+        Some(MetadataCompression::Fast) | None => Some(flate2::Compression::Fast),
+        Some(MetadataCompression::Best) => Some(flate2::Compression::Best),
+    };
+
+    write_file_header(stream)?;
+    match level {
+        Some(level) => {
+            stream.write_all(&[Compression::Zlib as u8])?;
+            let mut encoder = ZlibEncoder::new(&mut *stream, level);
+            encoder.write_all(data)?;
+            encoder.finish()?;
+        }
+        None => {
+            stream.write_all(&[Compression::None as u8])?;
+            stream.write_all(data)?;
+        }
+    }
+    stream.flush()
+}
+
+fn write_file_header<W: io::Write>(stream: &mut W) -> io::Result<()> {
     stream.write_all(FILE_MAGIC)?;
     stream.write_all(&[(HEADER_FORMAT_VERSION >> 0) as u8,
                        (HEADER_FORMAT_VERSION >> 8) as u8])?;
+    stream.write_all(&[(DATA_FORMAT_VERSION >> 0) as u8,
+                       (DATA_FORMAT_VERSION >> 8) as u8])?;
 
     let rustc_version = rustc_version();
     assert_eq!(rustc_version.len(), (rustc_version.len() as u8) as usize);
@@ -55,9 +108,9 @@ pub fn write_file_header<W: io::Write>(stream: &mut W) -> io::Result<()> {
 ///
 /// - Returns `Ok(Some(data))` if the file existed and was generated by a
 ///   compatible compiler version. `data` is the entire contents of the file
-///   *after* the header.
+///   *after* the header, decompressed.
 /// - Returns `Ok(None)` if the file did not exist or was generated by an
-///   incompatible version of the compiler.
+///   incompatible version of the compiler, which is noted.
 /// - Returns `Err(..)` if some kind of IO error occurred while reading the
 ///   file.
 pub fn read_file(sess: &Session, path: &Path) -> io::Result<Option<Vec<u8>>> {
@@ -71,7 +124,9 @@ pub fn read_file(sess: &Session, path: &Path) -> io::Result<Option<Vec<u8>>> {
     {
         debug_assert!(FILE_MAGIC.len() == 4);
         let mut file_magic = [0u8; 4];
-        file.read_exact(&mut file_magic)?;
+        if !read_header_bytes(sess, path, &mut file, &mut file_magic)? {
+            return Ok(None)
+        }
         if file_magic != FILE_MAGIC {
             report_format_mismatch(sess, path, "Wrong FILE_MAGIC");
             return Ok(None)
@@ -82,7 +137,9 @@ pub fn read_file(sess: &Session, path: &Path) -> io::Result<Option<Vec<u8>>> {
     {
         debug_assert!(::std::mem::size_of_val(&HEADER_FORMAT_VERSION) == 2);
         let mut header_format_version = [0u8; 2];
-        file.read_exact(&mut header_format_version)?;
+        if !read_header_bytes(sess, path, &mut file, &mut header_format_version)? {
+            return Ok(None)
+        }
         let header_format_version = (header_format_version[0] as u16) |
                                     ((header_format_version[1] as u16) << 8);
 
@@ -92,14 +149,33 @@ pub fn read_file(sess: &Session, path: &Path) -> io::Result<Option<Vec<u8>>> {
         }
     }
 
+    // Check DATA_FORMAT_VERSION
+    {
+        let mut data_format_version = [0u8; 2];
+        if !read_header_bytes(sess, path, &mut file, &mut data_format_version)? {
+            return Ok(None)
+        }
+        let data_format_version = (data_format_version[0] as u16) |
+                                  ((data_format_version[1] as u16) << 8);
+
+        if data_format_version != DATA_FORMAT_VERSION {
+            report_format_mismatch(sess, path, "Wrong DATA_FORMAT_VERSION");
+            return Ok(None)
+        }
+    }
+
     // Check RUSTC_VERSION
     {
         let mut rustc_version_str_len = [0u8; 1];
-        file.read_exact(&mut rustc_version_str_len)?;
+        if !read_header_bytes(sess, path, &mut file, &mut rustc_version_str_len)? {
+            return Ok(None)
+        }
         let rustc_version_str_len = rustc_version_str_len[0] as usize;
         let mut buffer = Vec::with_capacity(rustc_version_str_len);
         buffer.resize(rustc_version_str_len, 0);
-        file.read_exact(&mut buffer)?;
+        if !read_header_bytes(sess, path, &mut file, &mut buffer)? {
+            return Ok(None)
+        }
 
         if buffer != rustc_version().as_bytes() {
             report_format_mismatch(sess, path, "Different compiler version");
@@ -107,19 +183,63 @@ pub fn read_file(sess: &Session, path: &Path) -> io::Result<Option<Vec<u8>>> {
         }
     }
 
+    // Check the compression, which goes last
+    let compression = {
+        let mut compression = [0u8; 1];
+        if !read_header_bytes(sess, path, &mut file, &mut compression)? {
+            return Ok(None)
+        }
+        match compression[0] {
+            0 => Compression::None,
+            1 => Compression::Zlib,
+            _ => {
+                report_format_mismatch(sess, path, "Unknown compression");
+                return Ok(None)
+            }
+        }
+    };
+
     let mut data = vec![];
-    file.read_to_end(&mut data)?;
+    match compression {
+        Compression::None => file.read_to_end(&mut data)?,
+        Compression::Zlib => ZlibDecoder::new(file).read_to_end(&mut data)?,
+    };
 
     Ok(Some(data))
 }
 
+// Like `read_exact`, except that a file ending within the header, say
+// because the compiler that wrote it was killed, is not an error: it is
+// only ignored, like a file with a header that does not match.
+fn read_header_bytes(sess: &Session, path: &Path, file: &mut File, buf: &mut [u8])
+                     -> io::Result<bool> {
+    match file.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+            report_format_mismatch(sess, path, "Truncated header");
+            Ok(false)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+// Notes the first file that is ignored, or every one of them under
+// `-Z incremental-info`: they are usually all written by the same compiler.
 fn report_format_mismatch(sess: &Session, file: &Path, message: &str) {
     debug!("read_file: {}", message);
 
     if sess.opts.debugging_opts.incremental_info {
-        eprintln!("incremental: ignoring cache artifact `{}`: {}",
-                  file.file_name().unwrap().to_string_lossy(),
-                  message);
+        sess.note_without_error(&format!("incremental compilation: ignoring cache artifact \
+                                          `{}`: {}",
+                                         file.display(),
+                                         message));
+    } else if !sess.incr_comp_artifact_ignored.get() {
+        sess.incr_comp_artifact_ignored.set(true);
+        sess.note_without_error(&format!("incremental compilation: ignoring cache artifacts \
+                                          that don't match this compiler, like `{}`: {} \
+                                          (see `-Z incremental-info` for the others)",
+                                         file.display(),
+                                         message));
     }
 }
 
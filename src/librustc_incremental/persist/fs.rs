@@ -61,6 +61,16 @@
 //! any more and will delete those. It will also delete any finalized session
 //! directories for a given crate except for the most recent one.
 //!
+//! The cache can also be given a size quota, with `-Z incremental-quota` or
+//! the `RUSTC_INCREMENTAL_QUOTA` environment variable (in megabytes). At the
+//! end of each session, if the whole incremental compilation directory
+//! takes more space than that, finalized session directories of any crate
+//! are deleted until it does not any more, starting with those of the
+//! sessions that started the longest ago. Since a session only reuses the
+//! most recent session directory of its crate, this evicts the least
+//! recently used ones first. Directories that are in use are locked, and
+//! left alone like above.
+//!
 //! ## Synchronization
 //!
 //! There is some synchronization needed in order for the compiler to be able to
@@ -109,10 +119,10 @@
 //! If file locking does not work reliably (like on NFS), some of the
 //! synchronization will go haywire.
 //! In both cases we recommend to locate the incremental compilation directory
-//! on a file system that supports these things.
-//! It might be a good idea though to try and detect whether we are on an
-//! unsupported file system and emit a warning in that case. This is not yet
-//! implemented.
+//! on a file system that supports these things. The compiler warns when it
+//! has to fall back to copying, and refuses to start a session (pointing at
+//! the file system) if the lock file of its session directory cannot be
+//! locked because the file system does not support locking at all.
 
 use rustc::hir::def_id::{CrateNum, LOCAL_CRATE};
 use rustc::hir::svh::Svh;
@@ -122,6 +132,7 @@ use rustc::util::fs as fs_util;
 use rustc_data_structures::{flock, base_n};
 use rustc_data_structures::fx::{FxHashSet, FxHashMap};
 
+use std::env;
 use std::ffi::OsString;
 use std::fs as std_fs;
 use std::io;
@@ -134,6 +145,7 @@ const LOCK_FILE_EXT: &'static str = ".lock";
 const DEP_GRAPH_FILENAME: &'static str = "dep-graph.bin";
 const WORK_PRODUCTS_FILENAME: &'static str = "work-products.bin";
 const METADATA_HASHES_FILENAME: &'static str = "metadata.bin";
+const SOURCE_FILES_FILENAME: &'static str = "source-files.bin";
 
 // We encode integers using the following base, so they are shorter than decimal
 // or hexadecimal numbers (we want short file and directory names). Since these
@@ -153,6 +165,10 @@ pub fn metadata_hash_export_path(sess: &Session) -> PathBuf {
     in_incr_comp_dir_sess(sess, METADATA_HASHES_FILENAME)
 }
 
+pub fn source_files_path(sess: &Session) -> PathBuf {
+    in_incr_comp_dir_sess(sess, SOURCE_FILES_FILENAME)
+}
+
 pub fn metadata_hash_import_path(import_session_dir: &Path) -> PathBuf {
     import_session_dir.join(METADATA_HASHES_FILENAME)
 }
@@ -373,6 +389,7 @@ pub fn finalize_session_directory(sess: &Session, svh: Svh) {
     }
 
     let _ = garbage_collect_session_directories(sess);
+    enforce_cache_quota(sess);
 }
 
 pub fn delete_all_session_dir_contents(sess: &Session) -> io::Result<()> {
@@ -492,6 +509,14 @@ fn lock_directory(sess: &Session,
         Err(err) => {
             sess.err(&format!("incremental compilation: could not create \
                                session directory lock file: {}", err));
+            if flock::Lock::error_unsupported(&err) {
+                sess.note_without_error(&format!(
+                    "the file system of `{}` does not appear to support file \
+                     locking, which is needed to keep concurrent compiler \
+                     processes from interfering with each other; consider \
+                     moving the incremental compilation directory elsewhere",
+                    session_dir.parent().unwrap().display()));
+            }
             Err(())
         }
     }
@@ -903,6 +928,144 @@ pub fn garbage_collect_session_directories(sess: &Session) -> io::Result<()> {
     Ok(())
 }
 
+/// The most space, in bytes, that the incremental compilation directory
+/// may take, if given by `-Z incremental-quota` or else by
+/// `RUSTC_INCREMENTAL_QUOTA`, in megabytes.
+fn cache_quota(sess: &Session) -> Option<u64> {
+    let megabytes = match sess.opts.debugging_opts.incremental_quota {
+        Some(megabytes) => megabytes as u64,
+        None => {
+            let value = match env::var("RUSTC_INCREMENTAL_QUOTA") {
+                Ok(value) => value,
+                Err(_) => return None,
+            };
+            match value.parse() {
+                Ok(megabytes) => megabytes,
+                Err(_) => {
+                    sess.warn(&format!("ignoring `RUSTC_INCREMENTAL_QUOTA={}`, which is not \
+                                        a number of megabytes",
+                                       value));
+                    return None;
+                }
+            }
+        }
+    };
+    Some(megabytes * 1024 * 1024)
+}
+
+/// Deletes finalized session directories, of any crate, least recently
+/// used first, until the incremental compilation directory fits in its
+/// quota; see the module documentation.
+fn enforce_cache_quota(sess: &Session) {
+    let quota = match cache_quota(sess) {
+        Some(quota) => quota,
+        None => return,
+    };
+    let incr_dir = sess.opts.incremental.as_ref().unwrap();
+    let session_directory: PathBuf = sess.incr_comp_session_dir().clone();
+    let print_evictions = sess.opts.debugging_opts.incremental_info;
+
+    // Measure everything, and collect the session directories that may go
+    let mut total = 0;
+    let mut candidates = vec![];
+    let crate_directories = match incr_dir.read_dir() {
+        Ok(entries) => entries,
+        Err(err) => {
+            sess.warn(&format!("could not enforce the quota of incremental compilation \
+                                directory `{}`: {}",
+                               incr_dir.display(),
+                               err));
+            return;
+        }
+    };
+    for crate_directory in crate_directories.filter_map(|entry| entry.ok()) {
+        let entries = match crate_directory.path().read_dir() {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let size = match entry.metadata() {
+                Ok(ref metadata) if metadata.is_dir() => directory_size(&path),
+                Ok(metadata) => metadata.len(),
+                Err(_) => continue,
+            };
+            total += size;
+
+            let entry_name = entry.file_name();
+            let entry_name = entry_name.to_string_lossy();
+            if is_session_directory(&entry_name) && is_finalized(&entry_name) &&
+               path != session_directory {
+                if let Ok(timestamp) = extract_timestamp_from_session_dir(&entry_name) {
+                    candidates.push((timestamp, path, size));
+                }
+            }
+        }
+    }
+    debug!("enforce_cache_quota() - {} bytes used, quota is {} bytes", total, quota);
+
+    candidates.sort_by_key(|&(timestamp, ..)| timestamp);
+    for (_, path, size) in candidates {
+        if total <= quota {
+            break;
+        }
+        let lock_file_path = lock_file_path(&path);
+        let lock = match flock::Lock::new(&lock_file_path,
+                                          false,  // don't wait
+                                          false,  // don't create the lock-file
+                                          true) { // get an exclusive lock
+            Ok(lock) => lock,
+            Err(_) => {
+                debug!("enforce_cache_quota() - not evicting `{}`, still in use",
+                       path.display());
+                continue;
+            }
+        };
+        match safe_remove_dir_all(&path) {
+            Ok(()) => {
+                delete_session_dir_lock_file(sess, &lock_file_path);
+                total -= size;
+                if print_evictions {
+                    eprintln!("incremental: evicted `{}` ({} bytes) from the cache",
+                              path.display(),
+                              size);
+                }
+            }
+            Err(err) => {
+                sess.warn(&format!("Failed to evict incremental compilation session \
+                                    directory `{}`: {}",
+                                   path.display(),
+                                   err));
+            }
+        }
+        mem::drop(lock);
+    }
+
+    if print_evictions && total > quota {
+        eprintln!("incremental: cache takes {} bytes, over its quota of {} bytes",
+                  total,
+                  quota);
+    }
+}
+
+// The total size of the files in `path` and its subdirectories. Files that
+// are hard-linked from several session directories count once for each.
+fn directory_size(path: &Path) -> u64 {
+    let entries = match path.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    entries.filter_map(|entry| entry.ok())
+           .map(|entry| {
+               match entry.metadata() {
+                   Ok(ref metadata) if metadata.is_dir() => directory_size(&entry.path()),
+                   Ok(metadata) => metadata.len(),
+                   Err(_) => 0,
+               }
+           })
+           .sum()
+}
+
 fn all_except_most_recent(deletion_candidates: Vec<(SystemTime, PathBuf, Option<flock::Lock>)>)
                           -> FxHashMap<PathBuf, Option<flock::Lock>> {
     let most_recent = deletion_candidates.iter()
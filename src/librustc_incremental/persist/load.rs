@@ -10,12 +10,14 @@
 
 //! Code to save/load the dep-graph from files.
 
-use rustc::dep_graph::{DepNode, WorkProductId, DepKind};
+use rustc::dep_graph::{DepNode, DepNodeColor, WorkProductId, DepKind};
+use rustc::dep_graph::debug::DepNodeFilter;
 use rustc::hir::def_id::DefId;
 use rustc::hir::svh::Svh;
 use rustc::ich::Fingerprint;
 use rustc::session::Session;
 use rustc::ty::TyCtxt;
+use rustc::ty::maps::SignatureFingerprint;
 use rustc_data_structures::fx::{FxHashSet, FxHashMap};
 use rustc_data_structures::indexed_vec::IndexVec;
 use rustc_serialize::Decodable as RustcDecodable;
@@ -65,9 +67,40 @@ pub fn load_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     }
 
     let _ignore = tcx.dep_graph.in_ignore();
+    if tcx.sess.opts.debugging_opts.incremental_info {
+        report_changed_source_files(tcx.sess);
+    }
     load_dep_graph_if_exists(tcx, incremental_hashes_map);
 }
 
+/// Compares the source files of the local crate with the ones recorded by
+/// the previous session.
+fn report_changed_source_files(sess: &Session) {
+    // Older session directories don't have this file. That's no reason to
+    // throw away their contents, so don't go through `load_data`.
+    let data = match file_format::read_file(sess, &source_files_path(sess)) {
+        Ok(Some(data)) => data,
+        Ok(None) | Err(_) => return,
+    };
+    let mut decoder = Decoder::new(&data, 0);
+    let prev_source_files = match <Vec<SerializedSourceFile>>::decode(&mut decoder) {
+        Ok(source_files) => source_files,
+        Err(_) => return,
+    };
+    let prev_hashes: FxHashMap<_, _> = prev_source_files.iter()
+        .map(|source_file| (&source_file.name[..], source_file.src_hash))
+        .collect();
+
+    let files = sess.codemap().files();
+    let local_files: Vec<_> = files.iter().filter(|filemap| !filemap.is_imported()).collect();
+    let changed = local_files.iter()
+        .filter(|filemap| prev_hashes.get(&filemap.name[..]) != Some(&filemap.src_hash))
+        .count();
+    eprintln!("incremental: {} of {} source files changed since the previous session",
+              changed,
+              local_files.len());
+}
+
 fn load_dep_graph_if_exists<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                       incremental_hashes_map: &IncrementalHashesMap) {
     let dep_graph_path = dep_graph_path(tcx.sess);
@@ -149,7 +182,7 @@ pub fn decode_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     let mut dep_graph_decoder = Decoder::new(dep_graph_data, 0);
     let prev_commandline_args_hash = u64::decode(&mut dep_graph_decoder)?;
 
-    if prev_commandline_args_hash != tcx.sess.opts.dep_tracking_hash() {
+    if prev_commandline_args_hash != tcx.sess.opts.incremental_dep_tracking_hash() {
         if tcx.sess.opts.debugging_opts.incremental_info {
             eprintln!("incremental: completely ignoring cache because of \
                        differing commandline arguments");
@@ -172,8 +205,25 @@ pub fn decode_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                               incremental_hashes_map,
                                               &serialized_dep_graph.nodes,
                                               &serialized_dep_graph.hashes);
-    let dirty_raw_nodes = transitive_dirty_nodes(&serialized_dep_graph,
-                                                 dirty_raw_nodes);
+    let explain_filter = tcx.sess.opts.debugging_opts.incremental_explain
+                                                     .as_ref()
+                                                     .map(|s| DepNodeFilter::new(s));
+    let mut dirtied_by = FxHashMap();
+    let dirty_raw_nodes = transitive_dirty_nodes(tcx,
+                                                 &serialized_dep_graph,
+                                                 dirty_raw_nodes,
+                                                 if explain_filter.is_some() {
+                                                     Some(&mut dirtied_by)
+                                                 } else {
+                                                     None
+                                                 });
+    if let Some(ref filter) = explain_filter {
+        explain_dirty_nodes(filter, &serialized_dep_graph.nodes, &dirty_raw_nodes, &dirtied_by);
+    }
+
+    let stats = &tcx.sess.incr_comp_stats;
+    stats.nodes_loaded.set(serialized_dep_graph.nodes.len() as u64);
+    stats.nodes_dirty.set(dirty_raw_nodes.len() as u64);
 
     // Recreate the edges in the graph that are still clean.
     let mut clean_work_products = FxHashSet();
@@ -269,20 +319,42 @@ fn initial_dirty_nodes<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     dirty_nodes
 }
 
-fn transitive_dirty_nodes(serialized_dep_graph: &SerializedDepGraph,
-                          mut dirty_nodes: DirtyNodes)
-                          -> DirtyNodes
+/// Dirties everything reachable from the initially dirty nodes, except
+/// for what can only be reached through nodes that `try_mark_green` finds
+/// unchanged. If `dirtied_by` is given, it records for each node dirtied
+/// that way the node it was reached from.
+fn transitive_dirty_nodes<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                    serialized_dep_graph: &SerializedDepGraph,
+                                    mut dirty_nodes: DirtyNodes,
+                                    mut dirtied_by: Option<&mut FxHashMap<DepNodeIndex,
+                                                                          DepNodeIndex>>)
+                                    -> DirtyNodes
 {
+    let signatures: FxHashMap<_, _> =
+        serialized_dep_graph.signatures
+                            .iter()
+                            .map(|&(index, ref fingerprint)| (index, fingerprint))
+                            .collect();
+
     let mut stack: Vec<(DepNodeIndex, DepNodeIndex)> = vec![];
     stack.extend(dirty_nodes.iter().map(|(&s, &b)| (s, b)));
     while let Some((source, blame)) = stack.pop() {
         // we know the source is dirty (because of the node `blame`)...
         debug_assert!(dirty_nodes.contains_key(&source));
 
-        // ...so we dirty all the targets (with the same blame)
+        // ...so we dirty all the targets (with the same blame), unless
+        // they turn out not to have changed
         for &target in serialized_dep_graph.edge_targets_from(source) {
             if !dirty_nodes.contains_key(&target) {
+                if let Some(prev) = signatures.get(&target) {
+                    if try_mark_green(tcx, serialized_dep_graph.nodes[target], prev) {
+                        continue;
+                    }
+                }
                 dirty_nodes.insert(target, blame);
+                if let Some(ref mut dirtied_by) = dirtied_by {
+                    dirtied_by.insert(target, source);
+                }
                 stack.push((target, blame));
             }
         }
@@ -290,6 +362,71 @@ fn transitive_dirty_nodes(serialized_dep_graph: &SerializedDepGraph,
     dirty_nodes
 }
 
+/// Checks whether `dep_node`, a node of the previous session that some
+/// changed input reaches, still has the results fingerprinted as `prev`:
+/// if so, it is marked green and the nodes depending on it are not dirtied
+/// on its account. This computes the queries of the node again, which
+/// records the node in the current dep-graph with what it reads now.
+fn try_mark_green<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                            dep_node: DepNode,
+                            prev: &SignatureFingerprint)
+                            -> bool {
+    if let Some(color) = tcx.dep_graph.color(&dep_node) {
+        return color == DepNodeColor::Green;
+    }
+
+    // Results computed with errors are not worth comparing, so a node whose
+    // queries report any is left red.
+    let err_count = tcx.sess.err_count();
+    let green = match dep_node.extract_def_id(tcx) {
+        Some(def_id) => tcx.signature_unchanged(def_id, prev) &&
+                        tcx.sess.err_count() == err_count,
+        None => false,
+    };
+
+    let color = if green { DepNodeColor::Green } else { DepNodeColor::Red };
+    debug!("try_mark_green: {:?} is {:?}", dep_node, color);
+    if tcx.sess.opts.debugging_opts.incremental_dump_hash {
+        println!("node {:?} is {:?}", dep_node, color);
+    }
+    tcx.dep_graph.mark_color(dep_node, color);
+    if green {
+        let stats = &tcx.sess.incr_comp_stats;
+        stats.nodes_green.set(stats.nodes_green.get() + 1);
+    }
+    green
+}
+
+/// For `-Z incremental-explain`: prints, for each dirty node matching
+/// `filter`, the chain of edges it was dirtied along, starting with the
+/// input that changed or was removed.
+fn explain_dirty_nodes(filter: &DepNodeFilter,
+                       nodes: &IndexVec<DepNodeIndex, DepNode>,
+                       dirty_nodes: &DirtyNodes,
+                       dirtied_by: &FxHashMap<DepNodeIndex, DepNodeIndex>) {
+    let mut explained: Vec<DepNodeIndex> = dirty_nodes.keys()
+                                                      .cloned()
+                                                      .filter(|&index| filter.test(&nodes[index]))
+                                                      .collect();
+    explained.sort();
+
+    for index in explained {
+        let mut chain = vec![index];
+        let mut current = index;
+        while let Some(&source) = dirtied_by.get(&current) {
+            chain.push(source);
+            current = source;
+        }
+        chain.reverse();
+
+        eprintln!("incremental: {:?} is dirty because:", nodes[index]);
+        eprintln!("    {:?} changed or was removed", nodes[chain[0]]);
+        for &link in &chain[1..] {
+            eprintln!("    -> {:?}", nodes[link]);
+        }
+    }
+}
+
 /// Go through the list of work-products produced in the previous run.
 /// Delete any whose nodes have been found to be dirty or which are
 /// otherwise no longer applicable.
@@ -428,6 +565,13 @@ fn process_edge<'a, 'tcx, 'edges>(
         return;
     }
 
+    // A target marked green was computed again by `try_mark_green`, and the
+    // current dep-graph already has the edges it has now: the old ones may
+    // come from dirty sources, and should not be added back.
+    if tcx.dep_graph.color(&nodes[target]) == Some(DepNodeColor::Green) {
+        return;
+    }
+
     // At this point we have asserted that the target is clean -- otherwise, we
     // would have hit the return above. We can do some further consistency
     // checks based on this fact:
@@ -13,6 +13,7 @@
 //! various HIR nodes.
 
 mod data;
+mod diff;
 mod dirty_clean;
 mod fs;
 mod hash;
@@ -22,6 +23,7 @@ mod save;
 mod work_product;
 mod file_format;
 
+pub use self::diff::diff_dep_graphs;
 pub use self::fs::finalize_session_directory;
 pub use self::fs::in_incr_comp_dir;
 pub use self::load::load_dep_graph;
@@ -15,12 +15,12 @@ use super::*;
 #[cfg(test)]
 mod test;
 
-pub struct Classify<'a, 'g: 'a, N: 'g, I: 'a, O: 'a>
+pub struct Classify<'a, 'g: 'a, N: 'g, E: 'g, I: 'a, O: 'a>
     where N: Debug + Clone + 'g,
           I: Fn(&N) -> bool,
           O: Fn(&N) -> bool,
 {
-    r: &'a mut GraphReduce<'g, N, I, O>,
+    r: &'a mut GraphReduce<'g, N, E, I, O>,
     stack: Vec<NodeIndex>,
     colors: Vec<Color>,
     dag: Dag,
@@ -38,12 +38,12 @@ enum Color {
     Black,
 }
 
-impl<'a, 'g, N, I, O> Classify<'a, 'g, N, I, O>
+impl<'a, 'g, N, E, I, O> Classify<'a, 'g, N, E, I, O>
     where N: Debug + Clone + 'g,
           I: Fn(&N) -> bool,
           O: Fn(&N) -> bool,
 {
-    pub(super) fn new(r: &'a mut GraphReduce<'g, N, I, O>) -> Self {
+    pub(super) fn new(r: &'a mut GraphReduce<'g, N, E, I, O>) -> Self {
         Classify {
             r: r,
             colors: vec![Color::White; r.in_graph.len_nodes()],
@@ -26,8 +26,8 @@ use rustc_data_structures::fx::FxHashMap;
 
 use super::*;
 
-pub(super) fn construct_graph<'g, N, I, O>(r: &mut GraphReduce<'g, N, I, O>, dag: Dag)
-                                           -> Reduction<'g, N>
+pub(super) fn construct_graph<'g, N, E, I, O>(r: &mut GraphReduce<'g, N, E, I, O>, dag: Dag)
+                                              -> Reduction<'g, N>
     where N: Debug + Clone, I: Fn(&N) -> bool, O: Fn(&N) -> bool,
 {
     let Dag { parents: old_parents, input_nodes, output_nodes, cross_edges } = dag;
@@ -29,9 +29,9 @@ use self::dag_id::DagId;
 #[cfg(test)]
 mod test;
 
-pub fn reduce_graph<N, I, O>(graph: &Graph<N, ()>,
-                             is_input: I,
-                             is_output: O) -> Reduction<N>
+pub fn reduce_graph<N, E, I, O>(graph: &Graph<N, E>,
+                                is_input: I,
+                                is_output: O) -> Reduction<N>
     where N: Debug + Clone,
           I: Fn(&N) -> bool,
           O: Fn(&N) -> bool,
@@ -44,12 +44,13 @@ pub struct Reduction<'q, N> where N: 'q + Debug + Clone {
     pub input_nodes: Vec<NodeIndex>,
 }
 
-struct GraphReduce<'q, N, I, O>
+struct GraphReduce<'q, N, E, I, O>
     where N: 'q + Debug + Clone,
+          E: 'q,
           I: Fn(&N) -> bool,
           O: Fn(&N) -> bool,
 {
-    in_graph: &'q Graph<N, ()>,
+    in_graph: &'q Graph<N, E>,
     unify: UnificationTable<DagId>,
     is_input: I,
     is_output: O,
@@ -77,12 +78,12 @@ struct DagNode {
     in_index: NodeIndex
 }
 
-impl<'q, N, I, O> GraphReduce<'q, N, I, O>
+impl<'q, N, E, I, O> GraphReduce<'q, N, E, I, O>
     where N: Debug + Clone,
           I: Fn(&N) -> bool,
           O: Fn(&N) -> bool,
 {
-    fn new(in_graph: &'q Graph<N, ()>, is_input: I, is_output: O) -> Self {
+    fn new(in_graph: &'q Graph<N, E>, is_input: I, is_output: O) -> Self {
         let mut unify = UnificationTable::new();
 
         // create a set of unification keys whose indices
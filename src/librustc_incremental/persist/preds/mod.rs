@@ -10,6 +10,7 @@
 
 use rustc::dep_graph::{DepGraphQuery, DepNode, DepKind};
 use rustc::ich::Fingerprint;
+use rustc::ty::maps::SignatureFingerprint;
 use rustc_data_structures::fx::FxHashMap;
 use rustc_data_structures::graph::{Graph, NodeIndex};
 
@@ -37,17 +38,37 @@ pub struct Predecessors<'query> {
 
     // For the inputs (hir/foreign-metadata), we include hashes.
     pub hashes: FxHashMap<&'query DepNode, Fingerprint>,
+
+    // For the `ItemSignature` nodes of local items, we include fingerprints
+    // of their results, for red/green marking. These nodes are kept in the
+    // reduced graph like outputs.
+    pub signatures: FxHashMap<&'query DepNode, SignatureFingerprint>,
 }
 
 impl<'q> Predecessors<'q> {
     pub fn new(query: &'q DepGraphQuery, hcx: &mut HashContext) -> Self {
         let tcx = hcx.tcx;
 
+        let mut signatures = FxHashMap();
+        for node in query.graph.all_nodes() {
+            let node = &node.data;
+            if let DepKind::ItemSignature = node.kind {
+                let fingerprint = node.extract_def_id(tcx)
+                                      .and_then(|def_id| tcx.signature_fingerprint(def_id));
+                if let Some(fingerprint) = fingerprint {
+                    signatures.insert(node, fingerprint);
+                }
+            }
+        }
+
         // Find the set of "start nodes". These are nodes that we will
-        // possibly query later.
-        let is_output = |node: &DepNode| -> bool {
+        // possibly query later. (`signatures` is passed in so that it
+        // is not borrowed for as long as `is_output` exists.)
+        let is_output = |node: &DepNode,
+                         signatures: &FxHashMap<&'q DepNode, SignatureFingerprint>| -> bool {
             match node.kind {
                 DepKind::WorkProduct => true,
+                DepKind::ItemSignature => signatures.contains_key(node),
                 DepKind::MetaData => {
                     // We do *not* create dep-nodes for the current crate's
                     // metadata anymore, just for metadata that we import/read
@@ -67,7 +88,7 @@ impl<'q> Predecessors<'q> {
         let compress::Reduction { graph, input_nodes } =
             compress::reduce_graph(&query.graph,
                                    |n| HashContext::is_hashable(tcx, n),
-                                   |n| is_output(n));
+                                   |n| is_output(n, &signatures));
 
         let mut hashes = FxHashMap();
         for input_index in input_nodes {
@@ -98,13 +119,14 @@ impl<'q> Predecessors<'q> {
             .map(NodeIndex)
             .filter(|&n| graph.incoming_edges(n).next().is_none())
             .map(|n| *graph.node_data(n))
-            .filter(|n| is_output(n))
+            .filter(|n| is_output(n, &signatures))
             .collect();
 
         Predecessors {
             reduced_graph: graph,
             bootstrap_outputs: bootstrap_outputs,
             hashes: hashes,
+            signatures: signatures,
         }
     }
 }
@@ -9,20 +9,20 @@
 // except according to those terms.
 
 use rustc::dep_graph::DepNode;
-use rustc::hir::def_id::DefId;
+use rustc::hir::def_id::{DefId, LOCAL_CRATE};
 use rustc::hir::svh::Svh;
 use rustc::ich::Fingerprint;
 use rustc::middle::cstore::EncodedMetadataHashes;
 use rustc::session::Session;
 use rustc::ty::TyCtxt;
-use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_data_structures::graph;
 use rustc_data_structures::indexed_vec::IndexVec;
 use rustc_serialize::Encodable as RustcEncodable;
 use rustc_serialize::opaque::Encoder;
-use std::io::{self, Cursor, Write};
+use std::io::{self, BufWriter, Cursor};
 use std::fs::{self, File};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use IncrementalHashesMap;
 use super::data::*;
@@ -73,6 +73,14 @@ pub fn save_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             dep_graph_path(sess),
             |e| encode_dep_graph(tcx, &preds, e));
 
+    if let Some(ref dir) = sess.opts.debugging_opts.incremental_dump_dir {
+        copy_dep_graph(sess, &tcx.crate_name(LOCAL_CRATE).as_str(), Path::new(dir));
+    }
+
+    save_in(sess,
+            source_files_path(sess),
+            |e| encode_source_files(sess, e));
+
     let prev_metadata_hashes = incremental_hashes_map.prev_metadata_hashes.borrow();
     dirty_clean::check_dirty_clean_metadata(tcx,
                                             &*prev_metadata_hashes,
@@ -95,15 +103,37 @@ pub fn save_work_products(sess: &Session) {
     let new_work_products = sess.dep_graph.work_products();
     let previous_work_products = sess.dep_graph.previous_work_products();
 
+    let (mut deleted_files, mut deleted_bytes) = (0, 0);
     for (id, wp) in previous_work_products.iter() {
         if !new_work_products.contains_key(id) {
-            work_product::delete_workproduct_files(sess, wp);
+            let (files, bytes) = work_product::delete_workproduct_files(sess, wp);
+            deleted_files += files;
+            deleted_bytes += bytes;
             debug_assert!(wp.saved_files.iter().all(|&(_, ref file_name)| {
                 !in_incr_comp_dir_sess(sess, file_name).exists()
             }));
         }
     }
 
+    // Then collect the files that no work-product owns any more, but that
+    // the previous session did not know about either. Only do so if this
+    // session succeeded, since otherwise its directory is thrown away.
+    if !sess.has_errors() {
+        let referenced: FxHashSet<&str> =
+            new_work_products.values()
+                             .flat_map(|wp| wp.saved_files.iter().map(|&(_, ref name)| &name[..]))
+                             .collect();
+        let (files, bytes) = work_product::delete_unreferenced_files(sess, &referenced);
+        deleted_files += files;
+        deleted_bytes += bytes;
+    }
+
+    if sess.opts.debugging_opts.incremental_info {
+        eprintln!("incremental: deleted {} stale work-product files ({} bytes)",
+                  deleted_files,
+                  deleted_bytes);
+    }
+
     // Check that we did not delete one of the current work-products:
     debug_assert!({
         new_work_products.iter()
@@ -115,6 +145,38 @@ pub fn save_work_products(sess: &Session) {
     });
 }
 
+/// For `-Z incremental-dump-dir`: keeps a copy of the dep-graph just saved
+/// in `dir`, as `<crate>-<n>.bin` for the first `n` not taken yet, so that
+/// the graphs of successive sessions can be compared with
+/// `-Z diff-dep-graph` after the session directories are gone.
+fn copy_dep_graph(sess: &Session, crate_name: &str, dir: &Path) {
+    if let Err(err) = fs::create_dir_all(dir) {
+        sess.warn(&format!("could not create dep-graph dump directory `{}`: {}",
+                           dir.display(), err));
+        return;
+    }
+
+    let mut n = 0;
+    let path = loop {
+        let path = dir.join(format!("{}-{}.bin", crate_name, n));
+        if !path.exists() {
+            break path;
+        }
+        n += 1;
+    };
+
+    match fs::copy(dep_graph_path(sess), &path) {
+        Ok(_) => {
+            if sess.opts.debugging_opts.incremental_info {
+                eprintln!("incremental: dep-graph copied to `{}`", path.display());
+            }
+        }
+        Err(err) => {
+            sess.warn(&format!("could not copy dep-graph to `{}`: {}", path.display(), err));
+        }
+    }
+}
+
 fn save_in<F>(sess: &Session, path_buf: PathBuf, encode: F)
     where F: FnOnce(&mut Encoder) -> io::Result<()>
 {
@@ -140,7 +202,6 @@ fn save_in<F>(sess: &Session, path_buf: PathBuf, encode: F)
 
     // generate the data in a memory buffer
     let mut wr = Cursor::new(Vec::new());
-    file_format::write_file_header(&mut wr).unwrap();
     match encode(&mut Encoder::new(&mut wr)) {
         Ok(()) => {}
         Err(err) => {
@@ -151,9 +212,12 @@ fn save_in<F>(sess: &Session, path_buf: PathBuf, encode: F)
         }
     }
 
-    // write the data out
+    // write the data out, after the header
     let data = wr.into_inner();
-    match File::create(&path_buf).and_then(|mut file| file.write_all(&data)) {
+    let result = File::create(&path_buf).and_then(|file| {
+        file_format::write_file(sess, &mut BufWriter::new(file), &data)
+    });
+    match result {
         Ok(_) => {
             debug!("save: data written to disk successfully");
         }
@@ -171,7 +235,7 @@ pub fn encode_dep_graph(tcx: TyCtxt,
                         encoder: &mut Encoder)
                         -> io::Result<()> {
     // First encode the commandline arguments hash
-    tcx.sess.opts.dep_tracking_hash().encode(encoder)?;
+    tcx.sess.opts.incremental_dep_tracking_hash().encode(encoder)?;
 
     // NB: We rely on this Vec being indexable by reduced_graph's NodeIndex.
     let mut nodes: IndexVec<DepNodeIndex, DepNode> = preds
@@ -209,8 +273,10 @@ pub fn encode_dep_graph(tcx: TyCtxt,
     // Next, build the map of content hashes. To this end, we need to transform
     // the (DepNode -> Fingerprint) map that we have into a
     // (DepNodeIndex -> Fingerprint) map. This may necessitate adding nodes back
-    // to the dep-graph that have been filtered out during reduction.
-    let content_hashes = {
+    // to the dep-graph that have been filtered out during reduction. The
+    // signature fingerprints are mapped the same way, but their nodes are all
+    // in the reduced graph already, since they count as outputs.
+    let (content_hashes, signatures) = {
         // We have to build a (DepNode -> DepNodeIndex) map. We over-allocate a
         // little because we expect some more nodes to be added.
         let capacity = (nodes.len() * 120) / 100;
@@ -243,7 +309,14 @@ pub fn encode_dep_graph(tcx: TyCtxt,
             content_hashes.push((dep_node_index, hash));
         }
 
-        content_hashes
+        let signatures: Vec<_> = preds.signatures
+                              .iter()
+                              .map(|(&&dep_node, fingerprint)| {
+                                  (node_to_index[&dep_node], fingerprint.clone())
+                              })
+                              .collect();
+
+        (content_hashes, signatures)
     };
 
     let graph = SerializedDepGraph {
@@ -252,6 +325,7 @@ pub fn encode_dep_graph(tcx: TyCtxt,
         edge_list_data,
         bootstrap_outputs,
         hashes: content_hashes,
+        signatures,
     };
 
     // Encode the graph data.
@@ -261,6 +335,8 @@ pub fn encode_dep_graph(tcx: TyCtxt,
         eprintln!("incremental: {} nodes in reduced dep-graph", graph.nodes.len());
         eprintln!("incremental: {} edges in serialized dep-graph", graph.edge_list_data.len());
         eprintln!("incremental: {} hashes in serialized dep-graph", graph.hashes.len());
+        eprintln!("incremental: {} signature fingerprints in serialized dep-graph",
+                  graph.signatures.len());
     }
 
     if tcx.sess.opts.debugging_opts.incremental_dump_hash {
@@ -309,6 +385,22 @@ pub fn encode_metadata_hashes(tcx: TyCtxt,
     Ok(())
 }
 
+pub fn encode_source_files(sess: &Session, encoder: &mut Encoder) -> io::Result<()> {
+    let source_files: Vec<_> = sess.codemap()
+        .files()
+        .iter()
+        .filter(|filemap| !filemap.is_imported())
+        .map(|filemap| {
+            SerializedSourceFile {
+                name: filemap.name.clone(),
+                src_hash: filemap.src_hash,
+            }
+        })
+        .collect();
+
+    source_files.encode(encoder)
+}
+
 pub fn encode_work_products(sess: &Session, encoder: &mut Encoder) -> io::Result<()> {
     let work_products: Vec<_> = sess.dep_graph
         .work_products()
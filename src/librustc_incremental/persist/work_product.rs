@@ -15,9 +15,13 @@ use rustc::dep_graph::{WorkProduct, WorkProductId};
 use rustc::session::Session;
 use rustc::session::config::OutputType;
 use rustc::util::fs::link_or_copy;
-use std::path::PathBuf;
+use rustc_data_structures::fx::FxHashSet;
+use std::path::{Path, PathBuf};
 use std::fs as std_fs;
 
+// The prefix of the names of the files of work products.
+const WORK_PRODUCT_FILE_PREFIX: &'static str = "cgu-";
+
 pub fn save_trans_partition(sess: &Session,
                             cgu_name: &str,
                             partition_hash: u64,
@@ -34,7 +38,10 @@ pub fn save_trans_partition(sess: &Session,
     let saved_files: Option<Vec<_>> =
         files.iter()
              .map(|&(kind, ref path)| {
-                 let file_name = format!("cgu-{}.{}", cgu_name, kind.extension());
+                 let file_name = format!("{}{}.{}",
+                                         WORK_PRODUCT_FILE_PREFIX,
+                                         cgu_name,
+                                         kind.extension());
                  let path_in_incr_dir = in_incr_comp_dir_sess(sess, &file_name);
                  match link_or_copy(path, &path_in_incr_dir) {
                      Ok(_) => Some((kind, file_name)),
@@ -62,16 +69,69 @@ pub fn save_trans_partition(sess: &Session,
     sess.dep_graph.insert_work_product(&work_product_id, work_product);
 }
 
-pub fn delete_workproduct_files(sess: &Session, work_product: &WorkProduct) {
+/// Deletes the files of `work_product`. Returns how many were deleted, and
+/// how many bytes that freed.
+pub fn delete_workproduct_files(sess: &Session, work_product: &WorkProduct) -> (usize, u64) {
+    let mut deleted = (0, 0);
     for &(_, ref file_name) in &work_product.saved_files {
         let path = in_incr_comp_dir_sess(sess, file_name);
-        match std_fs::remove_file(&path) {
-            Ok(()) => { }
-            Err(err) => {
-                sess.warn(
-                    &format!("file-system error deleting outdated file `{}`: {}",
-                             path.display(), err));
+        if delete_file(sess, &path, &mut deleted) {
+            debug!("delete_workproduct_files: deleted `{}`", path.display());
+        }
+    }
+    deleted
+}
+
+/// Deletes the files of work products in the session directory that are
+/// not in `referenced`, i.e. that no work product of this session owns.
+/// The previous session may have left some behind, say if its work
+/// products could not be loaded. Returns how many files were deleted, and
+/// how many bytes that freed.
+pub fn delete_unreferenced_files(sess: &Session, referenced: &FxHashSet<&str>) -> (usize, u64) {
+    let mut deleted = (0, 0);
+    let session_dir = sess.incr_comp_session_dir().clone();
+    let entries = match std_fs::read_dir(&session_dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            sess.warn(&format!("could not list incremental compilation session directory \
+                                `{}`: {}",
+                               session_dir.display(), err));
+            return deleted;
+        }
+    };
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        let file_name = entry.file_name();
+        let file_name = match file_name.to_str() {
+            Some(file_name) => file_name,
+            None => continue,
+        };
+        if file_name.starts_with(WORK_PRODUCT_FILE_PREFIX) && !referenced.contains(file_name) {
+            if delete_file(sess, &entry.path(), &mut deleted) {
+                debug!("delete_unreferenced_files: deleted `{}`", file_name);
             }
         }
     }
+    deleted
+}
+
+// Deletes the file at `path`, adding it and its size to `deleted`.
+fn delete_file(sess: &Session, path: &Path, deleted: &mut (usize, u64)) -> bool {
+    let size = std_fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0);
+    match std_fs::remove_file(path) {
+        Ok(()) => {
+            deleted.0 += 1;
+            deleted.1 += size;
+            true
+        }
+        Err(err) => {
+            sess.warn(
+                &format!("file-system error deleting outdated file `{}`: {}",
+                         path.display(), err));
+            false
+        }
+    }
 }
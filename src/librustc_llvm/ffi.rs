@@ -1219,6 +1219,13 @@ extern "C" {
     /// Writes a module to the specified path. Returns 0 on success.
     pub fn LLVMWriteBitcodeToFile(M: ModuleRef, Path: *const c_char) -> c_int;
 
+    /// Writes a module to a new memory buffer. Use LLVMDisposeMemoryBuffer()
+    /// to get rid of it.
+    pub fn LLVMWriteBitcodeToMemoryBuffer(M: ModuleRef) -> MemoryBufferRef;
+    pub fn LLVMGetBufferStart(MemBuf: MemoryBufferRef) -> *const c_char;
+    pub fn LLVMGetBufferSize(MemBuf: MemoryBufferRef) -> size_t;
+    pub fn LLVMDisposeMemoryBuffer(MemBuf: MemoryBufferRef);
+
     /// Creates target data from a target layout string.
     pub fn LLVMCreateTargetData(StringRep: *const c_char) -> TargetDataRef;
     /// Number of bytes clobbered when doing a Store to *T.
@@ -1587,7 +1594,10 @@ extern "C" {
                                                OptLevel: CodeGenOptLevel,
                                                MergeFunctions: bool,
                                                SLPVectorize: bool,
-                                               LoopVectorize: bool);
+                                               LoopVectorize: bool,
+                                               PGOGenPath: *const c_char,
+                                               PGOUsePath: *const c_char);
+    pub fn LLVMRustPGOAvailable() -> bool;
     pub fn LLVMRustAddLibraryInfo(PM: PassManagerRef,
                                   M: ModuleRef,
                                   DisableSimplifyLibCalls: bool);
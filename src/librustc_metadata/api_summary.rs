@@ -0,0 +1,267 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `--emit=api-summary`: a machine-readable description of a crate's
+//! public API.
+//!
+//! The summary is computed from the same queries the metadata encoder uses
+//! (`type_of`, `fn_sig`, `impl_trait_ref`, `lookup_stability`, ...), so it
+//! describes exactly what downstream crates get to see, without tools such
+//! as semver checkers having to reimplement a compiler front-end.
+//!
+//! Items are listed when they are exported (see `AccessLevels`), sorted by
+//! path. Types and signatures are rendered with their `Display`
+//! implementations, with paths forced to be absolute so that the rendering
+//! doesn't depend on which `extern crate` items happen to be in scope.
+
+use rustc::hir;
+use rustc::hir::def_id::LOCAL_CRATE;
+use rustc::hir::itemlikevisit::ItemLikeVisitor;
+use rustc::middle::privacy::AccessLevels;
+use rustc::session::config::{OutputFilenames, OutputType};
+use rustc::ty::TyCtxt;
+use rustc::ty::item_path;
+use rustc_serialize::json;
+
+use std::fs::File;
+use std::io::{self, Write};
+
+use syntax::ast::NodeId;
+use syntax::attr;
+
+#[derive(RustcEncodable)]
+struct ApiSummary {
+    crate_name: String,
+    items: Vec<ApiItem>,
+    impls: Vec<ApiImpl>,
+}
+
+#[derive(RustcEncodable)]
+struct ApiItem {
+    path: String,
+    kind: String,
+    /// The type of values and fields, or the signature of functions.
+    signature: Option<String>,
+    stability: Option<ApiStability>,
+    deprecation: Option<ApiDeprecation>,
+}
+
+#[derive(RustcEncodable)]
+struct ApiImpl {
+    self_ty: String,
+    trait_ref: Option<String>,
+    items: Vec<ApiItem>,
+}
+
+#[derive(RustcEncodable)]
+struct ApiStability {
+    feature: String,
+    stable_since: Option<String>,
+}
+
+#[derive(RustcEncodable)]
+struct ApiDeprecation {
+    since: Option<String>,
+    note: Option<String>,
+}
+
+pub fn emit_api_summary<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                  outputs: &OutputFilenames)
+                                  -> io::Result<()> {
+    let access_levels = tcx.privacy_access_levels(LOCAL_CRATE);
+    let mut collector = ApiCollector {
+        tcx,
+        access_levels: &access_levels,
+        items: vec![],
+        impls: vec![],
+    };
+    item_path::with_forced_absolute_paths(|| {
+        tcx.hir.krate().visit_all_item_likes(&mut collector);
+    });
+
+    let ApiCollector { mut items, mut impls, .. } = collector;
+    items.sort_by(|a, b| a.path.cmp(&b.path));
+    impls.sort_by(|a, b| (&a.self_ty, &a.trait_ref).cmp(&(&b.self_ty, &b.trait_ref)));
+
+    let summary = ApiSummary {
+        crate_name: tcx.crate_name(LOCAL_CRATE).to_string(),
+        items,
+        impls,
+    };
+
+    let path = outputs.path(OutputType::ApiSummary);
+    let mut file = File::create(&path)?;
+    write!(file, "{}", json::as_pretty_json(&summary))?;
+    Ok(())
+}
+
+struct ApiCollector<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    access_levels: &'a AccessLevels,
+    items: Vec<ApiItem>,
+    impls: Vec<ApiImpl>,
+}
+
+impl<'a, 'tcx> ApiCollector<'a, 'tcx> {
+    fn api_item(&self, id: NodeId, kind: &str, signature: Option<String>) -> ApiItem {
+        let def_id = self.tcx.hir.local_def_id(id);
+        ApiItem {
+            path: self.tcx.item_path_str(def_id),
+            kind: kind.to_string(),
+            signature,
+            stability: self.tcx.lookup_stability(def_id).map(|stab| {
+                ApiStability {
+                    feature: stab.feature.to_string(),
+                    stable_since: match stab.level {
+                        attr::Stable { since } => Some(since.to_string()),
+                        attr::Unstable { .. } => None,
+                    },
+                }
+            }),
+            deprecation: self.tcx.lookup_deprecation(def_id).map(|depr| {
+                ApiDeprecation {
+                    since: depr.since.map(|s| s.to_string()),
+                    note: depr.note.map(|s| s.to_string()),
+                }
+            }),
+        }
+    }
+
+    fn type_of(&self, id: NodeId) -> Option<String> {
+        Some(self.tcx.type_of(self.tcx.hir.local_def_id(id)).to_string())
+    }
+
+    fn fn_sig(&self, id: NodeId) -> Option<String> {
+        // Render through a fn pointer type, which prints late-bound regions.
+        let sig = self.tcx.fn_sig(self.tcx.hir.local_def_id(id));
+        Some(self.tcx.mk_fn_ptr(sig).to_string())
+    }
+
+    fn push_fields(&mut self, data: &hir::VariantData) {
+        for field in data.fields() {
+            if self.access_levels.is_exported(field.id) {
+                let ty = self.type_of(field.id);
+                let item = self.api_item(field.id, "field", ty);
+                self.items.push(item);
+            }
+        }
+    }
+
+    fn impl_item_summary(&self, id: NodeId) -> ApiItem {
+        let impl_item = self.tcx.hir.impl_item(hir::ImplItemId { node_id: id });
+        let (kind, signature) = match impl_item.node {
+            hir::ImplItemKind::Const(..) => ("associated constant", self.type_of(id)),
+            hir::ImplItemKind::Method(..) => ("method", self.fn_sig(id)),
+            hir::ImplItemKind::Type(..) => ("associated type", self.type_of(id)),
+        };
+        self.api_item(id, kind, signature)
+    }
+}
+
+impl<'a, 'tcx, 'v> ItemLikeVisitor<'v> for ApiCollector<'a, 'tcx> {
+    fn visit_item(&mut self, item: &'v hir::Item) {
+        if !self.access_levels.is_exported(item.id) {
+            return;
+        }
+
+        let signature = match item.node {
+            hir::ItemExternCrate(..) |
+            hir::ItemUse(..) |
+            hir::ItemGlobalAsm(..) => return,
+
+            hir::ItemForeignMod(ref fm) => {
+                for foreign_item in &fm.items {
+                    if !self.access_levels.is_exported(foreign_item.id) {
+                        continue;
+                    }
+                    let api_item = match foreign_item.node {
+                        hir::ForeignItemFn(..) => {
+                            let sig = self.fn_sig(foreign_item.id);
+                            self.api_item(foreign_item.id, "foreign function", sig)
+                        }
+                        hir::ForeignItemStatic(..) => {
+                            let ty = self.type_of(foreign_item.id);
+                            self.api_item(foreign_item.id, "foreign static", ty)
+                        }
+                    };
+                    self.items.push(api_item);
+                }
+                return;
+            }
+
+            hir::ItemImpl(.., ref impl_item_refs) => {
+                let def_id = self.tcx.hir.local_def_id(item.id);
+                let items = impl_item_refs.iter()
+                    .map(|item_ref| item_ref.id.node_id)
+                    .filter(|&id| self.access_levels.is_exported(id))
+                    .map(|id| self.impl_item_summary(id))
+                    .collect();
+                self.impls.push(ApiImpl {
+                    self_ty: self.tcx.type_of(def_id).to_string(),
+                    trait_ref: self.tcx.impl_trait_ref(def_id).map(|t| t.to_string()),
+                    items,
+                });
+                return;
+            }
+            hir::ItemDefaultImpl(..) => {
+                let def_id = self.tcx.hir.local_def_id(item.id);
+                self.impls.push(ApiImpl {
+                    self_ty: "..".to_string(),
+                    trait_ref: self.tcx.impl_trait_ref(def_id).map(|t| t.to_string()),
+                    items: vec![],
+                });
+                return;
+            }
+
+            hir::ItemStruct(ref data, _) |
+            hir::ItemUnion(ref data, _) => {
+                self.push_fields(data);
+                self.type_of(item.id)
+            }
+            hir::ItemEnum(ref def, _) => {
+                for variant in &def.variants {
+                    let id = variant.node.data.id();
+                    let api_item = self.api_item(id, "variant", None);
+                    self.items.push(api_item);
+                    self.push_fields(&variant.node.data);
+                }
+                self.type_of(item.id)
+            }
+
+            hir::ItemStatic(..) |
+            hir::ItemConst(..) |
+            hir::ItemTy(..) => self.type_of(item.id),
+            hir::ItemFn(..) => self.fn_sig(item.id),
+            hir::ItemMod(..) |
+            hir::ItemTrait(..) => None,
+        };
+
+        let api_item = self.api_item(item.id, item.node.descriptive_variant(), signature);
+        self.items.push(api_item);
+    }
+
+    fn visit_trait_item(&mut self, trait_item: &'v hir::TraitItem) {
+        if !self.access_levels.is_exported(trait_item.id) {
+            return;
+        }
+
+        let (kind, signature) = match trait_item.node {
+            hir::TraitItemKind::Const(..) => ("associated constant", self.type_of(trait_item.id)),
+            hir::TraitItemKind::Method(..) => ("method", self.fn_sig(trait_item.id)),
+            hir::TraitItemKind::Type(..) => ("associated type", None),
+        };
+        let api_item = self.api_item(trait_item.id, kind, signature);
+        self.items.push(api_item);
+    }
+
+    fn visit_impl_item(&mut self, _impl_item: &'v hir::ImplItem) {
+        // Listed with their impl, above.
+    }
+}
@@ -285,6 +285,21 @@ impl<'a> CrateLoader<'a> {
                       -> (CrateNum, Rc<cstore::CrateMetadata>) {
         info!("register crate `extern crate {} as {}`", name, ident);
         let crate_root = lib.metadata.get_root();
+        let checksum = if self.sess.opts.debugging_opts.verify_metadata {
+            lib.metadata.verify_checksum(&crate_root)
+        } else {
+            Ok(())
+        };
+        if let Err(reason) = checksum {
+            let path = lib.dylib.as_ref().or(lib.rlib.as_ref()).or(lib.rmeta.as_ref()).unwrap();
+            let mut err = self.sess.struct_span_fatal(span, &format!(
+                "the metadata of crate `{}` is corrupted", name));
+            err.note(&format!("loaded from {}: {}", path.0.display(), reason));
+            err.help("delete the file and rebuild the crate");
+            err.emit();
+            self.sess.abort_if_errors();
+            unreachable!();
+        }
         self.verify_no_symbol_conflicts(span, &crate_root);
 
         // Claim this crate number and cache it
@@ -821,9 +836,14 @@ impl<'a> CrateLoader<'a> {
                                    name));
         }
         if data.panic_strategy(dep_graph) != desired_strategy {
-            self.sess.err(&format!("the crate `{}` does not have the panic \
-                                    strategy `{}`",
-                                   name, desired_strategy.desc()));
+            self.sess.struct_err(&format!("the crate `{}` does not have the panic \
+                                           strategy `{}`",
+                                          name, desired_strategy.desc()))
+                .help(&format!("the default panic runtime for `-C panic={}` is \
+                                `{}`, make sure the one in the sysroot was built \
+                                with that strategy",
+                               desired_strategy.desc(), name))
+                .emit();
         }
 
         self.sess.injected_panic_runtime.set(Some(cnum));
@@ -905,7 +925,8 @@ impl<'a> CrateLoader<'a> {
     }
 
     fn inject_profiler_runtime(&mut self) {
-        if self.sess.opts.debugging_opts.profile {
+        if self.sess.opts.debugging_opts.profile ||
+           self.sess.opts.cg.profile_generate.is_some() {
             info!("loading profiler");
 
             let symbol = Symbol::intern("profiler_builtins");
@@ -53,6 +53,9 @@ macro_rules! provide {
 
                 $tcx.dep_graph.read(dep_node);
 
+                let perf_stats = &$tcx.sess.perf_stats;
+                perf_stats.metadata_queries_count.set(perf_stats.metadata_queries_count.get() + 1);
+
                 let $cdata = $tcx.sess.cstore.crate_data_as_rc_any($def_id.krate);
                 let $cdata = $cdata.downcast_ref::<cstore::CrateMetadata>()
                     .expect("CrateStore crated ata is not a CrateMetadata");
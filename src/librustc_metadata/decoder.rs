@@ -387,13 +387,36 @@ impl<'a, 'tcx> MetadataBlob {
         Lazy::with_position(METADATA_HEADER.len() + 4).decode(self)
     }
 
-    pub fn get_root(&self) -> CrateRoot {
+    fn root_position(&self) -> usize {
         let slice = self.raw_bytes();
         let offset = METADATA_HEADER.len();
-        let pos = (((slice[offset + 0] as u32) << 24) | ((slice[offset + 1] as u32) << 16) |
-                   ((slice[offset + 2] as u32) << 8) |
-                   ((slice[offset + 3] as u32) << 0)) as usize;
-        Lazy::with_position(pos).decode(self)
+        (((slice[offset + 0] as u32) << 24) | ((slice[offset + 1] as u32) << 16) |
+         ((slice[offset + 2] as u32) << 8) |
+         ((slice[offset + 3] as u32) << 0)) as usize
+    }
+
+    pub fn get_root(&self) -> CrateRoot {
+        Lazy::with_position(self.root_position()).decode(self)
+    }
+
+    /// Checks that the metadata preceding the `CrateRoot` hasn't been
+    /// truncated or otherwise corrupted since it was encoded, if its
+    /// checksum was recorded.
+    pub fn verify_checksum(&self, root: &CrateRoot) -> Result<(), String> {
+        let checksum = match root.checksum {
+            Some(checksum) => checksum,
+            None => return Ok(()),
+        };
+        let bytes = self.raw_bytes();
+        let pos = self.root_position();
+        if pos > bytes.len() {
+            return Err(format!("the root is at byte {}, past the end of its {} bytes",
+                               pos, bytes.len()));
+        }
+        if metadata_checksum(&bytes[..pos])? != checksum {
+            return Err("its checksum does not match".to_string());
+        }
+        Ok(())
     }
 
     pub fn list_crate_metadata(&self,
@@ -21,8 +21,10 @@ use rustc::hir::def_id::{CrateNum, CRATE_DEF_INDEX, DefIndex, DefId, LOCAL_CRATE
 use rustc::hir::map::definitions::{DefPathTable, GlobalMetaDataKind};
 use rustc::ich::Fingerprint;
 use rustc::middle::dependency_format::Linkage;
+use rustc::middle::const_val::ConstVal;
 use rustc::middle::lang_items;
 use rustc::mir;
+use rustc::mir::visit::{Visitor as MirVisitor, LvalueContext};
 use rustc::traits::specialization_graph;
 use rustc::ty::{self, Ty, TyCtxt, ReprOptions};
 
@@ -403,6 +405,12 @@ impl<'a, 'tcx> EncodeContext<'a, 'tcx> {
         let has_default_lib_allocator =
             attr::contains_name(tcx.hir.krate_attrs(), "default_lib_allocator");
         let has_global_allocator = tcx.sess.has_global_allocator.get();
+        let checksum = if tcx.sess.opts.debugging_opts.verify_metadata {
+            let checksum = metadata_checksum(&self.opaque.cursor.get_ref()[..self.position()]);
+            Some(checksum.unwrap_or_else(|err| bug!("metadata without a header: {}", err)))
+        } else {
+            None
+        };
         let root = self.lazy(&CrateRoot {
             name: tcx.crate_name(LOCAL_CRATE),
             triple: tcx.sess.opts.target_triple.clone(),
@@ -432,6 +440,7 @@ impl<'a, 'tcx> EncodeContext<'a, 'tcx> {
             impls: impls,
             exported_symbols: exported_symbols,
             index: index,
+            checksum: checksum,
         });
 
         let total_bytes = self.position();
@@ -473,6 +482,66 @@ impl<'a, 'tcx> EncodeContext<'a, 'tcx> {
 // taking IsolatedEncoder as first argument would be just fine) but by making
 // them methods we don't have to repeat the lengthy `<'a, 'b: 'a, 'tcx: 'b>`
 // clause again and again.
+/// The number of statements up to which a function without `#[inline]` is
+/// still considered for cross-crate MIR inlining.
+const TRIVIAL_MIR_MAX_STATEMENTS: usize = 16;
+
+/// Looks for anything making a MIR body unsuitable for inlining into other
+/// crates when it wasn't asked for, see `is_trivially_inlinable`.
+struct TrivialMirChecker {
+    statements: usize,
+    refers_to_items: bool,
+}
+
+impl<'tcx> MirVisitor<'tcx> for TrivialMirChecker {
+    fn visit_statement(&mut self,
+                       block: mir::BasicBlock,
+                       statement: &mir::Statement<'tcx>,
+                       location: mir::Location) {
+        match statement.kind {
+            mir::StatementKind::StorageLive(_) |
+            mir::StatementKind::StorageDead(_) |
+            mir::StatementKind::Nop => {}
+            _ => self.statements += 1,
+        }
+        self.super_statement(block, statement, location);
+    }
+
+    fn visit_terminator_kind(&mut self,
+                             block: mir::BasicBlock,
+                             kind: &mir::TerminatorKind<'tcx>,
+                             location: mir::Location) {
+        match *kind {
+            // Calls and drops may end up in functions private to this crate.
+            mir::TerminatorKind::Call { .. } |
+            mir::TerminatorKind::Drop { .. } |
+            mir::TerminatorKind::DropAndReplace { .. } => self.refers_to_items = true,
+            _ => {}
+        }
+        self.super_terminator_kind(block, kind, location);
+    }
+
+    fn visit_static(&mut self,
+                    static_: &mir::Static<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: mir::Location) {
+        self.refers_to_items = true;
+        self.super_static(static_, context, location);
+    }
+
+    fn visit_literal(&mut self, literal: &mir::Literal<'tcx>, location: mir::Location) {
+        match *literal {
+            mir::Literal::Item { .. } |
+            mir::Literal::Value { value: ConstVal::Function(..) } => {
+                self.refers_to_items = true;
+            }
+            mir::Literal::Value { .. } |
+            mir::Literal::Promoted { .. } => {}
+        }
+        self.super_literal(literal, location);
+    }
+}
+
 impl<'a, 'b: 'a, 'tcx: 'b> IsolatedEncoder<'a, 'b, 'tcx> {
     fn encode_variances_of(&mut self, def_id: DefId) -> LazySeq<ty::Variance> {
         debug!("IsolatedEncoder::encode_variances_of({:?})", def_id);
@@ -822,7 +891,8 @@ impl<'a, 'b: 'a, 'tcx: 'b> IsolatedEncoder<'a, 'b, 'tcx> {
             let is_const_fn = sig.constness == hir::Constness::Const;
             let ast = if is_const_fn { Some(body) } else { None };
             let always_encode_mir = self.tcx.sess.opts.debugging_opts.always_encode_mir;
-            (ast, needs_inline || is_const_fn || always_encode_mir)
+            (ast, needs_inline || is_const_fn || always_encode_mir ||
+                  self.is_trivially_inlinable(def_id))
         } else {
             (None, false)
         };
@@ -868,6 +938,25 @@ impl<'a, 'b: 'a, 'tcx: 'b> IsolatedEncoder<'a, 'b, 'tcx> {
         self.lazy_seq(names.iter().map(|name| name.node))
     }
 
+    /// Functions which are small and don't refer to any other item can have
+    /// their MIR inlined into other crates even without `#[inline]`: there's
+    /// no symbol in their body that might not be exported. This is only done
+    /// when MIR inlining is enabled, as it's useless otherwise.
+    fn is_trivially_inlinable(&self, def_id: DefId) -> bool {
+        if self.tcx.sess.opts.debugging_opts.mir_opt_level < 2 ||
+           !self.tcx.mir_keys(LOCAL_CRATE).contains(&def_id) {
+            return false;
+        }
+
+        let mir = self.tcx.optimized_mir(def_id);
+        let mut checker = TrivialMirChecker {
+            statements: 0,
+            refers_to_items: false,
+        };
+        checker.visit_mir(mir);
+        !checker.refers_to_items && checker.statements <= TRIVIAL_MIR_MAX_STATEMENTS
+    }
+
     fn encode_optimized_mir(&mut self, def_id: DefId) -> Option<Lazy<mir::Mir<'tcx>>> {
         debug!("EntryBuilder::encode_mir({:?})", def_id);
         if self.tcx.mir_keys(LOCAL_CRATE).contains(&def_id) {
@@ -1120,7 +1209,8 @@ impl<'a, 'b: 'a, 'tcx: 'b> IsolatedEncoder<'a, 'b, 'tcx> {
                     let tps_len = generics.ty_params.len();
                     let needs_inline = tps_len > 0 || attr::requests_inline(&item.attrs);
                     let always_encode_mir = self.tcx.sess.opts.debugging_opts.always_encode_mir;
-                    if needs_inline || constness == hir::Constness::Const || always_encode_mir {
+                    if needs_inline || constness == hir::Constness::Const || always_encode_mir ||
+                       self.is_trivially_inlinable(def_id) {
                         self.encode_optimized_mir(def_id)
                     } else {
                         None
@@ -8,6 +8,16 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+//! The table mapping each `DefIndex` of a crate to the position of its
+//! `Entry` in the metadata blob.
+//!
+//! The table is a flat array of little-endian `u32` positions, one per
+//! `DefIndex` (with `u32::MAX` for definitions that have no entry), preceded
+//! by the length of the low address space so that the high one can be found.
+//! Decoding an upstream item therefore only needs a single array access to
+//! find its `Entry`, whose fields are in turn `Lazy` positions into the blob;
+//! nothing has to walk the metadata of other items to get there.
+
 use schema::*;
 
 use rustc::hir::def_id::{DefId, DefIndex, DefIndexAddressSpace};
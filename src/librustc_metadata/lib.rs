@@ -58,8 +58,10 @@ mod cstore_impl;
 mod isolated_encoder;
 mod schema;
 
+pub mod api_summary;
 pub mod creader;
 pub mod cstore;
 pub mod locator;
+pub mod metadata_cache;
 
 __build_diagnostic_array! { librustc_metadata, DIAGNOSTICS }
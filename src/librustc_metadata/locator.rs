@@ -224,7 +224,7 @@ use creader::Library;
 use schema::{METADATA_HEADER, rustc_version};
 
 use rustc::hir::svh::Svh;
-use rustc::middle::cstore::MetadataLoader;
+use rustc::middle::cstore::{MetadataCodec, MetadataLoader};
 use rustc::session::{config, Session};
 use rustc::session::filesearch::{FileSearch, FileMatches, FileDoesntMatch};
 use rustc::session::search_paths::PathKind;
@@ -234,6 +234,7 @@ use errors::DiagnosticBuilder;
 use syntax::symbol::Symbol;
 use syntax_pos::Span;
 use rustc_back::target::Target;
+use rustc_data_structures::mmap::Mmap;
 
 use std::cmp;
 use std::fmt;
@@ -373,8 +374,12 @@ impl<'a> Context<'a> {
         if !self.rejected_via_hash.is_empty() {
             err.note("perhaps that crate needs to be recompiled?");
             let mismatches = self.rejected_via_hash.iter();
-            for (i, &CrateMismatch { ref path, .. }) in mismatches.enumerate() {
-                err.note(&format!("crate `{}` path #{}: {}", self.ident, i + 1, path.display()));
+            for (i, &CrateMismatch { ref path, ref got }) in mismatches.enumerate() {
+                err.note(&format!("crate `{}` path #{}: {}, with svh `{}`",
+                                  self.ident,
+                                  i + 1,
+                                  path.display(),
+                                  got));
             }
             match self.root {
                 &None => {}
@@ -385,6 +390,17 @@ impl<'a> Context<'a> {
                                           i + 1,
                                           path.display()));
                     }
+                    if let Some(hash) = self.hash {
+                        err.note(&format!("`{}` was compiled against crate `{}` with svh `{}`",
+                                          r.ident,
+                                          self.ident,
+                                          hash));
+                        err.help(&format!("recompile `{}` against the `{}` found above, or \
+                                           make the `{}` it was compiled against available",
+                                          r.ident,
+                                          self.ident,
+                                          self.ident));
+                    }
                 }
             }
         }
@@ -713,7 +729,7 @@ impl<'a> Context<'a> {
                 info!("Rejecting via hash: expected {} got {}", *myhash, root.hash);
                 self.rejected_via_hash.push(CrateMismatch {
                     path: libpath.to_path_buf(),
-                    got: myhash.to_string(),
+                    got: root.hash.to_string(),
                 });
                 return None;
             }
@@ -858,27 +874,43 @@ fn get_metadata_section_imp(target: &Target,
                                    filename.display()));
             }
 
-            // Header is okay -> inflate the actual metadata
-            let compressed_bytes = &buf[header_len..];
-            debug!("inflating {} bytes of compressed metadata", compressed_bytes.len());
-            let mut inflated = Vec::new();
-            match ZlibDecoder::new(compressed_bytes).read_to_end(&mut inflated) {
-                Ok(_) => {
-                    let buf = unsafe { OwningRef::new_assert_stable_address(inflated) };
-                    buf.map_owner_box().erase_owner()
+            // Header is okay -> the next byte tells how the actual metadata
+            // was stored.
+            let codec = match buf.get(header_len).and_then(|&tag| MetadataCodec::from_tag(tag)) {
+                Some(codec) => codec,
+                None => {
+                    return Err(format!("unknown metadata compression in: '{}'",
+                                       filename.display()));
                 }
-                Err(_) => {
-                    return Err(format!("failed to decompress metadata: {}", filename.display()));
+            };
+            match codec {
+                MetadataCodec::Uncompressed => buf.map(|b| &b[header_len + 1..]),
+                MetadataCodec::Zlib => {
+                    let compressed_bytes = &buf[header_len + 1..];
+                    debug!("inflating {} bytes of compressed metadata", compressed_bytes.len());
+                    let mut inflated = Vec::new();
+                    match ZlibDecoder::new(compressed_bytes).read_to_end(&mut inflated) {
+                        Ok(_) => {
+                            let buf = unsafe { OwningRef::new_assert_stable_address(inflated) };
+                            buf.map_owner_box().erase_owner()
+                        }
+                        Err(_) => {
+                            return Err(format!("failed to decompress metadata: {}",
+                                               filename.display()));
+                        }
+                    }
                 }
             }
         }
         CrateFlavor::Rmeta => {
-            let mut file = File::open(filename).map_err(|_|
+            let file = File::open(filename).map_err(|_|
                 format!("could not open file: '{}'", filename.display()))?;
-            let mut buf = vec![];
-            file.read_to_end(&mut buf).map_err(|_|
-                format!("failed to read rmeta metadata: '{}'", filename.display()))?;
-            OwningRef::new(buf).map_owner_box().erase_owner()
+            // Map rather than read the file: loading a crate only decodes a
+            // small part of its metadata, which need not be paged in at all.
+            let mmap = unsafe { Mmap::map(&file) };
+            let mmap = mmap.map_err(|_|
+                format!("failed to mmap rmeta metadata: '{}'", filename.display()))?;
+            OwningRef::new(Box::new(mmap)).map(|mmap| &mmap[..]).erase_owner()
         }
     };
     let blob = MetadataBlob(raw_bytes);
@@ -0,0 +1,147 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A `MetadataLoader` that remembers the metadata it has loaded.
+//!
+//! Every compilation session builds its own `CStore`, because the decoded
+//! `CrateMetadata` refers to the session's codemap and is handed out under
+//! crate numbers that are only meaningful within that session. What *can*
+//! be shared between the sessions of a process compiling several crates is
+//! the raw metadata of the upstream libraries, i.e. the result of opening an
+//! rlib archive or a dylib object file and extracting its metadata section.
+//! That is what `MetadataCache` keeps, keyed by target and path, and only
+//! for as long as the file's size and modification time are unchanged.
+
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use rustc::middle::cstore::MetadataLoader;
+use rustc::util::nodemap::FxHashMap;
+use rustc_back::target::Target;
+
+use owning_ref::{ErasedBoxRef, OwningRef};
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    target: String,
+    path: PathBuf,
+    dylib: bool,
+}
+
+struct CacheEntry {
+    modified: SystemTime,
+    len: u64,
+    data: Rc<ErasedBoxRef<[u8]>>,
+    hits: Cell<usize>,
+}
+
+/// Metadata sections loaded so far, shared by all the `CachingMetadataLoader`s
+/// created from it.
+pub struct MetadataCache {
+    entries: RefCell<FxHashMap<CacheKey, CacheEntry>>,
+}
+
+impl MetadataCache {
+    pub fn new() -> MetadataCache {
+        MetadataCache {
+            entries: RefCell::new(FxHashMap()),
+        }
+    }
+
+    /// The number of times the metadata of the library at `path` was served
+    /// from the cache rather than loaded, since it last changed.
+    pub fn hits(&self, path: &Path) -> usize {
+        self.entries.borrow().iter()
+            .filter(|&(key, _)| key.path == path)
+            .map(|(_, entry)| entry.hits.get())
+            .sum()
+    }
+}
+
+/// Forwards to another `MetadataLoader`, unless the requested metadata is
+/// already in the cache.
+pub struct CachingMetadataLoader {
+    inner: Box<MetadataLoader>,
+    cache: Rc<MetadataCache>,
+}
+
+impl CachingMetadataLoader {
+    pub fn new(inner: Box<MetadataLoader>, cache: Rc<MetadataCache>) -> CachingMetadataLoader {
+        CachingMetadataLoader {
+            inner: inner,
+            cache: cache,
+        }
+    }
+
+    fn load<F>(&self,
+               target: &Target,
+               filename: &Path,
+               dylib: bool,
+               load: F)
+               -> Result<ErasedBoxRef<[u8]>, String>
+        where F: FnOnce() -> Result<ErasedBoxRef<[u8]>, String>
+    {
+        let stamp = fs::metadata(filename).and_then(|m| Ok((m.modified()?, m.len())));
+        let (modified, len) = match stamp {
+            Ok(stamp) => stamp,
+            // Without a timestamp there is no telling whether the file
+            // changed since we last saw it.
+            Err(_) => return load(),
+        };
+
+        let key = CacheKey {
+            target: target.llvm_target.clone(),
+            path: filename.to_path_buf(),
+            dylib: dylib,
+        };
+        let cached = self.cache.entries.borrow().get(&key).and_then(|entry| {
+            if entry.modified == modified && entry.len == len {
+                entry.hits.set(entry.hits.get() + 1);
+                Some(entry.data.clone())
+            } else {
+                None
+            }
+        });
+        let data = match cached {
+            Some(data) => data,
+            None => {
+                let data = Rc::new(load()?);
+                self.cache.entries.borrow_mut().insert(key, CacheEntry {
+                    modified: modified,
+                    len: len,
+                    data: data.clone(),
+                    hits: Cell::new(0),
+                });
+                data
+            }
+        };
+
+        Ok(OwningRef::new(Box::new(data)).map(|data| &data[..]).erase_owner())
+    }
+}
+
+impl MetadataLoader for CachingMetadataLoader {
+    fn get_rlib_metadata(&self,
+                         target: &Target,
+                         filename: &Path)
+                         -> Result<ErasedBoxRef<[u8]>, String> {
+        self.load(target, filename, false, || self.inner.get_rlib_metadata(target, filename))
+    }
+
+    fn get_dylib_metadata(&self,
+                          target: &Target,
+                          filename: &Path)
+                          -> Result<ErasedBoxRef<[u8]>, String> {
+        self.load(target, filename, true, || self.inner.get_dylib_metadata(target, filename))
+    }
+}
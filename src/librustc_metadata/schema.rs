@@ -20,12 +20,14 @@ use rustc::middle::lang_items;
 use rustc::mir;
 use rustc::ty::{self, Ty, ReprOptions};
 use rustc_back::PanicStrategy;
+use rustc_data_structures::fnv::FnvHasher;
 
 use rustc_serialize as serialize;
 use syntax::{ast, attr};
 use syntax::symbol::Symbol;
 use syntax_pos::{self, Span};
 
+use std::hash::Hasher;
 use std::marker::PhantomData;
 use std::mem;
 
@@ -42,7 +44,7 @@ pub fn rustc_version() -> String {
 /// Metadata encoding version.
 /// NB: increment this if you change the format of metadata such that
 /// the rustc version can't be found to compare with `rustc_version()`.
-pub const METADATA_VERSION: u8 = 4;
+pub const METADATA_VERSION: u8 = 5;
 
 /// Metadata header which includes `METADATA_VERSION`.
 /// To get older versions of rustc to ignore this metadata,
@@ -55,6 +57,22 @@ pub const METADATA_VERSION: u8 = 4;
 pub const METADATA_HEADER: &'static [u8; 12] =
     &[0, 0, 0, 0, b'r', b'u', b's', b't', 0, 0, 0, METADATA_VERSION];
 
+/// Computes `CrateRoot::checksum` over `bytes`, the metadata preceding the
+/// `CrateRoot`. The root position following the header is only filled in
+/// once everything is encoded, so it's hashed as if it were still zero.
+/// Fails if `bytes` is too short to even hold the header and root position.
+pub fn metadata_checksum(bytes: &[u8]) -> Result<u64, String> {
+    let header = METADATA_HEADER.len();
+    if bytes.len() < header + 4 {
+        return Err(format!("only {} bytes long", bytes.len()));
+    }
+    let mut hasher = FnvHasher::default();
+    hasher.write(&bytes[..header]);
+    hasher.write(&[0, 0, 0, 0]);
+    hasher.write(&bytes[header + 4..]);
+    Ok(hasher.finish())
+}
+
 /// The shorthand encoding uses an enum's variant index `usize`
 /// and is offset by this value so it never matches a real variant.
 /// This offset is also chosen so that the first byte is never < 0x80.
@@ -258,6 +276,13 @@ pub struct CrateRoot {
     pub impls: Tracked<LazySeq<TraitImpls>>,
     pub exported_symbols: Tracked<LazySeq<DefIndex>>,
     pub index: LazySeq<index::Index>,
+
+    /// See `metadata_checksum`. Computing or checking it means reading all
+    /// of the metadata, while loading a crate otherwise only touches the
+    /// parts (of the memory-mapped file) that are used. So it is only
+    /// recorded under `-Z verify-metadata`, and only checked under it too,
+    /// once a crate has been picked among the candidates (see `creader`).
+    pub checksum: Option<u64>,
 }
 
 #[derive(RustcEncodable, RustcDecodable)]
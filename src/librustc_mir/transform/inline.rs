@@ -109,9 +109,11 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                     continue;
                 }
 
-                let callee_mir = match ty::queries::optimized_mir::try_get(self.tcx,
-                                                                           callsite.location.span,
-                                                                           callsite.callee) {
+                let callee_mir = match ty::queries::optimized_mir::try_get_at(
+                    self.tcx,
+                    callsite.location.span,
+                    callsite.callee,
+                    dep_location!()) {
                     Ok(ref callee_mir) if self.should_inline(callsite, callee_mir) => {
                         callee_mir.subst(self.tcx, callsite.substs)
                     }
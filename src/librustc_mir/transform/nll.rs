@@ -0,0 +1,474 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A prototype of non-lexical lifetimes, enabled with `-Z nll`.
+//!
+//! With lexical lifetimes, a borrow lasts until the end of the scope its
+//! region was inferred to, i.e. up to the matching `EndRegion` statement in
+//! the MIR. Here, a borrow only lasts for as long as its region is *live*:
+//! its scope is the set of points reachable from the borrow at which some
+//! local whose type mentions the region may still be used (see
+//! `util::liveness`).
+//!
+//! For now the pass runs in "diff" mode. A simple conflict check is run over
+//! the MIR twice, once with each notion of a borrow's scope, and whatever
+//! is only found by one of the runs is reported as a warning: errors the
+//! non-lexical model would remove, and errors it would add. The regular
+//! (AST) borrow checker is left in charge of actually rejecting programs.
+//!
+//! With `#![feature(two_phase_borrows)]`, a mutable borrow into a temporary
+//! that is only ever passed as the first argument of a call (i.e. the
+//! autoref of a method receiver) is treated as a shared borrow up to that
+//! call, where it is "activated" and becomes a mutable one, as in the AST
+//! borrow checker.
+
+use rustc::mir::{BasicBlock, BorrowKind, Local, LocalKind, Location, Lvalue, LvalueElem, Mir};
+use rustc::mir::{Operand, ProjectionElem, Rvalue, StatementKind, TerminatorKind};
+use rustc::mir::transform::{MirPass, MirSource};
+use rustc::mir::visit::{LvalueContext, Visitor};
+use rustc::ty::{Region, RegionKind, TyCtxt};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+use syntax_pos::Span;
+use util::liveness::{self, LocalSet};
+
+pub struct NLL;
+
+impl MirPass for NLL {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        if !tcx.sess.opts.debugging_opts.nll {
+            return;
+        }
+
+        let item_id = match source {
+            MirSource::Fn(item_id) => item_id,
+            _ => return,
+        };
+        if tcx.typeck_tables_of(tcx.hir.local_def_id(item_id)).tainted_by_errors {
+            return;
+        }
+
+        let liveness = liveness::liveness_of_locals(mir);
+        let live_at: IndexVec<BasicBlock, Vec<LocalSet>> = mir.basic_blocks()
+            .indices()
+            .map(|bb| liveness.live_in_block(mir, bb))
+            .collect();
+
+        let two_phase = if tcx.sess.features.borrow().two_phase_borrows {
+            two_phase_borrows(mir)
+        } else {
+            FxHashMap()
+        };
+
+        let mut cx = NllContext {
+            tcx,
+            mir,
+            live_at,
+            region_locals: FxHashMap(),
+            two_phase,
+        };
+        cx.report_diff();
+    }
+}
+
+/// A borrow, as found in an `Rvalue::Ref`.
+struct Loan<'tcx> {
+    location: Location,
+    region: Region<'tcx>,
+    kind: BorrowKind,
+    lvalue: Lvalue<'tcx>,
+    span: Span,
+    /// For a two-phase borrow, the call at which it becomes mutable.
+    activation: Option<Location>,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Access {
+    Read,
+    Write,
+    StorageDead,
+    Borrow(BorrowKind),
+}
+
+/// Collects the lvalues accessed by a statement or terminator.
+struct AccessCollector<'tcx> {
+    accesses: Vec<(Lvalue<'tcx>, Access)>,
+}
+
+impl<'tcx> Visitor<'tcx> for AccessCollector<'tcx> {
+    fn visit_lvalue(&mut self,
+                    lvalue: &Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        let access = match context {
+            LvalueContext::Store |
+            LvalueContext::Call |
+            LvalueContext::Drop => Some(Access::Write),
+            LvalueContext::StorageDead => Some(Access::StorageDead),
+            LvalueContext::Borrow { kind, .. } => Some(Access::Borrow(kind)),
+            LvalueContext::Inspect |
+            LvalueContext::Consume => Some(Access::Read),
+            // The base of a projection is accessed as part of the whole
+            // lvalue, which is recorded on its own.
+            LvalueContext::Projection(..) |
+            LvalueContext::StorageLive => None,
+        };
+        if let Some(access) = access {
+            self.accesses.push((lvalue.clone(), access));
+        }
+        self.super_lvalue(lvalue, context, location);
+    }
+}
+
+struct NllContext<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    mir: &'a Mir<'tcx>,
+    /// The locals live before each statement of each block.
+    live_at: IndexVec<BasicBlock, Vec<LocalSet>>,
+    /// The locals whose types mention a given region.
+    region_locals: FxHashMap<Region<'tcx>, LocalSet>,
+    /// The two-phase borrows, mapped to the calls activating them.
+    two_phase: FxHashMap<Location, Location>,
+}
+
+/// The conflicts found under one model, keyed by the location of the
+/// offending access and the error message, with the span of the loan.
+type Conflicts = FxHashMap<(Location, String), Span>;
+
+impl<'a, 'tcx> NllContext<'a, 'tcx> {
+    fn report_diff(&mut self) {
+        let loans = self.gather_loans();
+
+        let mut lexical = FxHashMap();
+        let mut non_lexical = FxHashMap();
+        for loan in &loans {
+            // Up to its activation, a two-phase borrow is only reserved.
+            let reserved = match loan.activation {
+                Some(activation) => self.loan_scope(loan.location, |_, location| {
+                    location != activation
+                }),
+                None => FxHashSet(),
+            };
+
+            let lexical_scope = self.loan_scope(loan.location, |cx, location| {
+                !cx.ends_region(location, loan.region)
+            });
+            self.find_conflicts(loan, &lexical_scope, &reserved, &mut lexical);
+
+            let non_lexical_scope = self.loan_scope(loan.location, |cx, location| {
+                cx.region_is_live(location, loan.region)
+            });
+            self.find_conflicts(loan, &non_lexical_scope, &reserved, &mut non_lexical);
+        }
+
+        self.report(&lexical, &non_lexical, "non-lexical lifetimes would accept this");
+        self.report(&non_lexical, &lexical, "non-lexical lifetimes would reject this");
+    }
+
+    /// Warns about the conflicts in `found` that are not in `other`.
+    fn report(&self, found: &Conflicts, other: &Conflicts, what: &str) {
+        let mut diff: Vec<_> = found.iter().filter(|&(key, _)| !other.contains_key(key)).collect();
+        diff.sort_by_key(|&(&(location, _), _)| (location.block, location.statement_index));
+        for (&(location, ref message), &loan_span) in diff {
+            let data = &self.mir[location.block];
+            let span = match data.statements.get(location.statement_index) {
+                Some(statement) => statement.source_info.span,
+                None => data.terminator().source_info.span,
+            };
+            self.tcx.sess.struct_span_warn(span, &format!("{}: {}", what, message))
+                .span_label(loan_span, "borrow occurs here")
+                .emit();
+        }
+    }
+
+    fn gather_loans(&self) -> Vec<Loan<'tcx>> {
+        let mut loans = vec![];
+        for (block, data) in self.mir.basic_blocks().iter_enumerated() {
+            for (statement_index, statement) in data.statements.iter().enumerate() {
+                if let StatementKind::Assign(_, Rvalue::Ref(region, kind, ref lvalue)) =
+                    statement.kind
+                {
+                    let location = Location { block, statement_index };
+                    loans.push(Loan {
+                        location,
+                        region,
+                        kind,
+                        lvalue: lvalue.clone(),
+                        span: statement.source_info.span,
+                        activation: self.two_phase.get(&location).cloned(),
+                    });
+                }
+            }
+        }
+        loans
+    }
+
+    /// Computes the points reachable from `start` without leaving the
+    /// region, as determined by `in_region`. `start` itself isn't included.
+    fn loan_scope<F>(&mut self, start: Location, mut in_region: F) -> FxHashSet<Location>
+        where F: FnMut(&mut Self, Location) -> bool
+    {
+        let mut scope = FxHashSet();
+        let mut stack = self.successors(start);
+        while let Some(location) = stack.pop() {
+            if scope.contains(&location) || !in_region(self, location) {
+                continue;
+            }
+            scope.insert(location);
+            stack.extend(self.successors(location));
+        }
+        scope
+    }
+
+    fn successors(&self, location: Location) -> Vec<Location> {
+        let data = &self.mir[location.block];
+        if location.statement_index < data.statements.len() {
+            vec![Location { statement_index: location.statement_index + 1, ..location }]
+        } else {
+            data.terminator().successors().iter().map(|&block| {
+                Location { block, statement_index: 0 }
+            }).collect()
+        }
+    }
+
+    fn ends_region(&self, location: Location, region: Region<'tcx>) -> bool {
+        let data = &self.mir[location.block];
+        match data.statements.get(location.statement_index) {
+            Some(statement) => match (&statement.kind, region) {
+                (&StatementKind::EndRegion(extent), &RegionKind::ReScope(region_extent)) => {
+                    extent == region_extent
+                }
+                _ => false,
+            },
+            None => false,
+        }
+    }
+
+    fn region_is_live(&mut self, location: Location, region: Region<'tcx>) -> bool {
+        let tcx = self.tcx;
+        let mir = self.mir;
+        let region_locals = self.region_locals.entry(region).or_insert_with(|| {
+            let mut locals = LocalSet::new_empty(mir.local_decls.len());
+            for (local, decl) in mir.local_decls.iter_enumerated() {
+                let mut regions = FxHashSet();
+                tcx.collect_regions(&decl.ty, &mut regions);
+                if regions.contains(&region) {
+                    locals.add(&local);
+                }
+            }
+            locals
+        });
+        let live = &self.live_at[location.block][location.statement_index];
+        live.words().iter().zip(region_locals.words()).any(|(&a, &b)| a & b != 0)
+    }
+
+    fn find_conflicts(&self, loan: &Loan<'tcx>, scope: &FxHashSet<Location>,
+                      reserved: &FxHashSet<Location>, conflicts: &mut Conflicts) {
+        for &location in scope {
+            let mut collector = AccessCollector { accesses: vec![] };
+            let data = &self.mir[location.block];
+            match data.statements.get(location.statement_index) {
+                Some(statement) => {
+                    collector.visit_statement(location.block, statement, location)
+                }
+                None => collector.visit_terminator(location.block, data.terminator(), location),
+            }
+
+            let kind = if reserved.contains(&location) {
+                BorrowKind::Shared
+            } else {
+                loan.kind
+            };
+            for (lvalue, access) in self.two_phase_accesses(location, collector.accesses) {
+                if let Some(message) = self.conflict(loan, kind, &lvalue, access) {
+                    conflicts.entry((location, message)).or_insert(loan.span);
+                }
+            }
+        }
+    }
+
+    /// Accounts for two-phase borrows in the `accesses` at `location`: a
+    /// reservation only borrows its lvalue shared, and the call activating
+    /// it borrows it mutably.
+    fn two_phase_accesses(&self, location: Location, mut accesses: Vec<(Lvalue<'tcx>, Access)>)
+                          -> Vec<(Lvalue<'tcx>, Access)> {
+        if self.two_phase.contains_key(&location) {
+            for access in &mut accesses {
+                if access.1 == Access::Borrow(BorrowKind::Mut) {
+                    access.1 = Access::Borrow(BorrowKind::Shared);
+                }
+            }
+        }
+        for (&reservation, &activation) in &self.two_phase {
+            if activation != location {
+                continue;
+            }
+            let data = &self.mir[reservation.block];
+            let statement = &data.statements[reservation.statement_index];
+            if let StatementKind::Assign(_, Rvalue::Ref(_, _, ref lvalue)) = statement.kind {
+                accesses.push((lvalue.clone(), Access::Borrow(BorrowKind::Mut)));
+            }
+        }
+        accesses
+    }
+
+    /// Returns the borrowck error for `access` to `lvalue` while `loan`,
+    /// acting as a borrow of the given `kind`, is in scope, if any.
+    fn conflict(&self, loan: &Loan<'tcx>, kind: BorrowKind, lvalue: &Lvalue<'tcx>,
+                access: Access) -> Option<String> {
+        let desc = self.describe_lvalue(lvalue);
+        if access == Access::StorageDead {
+            // Only the storage of the borrowed data itself matters, not the
+            // storage of a reference it was borrowed through.
+            let (root, elems) = split_lvalue(&loan.lvalue);
+            let through_deref = elems.iter().any(|elem| **elem == ProjectionElem::Deref);
+            return if root == lvalue && !through_deref {
+                Some(format!("`{}` does not live long enough", desc))
+            } else {
+                None
+            };
+        }
+
+        if !overlaps(&loan.lvalue, lvalue) {
+            return None;
+        }
+        match (access, kind) {
+            (Access::Write, _) => {
+                Some(format!("cannot assign to `{}` because it is borrowed", desc))
+            }
+            (Access::Borrow(BorrowKind::Shared), BorrowKind::Shared) |
+            (Access::Read, BorrowKind::Shared) => None,
+            (Access::Borrow(BorrowKind::Shared), _) => {
+                Some(format!("cannot borrow `{}` as immutable because it is also \
+                              borrowed as mutable", desc))
+            }
+            (Access::Borrow(_), _) => {
+                Some(format!("cannot borrow `{}` as mutable because it is also borrowed", desc))
+            }
+            (Access::Read, _) => {
+                Some(format!("cannot use `{}` because it was mutably borrowed", desc))
+            }
+            (Access::StorageDead, _) => unreachable!(),
+        }
+    }
+
+    fn describe_lvalue(&self, lvalue: &Lvalue<'tcx>) -> String {
+        match *lvalue {
+            Lvalue::Local(local) => match self.mir.local_decls[local].name {
+                Some(name) => name.to_string(),
+                None => format!("{:?}", local),
+            },
+            Lvalue::Static(..) => format!("{:?}", lvalue),
+            Lvalue::Projection(ref proj) => {
+                let base = self.describe_lvalue(&proj.base);
+                match proj.elem {
+                    ProjectionElem::Deref => format!("*{}", base),
+                    ProjectionElem::Field(field, _) => format!("{}.{}", base, field.index()),
+                    ProjectionElem::Index(_) |
+                    ProjectionElem::ConstantIndex { .. } |
+                    ProjectionElem::Subslice { .. } => format!("{}[..]", base),
+                    ProjectionElem::Downcast(..) => base,
+                }
+            }
+        }
+    }
+}
+
+/// Finds the mutable borrows that are only ever passed as the first
+/// argument of a call, i.e. the autorefs of method receivers, and maps
+/// each one to the location of that call.
+fn two_phase_borrows(mir: &Mir) -> FxHashMap<Location, Location> {
+    let mut mentions = MentionCounter {
+        counts: IndexVec::from_elem_n(0, mir.local_decls.len()),
+    };
+    mentions.visit_mir(mir);
+
+    let mut borrows = FxHashMap();
+    for (block, data) in mir.basic_blocks().iter_enumerated() {
+        let temp = match data.terminator().kind {
+            TerminatorKind::Call { ref args, .. } => match args.first() {
+                Some(&Operand::Consume(Lvalue::Local(temp))) => temp,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        if mir.local_kind(temp) != LocalKind::Temp || mentions.counts[temp] != 2 {
+            continue;
+        }
+
+        // The only other mention of `temp` must be the borrow itself.
+        for (def_block, def_data) in mir.basic_blocks().iter_enumerated() {
+            for (statement_index, statement) in def_data.statements.iter().enumerate() {
+                if let StatementKind::Assign(Lvalue::Local(local),
+                                             Rvalue::Ref(_, BorrowKind::Mut, _)) = statement.kind {
+                    if local == temp {
+                        let reservation = Location { block: def_block, statement_index };
+                        let activation = Location {
+                            block,
+                            statement_index: data.statements.len(),
+                        };
+                        borrows.insert(reservation, activation);
+                    }
+                }
+            }
+        }
+    }
+    borrows
+}
+
+/// Counts how often each local is mentioned, not counting the storage
+/// statements.
+struct MentionCounter {
+    counts: IndexVec<Local, usize>,
+}
+
+impl<'tcx> Visitor<'tcx> for MentionCounter {
+    fn visit_lvalue(&mut self,
+                    lvalue: &Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        if let Lvalue::Local(local) = *lvalue {
+            match context {
+                LvalueContext::StorageLive | LvalueContext::StorageDead => {}
+                _ => self.counts[local] += 1,
+            }
+        }
+        self.super_lvalue(lvalue, context, location);
+    }
+}
+
+/// Splits `lvalue` into the local or static it is based on and the
+/// projections applied to it, outermost last.
+fn split_lvalue<'a, 'tcx>(lvalue: &'a Lvalue<'tcx>)
+                          -> (&'a Lvalue<'tcx>, Vec<&'a LvalueElem<'tcx>>) {
+    match *lvalue {
+        Lvalue::Projection(ref proj) => {
+            let (root, mut elems) = split_lvalue(&proj.base);
+            elems.push(&proj.elem);
+            (root, elems)
+        }
+        _ => (lvalue, vec![]),
+    }
+}
+
+/// Whether `a` and `b` may refer to overlapping memory, i.e. when one is a
+/// (possible) prefix of the other. Indices are never assumed to differ.
+fn overlaps<'tcx>(a: &Lvalue<'tcx>, b: &Lvalue<'tcx>) -> bool {
+    let (root_a, elems_a) = split_lvalue(a);
+    let (root_b, elems_b) = split_lvalue(b);
+    root_a == root_b && elems_a.iter().zip(&elems_b).all(|(a, b)| match (*a, *b) {
+        (&ProjectionElem::Field(f1, _), &ProjectionElem::Field(f2, _)) => f1 == f2,
+        (&ProjectionElem::Downcast(_, v1), &ProjectionElem::Downcast(_, v2)) => v1 == v2,
+        _ => true,
+    })
+}
@@ -0,0 +1,138 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Liveness analysis of MIR locals.
+//!
+//! A local is live at a point if its current value may be used later on.
+//! This is a plain backwards dataflow over the MIR: every mention of a
+//! local counts as a use, except for assignments of the whole local (and
+//! the storage markers), which are definitions.
+//!
+//! Results are computed for the entry of each basic block; `simulate_block`
+//! walks a block backwards to recover the liveness at each statement.
+
+use rustc::mir::{BasicBlock, Local, Location, Lvalue, Mir};
+use rustc::mir::visit::{LvalueContext, Visitor};
+use rustc_data_structures::indexed_set::IdxSetBuf;
+use rustc_data_structures::indexed_vec::IndexVec;
+
+pub type LocalSet = IdxSetBuf<Local>;
+
+pub struct LivenessResult {
+    /// The locals live on entry to each basic block.
+    pub ins: IndexVec<BasicBlock, LocalSet>,
+}
+
+/// The locals defined and used by a single statement or terminator.
+#[derive(Default)]
+struct DefsUses {
+    defs: Vec<Local>,
+    uses: Vec<Local>,
+}
+
+impl DefsUses {
+    /// Transforms the set of locals live after the statement into the set
+    /// of locals live before it.
+    fn apply(&self, live: &mut LocalSet) {
+        for local in &self.defs {
+            live.remove(local);
+        }
+        for local in &self.uses {
+            live.add(local);
+        }
+    }
+}
+
+impl<'tcx> Visitor<'tcx> for DefsUses {
+    fn visit_lvalue(&mut self,
+                    lvalue: &Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        if let Lvalue::Local(local) = *lvalue {
+            match context {
+                LvalueContext::Store |
+                LvalueContext::Call |
+                LvalueContext::StorageLive |
+                LvalueContext::StorageDead => self.defs.push(local),
+
+                // A projection only ever writes part of the local, whatever
+                // remains is still live.
+                LvalueContext::Projection(..) |
+                LvalueContext::Drop |
+                LvalueContext::Inspect |
+                LvalueContext::Borrow { .. } |
+                LvalueContext::Consume => self.uses.push(local),
+            }
+        }
+        self.super_lvalue(lvalue, context, location);
+    }
+}
+
+fn defs_uses<'tcx>(mir: &Mir<'tcx>, location: Location) -> DefsUses {
+    let mut defs_uses = DefsUses::default();
+    let data = &mir[location.block];
+    match data.statements.get(location.statement_index) {
+        Some(statement) => defs_uses.visit_statement(location.block, statement, location),
+        None => defs_uses.visit_terminator(location.block, data.terminator(), location),
+    }
+    defs_uses
+}
+
+pub fn liveness_of_locals<'tcx>(mir: &Mir<'tcx>) -> LivenessResult {
+    let num_locals = mir.local_decls.len();
+    let mut ins: IndexVec<BasicBlock, LocalSet> =
+        mir.basic_blocks().indices().map(|_| LocalSet::new_empty(num_locals)).collect();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for bb in mir.basic_blocks().indices().rev() {
+            let mut live = LocalSet::new_empty(num_locals);
+            for &succ in mir[bb].terminator().successors().iter() {
+                live.union(&ins[succ]);
+            }
+            simulate_block(mir, bb, &mut live, |_, _| ());
+            if live.words() != ins[bb].words() {
+                ins[bb] = live;
+                changed = true;
+            }
+        }
+    }
+
+    LivenessResult { ins }
+}
+
+impl LivenessResult {
+    /// Computes the locals live before each statement (and the terminator)
+    /// of `block`, in order.
+    pub fn live_in_block<'tcx>(&self, mir: &Mir<'tcx>, block: BasicBlock) -> Vec<LocalSet> {
+        let mut live = LocalSet::new_empty(mir.local_decls.len());
+        for &succ in mir[block].terminator().successors().iter() {
+            live.union(&self.ins[succ]);
+        }
+        let mut result = vec![];
+        simulate_block(mir, block, &mut live, |_, live| result.push(live.clone()));
+        result.reverse();
+        result
+    }
+}
+
+/// Walks `block` backwards, starting with `live` holding the locals live on
+/// exit, and calls `callback` with the locals live before each statement.
+fn simulate_block<'tcx, F>(mir: &Mir<'tcx>, block: BasicBlock, live: &mut LocalSet, mut callback: F)
+    where F: FnMut(Location, &LocalSet)
+{
+    let num_statements = mir[block].statements.len();
+    for statement_index in (0..num_statements + 1).rev() {
+        let location = Location { block, statement_index };
+        defs_uses(mir, location).apply(live);
+        callback(location, live);
+    }
+}
@@ -10,6 +10,7 @@
 
 pub mod elaborate_drops;
 pub mod def_use;
+pub mod liveness;
 pub mod patch;
 
 mod graphviz;
@@ -223,6 +223,36 @@ To fix this, add a label specifying which loop is being broken out of:
 ```
 `foo: while break `foo {}
 ```
+"##,
+
+E0623: r##"
+A `#[naked]` function contained something other than inline assembly.
+
+Erroneous code example:
+
+```compile_fail,E0623
+#![feature(naked_functions)]
+
+#[naked]
+fn add_one(x: u32) -> u32 {
+    x + 1 // error: naked functions must only contain inline assembly
+}
+```
+
+Naked functions are translated without a prologue or an epilogue, so they
+have no stack frame: the arguments aren't where Rust code would expect them
+and there's no space for local variables. Their body has to be written in
+inline assembly instead, which is responsible for everything the omitted
+prologue and epilogue would have done, including returning:
+
+```ignore (x86 assembly)
+#![feature(asm, naked_functions)]
+
+#[naked]
+unsafe extern "C" fn nop() {
+    asm!("ret");
+}
+```
 "##
 }
 
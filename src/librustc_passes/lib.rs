@@ -43,5 +43,6 @@ pub mod consts;
 pub mod hir_stats;
 pub mod loops;
 pub mod mir_stats;
+pub mod naked_functions;
 pub mod no_asm;
 pub mod static_recursion;
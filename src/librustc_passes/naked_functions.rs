@@ -0,0 +1,88 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Checks the bodies of `#[naked]` functions.
+//
+// A naked function is translated without a prologue or an epilogue, so its
+// body has no stack frame to work with: anything but inline assembly would
+// end up reading its arguments or spilling into memory which was never
+// allocated. This pass makes sure that the body consists of `asm!`
+// statements only, optionally wrapped in `unsafe` blocks.
+
+use rustc::session::Session;
+
+use rustc::hir::map::Map;
+use rustc::hir::intravisit::{self, Visitor, NestedVisitorMap, FnKind};
+use rustc::hir;
+use syntax::ast;
+use syntax::attr;
+use syntax_pos::Span;
+
+struct CheckNakedFunctions<'a, 'hir: 'a> {
+    sess: &'a Session,
+    hir_map: &'a Map<'hir>,
+}
+
+pub fn check_crate(sess: &Session, map: &Map) {
+    let krate = map.krate();
+    krate.visit_all_item_likes(&mut CheckNakedFunctions {
+        sess: sess,
+        hir_map: map,
+    }.as_deep_visitor());
+}
+
+impl<'a, 'hir> Visitor<'hir> for CheckNakedFunctions<'a, 'hir> {
+    fn nested_visit_map<'this>(&'this mut self) -> NestedVisitorMap<'this, 'hir> {
+        NestedVisitorMap::OnlyBodies(&self.hir_map)
+    }
+
+    fn visit_fn(&mut self,
+                fk: FnKind<'hir>,
+                fd: &'hir hir::FnDecl,
+                b: hir::BodyId,
+                s: Span,
+                id: ast::NodeId) {
+        if attr::contains_name(fk.attrs(), "naked") {
+            let body = self.hir_map.body(b);
+            self.check_naked_expr(&body.value);
+        }
+        intravisit::walk_fn(self, fk, fd, b, s, id)
+    }
+}
+
+impl<'a, 'hir> CheckNakedFunctions<'a, 'hir> {
+    fn check_naked_expr(&self, e: &hir::Expr) {
+        match e.node {
+            hir::ExprInlineAsm(..) => {}
+            hir::ExprBlock(ref block) => {
+                for stmt in &block.stmts {
+                    match stmt.node {
+                        hir::StmtExpr(ref e, _) |
+                        hir::StmtSemi(ref e, _) => self.check_naked_expr(e),
+                        hir::StmtDecl(..) => self.report(stmt.span),
+                    }
+                }
+                if let Some(ref e) = block.expr {
+                    self.check_naked_expr(e);
+                }
+            }
+            _ => self.report(e.span),
+        }
+    }
+
+    fn report(&self, span: Span) {
+        struct_span_err!(self.sess, span, E0623,
+                         "naked functions must only contain inline assembly")
+            .span_label(span, "not inline assembly")
+            .note("naked functions have no prologue, so their bodies can't \
+                   access arguments or use the stack")
+            .emit();
+    }
+}
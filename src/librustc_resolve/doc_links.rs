@@ -0,0 +1,204 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Resolution of intra-doc links, for rustdoc.
+//!
+//! A link such as [`Vec::new`] or [`Foo`] in a doc comment, that has no
+//! reference definition of its own, names a path. That path is resolved
+//! like one written at the documented item, e.g. in the module containing
+//! it, or with `Self` bound to the implementing type for an impl item. The
+//! results are recorded in `Resolver::doc_links` for rustdoc, which turns
+//! them into reference definitions when rendering the documentation.
+
+use {PathResult, Resolver};
+use Namespace::{TypeNS, ValueNS};
+
+use rustc::hir::def::PathResolution;
+
+use syntax::ast::{self, Ident, NodeId};
+use syntax_pos::{Span, DUMMY_SP};
+
+impl<'a> Resolver<'a> {
+    /// Resolves the intra-doc links in `attrs`, the attributes of the item
+    /// `id`, in the current scope. Does nothing unless running rustdoc.
+    pub fn resolve_doc_links(&mut self, id: NodeId, attrs: &[ast::Attribute]) {
+        if !self.session.opts.actually_rustdoc {
+            return
+        }
+
+        let docs: Vec<(String, Span)> = attrs.iter().filter_map(|attr| {
+            if !attr.check_name("doc") {
+                return None
+            }
+            attr.with_desugared_doc(|attr| attr.value_str()).map(|s| (s.to_string(), attr.span))
+        }).collect();
+        if docs.is_empty() {
+            return
+        }
+
+        // Labels of the reference definitions written by the author, which
+        // take precedence over any path of the same name.
+        let defined: Vec<&str> = docs.iter().flat_map(|&(ref doc, _)| doc.lines())
+                                     .filter_map(reference_definition)
+                                     .collect();
+
+        let mut links = vec![];
+        let mut in_code_block = false;
+        for &(ref doc, span) in &docs {
+            for line in doc.lines() {
+                if line.trim_left().starts_with("```") {
+                    in_code_block = !in_code_block;
+                    continue
+                }
+                if in_code_block {
+                    continue
+                }
+                for label in link_labels(line) {
+                    if defined.contains(&label) || links.iter().any(|&(ref l, _)| l == label) {
+                        continue
+                    }
+                    let (path, is_code) = match link_path(label) {
+                        Some(path) => path,
+                        None => continue,
+                    };
+                    match self.resolve_doc_path(path) {
+                        Some(resolution) => links.push((label.to_string(), resolution)),
+                        // Brackets around a plain word are too common in
+                        // prose to complain about.
+                        None if is_code || path.contains("::") => {
+                            self.session.span_warn(span, &format!(
+                                "`[{}]` cannot be resolved, ignoring it", label));
+                        }
+                        None => {}
+                    }
+                }
+            }
+        }
+
+        if !links.is_empty() {
+            self.doc_links.insert(id, links);
+        }
+    }
+
+    fn resolve_doc_path(&mut self, path: &str) -> Option<PathResolution> {
+        let (path, namespaces) = if path.ends_with("()") {
+            (&path[..path.len() - 2], vec![ValueNS])
+        } else {
+            (path, vec![TypeNS, ValueNS])
+        };
+        let path: Vec<_> = path.split("::").map(Ident::from_str).collect();
+
+        // Prefer a full resolution in either namespace over a partial one,
+        // such as an associated item of a type in the type namespace.
+        let mut partial = None;
+        for ns in namespaces {
+            match self.resolve_path(&path, Some(ns), false, DUMMY_SP) {
+                PathResult::Module(module) => {
+                    if let Some(def) = module.def() {
+                        return Some(PathResolution::new(def));
+                    }
+                }
+                PathResult::NonModule(resolution) => {
+                    if resolution.unresolved_segments() == 0 {
+                        return Some(resolution);
+                    }
+                    partial = partial.or(Some(resolution));
+                }
+                PathResult::Indeterminate | PathResult::Failed(..) => {}
+            }
+        }
+        partial
+    }
+}
+
+/// If `line` is a reference definition, e.g. `[foo]: http://example.com`,
+/// returns its label.
+fn reference_definition(line: &str) -> Option<&str> {
+    let line = line.trim_left();
+    if !line.starts_with('[') {
+        return None
+    }
+    match line.find("]:") {
+        Some(end) if !line[1..end].contains(']') => Some(&line[1..end]),
+        _ => None,
+    }
+}
+
+/// Returns the labels of the reference links in a line of markdown, i.e.
+/// `foo` for `[foo]`, `[foo][]` and `[text][foo]`. Inline links and code
+/// spans are skipped.
+fn link_labels(line: &str) -> Vec<&str> {
+    let bytes = line.as_bytes();
+    let mut labels = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' => {
+                let ticks = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+                let fence = &line[i..i + ticks];
+                i += ticks;
+                if let Some(end) = line[i..].find(fence) {
+                    i += end + ticks;
+                }
+            }
+            b'[' => {
+                let close = match line[i + 1..].find(|c: char| c == '[' || c == ']') {
+                    Some(close) if bytes[i + 1 + close] == b']' => i + 1 + close,
+                    _ => {
+                        i += 1;
+                        continue
+                    }
+                };
+                let text = &line[i + 1..close];
+                let rest = &line[close + 1..];
+                i = close + 1;
+                if rest.starts_with('(') || rest.starts_with(':') {
+                    continue
+                }
+                if rest.starts_with('[') {
+                    if let Some(end) = rest.find(']') {
+                        i += end + 1;
+                        if end > 1 {
+                            labels.push(&rest[1..end]);
+                            continue
+                        }
+                    }
+                }
+                if !text.is_empty() {
+                    labels.push(text);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    labels
+}
+
+/// Returns the path named by a link label, such as `Foo::bar` for
+/// `` `Foo::bar()` ``, and whether it was written as code.
+fn link_path(label: &str) -> Option<(&str, bool)> {
+    let is_code = label.len() > 2 && label.starts_with('`') && label.ends_with('`');
+    let path = if is_code { &label[1..label.len() - 1] } else { label };
+    let segments = if path.ends_with("()") { &path[..path.len() - 2] } else { path };
+
+    let is_ident = |s: &str| {
+        let mut chars = s.chars();
+        match chars.next() {
+            Some(c) if c.is_alphabetic() || c == '_' => {}
+            _ => return false,
+        }
+        chars.all(|c| c.is_alphanumeric() || c == '_')
+    };
+    if segments.split("::").all(is_ident) {
+        Some((path, is_code))
+    } else {
+        None
+    }
+}
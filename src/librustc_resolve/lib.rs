@@ -81,6 +81,7 @@ mod diagnostics;
 
 mod macros;
 mod check_unused;
+mod doc_links;
 mod build_reduced_graph;
 mod resolve_imports;
 
@@ -626,6 +627,7 @@ impl<'a, 'tcx> Visitor<'tcx> for Resolver<'a> {
                      variant: &'tcx ast::Variant,
                      generics: &'tcx Generics,
                      item_id: ast::NodeId) {
+        self.resolve_doc_links(variant.node.data.id(), &variant.node.attrs);
         if let Some(ref dis_expr) = variant.node.disr_expr {
             // resolve the discriminator expr as a constant
             self.with_constant_rib(|this| {
@@ -640,7 +642,12 @@ impl<'a, 'tcx> Visitor<'tcx> for Resolver<'a> {
                                 item_id,
                                 variant.span);
     }
+    fn visit_struct_field(&mut self, field: &'tcx ast::StructField) {
+        self.resolve_doc_links(field.id, &field.attrs);
+        visit::walk_struct_field(self, field);
+    }
     fn visit_foreign_item(&mut self, foreign_item: &'tcx ForeignItem) {
+        self.resolve_doc_links(foreign_item.id, &foreign_item.attrs);
         let type_parameters = match foreign_item.node {
             ForeignItemKind::Fn(_, ref generics) => {
                 HasTypeParameters(generics, ItemRibKind)
@@ -1180,6 +1187,9 @@ pub struct Resolver<'a> {
     used_imports: FxHashSet<(NodeId, Namespace)>,
     pub maybe_unused_trait_imports: NodeSet,
 
+    /// Resolved intra-doc links of each item, by label; see `doc_links`.
+    pub doc_links: NodeMap<Vec<(String, PathResolution)>>,
+
     privacy_errors: Vec<PrivacyError<'a>>,
     ambiguity_errors: Vec<AmbiguityError<'a>>,
     gated_errors: FxHashSet<Span>,
@@ -1376,6 +1386,7 @@ impl<'a> Resolver<'a> {
 
             used_imports: FxHashSet(),
             maybe_unused_trait_imports: NodeSet(),
+            doc_links: NodeMap(),
 
             privacy_errors: Vec::new(),
             ambiguity_errors: Vec::new(),
@@ -1442,6 +1453,7 @@ impl<'a> Resolver<'a> {
         ImportResolver { resolver: self }.finalize_imports();
         self.current_module = self.graph_root;
         self.finalize_current_module_macro_resolutions();
+        self.resolve_doc_links(ast::CRATE_NODE_ID, &krate.attrs);
         visit::walk_crate(self, krate);
 
         check_unused::check_crate(self, krate);
@@ -1725,6 +1737,11 @@ impl<'a> Resolver<'a> {
         debug!("(resolving item) resolving {}", name);
 
         self.check_proc_macro_attrs(&item.attrs);
+        // Modules resolve the links in their docs in their own scope, below.
+        match item.node {
+            ItemKind::Mod(_) | ItemKind::ForeignMod(_) => {}
+            _ => self.resolve_doc_links(item.id, &item.attrs),
+        }
 
         match item.node {
             ItemKind::Enum(_, ref generics) |
@@ -1759,6 +1776,7 @@ impl<'a> Resolver<'a> {
 
                         for trait_item in trait_items {
                             this.check_proc_macro_attrs(&trait_item.attrs);
+                            this.resolve_doc_links(trait_item.id, &trait_item.attrs);
 
                             match trait_item.node {
                                 TraitItemKind::Const(ref ty, ref default) => {
@@ -1795,6 +1813,7 @@ impl<'a> Resolver<'a> {
 
             ItemKind::Mod(_) | ItemKind::ForeignMod(_) => {
                 self.with_scope(item.id, |this| {
+                    this.resolve_doc_links(item.id, &item.attrs);
                     visit::walk_item(this, item);
                 });
             }
@@ -1968,6 +1987,7 @@ impl<'a> Resolver<'a> {
                         this.with_current_self_type(self_type, |this| {
                             for impl_item in impl_items {
                                 this.check_proc_macro_attrs(&impl_item.attrs);
+                                this.resolve_doc_links(impl_item.id, &impl_item.attrs);
                                 this.resolve_visibility(&impl_item.vis);
                                 match impl_item.node {
                                     ImplItemKind::Const(..) => {
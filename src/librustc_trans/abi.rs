@@ -30,6 +30,7 @@ use cabi_sparc64;
 use cabi_nvptx;
 use cabi_nvptx64;
 use cabi_hexagon;
+use cabi_wasm32;
 use machine::llalign_of_min;
 use type_::Type;
 use type_of;
@@ -902,7 +903,11 @@ impl<'a, 'tcx> FnType<'tcx> {
             "powerpc64" => cabi_powerpc64::compute_abi_info(ccx, self),
             "s390x" => cabi_s390x::compute_abi_info(ccx, self),
             "asmjs" => cabi_asmjs::compute_abi_info(ccx, self),
-            "wasm32" => cabi_asmjs::compute_abi_info(ccx, self),
+            "wasm32" => if ccx.sess().target.target.options.is_like_emscripten {
+                cabi_asmjs::compute_abi_info(ccx, self);
+            } else {
+                cabi_wasm32::compute_abi_info(ccx, self);
+            },
             "msp430" => cabi_msp430::compute_abi_info(ccx, self),
             "sparc" => cabi_sparc::compute_abi_info(ccx, self),
             "sparc64" => cabi_sparc64::compute_abi_info(ccx, self),
@@ -27,7 +27,7 @@ use rustc::dep_graph::{DepKind, DepNode};
 use rustc::hir::def_id::CrateNum;
 use rustc::hir::svh::Svh;
 use rustc_back::tempdir::TempDir;
-use rustc_back::PanicStrategy;
+use rustc_back::LinkerFlavor;
 use rustc_incremental::IncrementalHashesMap;
 use context::get_reloc_model;
 use llvm;
@@ -155,6 +155,11 @@ pub fn get_linker(sess: &Session) -> (String, Command, Vec<(OsString, OsString)>
 
     if let Some(ref linker) = sess.opts.cg.linker {
         (linker.clone(), Command::new(linker), envs)
+    } else if sess.linker_flavor() == LinkerFlavor::Lld &&
+              sess.target.target.linker_flavor != LinkerFlavor::Lld {
+        // Targets name the linker of their default flavor, but when lld is
+        // requested on the command line we know what to invoke.
+        ("ld.lld".to_string(), Command::new("ld.lld"), envs)
     } else if sess.target.target.options.is_like_msvc {
         let (cmd, envs) = msvc_link_exe_cmd(sess);
         ("link.exe".to_string(), cmd, envs)
@@ -242,10 +247,10 @@ pub fn link_binary(sess: &Session,
             for obj in object_filenames(trans, outputs) {
                 remove(sess, &obj);
             }
-        }
-        remove(sess, &outputs.with_extension(METADATA_OBJ_NAME));
-        if trans.allocator_module.is_some() {
-            remove(sess, &outputs.with_extension(ALLOCATOR_OBJ_NAME));
+            remove(sess, &outputs.with_extension(METADATA_OBJ_NAME));
+            if trans.allocator_module.is_some() {
+                remove(sess, &outputs.with_extension(ALLOCATOR_OBJ_NAME));
+            }
         }
     }
 
@@ -273,6 +278,12 @@ pub fn default_output_for_target(sess: &Session) -> config::CrateType {
 /// Checks if target supports crate_type as output
 pub fn invalid_output_for_target(sess: &Session,
                                  crate_type: config::CrateType) -> bool {
+    if sess.target.target.options.only_cdylib {
+        match crate_type {
+            config::CrateTypeProcMacro | config::CrateTypeDylib => return true,
+            _ => {}
+        }
+    }
     match (sess.target.target.options.dynamic_linking,
            sess.target.target.options.executables, crate_type) {
         (false, _, config::CrateTypeCdylib) |
@@ -824,15 +835,6 @@ fn link_natively(sess: &Session,
         cmd.arg(root.join(obj));
     }
 
-    if sess.target.target.options.is_like_emscripten {
-        cmd.arg("-s");
-        cmd.arg(if sess.panic_strategy() == PanicStrategy::Abort {
-            "DISABLE_EXCEPTION_CATCHING=1"
-        } else {
-            "DISABLE_EXCEPTION_CATCHING=0"
-        });
-    }
-
     {
         let mut linker = trans.linker_info.to_linker(cmd, &sess);
         link_args(&mut *linker, sess, crate_type, tmpdir,
@@ -976,6 +978,7 @@ fn link_args(cmd: &mut Linker,
     let t = &sess.target.target;
 
     cmd.include_path(&fix_windows_verbatim_for_gcc(&lib_path));
+    cmd.panic_strategy(sess.panic_strategy());
     for obj in objects {
         cmd.add_object(obj);
     }
@@ -989,9 +992,11 @@ fn link_args(cmd: &mut Linker,
     }
 
     // If we're building a dynamic library then some platforms need to make sure
-    // that all symbols are exported correctly from the dynamic library.
+    // that all symbols are exported correctly from the dynamic library. On
+    // emscripten and wasm executables export their symbols as well.
     if crate_type != config::CrateTypeExecutable ||
-       sess.target.target.options.is_like_emscripten {
+       sess.target.target.options.is_like_emscripten ||
+       sess.target.target.options.is_like_wasm {
         cmd.export_symbols(tmpdir, crate_type);
     }
 
@@ -1035,6 +1040,17 @@ fn link_args(cmd: &mut Linker,
     // Pass debuginfo flags down to the linker.
     cmd.debuginfo();
 
+    // Make sure the profiling runtime is linked in for instrumented builds.
+    if sess.opts.cg.profile_generate.is_some() {
+        cmd.pgo_gen();
+    }
+
+    // Let the linker plugin know how to optimize and generate the bitcode
+    // objects we produced instead of machine code.
+    if sess.opts.debugging_opts.linker_plugin_lto.enabled() {
+        cmd.linker_plugin_lto();
+    }
+
     // We want to prevent the compiler from accidentally leaking in any system
     // libraries, so we explicitly ask gcc to not link to any libraries by
     // default. Note that this does not happen for windows because windows pulls
@@ -22,7 +22,7 @@ use back::archive;
 use back::symbol_export::{self, ExportedSymbols};
 use rustc::middle::dependency_format::Linkage;
 use rustc::hir::def_id::{LOCAL_CRATE, CrateNum};
-use rustc_back::LinkerFlavor;
+use rustc_back::{LinkerFlavor, PanicStrategy};
 use rustc::session::Session;
 use rustc::session::config::{self, CrateType, OptLevel, DebugInfoLevel};
 use serialize::{json, Encoder};
@@ -70,7 +70,16 @@ impl<'a, 'tcx> LinkerInfo {
                     is_ld: false,
                 }) as Box<Linker>
             }
-            LinkerFlavor::Ld => {
+            LinkerFlavor::Lld if sess.target.target.options.is_like_wasm => {
+                Box::new(WasmLd {
+                    cmd: cmd,
+                    sess: sess,
+                    info: self
+                }) as Box<Linker>
+            }
+            // `ld.lld` is a drop-in replacement for GNU ld and takes the same
+            // arguments.
+            LinkerFlavor::Ld | LinkerFlavor::Lld => {
                 Box::new(GccLinker {
                     cmd: cmd,
                     sess: sess,
@@ -111,6 +120,9 @@ pub trait Linker {
     fn args(&mut self, args: &[String]);
     fn export_symbols(&mut self, tmpdir: &Path, crate_type: CrateType);
     fn subsystem(&mut self, subsystem: &str);
+    fn panic_strategy(&mut self, strategy: PanicStrategy);
+    fn pgo_gen(&mut self);
+    fn linker_plugin_lto(&mut self);
     // Should have been finalize(self), but we don't support self-by-value on trait objects (yet?).
     fn finalize(&mut self) -> Command;
 }
@@ -366,6 +378,41 @@ impl<'a> Linker for GccLinker<'a> {
         self.linker_arg(&format!("--subsystem,{}", subsystem));
     }
 
+    fn panic_strategy(&mut self, _strategy: PanicStrategy) {
+        // noop, the runtime is linked like any other crate
+    }
+
+    fn pgo_gen(&mut self) {
+        // Make sure the profiling runtime's initialization object is pulled
+        // in from `profiler_builtins`; nothing in the instrumented code
+        // references it directly.
+        self.linker_arg("-u");
+        self.linker_arg("__llvm_profile_runtime");
+    }
+
+    fn linker_plugin_lto(&mut self) {
+        if let config::LinkerPluginLto::LinkerPlugin(ref path) =
+            self.sess.opts.debugging_opts.linker_plugin_lto {
+            self.linker_arg(&format!("-plugin={}", path.display()));
+        }
+
+        let opt_level = match self.sess.opts.optimize {
+            config::OptLevel::No => "O0",
+            config::OptLevel::Less => "O1",
+            config::OptLevel::Default => "O2",
+            config::OptLevel::Aggressive => "O3",
+            config::OptLevel::Size => "Os",
+            config::OptLevel::SizeMin => "Oz",
+        };
+        self.linker_arg(&format!("-plugin-opt={}", opt_level));
+
+        let target_cpu = match self.sess.opts.cg.target_cpu {
+            Some(ref s) => &s[..],
+            None => &self.sess.target.target.options.cpu[..],
+        };
+        self.linker_arg(&format!("-plugin-opt=mcpu={}", target_cpu));
+    }
+
     fn finalize(&mut self) -> Command {
         self.hint_dynamic(); // Reset to default before returning the composed command line.
         let mut cmd = Command::new("");
@@ -538,6 +585,18 @@ impl<'a> Linker for MsvcLinker<'a> {
         }
     }
 
+    fn panic_strategy(&mut self, _strategy: PanicStrategy) {
+        // noop, the runtime is linked like any other crate
+    }
+
+    fn pgo_gen(&mut self) {
+        self.cmd.arg("/INCLUDE:__llvm_profile_runtime");
+    }
+
+    fn linker_plugin_lto(&mut self) {
+        // Do nothing for now, lld-link picks up bitcode objects on its own.
+    }
+
     fn finalize(&mut self) -> Command {
         let mut cmd = Command::new("");
         ::std::mem::swap(&mut cmd, &mut self.cmd);
@@ -676,7 +735,157 @@ impl<'a> Linker for EmLinker<'a> {
         // noop
     }
 
+    fn panic_strategy(&mut self, strategy: PanicStrategy) {
+        // emcc implements unwinding with JS exceptions, which are expensive
+        // enough that we turn off catching them when we'll never unwind.
+        self.cmd.arg("-s");
+        self.cmd.arg(if strategy == PanicStrategy::Abort {
+            "DISABLE_EXCEPTION_CATCHING=1"
+        } else {
+            "DISABLE_EXCEPTION_CATCHING=0"
+        });
+    }
+
+    fn pgo_gen(&mut self) {
+        // noop, emscripten has no profiling runtime
+    }
+
+    fn linker_plugin_lto(&mut self) {
+        // noop, emcc already links bitcode objects
+    }
+
+    fn finalize(&mut self) -> Command {
+        let mut cmd = Command::new("");
+        ::std::mem::swap(&mut cmd, &mut self.cmd);
+        cmd
+    }
+}
+
+pub struct WasmLd<'a> {
+    cmd: Command,
+    sess: &'a Session,
+    info: &'a LinkerInfo,
+}
+
+impl<'a> Linker for WasmLd<'a> {
+    fn link_dylib(&mut self, lib: &str) {
+        self.cmd.arg("-l").arg(lib);
+    }
+
+    fn link_staticlib(&mut self, lib: &str) {
+        self.cmd.arg("-l").arg(lib);
+    }
+
+    fn link_rlib(&mut self, lib: &Path) {
+        self.cmd.arg(lib);
+    }
+
+    fn include_path(&mut self, path: &Path) {
+        self.cmd.arg("-L").arg(path);
+    }
+
+    fn framework_path(&mut self, _path: &Path) {
+        bug!("frameworks are not supported on wasm")
+    }
+
+    fn output_filename(&mut self, path: &Path) {
+        self.cmd.arg("-o").arg(path);
+    }
+
+    fn add_object(&mut self, path: &Path) {
+        self.cmd.arg(path);
+    }
+
+    fn position_independent_executable(&mut self) {
+        // noop
+    }
+
+    fn link_rust_dylib(&mut self, lib: &str, _path: &Path) {
+        self.cmd.arg("-l").arg(lib);
+    }
+
+    fn link_framework(&mut self, _framework: &str) {
+        bug!("frameworks are not supported on wasm")
+    }
+
+    fn link_whole_staticlib(&mut self, lib: &str, _search_path: &[PathBuf]) {
+        self.cmd.arg("-l").arg(lib);
+    }
+
+    fn link_whole_rlib(&mut self, lib: &Path) {
+        self.cmd.arg(lib);
+    }
+
+    fn gc_sections(&mut self, _keep_metadata: bool) {
+        self.cmd.arg("--gc-sections");
+    }
+
+    fn optimize(&mut self) {
+        self.cmd.arg(match self.sess.opts.optimize {
+            OptLevel::No => "-O0",
+            OptLevel::Less => "-O1",
+            OptLevel::Default => "-O2",
+            OptLevel::Aggressive => "-O3",
+            // Currently LLD doesn't support `Os` and `Oz`, so pass through `O2`
+            // instead.
+            OptLevel::Size => "-O2",
+            OptLevel::SizeMin => "-O2"
+        });
+    }
+
+    fn debuginfo(&mut self) {
+        // There's no debuginfo support for wasm in LLD yet, so don't leave
+        // the custom sections of the objects behind in the module.
+        self.cmd.arg("--strip-debug");
+    }
+
+    fn no_default_libraries(&mut self) {
+        // noop, there are no default libraries to begin with
+    }
+
+    fn build_dylib(&mut self, _out_filename: &Path) {
+        // noop, see `export_symbols` for how a cdylib is produced
+    }
+
+    fn args(&mut self, args: &[String]) {
+        self.cmd.args(args);
+    }
+
+    fn export_symbols(&mut self, _tmpdir: &Path, crate_type: CrateType) {
+        // A cdylib is a module which is only called into through its
+        // exports, whereas an executable starts running at `main`.
+        if crate_type == CrateType::CrateTypeCdylib {
+            self.cmd.arg("--no-entry");
+        } else {
+            self.cmd.arg("--entry=main");
+        }
+
+        for sym in self.info.exports[&crate_type].iter() {
+            self.cmd.arg("--export").arg(&sym);
+        }
+    }
+
+    fn subsystem(&mut self, _subsystem: &str) {
+        // noop
+    }
+
+    fn panic_strategy(&mut self, _strategy: PanicStrategy) {
+        // noop, the target only supports `abort`
+    }
+
+    fn pgo_gen(&mut self) {
+        // noop, there's no profiling runtime for wasm
+    }
+
+    fn linker_plugin_lto(&mut self) {
+        // noop, LLD's wasm port has no plugin interface
+    }
+
     fn finalize(&mut self) -> Command {
+        // Functions imported from the embedder show up as undefined symbols,
+        // they're resolved when the module is instantiated instead.
+        self.cmd.arg("--allow-undefined");
+
         let mut cmd = Command::new("");
         ::std::mem::swap(&mut cmd, &mut self.cmd);
         cmd
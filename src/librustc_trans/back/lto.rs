@@ -12,21 +12,24 @@ use back::link;
 use back::write;
 use back::symbol_export;
 use rustc::session::config;
+use rustc::session::Session;
 use errors::FatalError;
 use llvm;
 use llvm::archive_ro::ArchiveRO;
 use llvm::{ModuleRef, TargetMachineRef, True, False};
 use rustc::util::common::time;
 use rustc::util::common::path2cstr;
-use rustc::hir::def_id::LOCAL_CRATE;
+use rustc::hir::def_id::{CrateNum, LOCAL_CRATE};
 use back::write::{ModuleConfig, with_llvm_pmb, CodegenContext};
 
 use libc;
 use flate2::read::ZlibDecoder;
 
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::io::Read;
 use std::ffi::CString;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub fn crate_type_allows_lto(crate_type: config::CrateType) -> bool {
     match crate_type {
@@ -40,6 +43,40 @@ pub fn crate_type_allows_lto(crate_type: config::CrateType) -> bool {
     }
 }
 
+/// The rlibs whose bitcode LTO links into the crate.
+pub fn linked_rlibs(sess: &Session) -> Vec<(CrateNum, PathBuf)> {
+    let mut rlibs = Vec::new();
+    drop(link::each_linked_rlib(sess, &mut |cnum, path| {
+        if link::ignored_for_lto(sess, cnum) {
+            return
+        }
+        rlibs.push((cnum, path.to_path_buf()));
+    }));
+    rlibs
+}
+
+/// Hashes the bitcode of the `linked_rlibs`. The object of a module after
+/// LTO depends on it as much as on the module itself, while a change of an
+/// upstream function body need not make the module dirty.
+pub fn linked_bitcode_hash(sess: &Session) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for (_, path) in linked_rlibs(sess) {
+        let archive = match ArchiveRO::open(&path) {
+            Some(archive) => archive,
+            None => sess.fatal(&format!("failed to read rlib for LTO: '{}'", path.display())),
+        };
+        for child in archive.iter().filter_map(|child| child.ok()) {
+            if let Some(name) = child.name() {
+                if name.ends_with("bytecode.deflate") {
+                    name.hash(&mut hasher);
+                    child.data().hash(&mut hasher);
+                }
+            }
+        }
+    }
+    hasher.finish()
+}
+
 pub fn run(cgcx: &CodegenContext,
            llmod: ModuleRef,
            tm: TargetMachineRef,
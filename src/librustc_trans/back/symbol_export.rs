@@ -71,6 +71,15 @@ impl ExportedSymbols {
                               SymbolExportLevel::Rust));
         }
 
+        // The profiling runtime looks these up by name when writing out the
+        // collected data, so they must survive symbol internalization.
+        if scx.sess().opts.cg.profile_generate.is_some() {
+            local_crate.push(("__llvm_profile_raw_version".to_string(),
+                              SymbolExportLevel::C));
+            local_crate.push(("__llvm_profile_filename".to_string(),
+                              SymbolExportLevel::C));
+        }
+
         let mut exports = FxHashMap();
         exports.insert(LOCAL_CRATE, local_crate);
 
@@ -97,6 +97,7 @@
 //! virtually impossible. Thus, symbol hash generation exclusively relies on
 //! DefPaths which are much more robust in the face of changes to the code base.
 
+use back::symbol_names_v0;
 use monomorphize::Instance;
 
 use rustc::middle::weak_lang_items;
@@ -107,6 +108,7 @@ use rustc::ty::fold::TypeVisitor;
 use rustc::ty::item_path::{self, ItemPathBuffer, RootMode};
 use rustc::ty::maps::Providers;
 use rustc::ty::subst::Substs;
+use rustc::session::config::SymbolManglingVersion;
 use rustc::hir::map::definitions::DefPathData;
 use rustc::util::common::record_time;
 
@@ -142,6 +144,9 @@ fn get_symbol_hash<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
 
     let mut hasher = ty::util::TypeIdHasher::<u64>::new(tcx);
 
+    let perf_stats = &tcx.sess.perf_stats;
+    perf_stats.symbol_hash_count.set(perf_stats.symbol_hash_count.get() + 1);
+
     record_time(&tcx.sess.perf_stats.symbol_hash_time, || {
         // the main symbol name is not necessarily unique; hash in the
         // compiler's internal def-path, guaranteeing each symbol has a
@@ -255,6 +260,10 @@ fn compute_symbol_name<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, instance: Instance
         return tcx.item_name(def_id).as_str().to_string();
     }
 
+    if tcx.sess.opts.debugging_opts.symbol_mangling_version == SymbolManglingVersion::V0 {
+        return symbol_names_v0::compute_symbol_name(tcx, instance);
+    }
+
     // We want to compute the "type" of this item. Unfortunately, some
     // kinds of items (e.g., closures) don't have an entry in the
     // item-type array. So walk back up the find the closest parent
@@ -0,0 +1,292 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The `v0` symbol mangling scheme, selected with
+//! `-Z symbol-mangling-version=v0`.
+//!
+//! Instead of hashing everything that does not show up in an item's plain
+//! path (see the module documentation of `symbol_names`), this scheme
+//! encodes that information directly: DefPath disambiguators, impl self
+//! types and trait references, and the generic arguments of the instance.
+//! The result is unique without a hash and can be demangled back into a
+//! Rust path. The grammar is documented in `rustc_back::demangle`, which
+//! also provides the low-level encoding functions used here.
+//!
+//! The only hash left is the one identifying a crate, which is derived from
+//! the crate disambiguator (i.e. `-C metadata`), so that two versions of the
+//! same crate can still be linked together.
+
+use monomorphize::Instance;
+
+use rustc::hir;
+use rustc::hir::def_id::{CrateNum, DefId, LOCAL_CRATE};
+use rustc::hir::map::definitions::DefPathData;
+use rustc::ty::{self, Ty, TyCtxt};
+use rustc::ty::subst::Kind;
+use rustc_back::demangle::{push_disambiguator, push_ident, push_integer_62};
+
+use syntax::abi::Abi;
+use syntax::ast::{IntTy, UintTy, FloatTy};
+
+pub fn compute_symbol_name<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     instance: Instance<'tcx>)
+                                     -> String {
+    let def_id = instance.def_id();
+    let substs = instance.substs;
+
+    let mut mangler = SymbolMangler {
+        tcx,
+        out: String::from("_R"),
+    };
+    mangler.print_def_path(def_id, substs);
+
+    // Mirror the legacy scheme: instances of generic functions may be
+    // emitted in several crates of the same project, so we qualify them
+    // with the crate doing the instantiation.
+    if substs.types().next().is_some() {
+        mangler.print_crate_root(LOCAL_CRATE);
+    }
+
+    mangler.out
+}
+
+struct SymbolMangler<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    out: String,
+}
+
+impl<'a, 'tcx> SymbolMangler<'a, 'tcx> {
+    fn print_def_path(&mut self, def_id: DefId, substs: &[Kind<'tcx>]) {
+        // Lifetimes are erased by the time we translate, so only types can
+        // tell two instances apart.
+        if substs.iter().any(|k| k.as_type().is_some()) {
+            self.out.push('I');
+            self.print_path(def_id);
+            for ty in substs.iter().filter_map(|k| k.as_type()) {
+                self.print_type(ty);
+            }
+            self.out.push('E');
+        } else {
+            self.print_path(def_id);
+        }
+    }
+
+    fn print_path(&mut self, def_id: DefId) {
+        let key = self.tcx.def_key(def_id);
+        let parent = key.parent.map(|index| DefId { krate: def_id.krate, index });
+        let disambiguator = key.disambiguated_data.disambiguator as u64;
+
+        let (namespace, name) = match key.disambiguated_data.data {
+            DefPathData::CrateRoot => return self.print_crate_root(def_id.krate),
+            DefPathData::Impl => return self.print_impl_path(def_id, parent.unwrap(),
+                                                              disambiguator),
+
+            DefPathData::TypeNs(name) |
+            DefPathData::Module(name) |
+            DefPathData::MacroDef(name) |
+            DefPathData::TypeParam(name) |
+            DefPathData::LifetimeDef(name) |
+            DefPathData::EnumVariant(name) |
+            DefPathData::GlobalMetaData(name) => ('t', name.as_str().to_string()),
+
+            DefPathData::ValueNs(name) |
+            DefPathData::Field(name) |
+            DefPathData::Binding(name) => ('v', name.as_str().to_string()),
+
+            DefPathData::ClosureExpr => ('C', String::new()),
+            DefPathData::StructCtor => ('K', String::new()),
+            DefPathData::Initializer => ('A', String::new()),
+
+            DefPathData::Misc => ('X', "misc".to_string()),
+            DefPathData::ImplTrait => ('X', "impl_trait".to_string()),
+            DefPathData::Typeof => ('X', "typeof".to_string()),
+        };
+
+        self.out.push('N');
+        self.out.push(namespace);
+        self.print_path(parent.unwrap());
+        push_disambiguator(disambiguator, &mut self.out);
+        push_ident(&name, &mut self.out);
+    }
+
+    fn print_crate_root(&mut self, cnum: CrateNum) {
+        self.out.push('C');
+
+        // The crate disambiguator is a hex-encoded hash, the first 64 bits
+        // of which are plenty to tell apart different versions of a crate.
+        let disambiguator = self.tcx.crate_disambiguator(cnum).as_str();
+        let hash = if disambiguator.len() >= 16 {
+            u64::from_str_radix(&disambiguator[..16], 16).ok()
+        } else {
+            None
+        };
+        let hash = hash.unwrap_or_else(|| {
+            bug!("malformed crate disambiguator `{}`", disambiguator)
+        });
+        push_disambiguator(hash, &mut self.out);
+
+        let name = self.tcx.original_crate_name(cnum).as_str();
+        push_ident(&name, &mut self.out);
+    }
+
+    fn print_impl_path(&mut self, impl_def_id: DefId, parent: DefId, disambiguator: u64) {
+        let self_ty = self.tcx.type_of(impl_def_id);
+        match self.tcx.impl_trait_ref(impl_def_id) {
+            Some(trait_ref) => {
+                self.out.push('X');
+                push_disambiguator(disambiguator, &mut self.out);
+                self.print_path(parent);
+                self.print_type(self_ty);
+                self.print_def_path(trait_ref.def_id, &trait_ref.substs[1..]);
+            }
+            None => {
+                self.out.push('M');
+                push_disambiguator(disambiguator, &mut self.out);
+                self.print_path(parent);
+                self.print_type(self_ty);
+            }
+        }
+    }
+
+    fn print_type(&mut self, ty: Ty<'tcx>) {
+        let basic = match ty.sty {
+            ty::TyBool => "b",
+            ty::TyChar => "c",
+            ty::TyStr => "e",
+            ty::TyNever => "z",
+            ty::TyTuple(tys, _) if tys.is_empty() => "u",
+            ty::TyInt(IntTy::I8) => "a",
+            ty::TyInt(IntTy::I16) => "s",
+            ty::TyInt(IntTy::I32) => "l",
+            ty::TyInt(IntTy::I64) => "x",
+            ty::TyInt(IntTy::I128) => "n",
+            ty::TyInt(IntTy::Is) => "i",
+            ty::TyUint(UintTy::U8) => "h",
+            ty::TyUint(UintTy::U16) => "t",
+            ty::TyUint(UintTy::U32) => "m",
+            ty::TyUint(UintTy::U64) => "y",
+            ty::TyUint(UintTy::U128) => "o",
+            ty::TyUint(UintTy::Us) => "j",
+            ty::TyFloat(FloatTy::F32) => "f",
+            ty::TyFloat(FloatTy::F64) => "d",
+            _ => "",
+        };
+        if !basic.is_empty() {
+            self.out.push_str(basic);
+            return;
+        }
+
+        match ty.sty {
+            ty::TyRef(_, mt) => {
+                self.out.push(match mt.mutbl {
+                    hir::MutImmutable => 'R',
+                    hir::MutMutable => 'Q',
+                });
+                self.print_type(mt.ty);
+            }
+            ty::TyRawPtr(mt) => {
+                self.out.push(match mt.mutbl {
+                    hir::MutImmutable => 'P',
+                    hir::MutMutable => 'O',
+                });
+                self.print_type(mt.ty);
+            }
+            ty::TyArray(ty, len) => {
+                self.out.push('A');
+                self.print_type(ty);
+                push_integer_62(len as u64, &mut self.out);
+            }
+            ty::TySlice(ty) => {
+                self.out.push('S');
+                self.print_type(ty);
+            }
+            ty::TyTuple(tys, _) => {
+                self.out.push('T');
+                for &ty in tys.iter() {
+                    self.print_type(ty);
+                }
+                self.out.push('E');
+            }
+            ty::TyAdt(def, substs) => self.print_def_path(def.did, substs),
+            ty::TyFnDef(def_id, substs) => self.print_def_path(def_id, substs),
+            ty::TyClosure(def_id, closure_substs) => {
+                // Only the generics of the enclosing item identify a
+                // closure, the remaining substs are its upvar types.
+                let generics = self.tcx.generics_of(def_id);
+                let parent_types = generics.parent_types as usize;
+                let types: Vec<Kind<'tcx>> = closure_substs.substs
+                                                          .types()
+                                                          .take(parent_types)
+                                                          .map(Kind::from)
+                                                          .collect();
+                self.print_def_path(def_id, &types);
+            }
+            ty::TyFnPtr(sig) => {
+                let sig = sig.skip_binder();
+                self.out.push('F');
+                if sig.unsafety == hir::Unsafety::Unsafe {
+                    self.out.push('U');
+                }
+                if sig.abi != Abi::Rust {
+                    self.out.push('K');
+                    push_ident(&sig.abi.name().replace('-', "_"), &mut self.out);
+                }
+                if sig.variadic {
+                    self.out.push('V');
+                }
+                for &ty in sig.inputs() {
+                    self.print_type(ty);
+                }
+                self.out.push('E');
+                self.print_type(sig.output());
+            }
+            ty::TyDynamic(ref predicates, _) => {
+                self.out.push('D');
+                for predicate in predicates.skip_binder().iter() {
+                    match *predicate {
+                        ty::ExistentialPredicate::Trait(ref trait_ref) => {
+                            self.print_def_path(trait_ref.def_id, trait_ref.substs);
+                        }
+                        ty::ExistentialPredicate::Projection(ref projection) => {
+                            self.out.push('p');
+                            push_ident(&projection.item_name.as_str(), &mut self.out);
+                            self.print_type(projection.ty);
+                        }
+                        ty::ExistentialPredicate::AutoTrait(def_id) => {
+                            self.print_path(def_id);
+                        }
+                    }
+                }
+                self.out.push('E');
+            }
+            ty::TyProjection(ref data) => {
+                // Only reachable through the (unsubstituted) self type of
+                // an impl, e.g. `impl<T: Iterator> Foo<T::Item> { .. }`.
+                let trait_ref = data.trait_ref;
+                self.out.push('Y');
+                self.print_type(trait_ref.self_ty());
+                self.print_def_path(trait_ref.def_id, &trait_ref.substs[1..]);
+                let name = self.tcx.associated_item(data.item_def_id).name;
+                push_ident(&name.as_str(), &mut self.out);
+            }
+            ty::TyParam(ref p) => {
+                self.out.push('p');
+                push_integer_62(p.idx as u64, &mut self.out);
+            }
+
+            ty::TyBool | ty::TyChar | ty::TyStr | ty::TyNever |
+            ty::TyInt(_) | ty::TyUint(_) | ty::TyFloat(_) => unreachable!(),
+
+            ty::TyAnon(..) | ty::TyInfer(_) | ty::TyError => {
+                bug!("symbol_names_v0: unexpected type `{:?}`", ty)
+            }
+        }
+    }
+}
@@ -9,7 +9,7 @@
 // except according to those terms.
 
 use back::lto;
-use back::link::{self, get_linker, remove};
+use back::link::{get_linker, remove};
 use back::symbol_export::ExportedSymbols;
 use rustc_incremental::{save_trans_partition, in_incr_comp_dir};
 use rustc::session::config::{self, OutputFilenames, OutputType, OutputTypes, Passes, SomePasses,
@@ -20,23 +20,26 @@ use llvm::{ModuleRef, TargetMachineRef, PassManagerRef, DiagnosticInfoRef, Conte
 use llvm::SMDiagnosticRef;
 use {CrateTranslation, ModuleLlvm, ModuleSource, ModuleTranslation};
 use rustc::hir::def_id::CrateNum;
-use rustc::util::common::{time, time_depth, set_time_depth, path2cstr};
+use rustc::util::common::{time, time_depth, set_time_depth, path2cstr, record_time};
 use rustc::util::fs::link_or_copy;
 use errors::{self, Handler, Level, DiagnosticBuilder, FatalError};
 use errors::emitter::Emitter;
 use syntax::ext::hygiene::Mark;
 use syntax_pos::MultiSpan;
+use common::{C_bytes_in_context, val_ty};
 use context::{is_pie_binary, get_reloc_model};
 use jobserver::{Client, Acquired};
 use crossbeam::{scope, Scope};
+use rustc_back::demangle;
 use rustc_demangle;
 
 use std::cmp;
 use std::ffi::CString;
 use std::fs;
 use std::io;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::ptr;
 use std::str;
 use std::sync::mpsc::{channel, Sender};
 use std::slice;
@@ -220,10 +223,17 @@ pub struct ModuleConfig {
     vectorize_slp: bool,
     merge_functions: bool,
     inline_threshold: Option<usize>,
+    // Directory into which instrumented code writes its profiling data,
+    // and the `.profdata` file to read profiling data back from.
+    pgo_gen: Option<String>,
+    pgo_use: Option<String>,
     // Instead of creating an object file by doing LLVM codegen, just
     // make the object file bitcode. Provides easy compatibility with
     // emscripten's ecc compiler, when used as the linker.
     obj_is_bitcode: bool,
+    // Embed the optimized bitcode of the module in a section of the object
+    // file, next to the machine code.
+    embed_bitcode: bool,
 }
 
 unsafe impl Send for ModuleConfig { }
@@ -243,6 +253,7 @@ impl ModuleConfig {
             emit_asm: false,
             emit_obj: false,
             obj_is_bitcode: false,
+            embed_bitcode: false,
 
             no_verify: false,
             no_prepopulate_passes: false,
@@ -251,7 +262,9 @@ impl ModuleConfig {
             vectorize_loop: false,
             vectorize_slp: false,
             merge_functions: false,
-            inline_threshold: None
+            inline_threshold: None,
+            pgo_gen: None,
+            pgo_use: None,
         }
     }
 
@@ -261,7 +274,8 @@ impl ModuleConfig {
         self.no_builtins = trans.no_builtins;
         self.time_passes = sess.time_passes();
         self.inline_threshold = sess.opts.cg.inline_threshold;
-        self.obj_is_bitcode = sess.target.target.options.obj_is_bitcode;
+        self.obj_is_bitcode = sess.target.target.options.obj_is_bitcode ||
+                              sess.opts.debugging_opts.linker_plugin_lto.enabled();
 
         // Copy what clang does by turning on loop vectorization at O2 and
         // slp vectorization at O3. Otherwise configure other optimization aspects
@@ -503,6 +517,10 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
     let bc_out = output_names.temp_path(OutputType::Bitcode, module_name);
     let obj_out = output_names.temp_path(OutputType::Object, module_name);
 
+    if config.embed_bitcode && (write_obj || config.emit_asm) {
+        embed_bitcode(cgcx, llcx, llmod);
+    }
+
     if write_bc {
         let bc_out_c = path2cstr(&bc_out);
         llvm::LLVMWriteBitcodeToFile(llmod, bc_out_c.as_ptr());
@@ -532,12 +550,16 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
                 };
                 let mut cursor = io::Cursor::new(output);
 
-                let demangled = match rustc_demangle::try_demangle(input) {
-                    Ok(d) => d,
-                    Err(_) => return 0,
+                let result = if let Some(demangled) = demangle::demangle(input) {
+                    write!(cursor, "{}", demangled)
+                } else {
+                    match rustc_demangle::try_demangle(input) {
+                        Ok(demangled) => write!(cursor, "{:#}", demangled),
+                        Err(_) => return 0,
+                    }
                 };
 
-                if let Err(_) = write!(cursor, "{:#}", demangled) {
+                if let Err(_) = result {
                     // Possible only if provided buffer is not big enough
                     return 0;
                 }
@@ -601,7 +623,10 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
 
 
 pub fn cleanup_llvm(trans: &CrateTranslation) {
-    for module in trans.modules.iter() {
+    let modules = trans.modules.iter()
+                               .chain(Some(&trans.metadata_module))
+                               .chain(trans.allocator_module.as_ref());
+    for module in modules {
         unsafe {
             match module.source {
                 ModuleSource::Translated(llvm) => {
@@ -666,6 +691,12 @@ pub fn run_passes(sess: &Session,
         modules_config.passes.push("insert-gcov-profiling".to_owned())
     }
 
+    if let Some(ref path) = sess.opts.cg.profile_use {
+        check_profile_data(sess, Path::new(path));
+    }
+    modules_config.pgo_gen = sess.opts.cg.profile_generate.clone();
+    modules_config.pgo_use = sess.opts.cg.profile_use.clone();
+
     modules_config.opt_level = Some(get_llvm_opt_level(sess.opts.optimize));
     modules_config.opt_size = Some(get_llvm_opt_size(sess.opts.optimize));
 
@@ -713,11 +744,16 @@ pub fn run_passes(sess: &Session,
             },
             OutputType::Mir => {}
             OutputType::DepInfo => {}
+            OutputType::ApiSummary => {}
         }
     }
 
     modules_config.set_flags(sess, trans);
     metadata_config.set_flags(sess, trans);
+
+    // Only the translated code is interesting for LTO; the metadata and
+    // allocator modules are never embedded.
+    modules_config.embed_bitcode = sess.opts.debugging_opts.embed_bitcode;
     allocator_config.set_flags(sess, trans);
 
 
@@ -750,6 +786,7 @@ pub fn run_passes(sess: &Session,
         work_items.push(work);
     }
 
+    record_module_reuse(sess, &trans);
     if sess.opts.debugging_opts.incremental_info {
         dump_incremental_data(&trans);
     }
@@ -779,7 +816,9 @@ pub fn run_passes(sess: &Session,
             files.push((OutputType::Bitcode, path));
         }
 
-        save_trans_partition(sess, &mtrans.name, mtrans.symbol_name_hash, &files);
+        record_time(&sess.incr_comp_stats.save_time, || {
+            save_trans_partition(sess, &mtrans.name, mtrans.input_hash, &files)
+        });
     }
 
     // All codegen is finished.
@@ -860,7 +899,8 @@ pub fn run_passes(sess: &Session,
             OutputType::Mir |
             OutputType::Metadata |
             OutputType::Exe |
-            OutputType::DepInfo => {}
+            OutputType::DepInfo |
+            OutputType::ApiSummary => {}
         }
     }
     let user_wants_bitcode = user_wants_bitcode;
@@ -940,6 +980,107 @@ pub fn run_passes(sess: &Session,
     }
 }
 
+/// Embeds the bitcode of `llmod` into a section of the module itself, in
+/// the same format as clang's `-fembed-bitcode`, so that the object file can
+/// later take part in (cross-language) LTO.
+unsafe fn embed_bitcode(cgcx: &CodegenContext, llcx: ContextRef, llmod: ModuleRef) {
+    let buffer = llvm::LLVMWriteBitcodeToMemoryBuffer(llmod);
+    let bitcode = slice::from_raw_parts(llvm::LLVMGetBufferStart(buffer) as *const u8,
+                                        llvm::LLVMGetBufferSize(buffer) as usize);
+    let llconst = C_bytes_in_context(llcx, bitcode);
+    llvm::LLVMDisposeMemoryBuffer(buffer);
+
+    let is_apple = cgcx.opts.target_triple.contains("-ios") ||
+                   cgcx.opts.target_triple.contains("-darwin");
+
+    let llglobal = llvm::LLVMAddGlobal(llmod,
+                                       val_ty(llconst).to_ref(),
+                                       "rustc.embedded.module\0".as_ptr() as *const _);
+    llvm::LLVMSetInitializer(llglobal, llconst);
+    let section = if is_apple { "__LLVM,__bitcode\0" } else { ".llvmbc\0" };
+    llvm::LLVMSetSection(llglobal, section.as_ptr() as *const _);
+    llvm::LLVMRustSetLinkage(llglobal, llvm::Linkage::PrivateLinkage);
+    llvm::LLVMSetGlobalConstant(llglobal, llvm::True);
+
+    // Linkers expect the (here empty) command line section to be present
+    // whenever there is an embedded module.
+    let llconst = C_bytes_in_context(llcx, &[]);
+    let llglobal = llvm::LLVMAddGlobal(llmod,
+                                       val_ty(llconst).to_ref(),
+                                       "rustc.embedded.cmdline\0".as_ptr() as *const _);
+    llvm::LLVMSetInitializer(llglobal, llconst);
+    let section = if is_apple { "__LLVM,__cmdline\0" } else { ".llvmcmd\0" };
+    llvm::LLVMSetSection(llglobal, section.as_ptr() as *const _);
+    llvm::LLVMRustSetLinkage(llglobal, llvm::Linkage::PrivateLinkage);
+}
+
+/// Magic number at the start of an indexed profile (`.profdata`) file, as
+/// produced by `llvm-profdata merge`.
+const INDEXED_PROF_MAGIC: u64 = 0xff6c70726f667281;
+
+/// The newest indexed profile format version understood by the LLVM we
+/// link against. Profiles written by a newer `llvm-profdata` cannot be read.
+const INDEXED_PROF_MAX_VERSION: u64 = 4;
+
+/// Sanity-checks the file given to `-C profile-use` before handing it to
+/// LLVM, which would otherwise silently ignore an unreadable profile.
+fn check_profile_data(sess: &Session, path: &Path) {
+    let mut data = Vec::new();
+    match fs::File::open(path).and_then(|mut f| f.read_to_end(&mut data)) {
+        Ok(_) => {}
+        Err(err) => {
+            sess.fatal(&format!("failed to read profile data file `{}`: {}",
+                                path.display(), err))
+        }
+    }
+
+    let read_u64 = |pos: usize| -> Option<u64> {
+        data.get(pos..pos + 8).map(|bytes| {
+            bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u64)
+        })
+    };
+
+    match (read_u64(0), read_u64(8)) {
+        (Some(INDEXED_PROF_MAGIC), Some(version)) => {
+            // The upper bits of the version field are used as flags (e.g.
+            // for IR-level instrumentation), only the low word is the
+            // actual format version.
+            let version = version & 0xffff_ffff;
+            if version > INDEXED_PROF_MAX_VERSION {
+                let mut err = sess.struct_err(&format!(
+                    "profile data file `{}` uses format version {}, but this \
+                     compiler only supports versions up to {}",
+                    path.display(), version, INDEXED_PROF_MAX_VERSION));
+                err.note("the profile was probably merged by an `llvm-profdata` \
+                          newer than the LLVM used by rustc");
+                err.emit();
+                sess.abort_if_errors();
+            }
+        }
+        _ => {
+            let mut err = sess.struct_err(&format!(
+                "file `{}` is not an indexed profile data file", path.display()));
+            err.help("raw `.profraw` files must first be merged with \
+                      `llvm-profdata merge -o FILE.profdata`");
+            err.emit();
+            sess.abort_if_errors();
+        }
+    }
+}
+
+fn record_module_reuse(sess: &Session, trans: &CrateTranslation) {
+    let reused = trans.modules.iter().filter(|mtrans| {
+        match mtrans.source {
+            ModuleSource::Preexisting(..) => true,
+            ModuleSource::Translated(..) => false,
+        }
+    }).count() as u64;
+    let stats = &sess.incr_comp_stats;
+    stats.modules_reused.set(stats.modules_reused.get() + reused);
+    stats.modules_translated.set(stats.modules_translated.get() +
+                                 trans.modules.len() as u64 - reused);
+}
+
 fn dump_incremental_data(trans: &CrateTranslation) {
     let mut reuse = 0;
     for mtrans in trans.modules.iter() {
@@ -1218,13 +1359,7 @@ fn spawn_work<'a>(sess: &'a Session,
     let depth = time_depth();
     let lto = sess.lto();
     let crate_types = sess.crate_types.borrow().clone();
-    let mut each_linked_rlib_for_lto = Vec::new();
-    drop(link::each_linked_rlib(sess, &mut |cnum, path| {
-        if link::ignored_for_lto(sess, cnum) {
-            return
-        }
-        each_linked_rlib_for_lto.push((cnum, path.to_path_buf()));
-    }));
+    let each_linked_rlib_for_lto = lto::linked_rlibs(sess);
     let time_passes = sess.time_passes();
     let no_landing_pads = sess.no_landing_pads();
     let opts = &sess.opts;
@@ -1331,10 +1466,21 @@ pub unsafe fn with_llvm_pmb(llmod: ModuleRef,
     let opt_size = config.opt_size.unwrap_or(llvm::CodeGenOptSizeNone);
     let inline_threshold = config.inline_threshold;
 
+    // Like clang's `-fprofile-generate=DIR`, have each instrumented binary
+    // write a `default_<signature>.profraw` file into the given directory.
+    let pgo_gen_path = config.pgo_gen.as_ref().map(|dir| {
+        path2cstr(&Path::new(dir).join("default_%m.profraw"))
+    });
+    let pgo_use_path = config.pgo_use.as_ref().map(|file| path2cstr(Path::new(file)));
+
     llvm::LLVMRustConfigurePassManagerBuilder(builder, opt_level,
                                               config.merge_functions,
                                               config.vectorize_slp,
-                                              config.vectorize_loop);
+                                              config.vectorize_loop,
+                                              pgo_gen_path.as_ref().map_or(ptr::null(),
+                                                                           |s| s.as_ptr()),
+                                              pgo_use_path.as_ref().map_or(ptr::null(),
+                                                                           |s| s.as_ptr()));
     llvm::LLVMPassManagerBuilderSetSizeLevel(builder, opt_size as u32);
 
     if opt_size != llvm::CodeGenOptSizeNone {
@@ -30,6 +30,7 @@ use super::ModuleTranslation;
 
 use assert_module_sources;
 use back::link;
+use back::lto;
 use back::linker::LinkerInfo;
 use back::symbol_export::{self, ExportedSymbols};
 use llvm::{ContextRef, Linkage, ModuleRef, ValueRef, Vector, get_param};
@@ -37,7 +38,7 @@ use llvm;
 use metadata;
 use rustc::hir::def_id::LOCAL_CRATE;
 use rustc::middle::lang_items::StartFnLangItem;
-use rustc::middle::cstore::EncodedMetadata;
+use rustc::middle::cstore::{EncodedMetadata, MetadataCodec};
 use rustc::ty::{self, Ty, TyCtxt};
 use rustc::dep_graph::AssertDepGraphSafe;
 use rustc::middle::cstore::LinkMeta;
@@ -75,7 +76,9 @@ use value::Value;
 use rustc::util::nodemap::{NodeSet, FxHashMap, FxHashSet};
 
 use libc::c_uint;
+use std::collections::hash_map::DefaultHasher;
 use std::ffi::{CStr, CString};
+use std::hash::{Hash, Hasher};
 use std::str;
 use std::i32;
 use syntax_pos::Span;
@@ -769,9 +772,24 @@ fn write_metadata<'a, 'gcx>(tcx: TyCtxt<'a, 'gcx, 'gcx>,
     }
 
     assert!(kind == MetadataKind::Compressed);
+    let level = match tcx.sess.opts.debugging_opts.metadata_compression {
+        Some(config::MetadataCompression::None) => None,
+        Some(config::MetadataCompression::Fast) => Some(Compression::Fast),
+        Some(config::MetadataCompression::Best) => Some(Compression::Best),
+        None => Some(Compression::Default),
+    };
     let mut compressed = cstore.metadata_encoding_version().to_vec();
-    ZlibEncoder::new(&mut compressed, Compression::Default)
-        .write_all(&metadata.raw_data).unwrap();
+    match level {
+        Some(level) => {
+            compressed.push(MetadataCodec::Zlib as u8);
+            ZlibEncoder::new(&mut compressed, level)
+                .write_all(&metadata.raw_data).unwrap();
+        }
+        None => {
+            compressed.push(MetadataCodec::Uncompressed as u8);
+            compressed.extend_from_slice(&metadata.raw_data);
+        }
+    }
 
     let llmeta = C_bytes_in_context(metadata_llcx, &compressed);
     let llconst = C_struct_in_context(metadata_llcx, &[llmeta], false);
@@ -1081,7 +1099,7 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
 
     let metadata_module = ModuleTranslation {
         name: link::METADATA_MODULE_NAME.to_string(),
-        symbol_name_hash: 0, // we always rebuild metadata, at least for now
+        input_hash: 0, // we always rebuild metadata, at least for now
         source: ModuleSource::Translated(ModuleLlvm {
             llcx: metadata_llcx,
             llmod: metadata_llmod,
@@ -1141,15 +1159,22 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
 
         let cgu_name = String::from(cgu.name());
         let cgu_id = cgu.work_product_id();
-        let symbol_name_hash = cgu.compute_symbol_name_hash(scx);
+        let mut input_hash = cgu.compute_symbol_name_hash(scx);
+        if scx.sess().lto() && scx.sess().opts.incremental.is_some() {
+            let mut hasher = DefaultHasher::new();
+            input_hash.hash(&mut hasher);
+            lto::linked_bitcode_hash(scx.sess()).hash(&mut hasher);
+            input_hash = hasher.finish();
+        }
 
         // Check whether there is a previous work-product we can
         // re-use.  Not only must the file exist, and the inputs not
         // be dirty, but the hash of the symbols we will generate must
-        // be the same.
+        // be the same. Under LTO, the object also holds the code of the
+        // upstream crates, so their bitcode must be the same too.
         let previous_work_product =
             scx.dep_graph().previous_work_product(&cgu_id).and_then(|work_product| {
-                if work_product.input_hash == symbol_name_hash {
+                if work_product.input_hash == input_hash {
                     debug!("trans_reuse_previous_work_products: reusing {:?}", work_product);
                     Some(work_product)
                 } else {
@@ -1160,7 +1185,7 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                     }
                     debug!("trans_reuse_previous_work_products: \
                             not reusing {:?} because hash changed to {:?}",
-                           work_product, symbol_name_hash);
+                           work_product, input_hash);
                     None
                 }
             });
@@ -1169,7 +1194,7 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             // Don't need to translate this module.
             let module = ModuleTranslation {
                 name: cgu_name,
-                symbol_name_hash,
+                input_hash,
                 source: ModuleSource::Preexisting(buf.clone())
             };
             return (Stats::default(), module);
@@ -1227,7 +1252,7 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
 
             ModuleTranslation {
                 name: cgu_name,
-                symbol_name_hash,
+                input_hash,
                 source: ModuleSource::Translated(ModuleLlvm {
                     llcx: ccx.llcx(),
                     llmod: ccx.llmod(),
@@ -1310,12 +1335,17 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     // links in an object file that has allocator functions. When we're
     // compiling a final LTO artifact, though, there's no need to worry about
     // this as we're not working with this dual "rlib/dylib" functionality.
+    //
+    // A module reused under LTO already has the shim, translated into it
+    // when its object was produced.
     let allocator_module = tcx.sess.allocator_kind.get().and_then(|kind| unsafe {
         if sess.lto() && llvm_modules.len() > 0 {
             time(tcx.sess.time_passes(), "write allocator module", || {
                 allocator::trans(tcx, &llvm_modules[0], kind)
             });
             None
+        } else if sess.lto() && modules.len() > 0 {
+            None
         } else {
             let (llcx, llmod) =
                 context::create_context_and_module(tcx.sess, "allocator");
@@ -1329,7 +1359,7 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
 
             Some(ModuleTranslation {
                 name: link::ALLOCATOR_MODULE_NAME.to_string(),
-                symbol_name_hash: 0, // we always rebuild allocator shims
+                input_hash: 0, // we always rebuild allocator shims
                 source: ModuleSource::Translated(modules),
             })
         }
@@ -1442,7 +1472,11 @@ fn collect_and_partition_translation_items<'a, 'tcx>(scx: &SharedCrateContext<'a
 
     assert_symbols_are_distinct(scx.tcx(), items.iter());
 
-    let strategy = if scx.sess().opts.debugging_opts.incremental.is_some() {
+    // LTO merges the whole crate into one module, which the work product of
+    // its single codegen unit then holds after LTO.
+    let per_module = scx.sess().opts.debugging_opts.incremental.is_some() &&
+                     !scx.sess().lto();
+    let strategy = if per_module {
         PartitioningStrategy::PerModule
     } else {
         PartitioningStrategy::FixedUnitCount(scx.sess().opts.cg.codegen_units)
@@ -1456,7 +1490,7 @@ fn collect_and_partition_translation_items<'a, 'tcx>(scx: &SharedCrateContext<'a
     });
 
     assert!(scx.tcx().sess.opts.cg.codegen_units == codegen_units.len() ||
-            scx.tcx().sess.opts.debugging_opts.incremental.is_some());
+            per_module);
 
     let translation_items: FxHashSet<TransItem<'tcx>> = items.iter().cloned().collect();
 
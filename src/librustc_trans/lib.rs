@@ -78,6 +78,7 @@ pub mod back {
     mod lto;
     pub(crate) mod symbol_export;
     pub(crate) mod symbol_names;
+    mod symbol_names_v0;
     pub mod write;
     pub mod rpath;
 }
@@ -106,6 +107,7 @@ mod cabi_powerpc64;
 mod cabi_s390x;
 mod cabi_sparc;
 mod cabi_sparc64;
+mod cabi_wasm32;
 mod cabi_x86;
 mod cabi_x86_64;
 mod cabi_x86_win64;
@@ -140,7 +142,10 @@ pub struct ModuleTranslation {
     /// something unique to this crate (e.g., a module path) as well
     /// as the crate name and disambiguator.
     pub name: String,
-    pub symbol_name_hash: u64,
+    /// The hash that the work product of the module is saved with, and
+    /// must be found again to reuse it: that of the symbols it defines,
+    /// and under LTO that of the upstream bitcode too.
+    pub input_hash: u64,
     pub source: ModuleSource,
 }
 
@@ -37,6 +37,14 @@ pub fn init(sess: &Session) {
         if POISONED.load(Ordering::SeqCst) {
             bug!("couldn't enable multi-threaded LLVM");
         }
+
+        // Complain before the profiler runtime is looked for, rather than
+        // when the passes are set up.
+        if (sess.opts.cg.profile_generate.is_some() || sess.opts.cg.profile_use.is_some()) &&
+           !llvm::LLVMRustPGOAvailable() {
+            sess.fatal("`-C profile-generate` and `-C profile-use` need a compiler built \
+                        with LLVM 4.0 or later");
+        }
     }
 }
 
@@ -330,12 +330,14 @@ impl<'a, 'gcx, 'tcx> CastCheck<'tcx> {
         } else if self.try_coercion_cast(fcx) {
             self.trivial_cast_lint(fcx);
             debug!(" -> CoercionCast");
-            fcx.tables.borrow_mut().cast_kinds.insert(self.expr.id, CastKind::CoercionCast);
+            let hir_id = fcx.tcx.hir.node_to_hir_id(self.expr.id);
+            fcx.tables.borrow_mut().cast_kinds_mut().insert(hir_id, CastKind::CoercionCast);
         } else {
             match self.do_check(fcx) {
                 Ok(k) => {
                     debug!(" -> {:?}", k);
-                    fcx.tables.borrow_mut().cast_kinds.insert(self.expr.id, k);
+                    let hir_id = fcx.tcx.hir.node_to_hir_id(self.expr.id);
+                    fcx.tables.borrow_mut().cast_kinds_mut().insert(hir_id, k);
                 }
                 Err(e) => self.report_cast_error(fcx, e),
             };
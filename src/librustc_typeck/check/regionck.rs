@@ -309,8 +309,8 @@ impl<'a, 'gcx, 'tcx> RegionCtxt<'a, 'gcx, 'tcx> {
         let old_call_site_scope = self.set_call_site_scope(Some(call_site));
 
         let fn_sig = {
-            let fn_sig_map = &self.tables.borrow().liberated_fn_sigs;
-            match fn_sig_map.get(&id) {
+            let fn_hir_id = self.tcx.hir.node_to_hir_id(id);
+            match self.tables.borrow().liberated_fn_sigs().get(fn_hir_id) {
                 Some(f) => f.clone(),
                 None => {
                     bug!("No fn-sig entry for id={}", id);
@@ -365,6 +365,10 @@ impl<'a, 'gcx, 'tcx> RegionCtxt<'a, 'gcx, 'tcx> {
                 .region_obligations(node_id)
                 .to_vec();
 
+        let perf_stats = &self.tcx.sess.perf_stats;
+        perf_stats.region_obligations_count.set(perf_stats.region_obligations_count.get() +
+                                                region_obligations.len() as u64);
+
         for r_o in &region_obligations {
             debug!("visit_region_obligations: r_o={:?} cause={:?}",
                    r_o, r_o.cause);
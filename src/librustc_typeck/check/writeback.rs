@@ -78,7 +78,7 @@ impl<'cx, 'gcx, 'tcx> WritebackCx<'cx, 'gcx, 'tcx> {
         -> WritebackCx<'cx, 'gcx, 'tcx> {
         WritebackCx {
             fcx: fcx,
-            tables: ty::TypeckTables::empty(),
+            tables: ty::TypeckTables::empty(fcx.tables.borrow().local_id_root),
             body: body
         }
     }
@@ -221,8 +221,10 @@ impl<'cx, 'gcx, 'tcx> WritebackCx<'cx, 'gcx, 'tcx> {
     }
 
     fn visit_cast_types(&mut self) {
+        let fcx_tables = self.fcx.tables.borrow();
+        debug_assert_eq!(fcx_tables.local_id_root, self.tables.local_id_root);
         self.tables.cast_kinds.extend(
-            self.fcx.tables.borrow().cast_kinds.iter().map(|(&key, &value)| (key, value)));
+            fcx_tables.cast_kinds.iter().map(|(&key, &value)| (key, value)));
     }
 
     fn visit_lints(&mut self) {
@@ -313,16 +315,24 @@ impl<'cx, 'gcx, 'tcx> WritebackCx<'cx, 'gcx, 'tcx> {
     }
 
     fn visit_liberated_fn_sigs(&mut self) {
-        for (&node_id, fn_sig) in self.fcx.tables.borrow().liberated_fn_sigs.iter() {
-            let fn_sig = self.resolve(fn_sig, &node_id);
-            self.tables.liberated_fn_sigs.insert(node_id, fn_sig.clone());
+        let fcx_tables = self.fcx.tables.borrow();
+        debug_assert_eq!(fcx_tables.local_id_root, self.tables.local_id_root);
+        let owner = fcx_tables.local_id_root.unwrap().index;
+        for (&local_id, fn_sig) in fcx_tables.liberated_fn_sigs.iter() {
+            let hir_id = hir::HirId { owner, local_id };
+            let fn_sig = self.resolve(fn_sig, &hir_id);
+            self.tables.liberated_fn_sigs.insert(local_id, fn_sig.clone());
         }
     }
 
     fn visit_fru_field_types(&mut self) {
-        for (&node_id, ftys) in self.fcx.tables.borrow().fru_field_types.iter() {
-            let ftys = self.resolve(ftys, &node_id);
-            self.tables.fru_field_types.insert(node_id, ftys);
+        let fcx_tables = self.fcx.tables.borrow();
+        debug_assert_eq!(fcx_tables.local_id_root, self.tables.local_id_root);
+        let owner = fcx_tables.local_id_root.unwrap().index;
+        for (&local_id, ftys) in fcx_tables.fru_field_types.iter() {
+            let hir_id = hir::HirId { owner, local_id };
+            let ftys = self.resolve(ftys, &hir_id);
+            self.tables.fru_field_types.insert(local_id, ftys);
         }
     }
 
@@ -352,6 +362,10 @@ impl Locatable for ast::NodeId {
     fn to_span(&self, tcx: &TyCtxt) -> Span { tcx.hir.span(*self) }
 }
 
+impl Locatable for hir::HirId {
+    fn to_span(&self, tcx: &TyCtxt) -> Span { tcx.hir.span(tcx.hir.hir_to_node_id(*self)) }
+}
+
 ///////////////////////////////////////////////////////////////////////////
 // The Resolver. This is the type folding engine that detects
 // unresolved types and so forth.
@@ -0,0 +1,126 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Intra-doc links: links such as [`Vec::new`], written without a target of
+//! their own, whose path has been resolved by `rustc_resolve`. Here each
+//! resolution is turned into the item whose page documents it, and the
+//! anchor on that page, which are then stored in `Attributes::links` for
+//! rendering.
+
+use rustc::hir::def::{Def, PathResolution};
+use rustc::hir::def_id::DefId;
+use rustc::ty;
+
+use core::DocContext;
+use fold::DocFolder;
+
+use super::Item;
+
+/// Fills in the links in the attributes of every local item in `krate`, the
+/// module of the crate root.
+pub fn collect(cx: &DocContext, krate: Item) -> Item {
+    LinkCollector { cx: cx }.fold_item(krate).unwrap()
+}
+
+struct LinkCollector<'a, 'b: 'a, 'tcx: 'b> {
+    cx: &'a DocContext<'b, 'tcx>,
+}
+
+impl<'a, 'b, 'tcx> DocFolder for LinkCollector<'a, 'b, 'tcx> {
+    fn fold_item(&mut self, mut item: Item) -> Option<Item> {
+        let node_id = self.cx.tcx.hir.as_local_node_id(item.def_id);
+        if let Some(links) = node_id.and_then(|id| self.cx.doc_links.get(&id)) {
+            item.attrs.links = links.iter().filter_map(|&(ref label, resolution)| {
+                link_target(self.cx, label, resolution).map(|(did, fragment)| {
+                    (label.clone(), did, fragment)
+                })
+            }).collect();
+        }
+        self.fold_item_recur(item)
+    }
+}
+
+/// Returns the item whose page documents what `label` was resolved to, and
+/// the anchor of its documentation on that page, if it's not the page itself.
+fn link_target(cx: &DocContext, label: &str, resolution: PathResolution)
+               -> Option<(DefId, Option<String>)> {
+    let tcx = cx.tcx;
+    let def = resolution.base_def();
+
+    // `Type::item` leaves the associated item unresolved, as it can only be
+    // found by looking through the inherent impls of the type.
+    match resolution.unresolved_segments() {
+        0 => {}
+        1 => {
+            let (page, impls) = match def {
+                Def::Struct(did) | Def::Union(did) | Def::Enum(did) => {
+                    (did, tcx.inherent_impls(did))
+                }
+                Def::SelfTy(_, Some(impl_did)) => {
+                    match tcx.type_of(impl_did).ty_adt_def() {
+                        Some(adt) => (adt.did, tcx.inherent_impls(adt.did)),
+                        None => return None,
+                    }
+                }
+                _ => return None,
+            };
+            let name = label.trim_matches('`').trim_right_matches("()");
+            let name = name.rsplit("::").next().unwrap();
+            return impls.iter()
+                        .flat_map(|&impl_did| tcx.associated_items(impl_did))
+                        .find(|item| item.name == name)
+                        .map(|item| (page, Some(assoc_item_fragment(&item))));
+        }
+        _ => return None,
+    }
+
+    match def {
+        Def::Mod(did) | Def::Struct(did) | Def::Union(did) | Def::Enum(did) |
+        Def::Trait(did) | Def::TyAlias(did) | Def::Fn(did) | Def::Const(did) |
+        Def::Static(did, _) => Some((did, None)),
+        Def::StructCtor(did, _) => tcx.parent_def_id(did).map(|parent| (parent, None)),
+        Def::Variant(did) | Def::VariantCtor(did, _) => {
+            let name = tcx.item_name(did);
+            tcx.parent_def_id(did).map(|parent| (parent, Some(format!("variant.{}", name))))
+        }
+        Def::Method(did) | Def::AssociatedConst(did) | Def::AssociatedTy(did) => {
+            let item = tcx.associated_item(did);
+            let page = match item.container {
+                ty::TraitContainer(trait_did) => trait_did,
+                ty::ImplContainer(impl_did) => {
+                    match tcx.type_of(impl_did).ty_adt_def() {
+                        Some(adt) => adt.did,
+                        None => return None,
+                    }
+                }
+            };
+            Some((page, Some(assoc_item_fragment(&item))))
+        }
+        Def::SelfTy(Some(trait_did), None) => Some((trait_did, None)),
+        Def::SelfTy(_, Some(impl_did)) => {
+            tcx.type_of(impl_did).ty_adt_def().map(|adt| (adt.did, None))
+        }
+        _ => None,
+    }
+}
+
+fn assoc_item_fragment(item: &ty::AssociatedItem) -> String {
+    let kind = match item.kind {
+        ty::AssociatedKind::Const => "associatedconstant",
+        ty::AssociatedKind::Type => "associatedtype",
+        ty::AssociatedKind::Method => {
+            match item.container {
+                ty::TraitContainer(_) if !item.defaultness.has_value() => "tymethod",
+                _ => "method",
+            }
+        }
+    };
+    format!("{}.{}", kind, item.name)
+}
@@ -51,7 +51,11 @@ use doctree;
 use visit_ast;
 use html::item_type::ItemType;
 
+use self::cfg::Cfg;
+
 pub mod inline;
+pub mod cfg;
+mod links;
 mod simplify;
 
 // extract the stability index for a node from tcx, if possible
@@ -137,7 +141,7 @@ impl<'a, 'tcx> Clean<Crate> for visit_ast::RustdocVisitor<'a, 'tcx> {
 
         // Clean the crate, translating the entire libsyntax AST to one that is
         // understood by rustdoc.
-        let mut module = self.module.clean(cx);
+        let mut module = links::collect(cx, self.module.clean(cx));
 
         let ExternalCrate { name, src, primitives, .. } = LOCAL_CRATE.clean(cx);
         {
@@ -536,32 +540,101 @@ impl<I: IntoIterator<Item=ast::NestedMetaItem>> NestedAttributesExt for I {
 pub struct Attributes {
     pub doc_strings: Vec<String>,
     pub other_attrs: Vec<ast::Attribute>,
+    pub cfg: Option<Rc<Cfg>>,
     pub span: Option<syntax_pos::Span>,
+    /// The intra-doc links of the item, i.e. their label, the item whose page
+    /// they point to, and the anchor on that page.
+    pub links: Vec<(String, DefId, Option<String>)>,
 }
 
 impl Attributes {
-    pub fn from_ast(attrs: &[ast::Attribute]) -> Attributes {
+    /// Extracts the content from an attribute `#[doc(cfg(content))]`.
+    fn extract_cfg(mi: &ast::MetaItem) -> Option<&ast::MetaItem> {
+        use syntax::ast::NestedMetaItemKind::MetaItem;
+
+        if let ast::MetaItemKind::List(ref nmis) = mi.node {
+            if nmis.len() == 1 {
+                if let MetaItem(ref cfg_mi) = nmis[0].node {
+                    if cfg_mi.check_name("cfg") {
+                        if let ast::MetaItemKind::List(ref cfg_nmis) = cfg_mi.node {
+                            if cfg_nmis.len() == 1 {
+                                if let MetaItem(ref content_mi) = cfg_nmis[0].node {
+                                    return Some(content_mi);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Extracts the content from an attribute `#[cfg(content)]`, unless it
+    /// only tells how the crate is being built (e.g. `not(test)`), not where
+    /// the item is available.
+    fn extract_plain_cfg(attr: &ast::Attribute) -> Option<Cfg> {
+        let content = match attr.meta_item_list() {
+            Some(ref mis) if mis.len() == 1 => mis[0].meta_item().cloned(),
+            _ => None,
+        };
+        // A malformed `#[cfg]` was already reported when it was evaluated.
+        match content.map(|mi| Cfg::parse(&mi)) {
+            Some(Ok(cfg)) => {
+                if cfg.mentions_any(&["test", "stage0", "dox"]) { None } else { Some(cfg) }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn from_ast(diagnostic: &::errors::Handler, attrs: &[ast::Attribute]) -> Attributes {
         let mut doc_strings = vec![];
         let mut sp = None;
+        let mut cfg = Cfg::True;
+        let mut plain_cfg = Cfg::True;
+
         let other_attrs = attrs.iter().filter_map(|attr| {
             attr.with_desugared_doc(|attr| {
-                if let Some(value) = attr.value_str() {
-                    if attr.check_name("doc") {
-                        doc_strings.push(value.to_string());
-                        if sp.is_none() {
-                            sp = Some(attr.span);
+                if attr.check_name("doc") {
+                    if let Some(mi) = attr.meta() {
+                        if let Some(value) = mi.value_str() {
+                            // Extracted #[doc = "..."]
+                            doc_strings.push(value.to_string());
+                            if sp.is_none() {
+                                sp = Some(attr.span);
+                            }
+                            return None;
+                        } else if let Some(cfg_mi) = Attributes::extract_cfg(&mi) {
+                            // Extracted #[doc(cfg(...))]
+                            match Cfg::parse(cfg_mi) {
+                                Ok(new_cfg) => cfg &= new_cfg,
+                                Err(e) => diagnostic.span_err(e.span, e.msg),
+                            }
+                            return None;
                         }
-                        return None;
+                    }
+                } else if attr.check_name("cfg") {
+                    // The item was kept when the crate was configured, so its
+                    // `#[cfg]` holds; it is still listed among the attributes.
+                    if let Some(new_cfg) = Attributes::extract_plain_cfg(attr) {
+                        plain_cfg &= new_cfg;
                     }
                 }
-
                 Some(attr.clone())
             })
         }).collect();
+        // An explicit `#[doc(cfg(...))]` overrides the `#[cfg]` of the item,
+        // which may also allow building the documentation elsewhere.
+        if cfg == Cfg::True {
+            cfg = plain_cfg;
+        }
         Attributes {
             doc_strings: doc_strings,
             other_attrs: other_attrs,
+            cfg: if cfg == Cfg::True { None } else { Some(Rc::new(cfg)) },
             span: sp,
+            links: vec![],
         }
     }
 
@@ -579,8 +652,8 @@ impl AttributesExt for Attributes {
 }
 
 impl Clean<Attributes> for [ast::Attribute] {
-    fn clean(&self, _cx: &DocContext) -> Attributes {
-        Attributes::from_ast(self)
+    fn clean(&self, cx: &DocContext) -> Attributes {
+        Attributes::from_ast(cx.sess().diagnostic(), self)
     }
 }
 
@@ -18,7 +18,8 @@ use rustc::middle::privacy::AccessLevels;
 use rustc::ty::{self, TyCtxt, GlobalArenas};
 use rustc::hir::map as hir_map;
 use rustc::lint;
-use rustc::util::nodemap::FxHashMap;
+use rustc::hir::def::PathResolution;
+use rustc::util::nodemap::{FxHashMap, NodeMap};
 use rustc_trans;
 use rustc_trans::back::link;
 use rustc_resolve as resolve;
@@ -65,6 +66,8 @@ pub struct DocContext<'a, 'tcx: 'a> {
     pub ty_substs: RefCell<FxHashMap<Def, clean::Type>>,
     /// Table node id of lifetime parameter definition -> substituted lifetime
     pub lt_substs: RefCell<FxHashMap<ast::NodeId, clean::Lifetime>>,
+    /// Intra-doc links of each item, as resolved by `rustc_resolve`
+    pub doc_links: NodeMap<Vec<(String, PathResolution)>>,
 }
 
 impl<'a, 'tcx> DocContext<'a, 'tcx> {
@@ -159,7 +162,7 @@ pub fn run_core(search_paths: SearchPaths,
 
     let name = link::find_crate_name(Some(&sess), &krate.attrs, &input);
 
-    let driver::ExpansionResult { defs, analysis, resolutions, mut hir_forest, .. } = {
+    let driver::ExpansionResult { defs, analysis, mut resolutions, mut hir_forest, .. } = {
         let result = driver::phase_2_configure_and_expand(&sess,
                                                           &cstore,
                                                           krate,
@@ -171,6 +174,8 @@ pub fn run_core(search_paths: SearchPaths,
         abort_on_err(result, &sess)
     };
 
+    let doc_links = mem::replace(&mut resolutions.doc_links, NodeMap());
+
     let arena = DroplessArena::new();
     let arenas = GlobalArenas::new();
     let hir_map = hir_map::map_crate(&mut hir_forest, defs);
@@ -205,6 +210,7 @@ pub fn run_core(search_paths: SearchPaths,
             renderinfo: Default::default(),
             ty_substs: Default::default(),
             lt_substs: Default::default(),
+            doc_links: doc_links,
         };
         debug!("crate: {:?}", tcx.hir.krate());
 
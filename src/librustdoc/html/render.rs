@@ -1642,6 +1642,7 @@ fn document_short(w: &mut fmt::Formatter, item: &clean::Item, link: AssocItemLin
         } else {
             format!("{}", &plain_summary_line(Some(s)))
         };
+        let markdown = with_doc_links(item, &markdown);
         write!(w, "<div class='docblock'>{}{}</div>", prefix, Markdown(&markdown, render_type))?;
     } else if !prefix.is_empty() {
         write!(w, "<div class='docblock'>{}</div>", prefix)?;
@@ -1666,13 +1667,30 @@ fn render_assoc_const_value(item: &clean::Item) -> String {
 fn document_full(w: &mut fmt::Formatter, item: &clean::Item,
                  render_type: RenderType, prefix: &str) -> fmt::Result {
     if let Some(s) = item.doc_value() {
-        write!(w, "<div class='docblock'>{}{}</div>", prefix, Markdown(s, render_type))?;
+        let s = with_doc_links(item, s);
+        write!(w, "<div class='docblock'>{}{}</div>", prefix, Markdown(&s, render_type))?;
     } else if !prefix.is_empty() {
         write!(w, "<div class='docblock'>{}</div>", prefix)?;
     }
     Ok(())
 }
 
+/// Appends to the markdown `s` a reference definition for each intra-doc link
+/// of `item`, pointing wherever its path was resolved to.
+fn with_doc_links(item: &clean::Item, s: &str) -> String {
+    let mut s = s.to_string();
+    for &(ref label, did, ref fragment) in &item.attrs.links {
+        if let Some((mut url, ..)) = href(did) {
+            if let Some(ref fragment) = *fragment {
+                url.push('#');
+                url.push_str(fragment);
+            }
+            s.push_str(&format!("\n\n[{}]: {}", label, url));
+        }
+    }
+    s
+}
+
 fn document_stability(w: &mut fmt::Formatter, cx: &Context, item: &clean::Item) -> fmt::Result {
     let stabilities = short_stability(item, cx, true);
     if !stabilities.is_empty() {
@@ -1950,6 +1968,14 @@ fn short_stability(item: &clean::Item, cx: &Context, show_reason: bool) -> Vec<S
         stability.push(format!("<div class='stab deprecated'>{}</div>", text))
     }
 
+    if let Some(ref cfg) = item.attrs.cfg {
+        stability.push(format!("<div class='stab portability'>{}</div>", if show_reason {
+            cfg.render_long_html()
+        } else {
+            cfg.render_short_html()
+        }));
+    }
+
     stability
 }
 
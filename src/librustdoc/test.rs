@@ -119,6 +119,7 @@ pub fn run(input: &str,
         let map = hir::map::map_crate(&mut hir_forest, defs);
         let krate = map.krate();
         let mut hir_collector = HirCollector {
+            sess: &sess,
             collector: &mut collector,
             map: &map
         };
@@ -572,6 +573,7 @@ impl Collector {
 }
 
 struct HirCollector<'a, 'hir: 'a> {
+    sess: &'a session::Session,
     collector: &'a mut Collector,
     map: &'a hir::map::Map<'hir>
 }
@@ -586,7 +588,7 @@ impl<'a, 'hir> HirCollector<'a, 'hir> {
             self.collector.names.push(name);
         }
 
-        let mut attrs = Attributes::from_ast(attrs);
+        let mut attrs = Attributes::from_ast(self.sess.diagnostic(), attrs);
         attrs.collapse_doc_comments();
         attrs.unindent_doc_comments();
         if let Some(doc) = attrs.doc_value() {
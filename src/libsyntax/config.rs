@@ -91,8 +91,9 @@ impl<'a> StripUnconfigured<'a> {
             parser.expect(&token::Comma)?;
             let lo = parser.span.lo;
             let (path, tokens) = parser.parse_path_and_tokens()?;
+            let span = Span { lo: lo, ..parser.prev_span };
             parser.expect(&token::CloseDelim(token::Paren))?;
-            Ok((cfg, path, tokens, Span { lo: lo, ..parser.prev_span }))
+            Ok((cfg, path, tokens, span))
         }) {
             Ok(result) => result,
             Err(mut e) => {
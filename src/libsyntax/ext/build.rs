@@ -14,6 +14,7 @@ use attr;
 use syntax_pos::{Pos, Span, DUMMY_SP};
 use codemap::{dummy_spanned, respan, Spanned};
 use ext::base::ExtCtxt;
+use fold::{Folder, Respanner};
 use ptr::P;
 use symbol::{Symbol, keywords};
 
@@ -299,8 +300,15 @@ pub trait AstBuilder {
                      path: Vec<ast::Ident>, imports: &[ast::Ident]) -> P<ast::Item>;
     fn item_use_glob(&self, sp: Span,
                      vis: ast::Visibility, path: Vec<ast::Ident>) -> P<ast::Item>;
+
+    // splicing: give the parts of a fragment that have no real location
+    // (`DUMMY_SP`) the span of the code they stand in for.
+    fn respan_expr(&self, sp: Span, expr: P<ast::Expr>) -> P<ast::Expr>;
+    fn respan_stmt(&self, sp: Span, stmt: ast::Stmt) -> ast::Stmt;
+    fn respan_item(&self, sp: Span, item: P<ast::Item>) -> P<ast::Item>;
 }
 
+
 impl<'a> AstBuilder for ExtCtxt<'a> {
     fn path(&self, span: Span, strs: Vec<ast::Ident> ) -> ast::Path {
         self.path_all(span, false, strs, Vec::new(), Vec::new(), Vec::new())
@@ -1199,4 +1207,19 @@ impl<'a> AstBuilder for ExtCtxt<'a> {
                       P(respan(sp,
                                ast::ViewPathGlob(self.path(sp, path)))))
     }
+
+    fn respan_expr(&self, sp: Span, expr: P<ast::Expr>) -> P<ast::Expr> {
+        let mut respanner = Respanner(|span: Span| if span == DUMMY_SP { sp } else { span });
+        respanner.fold_expr(expr)
+    }
+
+    fn respan_stmt(&self, sp: Span, stmt: ast::Stmt) -> ast::Stmt {
+        let mut respanner = Respanner(|span: Span| if span == DUMMY_SP { sp } else { span });
+        respanner.fold_stmt(stmt).expect_one("expected exactly one statement")
+    }
+
+    fn respan_item(&self, sp: Span, item: P<ast::Item>) -> P<ast::Item> {
+        let mut respanner = Respanner(|span: Span| if span == DUMMY_SP { sp } else { span });
+        respanner.fold_item(item).expect_one("expected exactly one item")
+    }
 }
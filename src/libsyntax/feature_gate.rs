@@ -365,6 +365,17 @@ declare_features! (
     // global allocators and their internals
     (active, global_allocator, "1.20.0", None),
     (active, allocator_internals, "1.20.0", None),
+
+    // Allows `v.push(v.len())`: the mutable autoref of a method receiver
+    // only takes effect once the arguments have been evaluated.
+    (active, two_phase_borrows, "1.20.0", None),
+
+    // Allows arbitrary token streams, rather than just meta items, in
+    // non-builtin attributes, e.g. `#[foo(a + b => c)]`.
+    (active, unrestricted_attribute_tokens, "1.20.0", None),
+
+    // Allows `#[doc(cfg(...))]`, which rustdoc renders as a portability note.
+    (active, doc_cfg, "1.20.0", None),
 );
 
 declare_features! (
@@ -1154,15 +1165,36 @@ impl<'a> Visitor<'a> for PostExpansionVisitor<'a> {
             self.context.check_attribute(attr, false);
         }
 
+        if attr.check_name("doc") {
+            if let Some(content) = attr.meta_item_list() {
+                if content.len() == 1 && content[0].check_name("cfg") {
+                    gate_feature_post!(&self, doc_cfg, attr.span,
+                        "#[doc(cfg(...))] is experimental"
+                    );
+                }
+            }
+        }
+
         if self.context.features.proc_macro && attr::is_known(attr) {
             return
         }
 
-        let meta = panictry!(attr.parse_meta(self.context.parse_sess));
-        if contains_novel_literal(&meta) {
-            gate_feature_post!(&self, attr_literals, attr.span,
-                               "non-string literals in attributes, or string \
-                               literals in top-level positions, are experimental");
+        match attr.parse_meta(self.context.parse_sess) {
+            Ok(meta) => if contains_novel_literal(&meta) {
+                gate_feature_post!(&self, attr_literals, attr.span,
+                                   "non-string literals in attributes, or string \
+                                   literals in top-level positions, are experimental");
+            },
+            // The tokens of builtin attributes have to be meta items, other
+            // attributes only get to look at their raw token stream.
+            Err(mut err) => if is_builtin_attr(attr) {
+                err.emit();
+                panic!(FatalError);
+            } else {
+                err.cancel();
+                gate_feature_post!(&self, unrestricted_attribute_tokens, attr.span,
+                                   "arbitrary tokens in non-builtin attributes are unstable");
+            },
         }
     }
 
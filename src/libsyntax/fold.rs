@@ -631,7 +631,10 @@ pub fn noop_fold_interpolated<T: Folder>(nt: token::Nonterminal, fld: &mut T)
         token::NtPat(pat) => token::NtPat(fld.fold_pat(pat)),
         token::NtExpr(expr) => token::NtExpr(fld.fold_expr(expr)),
         token::NtTy(ty) => token::NtTy(fld.fold_ty(ty)),
-        token::NtIdent(id) => token::NtIdent(Spanned::<Ident>{node: fld.fold_ident(id.node), ..id}),
+        token::NtIdent(id) => token::NtIdent(Spanned::<Ident>{
+            node: fld.fold_ident(id.node),
+            span: fld.new_span(id.span),
+        }),
         token::NtMeta(meta) => token::NtMeta(fld.fold_meta_item(meta)),
         token::NtPath(path) => token::NtPath(fld.fold_path(path)),
         token::NtTT(tt) => token::NtTT(fld.fold_tt(tt)),
@@ -833,7 +836,7 @@ pub fn noop_fold_struct_field<T: Folder>(f: StructField, fld: &mut T) -> StructF
 
 pub fn noop_fold_field<T: Folder>(f: Field, folder: &mut T) -> Field {
     Field {
-        ident: respan(f.ident.span, folder.fold_ident(f.ident.node)),
+        ident: respan(folder.new_span(f.ident.span), folder.fold_ident(f.ident.node)),
         expr: folder.fold_expr(f.expr),
         span: folder.new_span(f.span),
         is_shorthand: f.is_shorthand,
@@ -1158,14 +1161,16 @@ pub fn noop_fold_expr<T: Folder>(Expr {id, node, span, attrs}: Expr, folder: &mu
                     folder.fold_exprs(args))
             }
             ExprKind::Binary(binop, lhs, rhs) => {
-                ExprKind::Binary(binop,
+                ExprKind::Binary(respan(folder.new_span(binop.span), binop.node),
                         folder.fold_expr(lhs),
                         folder.fold_expr(rhs))
             }
             ExprKind::Unary(binop, ohs) => {
                 ExprKind::Unary(binop, folder.fold_expr(ohs))
             }
-            ExprKind::Lit(l) => ExprKind::Lit(l),
+            ExprKind::Lit(l) => {
+                ExprKind::Lit(l.map(|l| respan(folder.new_span(l.span), l.node)))
+            }
             ExprKind::Cast(expr, ty) => {
                 ExprKind::Cast(folder.fold_expr(expr), folder.fold_ty(ty))
             }
@@ -1224,7 +1229,7 @@ pub fn noop_fold_expr<T: Folder>(Expr {id, node, span, attrs}: Expr, folder: &mu
                 ExprKind::Assign(folder.fold_expr(el), folder.fold_expr(er))
             }
             ExprKind::AssignOp(op, el, er) => {
-                ExprKind::AssignOp(op,
+                ExprKind::AssignOp(respan(folder.new_span(op.span), op.node),
                             folder.fold_expr(el),
                             folder.fold_expr(er))
             }
@@ -1348,14 +1353,29 @@ pub fn noop_fold_vis<T: Folder>(vis: Visibility, folder: &mut T) -> Visibility {
     }
 }
 
+/// A Respanner maps every span of the fragment it folds through the given
+/// function, leaving node ids and everything else untouched.
+pub struct Respanner<F>(pub F);
+
+impl<F: FnMut(Span) -> Span> Folder for Respanner<F> {
+    fn new_span(&mut self, span: Span) -> Span {
+        (self.0)(span)
+    }
+
+    fn fold_mac(&mut self, mac: Mac) -> Mac {
+        noop_fold_mac(mac, self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::io;
     use ast::{self, Ident};
-    use util::parser_testing::{string_to_crate, matches_codepattern};
+    use util::parser_testing::{string_to_crate, string_to_expr, matches_codepattern};
     use print::pprust;
     use fold;
     use super::*;
+    use syntax_pos::{BytePos, NO_EXPANSION};
 
     // this version doesn't care about getting comments or docstrings in.
     fn fake_print_crate(s: &mut pprust::State,
@@ -1416,4 +1436,22 @@ mod tests {
             pprust::to_string(|s| fake_print_crate(s, &folded_crate)),
             "macro_rules! zz((zz$zz:zz$(zz $zz:zz)zz+=>(zz$(zz$zz$zz)+)));".to_string());
     }
+
+    // spans get replaced everywhere, ids stay put
+    #[test] fn respanner_preserves_ids() {
+        let sp = Span { lo: BytePos(1), hi: BytePos(2), ctxt: NO_EXPANSION };
+        let mut expr = string_to_expr("a + f(b)".to_string());
+        expr.id = ast::NodeId::new(42);
+        let folded = Respanner(|_| sp).fold_expr(expr);
+        assert_eq!(folded.span, sp);
+        assert_eq!(folded.id, ast::NodeId::new(42));
+        match folded.node {
+            ast::ExprKind::Binary(op, ref lhs, ref rhs) => {
+                assert_eq!(op.span, sp);
+                assert_eq!(lhs.span, sp);
+                assert_eq!(rhs.span, sp);
+            }
+            _ => panic!("expected a binary expression"),
+        }
+    }
 }
@@ -90,9 +90,11 @@ impl<'a> Parser<'a> {
         debug!("parse_attribute_with_inner_parse_policy: inner_parse_policy={:?} self.token={:?}",
                inner_parse_policy,
                self.token);
+        let doc_comment;
         let (span, path, tokens, mut style) = match self.token {
             token::Pound => {
                 let lo = self.span;
+                doc_comment = self.desugared_doc_comment();
                 self.bump();
 
                 if inner_parse_policy == InnerAttributeParsePolicy::Permitted {
@@ -139,6 +141,12 @@ impl<'a> Parser<'a> {
             style = ast::AttrStyle::Inner;
         }
 
+        // Turn the attribute back into the doc comment it was written as, so
+        // that its original text survives being passed through a macro.
+        if let Some(name) = doc_comment {
+            return Ok(attr::mk_sugared_doc_attr(attr::mk_attr_id(), name, span));
+        }
+
         Ok(ast::Attribute {
             id: attr::mk_attr_id(),
             style: style,
@@ -8,6 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use errors::DiagnosticBuilder;
 use print::pprust::token_to_string;
 use parse::lexer::StringReader;
 use parse::{token, PResult};
@@ -26,12 +27,14 @@ impl<'a> StringReader<'a> {
         Ok(TokenStream::concat(tts))
     }
 
-    // Parse a stream of tokens into a list of `TokenTree`s, up to a `CloseDelim`.
+    // Parse a stream of tokens into a list of `TokenTree`s, up to a `CloseDelim`
+    // or an `Eof`.
     fn parse_token_trees_until_close_delim(&mut self) -> TokenStream {
         let mut tts = vec![];
         loop {
-            if let token::CloseDelim(..) = self.token {
-                return TokenStream::concat(tts);
+            match self.token {
+                token::CloseDelim(..) | token::Eof => return TokenStream::concat(tts),
+                _ => {}
             }
             let tree = match self.parse_token_tree() {
                 Ok(tree) => tree,
@@ -47,14 +50,7 @@ impl<'a> StringReader<'a> {
 
     fn parse_token_tree(&mut self) -> PResult<'a, TokenTree> {
         match self.token {
-            token::Eof => {
-                let msg = "this file contains an un-closed delimiter";
-                let mut err = self.sess.span_diagnostic.struct_span_err(self.span, msg);
-                for &(_, sp) in &self.open_braces {
-                    err.span_help(sp, "did you mean to close this delimiter?");
-                }
-                Err(err)
-            },
+            token::Eof => Err(self.unclosed_delims_err()),
             token::OpenDelim(delim) => {
                 // The span for beginning of the delimited section
                 let pre_span = self.span;
@@ -106,9 +102,14 @@ impl<'a> StringReader<'a> {
                         }
                     }
                     token::Eof => {
-                        // Silently recover, the EOF token will be seen again
-                        // and an error emitted then. Thus we don't pop from
-                        // self.open_braces here.
+                        // Recover as if the delimiter was closed at the end of
+                        // the file. The innermost delimiter left open reports
+                        // all of them in one error, so the enclosing ones find
+                        // none left.
+                        if !self.open_braces.is_empty() {
+                            self.unclosed_delims_err().emit();
+                            self.open_braces.clear();
+                        }
                     },
                     _ => {}
                 }
@@ -133,4 +134,13 @@ impl<'a> StringReader<'a> {
             }
         }
     }
+
+    fn unclosed_delims_err(&self) -> DiagnosticBuilder<'a> {
+        let msg = "this file contains an un-closed delimiter";
+        let mut err = self.sess.span_diagnostic.struct_span_err(self.span, msg);
+        for &(_, sp) in &self.open_braces {
+            err.span_help(sp, "did you mean to close this delimiter?");
+        }
+        err
+    }
 }
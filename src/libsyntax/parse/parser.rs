@@ -199,6 +199,9 @@ pub struct Parser<'a> {
 struct TokenCursor {
     frame: TokenCursorFrame,
     stack: Vec<TokenCursorFrame>,
+    /// The span and text of the last doc comment turned into
+    /// `#[doc = "..."]` tokens by `next_desugared`.
+    last_doc_comment: Option<(Span, ast::Name)>,
 }
 
 #[derive(Clone)]
@@ -258,6 +261,7 @@ impl TokenCursor {
             tok => return tok,
         };
 
+        self.last_doc_comment = Some((sp, name));
         let stripped = strip_doc_comment_decoration(&name.as_str());
 
         // Searches for the occurrences of `"#*` and returns the minimum number of `#`s
@@ -469,6 +473,7 @@ impl<'a> Parser<'a> {
                     tts: tokens.into(),
                 }),
                 stack: Vec::new(),
+                last_doc_comment: None,
             },
             desugar_doc_comments: desugar_doc_comments,
             cfg_mods: true,
@@ -489,6 +494,16 @@ impl<'a> Parser<'a> {
         parser
     }
 
+    /// If the current token is the `#` of an attribute desugared from a doc
+    /// comment, returns the original text of that comment.
+    pub fn desugared_doc_comment(&self) -> Option<ast::Name> {
+        // All the tokens produced for a desugared doc comment share its span.
+        match self.token_cursor.last_doc_comment {
+            Some((sp, name)) if self.token == token::Pound && sp == self.span => Some(name),
+            _ => None,
+        }
+    }
+
     fn next_tok(&mut self) -> TokenAndSpan {
         let mut next = if self.desugar_doc_comments {
             self.token_cursor.next_desugared()
@@ -4175,6 +4190,7 @@ impl<'a> Parser<'a> {
             None => return Ok(None),
         };
 
+        let mut recovered_semi = false;
         match stmt.node {
             StmtKind::Expr(ref expr) if self.token != token::Eof => {
                 // expression without semicolon
@@ -4183,8 +4199,11 @@ impl<'a> Parser<'a> {
                     if let Err(mut e) =
                         self.expect_one_of(&[], &[token::Semi, token::CloseDelim(token::Brace)])
                     {
+                        recovered_semi = self.recover_missing_semi(&mut e);
                         e.emit();
-                        self.recover_stmt();
+                        if !recovered_semi {
+                            self.recover_stmt();
+                        }
                     }
                 }
             }
@@ -4192,14 +4211,18 @@ impl<'a> Parser<'a> {
                 // We used to incorrectly allow a macro-expanded let statement to lack a semicolon.
                 if macro_legacy_warnings && self.token != token::Semi {
                     self.warn_missing_semicolon();
-                } else {
-                    self.expect_one_of(&[token::Semi], &[])?;
+                } else if let Err(mut e) = self.expect_one_of(&[token::Semi], &[]) {
+                    if !self.recover_missing_semi(&mut e) {
+                        return Err(e);
+                    }
+                    e.emit();
+                    recovered_semi = true;
                 }
             }
             _ => {}
         }
 
-        if self.eat(&token::Semi) {
+        if self.eat(&token::Semi) || recovered_semi {
             stmt = stmt.add_trailing_semicolon();
         }
 
@@ -4207,6 +4230,36 @@ impl<'a> Parser<'a> {
         Ok(Some(stmt))
     }
 
+    /// Checks whether the statement just parsed was meant to end with a `;`
+    /// that is missing, either because a `,` was written in its place, or
+    /// because the next statement starts on a new line. If so, the stray `,`
+    /// is skipped, a suggestion is added to `err` and `true` is returned, so
+    /// that parsing can carry on as if the `;` had been there.
+    fn recover_missing_semi(&mut self, err: &mut DiagnosticBuilder) -> bool {
+        // Macro expansions don't have meaningful lines, and the tokens
+        // following a statement there are already reported as ignored.
+        if self.span.ctxt != syntax_pos::hygiene::SyntaxContext::empty() {
+            return false;
+        }
+
+        if self.token == token::Comma {
+            err.span_suggestion(self.span, "use `;` to end the statement", ";".to_string());
+            self.bump();
+            return true;
+        }
+
+        let cm = self.sess.codemap();
+        let on_new_line = cm.lookup_char_pos(self.prev_span.hi).line !=
+                          cm.lookup_char_pos(self.span.lo).line;
+        if on_new_line && (self.token.can_begin_expr() || self.token.is_keyword(keywords::Let)) {
+            err.span_suggestion(self.prev_span.next_point(),
+                                "add `;` here",
+                                ";".to_string());
+            return true;
+        }
+        false
+    }
+
     fn warn_missing_semicolon(&self) {
         self.diagnostic().struct_span_warn(self.span, {
             &format!("expected `;`, found `{}`", self.this_token_to_string())
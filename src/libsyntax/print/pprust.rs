@@ -14,10 +14,10 @@ use abi::{self, Abi};
 use ast::{self, BlockCheckMode, PatKind, RangeEnd};
 use ast::{SelfKind, RegionTyParamBound, TraitTyParamBound, TraitBoundModifier};
 use ast::Attribute;
-use util::parser::AssocOp;
+use util::parser::{self, AssocOp, Fixity};
 use attr;
 use codemap::{self, CodeMap};
-use syntax_pos::{self, BytePos};
+use syntax_pos::{self, BytePos, NO_EXPANSION};
 use parse::token::{self, BinOpToken, Token};
 use parse::lexer::comments;
 use parse::{self, ParseSess};
@@ -146,10 +146,10 @@ impl<'a> State<'a> {
             out,
             ann,
             Some(cmnts),
-            // If the code is post expansion, don't use the table of
-            // literals, since it doesn't correspond with the literals
-            // in the AST anymore.
-            if is_expanded { None } else { Some(lits) })
+            // Post expansion, the table of literals still has the original
+            // text of the literals that are not part of any expansion (see
+            // `print_literal`).
+            Some(lits))
     }
 
     pub fn new(cm: &'a CodeMap,
@@ -438,16 +438,6 @@ pub fn visibility_qualified(vis: &ast::Visibility, s: &str) -> String {
     format!("{}{}", to_string(|s| s.print_visibility(vis)), s)
 }
 
-fn needs_parentheses(expr: &ast::Expr) -> bool {
-    match expr.node {
-        ast::ExprKind::Assign(..) | ast::ExprKind::Binary(..) |
-        ast::ExprKind::Closure(..) |
-        ast::ExprKind::AssignOp(..) | ast::ExprKind::Cast(..) |
-        ast::ExprKind::InPlace(..) | ast::ExprKind::Type(..) => true,
-        _ => false,
-    }
-}
-
 pub trait PrintState<'a> {
     fn writer(&mut self) -> &mut pp::Printer<'a>;
     fn boxes(&mut self) -> &mut Vec<pp::Breaks>;
@@ -620,8 +610,12 @@ pub trait PrintState<'a> {
 
     fn print_literal(&mut self, lit: &ast::Lit) -> io::Result<()> {
         self.maybe_print_comment(lit.span.lo)?;
-        if let Some(ref ltrl) = self.next_lit(lit.span.lo) {
-            return word(self.writer(), &(*ltrl).lit);
+        // A literal produced by a macro, or passed to one, is printed from
+        // its value: its span need not be that of the text it was written as.
+        if lit.span.ctxt == NO_EXPANSION {
+            if let Some(ref ltrl) = self.next_lit(lit.span.lo) {
+                return word(self.writer(), &(*ltrl).lit);
+            }
         }
         match lit.node {
             ast::LitKind::Str(st, style) => self.print_string(&st.as_str(), style),
@@ -1751,7 +1745,7 @@ impl<'a> State<'a> {
                         self.cbox(INDENT_UNIT - 1)?;
                         self.ibox(0)?;
                         word(&mut self.s, " else if ")?;
-                        self.print_expr(i)?;
+                        self.print_expr_as_cond(i)?;
                         space(&mut self.s)?;
                         self.print_block(then)?;
                         self.print_else(e.as_ref().map(|e| &**e))
@@ -1764,7 +1758,7 @@ impl<'a> State<'a> {
                         self.print_pat(pat)?;
                         space(&mut self.s)?;
                         self.word_space("=")?;
-                        self.print_expr(expr)?;
+                        self.print_expr_as_cond(expr)?;
                         space(&mut self.s)?;
                         self.print_block(then)?;
                         self.print_else(e.as_ref().map(|e| &**e))
@@ -1789,7 +1783,7 @@ impl<'a> State<'a> {
     pub fn print_if(&mut self, test: &ast::Expr, blk: &ast::Block,
                     elseopt: Option<&ast::Expr>) -> io::Result<()> {
         self.head("if")?;
-        self.print_expr(test)?;
+        self.print_expr_as_cond(test)?;
         space(&mut self.s)?;
         self.print_block(blk)?;
         self.print_else(elseopt)
@@ -1801,7 +1795,7 @@ impl<'a> State<'a> {
         self.print_pat(pat)?;
         space(&mut self.s)?;
         self.word_space("=")?;
-        self.print_expr(expr)?;
+        self.print_expr_as_cond(expr)?;
         space(&mut self.s)?;
         self.print_block(blk)?;
         self.print_else(elseopt)
@@ -1836,19 +1830,27 @@ impl<'a> State<'a> {
         self.pclose()
     }
 
-    pub fn check_expr_bin_needs_paren(&mut self, sub_expr: &ast::Expr,
-                                      binop: ast::BinOp) -> bool {
-        match sub_expr.node {
-            ast::ExprKind::Binary(ref sub_op, _, _) => {
-                AssocOp::from_ast_binop(sub_op.node).precedence() <
-                    AssocOp::from_ast_binop(binop.node).precedence()
-            }
-            _ => true
-        }
+    /// Prints `expr` in parentheses if it binds less tightly than `prec`,
+    /// the precedence its position requires (see `parser::expr_precedence`).
+    pub fn print_expr_maybe_paren(&mut self, expr: &ast::Expr, prec: i8) -> io::Result<()> {
+        self.print_expr_cond_paren(expr, parser::expr_precedence(expr) < prec)
     }
 
-    pub fn print_expr_maybe_paren(&mut self, expr: &ast::Expr) -> io::Result<()> {
-        let needs_par = needs_parentheses(expr);
+    /// Prints an expression that is followed by a block, like the condition
+    /// of an `if` or the scrutinee of a `match`.
+    pub fn print_expr_as_cond(&mut self, expr: &ast::Expr) -> io::Result<()> {
+        let needs_par = match expr.node {
+            // `if return {}` parses as `if (return {})`, not as `if (return) {}`.
+            ast::ExprKind::Closure(..) |
+            ast::ExprKind::Ret(..) |
+            ast::ExprKind::Break(..) => true,
+            // A struct literal would be taken as the block.
+            _ => parser::contains_exterior_struct_lit(expr),
+        };
+        self.print_expr_cond_paren(expr, needs_par)
+    }
+
+    fn print_expr_cond_paren(&mut self, expr: &ast::Expr, needs_par: bool) -> io::Result<()> {
         if needs_par {
             self.popen()?;
         }
@@ -1862,10 +1864,11 @@ impl<'a> State<'a> {
     fn print_expr_in_place(&mut self,
                            place: &ast::Expr,
                            expr: &ast::Expr) -> io::Result<()> {
-        self.print_expr_maybe_paren(place)?;
+        let prec = AssocOp::Inplace.precedence() as i8;
+        self.print_expr_maybe_paren(place, prec + 1)?;
         space(&mut self.s)?;
         self.word_space("<-")?;
-        self.print_expr_maybe_paren(expr)
+        self.print_expr_maybe_paren(expr, prec)
     }
 
     fn print_expr_vec(&mut self, exprs: &[P<ast::Expr>],
@@ -1946,7 +1949,7 @@ impl<'a> State<'a> {
     fn print_expr_call(&mut self,
                        func: &ast::Expr,
                        args: &[P<ast::Expr>]) -> io::Result<()> {
-        self.print_expr_maybe_paren(func)?;
+        self.print_expr_maybe_paren(func, parser::PREC_POSTFIX)?;
         self.print_call_post(args)
     }
 
@@ -1955,7 +1958,7 @@ impl<'a> State<'a> {
                               tys: &[P<ast::Ty>],
                               args: &[P<ast::Expr>]) -> io::Result<()> {
         let base_args = &args[1..];
-        self.print_expr(&args[0])?;
+        self.print_expr_maybe_paren(&args[0], parser::PREC_POSTFIX)?;
         word(&mut self.s, ".")?;
         self.print_ident(ident.node)?;
         if !tys.is_empty() {
@@ -1971,25 +1974,35 @@ impl<'a> State<'a> {
                          op: ast::BinOp,
                          lhs: &ast::Expr,
                          rhs: &ast::Expr) -> io::Result<()> {
-        if self.check_expr_bin_needs_paren(lhs, op) {
-            self.print_expr_maybe_paren(lhs)?;
-        } else {
-            self.print_expr(lhs)?;
-        }
+        let assoc_op = AssocOp::from_ast_binop(op.node);
+        let prec = assoc_op.precedence() as i8;
+        let fixity = assoc_op.fixity();
+
+        let (left_prec, right_prec) = match fixity {
+            Fixity::Left => (prec, prec + 1),
+            Fixity::Right => (prec + 1, prec),
+            Fixity::None => (prec + 1, prec + 1),
+        };
+
+        let left_prec = match (&lhs.node, op.node) {
+            // These cases need parens: `x as i32 < y` has the parser interpret `i32 < y` as
+            // the start of generic arguments, and `x as i32 << y` is a lexing error.
+            (&ast::ExprKind::Cast(..), ast::BinOpKind::Lt) |
+            (&ast::ExprKind::Cast(..), ast::BinOpKind::Shl) => parser::PREC_FORCE_PAREN,
+            _ => left_prec,
+        };
+
+        self.print_expr_maybe_paren(lhs, left_prec)?;
         space(&mut self.s)?;
         self.word_space(op.node.to_string())?;
-        if self.check_expr_bin_needs_paren(rhs, op) {
-            self.print_expr_maybe_paren(rhs)
-        } else {
-            self.print_expr(rhs)
-        }
+        self.print_expr_maybe_paren(rhs, right_prec)
     }
 
     fn print_expr_unary(&mut self,
                         op: ast::UnOp,
                         expr: &ast::Expr) -> io::Result<()> {
         word(&mut self.s, ast::UnOp::to_string(op))?;
-        self.print_expr_maybe_paren(expr)
+        self.print_expr_maybe_paren(expr, parser::PREC_PREFIX)
     }
 
     fn print_expr_addr_of(&mut self,
@@ -1997,7 +2010,7 @@ impl<'a> State<'a> {
                           expr: &ast::Expr) -> io::Result<()> {
         word(&mut self.s, "&")?;
         self.print_mutability(mutability)?;
-        self.print_expr_maybe_paren(expr)
+        self.print_expr_maybe_paren(expr, parser::PREC_PREFIX)
     }
 
     pub fn print_expr(&mut self, expr: &ast::Expr) -> io::Result<()> {
@@ -2021,7 +2034,7 @@ impl<'a> State<'a> {
         match expr.node {
             ast::ExprKind::Box(ref expr) => {
                 self.word_space("box")?;
-                self.print_expr(expr)?;
+                self.print_expr_maybe_paren(expr, parser::PREC_PREFIX)?;
             }
             ast::ExprKind::InPlace(ref place, ref expr) => {
                 self.print_expr_in_place(place, expr)?;
@@ -2057,17 +2070,15 @@ impl<'a> State<'a> {
                 self.print_literal(lit)?;
             }
             ast::ExprKind::Cast(ref expr, ref ty) => {
-                if let ast::ExprKind::Cast(..) = expr.node {
-                    self.print_expr(expr)?;
-                } else {
-                    self.print_expr_maybe_paren(expr)?;
-                }
+                let prec = AssocOp::As.precedence() as i8;
+                self.print_expr_maybe_paren(expr, prec)?;
                 space(&mut self.s)?;
                 self.word_space("as")?;
                 self.print_type(ty)?;
             }
             ast::ExprKind::Type(ref expr, ref ty) => {
-                self.print_expr(expr)?;
+                let prec = AssocOp::Colon.precedence() as i8;
+                self.print_expr_maybe_paren(expr, prec)?;
                 self.word_space(":")?;
                 self.print_type(ty)?;
             }
@@ -2083,7 +2094,7 @@ impl<'a> State<'a> {
                     self.word_space(":")?;
                 }
                 self.head("while")?;
-                self.print_expr(test)?;
+                self.print_expr_as_cond(test)?;
                 space(&mut self.s)?;
                 self.print_block_with_attrs(blk, attrs)?;
             }
@@ -2096,7 +2107,7 @@ impl<'a> State<'a> {
                 self.print_pat(pat)?;
                 space(&mut self.s)?;
                 self.word_space("=")?;
-                self.print_expr(expr)?;
+                self.print_expr_as_cond(expr)?;
                 space(&mut self.s)?;
                 self.print_block_with_attrs(blk, attrs)?;
             }
@@ -2109,7 +2120,7 @@ impl<'a> State<'a> {
                 self.print_pat(pat)?;
                 space(&mut self.s)?;
                 self.word_space("in")?;
-                self.print_expr(iter)?;
+                self.print_expr_as_cond(iter)?;
                 space(&mut self.s)?;
                 self.print_block_with_attrs(blk, attrs)?;
             }
@@ -2126,7 +2137,7 @@ impl<'a> State<'a> {
                 self.cbox(INDENT_UNIT)?;
                 self.ibox(4)?;
                 self.word_nbsp("match")?;
-                self.print_expr(expr)?;
+                self.print_expr_as_cond(expr)?;
                 space(&mut self.s)?;
                 self.bopen()?;
                 self.print_inner_attributes_no_trailing_hardbreak(attrs)?;
@@ -2156,37 +2167,44 @@ impl<'a> State<'a> {
                 self.print_block_with_attrs(blk, attrs)?;
             }
             ast::ExprKind::Assign(ref lhs, ref rhs) => {
-                self.print_expr(lhs)?;
+                let prec = AssocOp::Assign.precedence() as i8;
+                self.print_expr_maybe_paren(lhs, prec + 1)?;
                 space(&mut self.s)?;
                 self.word_space("=")?;
-                self.print_expr(rhs)?;
+                self.print_expr_maybe_paren(rhs, prec)?;
             }
             ast::ExprKind::AssignOp(op, ref lhs, ref rhs) => {
-                self.print_expr(lhs)?;
+                let prec = AssocOp::Assign.precedence() as i8;
+                self.print_expr_maybe_paren(lhs, prec + 1)?;
                 space(&mut self.s)?;
                 word(&mut self.s, op.node.to_string())?;
                 self.word_space("=")?;
-                self.print_expr(rhs)?;
+                self.print_expr_maybe_paren(rhs, prec)?;
             }
             ast::ExprKind::Field(ref expr, id) => {
-                self.print_expr(expr)?;
+                self.print_expr_maybe_paren(expr, parser::PREC_POSTFIX)?;
                 word(&mut self.s, ".")?;
                 self.print_ident(id.node)?;
             }
             ast::ExprKind::TupField(ref expr, id) => {
-                self.print_expr(expr)?;
+                self.print_expr_maybe_paren(expr, parser::PREC_POSTFIX)?;
                 word(&mut self.s, ".")?;
                 self.print_usize(id.node)?;
             }
             ast::ExprKind::Index(ref expr, ref index) => {
-                self.print_expr(expr)?;
+                self.print_expr_maybe_paren(expr, parser::PREC_POSTFIX)?;
                 word(&mut self.s, "[")?;
                 self.print_expr(index)?;
                 word(&mut self.s, "]")?;
             }
             ast::ExprKind::Range(ref start, ref end, limits) => {
+                // Special case for `Range`. `AssocOp` claims that `Range` has higher precedence
+                // than `Assign`, but `x .. x = x` gives a parse error instead of `x .. (x = x)`.
+                // Here we use a fake precedence value so that any child with lower precedence than
+                // a "normal" binop gets parenthesized. (`LOr` is the lowest-precedence binop.)
+                let fake_prec = AssocOp::LOr.precedence() as i8;
                 if let Some(ref e) = *start {
-                    self.print_expr(e)?;
+                    self.print_expr_maybe_paren(e, fake_prec)?;
                 }
                 if limits == ast::RangeLimits::HalfOpen {
                     word(&mut self.s, "..")?;
@@ -2194,7 +2212,7 @@ impl<'a> State<'a> {
                     word(&mut self.s, "...")?;
                 }
                 if let Some(ref e) = *end {
-                    self.print_expr(e)?;
+                    self.print_expr_maybe_paren(e, fake_prec)?;
                 }
             }
             ast::ExprKind::Path(None, ref path) => {
@@ -2211,7 +2229,7 @@ impl<'a> State<'a> {
                     space(&mut self.s)?;
                 }
                 if let Some(ref expr) = *opt_expr {
-                    self.print_expr(expr)?;
+                    self.print_expr_maybe_paren(expr, parser::PREC_JUMP)?;
                     space(&mut self.s)?;
                 }
             }
@@ -2227,7 +2245,7 @@ impl<'a> State<'a> {
                 word(&mut self.s, "return")?;
                 if let Some(ref expr) = *result {
                     word(&mut self.s, " ")?;
-                    self.print_expr(expr)?;
+                    self.print_expr_maybe_paren(expr, parser::PREC_JUMP)?;
                 }
             }
             ast::ExprKind::InlineAsm(ref a) => {
@@ -2301,7 +2319,7 @@ impl<'a> State<'a> {
                 self.pclose()?;
             },
             ast::ExprKind::Try(ref e) => {
-                self.print_expr(e)?;
+                self.print_expr_maybe_paren(e, parser::PREC_POSTFIX)?;
                 word(&mut self.s, "?")?
             }
             ast::ExprKind::Catch(ref blk) => {
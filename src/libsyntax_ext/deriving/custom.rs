@@ -100,7 +100,7 @@ impl MultiItemModifier for ProcMacroDerive {
             match __internal::token_stream_parse_items(stream) {
                 Ok(new_items) => new_items.into_iter().map(Annotatable::Item).collect(),
                 Err(_) => {
-                    // FIXME: handle this better
+                    // The parse error itself has already been reported.
                     let msg = "proc-macro derive produced unparseable tokens";
                     ecx.struct_span_fatal(span, msg).emit();
                     panic!(FatalError);
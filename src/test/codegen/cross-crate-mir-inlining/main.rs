@@ -0,0 +1,33 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -C no-prepopulate-passes -Z mir-opt-level=2
+// aux-build:trivial.rs
+
+// Small upstream functions are inlined by MIR inlining even though they're
+// neither generic nor `#[inline]`, unless they refer to other items.
+
+#![crate_type = "lib"]
+
+extern crate trivial;
+
+// CHECK-LABEL: @call_answer
+#[no_mangle]
+pub fn call_answer() -> u32 {
+    // CHECK-NOT: call {{.*}}answer
+    trivial::answer()
+}
+
+// CHECK-LABEL: @call_counter
+#[no_mangle]
+pub fn call_counter() -> u32 {
+    // CHECK: call {{.*}}counter
+    trivial::counter()
+}
@@ -17,6 +17,7 @@ extern crate derive_bad;
     A
 )]
 //~^^ ERROR: proc-macro derive produced unparseable tokens
+//~| ERROR: expected `:`, found `}`
 struct A;
 
 fn main() {}
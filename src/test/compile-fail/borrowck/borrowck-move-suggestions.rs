@@ -0,0 +1,48 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Use of moved value errors suggest how to avoid the move, where possible.
+
+#[derive(Clone)]
+struct Data(Vec<u8>);
+
+struct NoClone;
+
+fn consume<T>(_: T) {}
+
+fn clone_argument() {
+    let data = Data(vec![]);
+    consume(data); //~ HELP consider cloning the value here
+    consume(data); //~ ERROR use of moved value: `data`
+}
+
+fn clone_initializer() {
+    let data = Data(vec![]);
+    let other = data; //~ HELP consider cloning the value here
+    consume(other);
+    consume(data); //~ ERROR use of moved value: `data`
+}
+
+fn bind_by_reference() {
+    let opt = Some(Data(vec![]));
+    match opt {
+        Some(inner) => consume(inner), //~ HELP consider binding by reference instead
+        None => {}
+    }
+    consume(opt); //~ ERROR use of partially moved value: `opt`
+}
+
+fn not_clone() {
+    let value = NoClone;
+    consume(value);
+    consume(value); //~ ERROR use of moved value: `value`
+}
+
+fn main() {}
@@ -0,0 +1,36 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Borrow conflicts suggest a block ending the first borrow in time, as long
+// as the bindings moved into that block are not needed afterwards.
+
+fn scope_resolves_conflict() {
+    let mut v = vec![1];
+    let first = &mut v; first.push(2);
+    //~^ HELP consider introducing a scope
+    v.push(3); //~ ERROR cannot borrow `v` as mutable more than once at a time
+}
+
+fn binding_used_later() {
+    let mut v = vec![1];
+    let first = &v;
+    v.push(2); //~ ERROR cannot borrow `v` as mutable because it is also borrowed as immutable
+    drop(first);
+}
+
+fn binding_captured_later() {
+    let mut v = vec![1];
+    let first = &v;
+    v.push(2); //~ ERROR cannot borrow `v` as mutable because it is also borrowed as immutable
+    let print = || drop(first);
+    print();
+}
+
+fn main() {}
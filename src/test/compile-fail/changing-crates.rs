@@ -20,5 +20,7 @@ extern crate b; //~ ERROR: found possibly newer version of crate `a` which `b` d
 //~| NOTE: perhaps that crate needs to be recompiled
 //~| NOTE: crate `a` path #1:
 //~| NOTE: crate `b` path #1:
+//~| NOTE: `b` was compiled against crate `a` with svh
+//~| HELP: recompile `b` against the `a` found above
 
 fn main() {}
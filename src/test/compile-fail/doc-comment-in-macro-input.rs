@@ -0,0 +1,26 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that a doc comment passed to a macro is still known to be one after
+// expansion.
+
+macro_rules! expr {
+    ($e:expr) => ($e)
+}
+
+fn main() {
+    let _ = expr!(
+        /// The answer.
+        //~^ ERROR attributes on non-item statements and expressions are experimental
+        //~| HELP add #![feature(stmt_expr_attributes)] to the crate attributes to enable
+        //~| HELP `///` is for documentation comments. For a plain comment, use `//`.
+        42
+    );
+}
@@ -0,0 +1,48 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![feature(asm, naked_functions)]
+
+#[naked]
+fn empty() {}
+
+#[naked]
+unsafe fn only_asm() {
+    asm!("");
+    asm!("")
+}
+
+#[naked]
+fn unsafe_block() {
+    unsafe { asm!("") }
+}
+
+#[naked]
+fn add_one(x: u32) -> u32 {
+    x + 1 //~ ERROR naked functions must only contain inline assembly
+}
+
+#[naked]
+fn local() {
+    let _x = 1; //~ ERROR naked functions must only contain inline assembly
+}
+
+struct Foo;
+
+impl Foo {
+    #[naked]
+    fn method(&self) {
+        call(); //~ ERROR naked functions must only contain inline assembly
+    }
+}
+
+fn call() {}
+
+fn main() {}
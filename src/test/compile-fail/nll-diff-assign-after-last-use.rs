@@ -0,0 +1,22 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z nll
+
+// Check that `-Z nll` points out borrowck errors that go away once a borrow
+// only lasts until the last use of the reference.
+
+fn main() {
+    let mut x = 22;
+    let p = &x;
+    let _y = *p;
+    x = 23; //~ ERROR cannot assign to `x` because it is borrowed
+    //~| WARN non-lexical lifetimes would accept this: cannot assign to `x`
+}
@@ -25,6 +25,7 @@ extern crate utb; //~ ERROR: found possibly newer version of crate `uta` which `
 //~| NOTE: perhaps that crate needs to be recompiled?
 //~| NOTE: crate `uta` path #1:
 //~| NOTE: crate `utb` path #1:
+//~| NOTE: `utb` was compiled against crate `uta` with svh
 
 fn main() {
     utb::foo()
@@ -0,0 +1,27 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Until the call, the mutable autoref of a two-phase receiver acts like a
+// shared borrow: it allows reading the receiver, but nothing else.
+
+#![feature(two_phase_borrows)]
+
+fn main() {
+    let mut v = vec![0];
+    v.push({ v.clear(); 0 });
+    //~^ ERROR cannot borrow `v` as mutable because it is also borrowed as immutable
+    v.push({ v = vec![]; 0 });
+    //~^ ERROR cannot assign to `v` because it is borrowed
+    v.push({ drop(v); 0 });
+    //~^ ERROR cannot move out of `v` because it is borrowed
+
+    let mut w = Box::new(vec![0]);
+    w.push(w.len()); // autoderef through a `Box` is fine
+}
@@ -26,6 +26,9 @@ fn main() {
         a::try_foo(foo2);
         //~^ ERROR mismatched types
         //~| Perhaps two different versions of crate `crate_a1`
+        //~| crate `crate_a1` with hash
+        //~| crate `crate_a2` with hash
+        //~| found in the library search path
         //~| expected struct `main::a::Foo`
         //~| expected type `main::a::Foo`
         //~| found type `main::a::Foo`
@@ -0,0 +1,38 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Changing the body of a function only dirties its HirBody node. Its
+// signature (the Hir node) and whatever only looks at the signature, such
+// as the type-check tables of its callers, stay clean.
+
+// must-compile-successfully
+// revisions: cfail1 cfail2
+// compile-flags: -Z query-dep-graph
+
+#![allow(warnings)]
+#![feature(rustc_attrs)]
+#![crate_type="rlib"]
+
+#[rustc_clean(label="Hir", cfg="cfail2")]
+#[rustc_dirty(label="HirBody", cfg="cfail2")]
+pub fn callee() -> u32 {
+    #[cfg(cfail1)]
+    let x = 1;
+    #[cfg(not(cfail1))]
+    let x = 2;
+    x
+}
+
+#[rustc_clean(label="Hir", cfg="cfail2")]
+#[rustc_clean(label="HirBody", cfg="cfail2")]
+#[rustc_clean(label="TypeckTables", cfg="cfail2")]
+pub fn caller() -> u32 {
+    callee()
+}
@@ -40,6 +40,13 @@ mod y {
         //[cfail2]~^ ERROR `TypeckTables(y::y)` not found in dep graph, but should be clean
         x::x();
     }
+
+    // Only the labels that don't match are reported.
+    #[rustc_clean(label="Hir, TypeckTables", cfg="cfail2")]
+    pub fn y2() {
+        //[cfail2]~^ ERROR `TypeckTables(y::y2)` not found in dep graph, but should be clean
+        x::x();
+    }
 }
 
 mod z {
@@ -47,4 +54,10 @@ mod z {
     pub fn z() {
         //[cfail2]~^ ERROR `TypeckTables(z::z)` found in dep graph, but should be dirty
     }
+
+    #[rustc_dirty(label="TypeckTables", label="Hir", cfg="cfail2")]
+    pub fn z2() {
+        //[cfail2]~^ ERROR `TypeckTables(z::z2)` found in dep graph, but should be dirty
+        //[cfail2]~| ERROR `Hir(z::z2)` not found in dirty set, but should be dirty
+    }
 }
@@ -0,0 +1,29 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Test that under LTO, the object of the crate, which holds the code of the
+// upstream crates too, is reused when nothing changed, but not when only the
+// body of a private upstream function did, which leaves the crate clean.
+
+// aux-build:upstream.rs
+// revisions:rpass1 rpass2 rpass3
+// no-prefer-dynamic
+// compile-flags: -Z query-dep-graph -C lto
+
+#![feature(rustc_attrs)]
+
+#![rustc_partition_reused(module="main.cgu-0", cfg="rpass2")]
+#![rustc_partition_translated(module="main.cgu-0", cfg="rpass3")]
+
+extern crate upstream;
+
+pub fn main() {
+    assert_eq!(upstream::answer(), 42);
+}
@@ -0,0 +1,48 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Changing the documentation of a function changes its Hir node, which its
+// signature is computed from, but not the signature itself: red/green
+// marking finds its ItemSignature node unchanged and marks it green.
+// Changing the return type of a function leaves the node red.
+
+// must-compile-successfully
+// revisions: cfail1 cfail2
+// compile-flags: -Z query-dep-graph
+
+#![allow(warnings)]
+#![feature(rustc_attrs)]
+#![crate_type="rlib"]
+
+#[cfg(cfail1)]
+/// Adds one.
+pub fn same_signature(x: u32) -> u32 {
+    x + 1
+}
+
+#[cfg(not(cfail1))]
+#[rustc_dirty(label="Hir", cfg="cfail2")]
+#[rustc_clean(label="ItemSignature", cfg="cfail2")]
+/// Adds one to `x`.
+pub fn same_signature(x: u32) -> u32 {
+    x + 1
+}
+
+#[cfg(cfail1)]
+pub fn changed_signature(x: u32) -> u32 {
+    x + 1
+}
+
+#[cfg(not(cfail1))]
+#[rustc_dirty(label="Hir", cfg="cfail2")]
+#[rustc_dirty(label="ItemSignature", cfg="cfail2")]
+pub fn changed_signature(x: u32) -> u64 {
+    x as u64 + 1
+}
@@ -0,0 +1,36 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that the source files recorded by one session can be read back and
+// compared by the next, and that what was built from a file that didn't
+// change is reused although another file of the crate did change.
+
+// revisions: rpass1 rpass2
+// compile-flags: -Z incremental-info -Z query-dep-graph
+
+#![feature(rustc_attrs)]
+
+mod auxiliary;
+
+#[cfg(rpass1)]
+pub fn x() -> i32 {
+    1
+}
+
+#[cfg(rpass2)]
+#[rustc_dirty(label="HirBody", cfg="rpass2")]
+pub fn x() -> i32 {
+    2
+}
+
+fn main() {
+    x();
+    auxiliary::y();
+}
@@ -0,0 +1,23 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z parse-only
+
+// Check that a missing or mistyped `;` doesn't stop the parser from reporting
+// the errors in the statements that follow it.
+
+fn main() {
+    let x = 1
+    let y = 2, //~ ERROR expected one of `.`, `;`, `?`, or an operator, found `let`
+    //~^ ERROR expected one of `.`, `;`, `?`, or an operator, found `,`
+    foo(x, y), //~ ERROR expected one of `.`, `;`, `?`, `}`, or an operator, found `,`
+    bar(x)
+    baz(y) //~ ERROR expected one of `.`, `;`, `?`, `}`, or an operator, found `baz`
+}
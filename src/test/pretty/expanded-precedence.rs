@@ -0,0 +1,38 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// pretty-expanded-reparse
+
+// Expressions passed to a macro are parsed on their own, so the expanded
+// source has to put back the parentheses that keep them together for it to
+// be parsed into the same program again.
+
+macro_rules! mul { ($a:expr, $b:expr) => ($a * $b) }
+macro_rules! sub { ($a:expr, $b:expr) => ($a - $b) }
+macro_rules! cast { ($a:expr) => ($a as u32) }
+macro_rules! count_ones { ($a:expr) => ($a.count_ones()) }
+macro_rules! first { ($a:expr) => ($a.0) }
+macro_rules! cond { ($a:expr) => (if $a { 1 } else { 0 }) }
+
+struct S {
+    x: u32,
+}
+
+fn main() {
+    let r = &(5,);
+
+    let _ = mul!(1 + 2, 3);
+    let _ = sub!(10, sub!(5, 2));
+    let _ = cast!(-1i32 + 2);
+    let _ = count_ones!(1u32 | 2);
+    let _ = first!(*r);
+    let _ = cond!(S { x: 1 }.x == 1);
+    let _ = 0x1_0;
+}
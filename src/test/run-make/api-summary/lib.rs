@@ -0,0 +1,30 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "lib"]
+
+pub fn add(a: u32, b: u32) -> u32 {
+    private(a) + b
+}
+
+fn private(a: u32) -> u32 {
+    a
+}
+
+#[derive(Clone)]
+pub struct Point {
+    pub x: i32,
+    y: i32,
+}
+
+#[deprecated(since = "0.2.0", note = "use `add` instead")]
+pub fn old_add(a: u32, b: u32) -> u32 {
+    add(a, b)
+}
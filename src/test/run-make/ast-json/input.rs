@@ -0,0 +1,23 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+macro_rules! define {
+    ($name:ident) => { fn $name() {} }
+}
+
+define!(expanded_fn);
+
+#[cfg(all())]
+fn enabled_fn() {}
+
+#[cfg(any())]
+fn disabled_fn() {}
+
+fn main() {}
@@ -0,0 +1,108 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "rustc.h"
+
+struct events {
+    int phases;
+    int analysis_done;
+    int errors;
+    int ices;
+    // cancelled when its "parse" phase is done, if not null
+    const rustc_session *cancel_after_parse;
+};
+
+static void callback(void *user_data, unsigned kind, const char *message) {
+    struct events *events = user_data;
+    switch (kind) {
+    case RUSTC_EVENT_PHASE:
+        events->phases++;
+        if (strcmp(message, "analysis") == 0)
+            events->analysis_done = 1;
+        if (strcmp(message, "parse") == 0 && events->cancel_after_parse != NULL)
+            rustc_session_cancel(events->cancel_after_parse);
+        break;
+    case RUSTC_EVENT_DIAGNOSTIC:
+        if (strstr(message, "\"level\":\"error\"") != NULL)
+            events->errors++;
+        break;
+    case RUSTC_EVENT_ICE:
+        assert(strstr(message, "treat_err_as_bug") != NULL);
+        events->ices++;
+        break;
+    default:
+        assert(0);
+    }
+}
+
+// The result of each call is kept in a local, so that the calls are still
+// made when `assert` is compiled out.
+static int compile(const char *input, const char *out_dir, const char *extra_flag,
+                   int cancel, struct events *events) {
+    int status;
+    rustc_session *session = rustc_session_new();
+    assert(session != NULL);
+    status = rustc_session_add_flag(session, "--crate-type=lib");
+    assert(status == RUSTC_OK);
+    if (extra_flag != NULL) {
+        status = rustc_session_add_flag(session, extra_flag);
+        assert(status == RUSTC_OK);
+    }
+    status = rustc_session_set_input(session, input);
+    assert(status == RUSTC_OK);
+    status = rustc_session_set_out_dir(session, out_dir);
+    assert(status == RUSTC_OK);
+    memset(events, 0, sizeof(*events));
+    if (cancel)
+        events->cancel_after_parse = session;
+    status = rustc_session_compile(session, callback, events);
+    rustc_session_free(session);
+    return status;
+}
+
+int main(int argc, char **argv) {
+    struct events events;
+    int status;
+    if (argc != 2) {
+        fprintf(stderr, "usage: driver OUT_DIR\n");
+        return 1;
+    }
+
+    status = rustc_session_compile(NULL, callback, &events);
+    assert(status == RUSTC_INVALID_ARGUMENT);
+
+    status = compile("ok.rs", argv[1], NULL, 0, &events);
+    assert(status == RUSTC_OK);
+    assert(events.phases > 0 && events.analysis_done && events.errors == 0 && events.ices == 0);
+
+    // Cancelling takes effect once the current phase is done, so nothing
+    // after parsing runs.
+    status = compile("ok.rs", argv[1], NULL, 1, &events);
+    assert(status == RUSTC_CANCELLED);
+    assert(events.phases == 1 && !events.analysis_done);
+
+    status = compile("error.rs", argv[1], NULL, 0, &events);
+    assert(status == RUSTC_ERRORED);
+    assert(events.errors > 0 && events.ices == 0);
+
+    // The first error is turned into a panic, whose message must reach the
+    // callback rather than be lost.
+    status = compile("error.rs", argv[1], "-Ztreat-err-as-bug", 0, &events);
+    assert(status == RUSTC_ICE);
+    assert(events.ices == 1);
+
+    (void)status;
+    printf("ok\n");
+    return 0;
+}
@@ -0,0 +1,52 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![feature(rustc_private)]
+
+extern crate rustc_driver;
+extern crate rustc_metadata;
+
+use rustc_driver::{run_compiler_batch, RustcDefaultCalls};
+use rustc_metadata::metadata_cache::MetadataCache;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        panic!("expected rustc path");
+    }
+
+    let tmpdir = PathBuf::from(&args[1]);
+    let mut sysroot = PathBuf::from(&args[2]);
+    sysroot.pop();
+    sysroot.pop();
+
+    let invocation = |input: &str| -> Vec<String> {
+        vec!["rustc".to_string(),
+             input.to_string(),
+             "--sysroot".to_string(), sysroot.display().to_string(),
+             "-L".to_string(), tmpdir.display().to_string(),
+             "--out-dir".to_string(), tmpdir.display().to_string()]
+    };
+
+    let metadata_cache = Rc::new(MetadataCache::new());
+    let results = run_compiler_batch(&[invocation("bar.rs"), invocation("baz.rs")],
+                                     &mut RustcDefaultCalls,
+                                     &metadata_cache);
+    assert!(results.iter().all(|result| result.is_ok()));
+
+    // The library search canonicalizes the paths it finds.
+    let foo = fs::canonicalize(tmpdir.join("libfoo.rlib")).unwrap();
+    assert_eq!(metadata_cache.hits(&foo), 1);
+}
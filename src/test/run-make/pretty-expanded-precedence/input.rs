@@ -0,0 +1,44 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Expressions passed to a macro are parsed on their own, so after expansion
+// the printed code has to add back the parentheses that keep them together.
+// None of these should need the ones written around them here.
+
+macro_rules! mul { ($a:expr, $b:expr) => ($a * $b) }
+macro_rules! sub { ($a:expr, $b:expr) => ($a - $b) }
+macro_rules! cast { ($a:expr) => ($a as u32) }
+macro_rules! count_ones { ($a:expr) => ($a.count_ones()) }
+macro_rules! first { ($a:expr) => ($a.0) }
+macro_rules! len { ($a:expr) => ($a.len()) }
+macro_rules! cond { ($a:expr) => (if $a { 1 } else { 0 }) }
+
+struct S {
+    x: u32,
+}
+
+fn check(actual: u32, expected: u32) {
+    if actual != expected {
+        std::process::exit(1);
+    }
+}
+
+fn main() {
+    let r = &(5,);
+
+    check(mul!(1 + 2, 3), 9);
+    check(sub!(10, sub!(5, 2)), 7);
+    check(cast!(-1i32 + 2), 1);
+    check(count_ones!(1u32 | 2), 2);
+    check(first!(*r), 5);
+    check(len!(0..3) as u32, 3);
+    check(cond!(S { x: 1 }.x == 1), 1);
+    check(0x1_0, 16);
+}
@@ -0,0 +1,30 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+pub mod foo {
+    pub struct Def {
+        pub id: i32,
+    }
+
+    impl Def {
+        pub fn new(id: i32) -> Def {
+            Def { id: id }
+        }
+    }
+
+    #[inline(never)]
+    pub fn identity<T>(x: T) -> T {
+        x
+    }
+}
+
+pub fn use_identity() -> usize {
+    foo::identity(42usize)
+}
@@ -0,0 +1,42 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-pretty pretty-printing is unhygienic
+
+#![feature(decl_macro)]
+
+// A `macro` with several arms, imported with `use` and recursively calling
+// itself. The paths in its body resolve where it is defined, not where it is
+// invoked.
+
+mod counting {
+    fn one() -> u32 { 1 }
+
+    pub macro count {
+        () => { 0 },
+        ($e:expr) => { one() },
+        ($e:expr, $($rest:expr),*) => { one() + count!($($rest),*) },
+    }
+}
+
+mod user {
+    use counting::count;
+
+    #[allow(dead_code)]
+    fn one() -> u32 { 100 }
+
+    pub fn counts() -> (u32, u32, u32) {
+        (count!(), count!(a), count!(a, b, c))
+    }
+}
+
+fn main() {
+    assert_eq!(user::counts(), (0, 1, 3));
+}
@@ -0,0 +1,45 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// revisions: ast nll
+//[nll] compile-flags: -Z nll
+
+#![feature(two_phase_borrows)]
+
+struct Counter {
+    values: Vec<usize>,
+}
+
+impl Counter {
+    fn next(&self) -> usize {
+        self.values.len()
+    }
+
+    fn record(&mut self, value: usize) {
+        self.values.push(value);
+    }
+}
+
+fn push_len(v: &mut Vec<usize>) {
+    v.push(v.len());
+}
+
+fn main() {
+    let mut v = vec![0];
+    v.push(v.len());
+    push_len(&mut v);
+    v.insert(0, v[1] + v[2]);
+    assert_eq!(v, [3, 0, 1, 2]);
+
+    let mut counter = Counter { values: vec![] };
+    counter.record(counter.next());
+    counter.record(counter.next());
+    assert_eq!(counter.values, [0, 1]);
+}
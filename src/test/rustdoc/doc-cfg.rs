@@ -0,0 +1,50 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![feature(doc_cfg)]
+
+// @has doc_cfg/struct.Portable.html
+// @!has - '//*[@class="stab portability"]' ''
+pub struct Portable;
+
+// @has doc_cfg/unix_only/index.html \
+//  '//*[@class="stability"]/*[@class="stab portability"]' \
+//  'This is supported on Unix only.'
+// @has - '//*[@class=" module-item"]//*[@class="stab portability"]' 'Unix and ARM'
+#[doc(cfg(unix))]
+pub mod unix_only {
+    // @has doc_cfg/unix_only/fn.unix_only_function.html \
+    //  '//*[@class="stability"]/*[@class="stab portability"]' \
+    //  'This is supported on Unix only.'
+    pub fn unix_only_function() {}
+
+    // @has doc_cfg/unix_only/trait.ArmOnly.html \
+    //  '//*[@class="stability"]/*[@class="stab portability"]' \
+    //  'This is supported on Unix and ARM only.'
+    #[doc(cfg(target_arch = "arm"))]
+    pub trait ArmOnly {
+        fn unix_and_arm_only_function();
+    }
+
+    impl ArmOnly for super::Portable {
+        fn unix_and_arm_only_function() {}
+    }
+}
+
+// @has doc_cfg/fn.unix_or_windows_only.html \
+//  '//*[@class="stability"]/*[@class="stab portability"]' \
+//  'This is supported on Unix or Windows only.'
+#[cfg(any(unix, windows))]
+pub fn unix_or_windows_only() {}
+
+// @has doc_cfg/fn.not_for_tests.html
+// @!has - '//*[@class="stab portability"]' ''
+#[cfg(not(test))]
+pub fn not_for_tests() {}
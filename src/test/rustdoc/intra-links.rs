@@ -0,0 +1,57 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// @has intra_links/index.html
+// @has - '//a[@href="../intra_links/struct.ThisType.html"]' 'ThisType'
+// @has - '//a[@href="../intra_links/enum.ThisEnum.html"]' 'ThisEnum'
+// @has - '//a[@href="../intra_links/trait.ThisTrait.html"]' 'ThisTrait'
+// @has - '//a[@href="../intra_links/fn.this_function.html"]' 'this_function'
+// @has - '//a[@href="../intra_links/enum.ThisEnum.html#variant.ThisVariant"]' 'ThisVariant'
+// @has - '//a[@href="../intra_links/struct.ThisType.html#method.this_method"]' 'ThisType::this_method'
+// @has - '//a[@href="../intra_links/trait.ThisTrait.html#tymethod.this_required"]' 'ThisTrait::this_required'
+// @has - '//a[@href="../intra_links/index.html"]' 'self'
+// @!has - '//a' 'NotAnItem'
+//! In this crate we would like to link to:
+//!
+//! * [`ThisType`], [`ThisEnum`] and [`ThisTrait`]
+//! * [`this_function()`]
+//! * [`ThisEnum::ThisVariant`]
+//! * [`ThisType::this_method`]
+//! * [`ThisTrait::this_required`]
+//! * [self], the crate itself
+//! * and [NotAnItem], which is just prose
+//!
+//! but not an [explicit link](ThisType) or a `[code span]`.
+
+pub struct ThisType;
+
+impl ThisType {
+    pub fn this_method() {}
+}
+
+pub enum ThisEnum { ThisVariant, }
+
+pub trait ThisTrait {
+    fn this_required();
+}
+
+pub fn this_function() {}
+
+pub struct SelfLinks;
+
+// @has intra_links/struct.SelfLinks.html
+// @has - '//a[@href="../intra_links/struct.SelfLinks.html#method.new"]' 'Self::new'
+// @has - '//a[@href="../intra_links/struct.SelfLinks.html#method.new"]' 'SelfLinks::new'
+impl SelfLinks {
+    pub fn new() -> SelfLinks { SelfLinks }
+
+    /// Same as [`Self::new`], i.e. [`SelfLinks::new`].
+    pub fn create() -> SelfLinks { SelfLinks }
+}
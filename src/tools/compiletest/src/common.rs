@@ -111,6 +111,10 @@ pub struct Config {
     // (or, alternatively, to silently run them like regular run-pass tests).
     pub force_valgrind: bool,
 
+    // Whether to overwrite the expected output of UI tests with the actual
+    // output, rather than failing when they differ
+    pub bless: bool,
+
     // The directory containing the tests to run
     pub src_base: PathBuf,
 
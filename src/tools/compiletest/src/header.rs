@@ -219,6 +219,9 @@ pub struct TestProps {
     pub no_prefer_dynamic: bool,
     // Run --pretty expanded when running pretty printing tests
     pub pretty_expanded: bool,
+    // Also check that the expanded source reparses to the same program, by
+    // printing it again and comparing it with itself
+    pub pretty_expanded_reparse: bool,
     // Which pretty mode are we testing with, default to 'normal'
     pub pretty_mode: String,
     // Only compare pretty output and don't try compiling
@@ -239,6 +242,10 @@ pub struct TestProps {
     // The test must be compiled and run successfully. Only used in UI tests for
     // now.
     pub run_pass: bool,
+    // Extra replacements applied to the stdout/stderr of UI tests before
+    // comparing them with the expected output
+    pub normalize_stdout: Vec<(String, String)>,
+    pub normalize_stderr: Vec<(String, String)>,
 }
 
 impl TestProps {
@@ -258,6 +265,7 @@ impl TestProps {
             check_stdout: false,
             no_prefer_dynamic: false,
             pretty_expanded: false,
+            pretty_expanded_reparse: false,
             pretty_mode: "normal".to_string(),
             pretty_compare_only: false,
             forbid_output: vec![],
@@ -265,6 +273,8 @@ impl TestProps {
             must_compile_successfully: false,
             check_test_line_numbers_match: false,
             run_pass: false,
+            normalize_stdout: vec![],
+            normalize_stderr: vec![],
         }
     }
 
@@ -340,6 +350,10 @@ impl TestProps {
                 self.pretty_expanded = config.parse_pretty_expanded(ln);
             }
 
+            if !self.pretty_expanded_reparse {
+                self.pretty_expanded_reparse = config.parse_pretty_expanded_reparse(ln);
+            }
+
             if let Some(m) = config.parse_pretty_mode(ln) {
                 self.pretty_mode = m;
             }
@@ -379,6 +393,13 @@ impl TestProps {
             if !self.run_pass {
                 self.run_pass = config.parse_run_pass(ln);
             }
+
+            if let Some(rule) = config.parse_normalize_rule(ln, "normalize-stdout") {
+                self.normalize_stdout.push(rule);
+            }
+            if let Some(rule) = config.parse_normalize_rule(ln, "normalize-stderr") {
+                self.normalize_stderr.push(rule);
+            }
         });
 
         for key in &["RUST_TEST_NOCAPTURE", "RUST_TEST_THREADS"] {
@@ -477,6 +498,10 @@ impl Config {
         self.parse_name_directive(line, "pretty-expanded")
     }
 
+    fn parse_pretty_expanded_reparse(&self, line: &str) -> bool {
+        self.parse_name_directive(line, "pretty-expanded-reparse")
+    }
+
     fn parse_pretty_mode(&self, line: &str) -> Option<String> {
         self.parse_name_value_directive(line, "pretty-mode")
     }
@@ -497,6 +522,20 @@ impl Config {
         self.parse_name_directive(line, "run-pass")
     }
 
+    /// Parses a rule of the form `"from" -> "to"`, such as
+    /// `// normalize-stderr: "64-bit" -> "$WORD-bit"`.
+    fn parse_normalize_rule(&self, line: &str, name: &str) -> Option<(String, String)> {
+        self.parse_name_value_directive(line, name).map(|rule| {
+            let parts: Vec<&str> = rule.split('"').collect();
+            if parts.len() != 5 || parts[0].trim() != "" || parts[2].trim() != "->" ||
+               parts[4].trim() != "" {
+                panic!("malformed {} rule: expected `\"from\" -> \"to\"`, found `{}`",
+                       name, rule);
+            }
+            (parts[1].to_owned(), parts[3].to_owned())
+        })
+    }
+
     fn parse_env(&self, line: &str, name: &str) -> Option<(String, String)> {
         self.parse_name_value_directive(line, name).map(|nv| {
             // nv is either FOO or FOO=BAR
@@ -308,7 +308,7 @@ impl<'test> TestCx<'test> {
             self.fatal_proc_rec("pretty-printed source does not typecheck", &proc_res);
         }
 
-        if !self.props.pretty_expanded { return }
+        if !self.props.pretty_expanded && !self.props.pretty_expanded_reparse { return }
 
         // additionally, run `--pretty expanded` and try to build it.
         let proc_res = self.print_source(srcs[round].clone(), "expanded");
@@ -317,12 +317,22 @@ impl<'test> TestCx<'test> {
         }
 
         let ProcRes{ stdout: expanded_src, .. } = proc_res;
-        let proc_res = self.typecheck_source(expanded_src);
+        let proc_res = self.typecheck_source(expanded_src.clone());
         if !proc_res.status.success() {
             self.fatal_proc_rec(
                 "pretty-printed source (expanded) does not typecheck",
                 &proc_res);
         }
+
+        if !self.props.pretty_expanded_reparse { return }
+
+        // Expansion leaves no macros to expand, so if the expanded source is
+        // parsed back into the same program, printing it gives it back as is.
+        let proc_res = self.print_source(expanded_src.clone(), "normal");
+        if !proc_res.status.success() {
+            self.fatal_proc_rec("pretty-printing (expanded, reparsed) failed", &proc_res);
+        }
+        self.compare_source(&expanded_src, &proc_res.stdout);
     }
 
     fn print_source(&self,
@@ -2055,17 +2065,17 @@ actual:\n\
     fn run_incremental_test(&self) {
         // Basic plan for a test incremental/foo/bar.rs:
         // - load list of revisions rpass1, cfail2, rpass3
-        //   - each should begin with `rpass`, `cfail`, or `cfail`
+        //   - each should begin with `rpass`, `rfail`, or `cfail`
         //   - if `rpass`, expect compile and execution to succeed
         //   - if `cfail`, expect compilation to fail
         //   - if `rfail`, expect execution to fail
-        // - create a directory build/foo/bar.incremental
-        // - compile foo/bar.rs with -Z incremental=.../foo/bar.incremental and -C rpass1
+        // - create a directory build/foo/bar.inc
+        // - compile foo/bar.rs with -Z incremental=.../foo/bar.inc and --cfg rpass1
         //   - because name of revision starts with "rpass", expect success
-        // - compile foo/bar.rs with -Z incremental=.../foo/bar.incremental and -C cfail2
+        // - compile foo/bar.rs with -Z incremental=.../foo/bar.inc and --cfg cfail2
         //   - because name of revision starts with "cfail", expect an error
-        //   - load expected errors as usual, but filter for those that end in `[rfail2]`
-        // - compile foo/bar.rs with -Z incremental=.../foo/bar.incremental and -C rpass3
+        //   - load expected errors as usual, but filter for those that end in `[cfail2]`
+        // - compile foo/bar.rs with -Z incremental=.../foo/bar.inc and --cfg rpass3
         //   - because name of revision starts with "rpass", expect success
         // - execute build/foo/bar.exe and save output
         //
@@ -2228,15 +2238,18 @@ actual:\n\
         let expected_stdout_path = self.expected_output_path("stdout");
         let expected_stdout = self.load_expected_output(&expected_stdout_path);
 
-        let normalized_stdout = self.normalize_output(&proc_res.stdout);
-        let normalized_stderr = self.normalize_output(&proc_res.stderr);
+        let normalized_stdout = self.normalize_output(&proc_res.stdout,
+                                                      &self.props.normalize_stdout);
+        let normalized_stderr = self.normalize_output(&proc_res.stderr,
+                                                      &self.props.normalize_stderr);
 
         let mut errors = 0;
         errors += self.compare_output("stdout", &normalized_stdout, &expected_stdout);
         errors += self.compare_output("stderr", &normalized_stderr, &expected_stderr);
 
         if errors > 0 {
-            println!("To update references, run this command from build directory:");
+            println!("To update references, rerun the tests with `--bless`, or run this \
+                      command from build directory:");
             let relative_path_to_file =
                 self.testpaths.relative_dir
                               .join(self.testpaths.file.file_name().unwrap());
@@ -2375,13 +2388,17 @@ actual:\n\
         mir_dump_dir
     }
 
-    fn normalize_output(&self, output: &str) -> String {
+    fn normalize_output(&self, output: &str, custom_rules: &[(String, String)]) -> String {
         let parent_dir = self.testpaths.file.parent().unwrap();
         let parent_dir_str = parent_dir.display().to_string();
-        output.replace(&parent_dir_str, "$DIR")
-              .replace("\\", "/") // normalize for paths on windows
-              .replace("\r\n", "\n") // normalize for linebreaks on windows
-              .replace("\t", "\\t") // makes tabs visible
+        let mut normalized = output.replace(&parent_dir_str, "$DIR")
+                                   .replace("\\", "/") // normalize for paths on windows
+                                   .replace("\r\n", "\n") // normalize for linebreaks on windows
+                                   .replace("\t", "\\t"); // makes tabs visible
+        for &(ref from, ref to) in custom_rules {
+            normalized = normalized.replace(&**from, to);
+        }
+        normalized
     }
 
     fn expected_output_path(&self, kind: &str) -> PathBuf {
@@ -2424,6 +2441,22 @@ actual:\n\
             }
         }
 
+        if self.config.bless {
+            let expected_path = self.expected_output_path(kind);
+            let result = if actual.is_empty() {
+                // An empty output is expected when there is no reference file
+                if expected_path.exists() { fs::remove_file(&expected_path) } else { Ok(()) }
+            } else {
+                File::create(&expected_path).and_then(|mut f| f.write_all(actual.as_bytes()))
+            };
+            if let Err(e) = result {
+                self.fatal(&format!("failed to bless {} into `{}`: {}",
+                                    kind, expected_path.display(), e))
+            }
+            println!("\nBlessed {} into {}", kind, expected_path.display());
+            return 0;
+        }
+
         let output_file = self.output_base_name().with_extension(kind);
         match File::create(&output_file).and_then(|mut f| f.write_all(actual.as_bytes())) {
             Ok(()) => { }